@@ -19,6 +19,9 @@ pub enum Condition {
     GT = 0b1100, // Signed Greater Than
     LE = 0b1101, // Signed Less or Equal
     AL = 0b1110, // Always
+    /// Riservata su ARMv4: le istruzioni con questa condizione sono
+    /// undefined, non "sempre eseguite"
+    NV = 0b1111,
 }
 
 impl Condition {
@@ -38,7 +41,8 @@ impl Condition {
             0b1011 => Condition::LT,
             0b1100 => Condition::GT,
             0b1101 => Condition::LE,
-            _ => Condition::AL,
+            0b1110 => Condition::AL,
+            _ => Condition::NV,
         }
     }
 
@@ -65,6 +69,7 @@ impl Condition {
             Condition::GT => !z && (n == v), // Z clear AND (N == V)
             Condition::LE => z || (n != v),  // Z set OR (N != V)
             Condition::AL => true,           // Always
+            Condition::NV => false,          // Mai (vedi execute_arm)
         }
     }
 }
@@ -179,6 +184,11 @@ pub fn decode_arm(instruction: u32) -> ArmInstruction {
         };
     }
 
+    // Undefined: xxxx 011x xxxx xxxx xxxx xxxx xxx1 xxxx
+    if (instruction & 0x0E00_0010) == 0x0600_0010 {
+        return ArmInstruction::Undefined;
+    }
+
     // Single Data Transfer: xxxx 01ip ubwl nnnn dddd oooo oooo oooo
     if (instruction & 0x0C00_0000) == 0x0400_0000 {
         return ArmInstruction::SingleDataTransfer {
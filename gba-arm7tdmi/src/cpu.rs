@@ -1,4 +1,8 @@
-use crate::registers::Registers;
+use crate::instructions::alu::barrel_shift;
+use crate::instructions::load_store::read_word_rotated;
+use crate::registers::{Mode, RegisterSnapshot, Registers};
+use alloc::boxed::Box;
+use core::fmt;
 
 //==============================================================================
 // MEMORIA E BUS
@@ -23,6 +27,17 @@ pub trait MemoryBus {
     fn write_byte(&mut self, addr: u32, value: u8);
     fn write_halfword(&mut self, addr: u32, value: u16);
     fn write_word(&mut self, addr: u32, value: u32);
+
+    /// Fetch di un'istruzione THUMB (permette al bus di distinguere
+    /// i fetch dagli accessi dati, es. per il prefetch del GamePak)
+    fn fetch_halfword(&mut self, addr: u32) -> u16 {
+        self.read_halfword(addr)
+    }
+
+    /// Fetch di un'istruzione ARM
+    fn fetch_word(&mut self, addr: u32) -> u32 {
+        self.read_word(addr)
+    }
 }
 
 //==============================================================================
@@ -42,16 +57,48 @@ pub trait MemoryBus {
 // 🚧 TODO: Pipeline accurata
 //==============================================================================
 
+/// Stato della CPU prima dell'esecuzione di un'istruzione (trace)
+///
+/// `regs[15]` contiene l'indirizzo dell'istruzione, come `pc`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub pc: u32,
+    pub opcode: u32,
+    pub thumb: bool,
+    pub regs: [u32; 16],
+    pub cpsr: u32,
+}
+
+impl fmt::Display for TraceEntry {
+    /// Formato stile mGBA: registri, CPSR, indirizzo e opcode
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for value in &self.regs {
+            write!(f, "{:08X} ", value)?;
+        }
+        write!(f, "cpsr: {:08X} | {:08X}: ", self.cpsr, self.pc)?;
+        if self.thumb {
+            write!(f, "{:04X}", self.opcode)
+        } else {
+            write!(f, "{:08X}", self.opcode)
+        }
+    }
+}
+
+/// Callback invocata prima di ogni istruzione
+pub type TraceCallback = Box<dyn FnMut(&TraceEntry)>;
+
 /// CPU ARM7TDMI del Game Boy Advance
 ///
 /// Campi:
 /// - `regs`: Registri della CPU (R0-R15, CPSR, SPSR, banked registers)
 /// - `cycles`: Contatore cicli totali eseguiti
 /// - `halted`: Se true, la CPU è in stato HALT (risparmio energetico)
+/// - `trace`: Callback opzionale di trace (nessun costo se assente)
 pub struct ARM7TDMI {
     pub regs: Registers,
     pub cycles: u64,
     pub halted: bool,
+    trace: Option<TraceCallback>,
 }
 
 impl ARM7TDMI {
@@ -60,6 +107,31 @@ impl ARM7TDMI {
             regs: Registers::new(),
             cycles: 0,
             halted: false,
+            trace: None,
+        }
+    }
+
+    /// Tutti i registri (visibili, banked, CPSR e SPSR) per debugger e script
+    pub fn dump_registers(&self) -> RegisterSnapshot {
+        self.regs.snapshot()
+    }
+
+    /// Imposta (o rimuove con `None`) la callback di trace
+    pub fn set_trace(&mut self, trace: Option<TraceCallback>) {
+        self.trace = trace;
+    }
+
+    /// Riporta l'istruzione appena letta alla callback di trace
+    #[inline]
+    fn trace(&mut self, pc: u32, opcode: u32) {
+        if let Some(trace) = &mut self.trace {
+            trace(&TraceEntry {
+                pc,
+                opcode,
+                thumb: self.regs.is_thumb(),
+                regs: self.regs.r,
+                cpsr: self.regs.cpsr,
+            });
         }
     }
 
@@ -136,11 +208,16 @@ impl ARM7TDMI {
     /// Esegui un'istruzione ARM (32-bit)
     fn execute_arm<M: MemoryBus>(&mut self, bus: &mut M) -> u32 {
         let pc = self.regs.pc();
-        let instruction = bus.read_word(pc);
+        let instruction = bus.fetch_word(pc);
+        self.trace(pc, instruction);
         self.regs.set_pc(pc.wrapping_add(4));
 
-        // Verifica condition code
+        // Verifica condition code (0xF: undefined sull'ARM7TDMI)
         let condition = crate::arm::Condition::from_opcode(instruction);
+        if matches!(condition, crate::arm::Condition::NV) {
+            self.raise_undefined();
+            return 3;
+        }
         if !condition.check(self.regs.cpsr) {
             return 1; // Istruzione skippata, 1 ciclo
         }
@@ -219,10 +296,10 @@ impl ARM7TDMI {
                 load,
                 pre_index,
                 add,
+                user_mode,
                 writeback,
                 rn,
                 register_list,
-                ..
             } => crate::instructions::load_store::execute_block_data_transfer(
                 &mut self.regs,
                 bus,
@@ -231,6 +308,7 @@ impl ARM7TDMI {
                     pre_index,
                     add,
                     writeback,
+                    user_mode,
                     rn,
                     register_list,
                 },
@@ -259,11 +337,15 @@ impl ARM7TDMI {
                 if set_flags {
                     self.regs.set_flag_n((result & 0x8000_0000) != 0);
                     self.regs.set_flag_z(result == 0);
-                    // C è undefined, V non modificato
+                    // Sull'ARM7TDMI C viene "distrutto": assume un valore
+                    // senza significato che dipende dallo stato interno del
+                    // moltiplicatore. Qui resta invariato, così il risultato
+                    // è deterministico. V non modificato.
                 }
 
-                // MUL/MLA: dipende dai cicli, base 1-4
-                2
+                // MUL: 1S + mI, MLA: 1S + (m+1)I
+                let internal = multiply_internal_cycles(rs_val) + accumulate as u32;
+                1 + internal
             }
 
             ArmInstruction::SWI { comment: _ } => {
@@ -278,9 +360,9 @@ impl ARM7TDMI {
             }
 
             ArmInstruction::Undefined => {
-                // Istruzione non riconosciuta
-                // TODO: Generare undefined instruction exception
-                1
+                // Istruzione non riconosciuta: undefined instruction exception
+                self.raise_undefined();
+                3
             }
         }
     } //==========================================================================
@@ -310,7 +392,8 @@ impl ARM7TDMI {
     /// Esegui un'istruzione THUMB (16-bit)
     fn execute_thumb<M: MemoryBus>(&mut self, bus: &mut M) -> u32 {
         let pc = self.regs.pc();
-        let instruction = bus.read_halfword(pc);
+        let instruction = bus.fetch_halfword(pc);
+        self.trace(pc, instruction as u32);
         self.regs.set_pc(pc.wrapping_add(2));
 
         // Decodifica istruzione THUMB
@@ -321,38 +404,18 @@ impl ARM7TDMI {
         match decoded {
             ThumbInstruction::MoveShiftedRegister { op, offset, rs, rd } => {
                 let value = self.regs.r[rs as usize];
-                let result = match op {
-                    0 => value << offset, // LSL
-                    1 => {
-                        if offset == 0 {
-                            0
-                        } else {
-                            value >> offset
-                        }
-                    } // LSR
-                    2 => {
-                        // ASR
-                        if offset == 0 {
-                            if (value & 0x80000000) != 0 {
-                                0xFFFFFFFF
-                            } else {
-                                0
-                            }
-                        } else {
-                            ((value as i32) >> offset) as u32
-                        }
-                    }
-                    _ => value,
+                // LSR/ASR #0 codificano uno shift di 32, LSL #0 non sposta nulla
+                let amount = if offset == 0 && op != 0 {
+                    32
+                } else {
+                    offset as u32
                 };
+                let (result, carry) = barrel_shift(value, op as u32, amount, self.regs.flag_c());
 
                 self.regs.r[rd as usize] = result;
                 self.regs.set_flag_n((result & 0x80000000) != 0);
                 self.regs.set_flag_z(result == 0);
-                if offset != 0 && op == 0 {
-                    self.regs.set_flag_c((value & (1 << (32 - offset))) != 0);
-                } else if offset != 0 {
-                    self.regs.set_flag_c((value & (1 << (offset - 1))) != 0);
-                }
+                self.regs.set_flag_c(carry);
                 1
             }
 
@@ -444,23 +507,53 @@ impl ARM7TDMI {
                 let result = match op {
                     AND => rd_val & rs_val,
                     EOR => rd_val ^ rs_val,
-                    LSL => rd_val << (rs_val & 0xFF),
-                    LSR => rd_val >> (rs_val & 0xFF),
-                    ASR => ((rd_val as i32) >> (rs_val & 0xFF)) as u32,
-                    ADC => {
-                        let c = if self.regs.flag_c() { 1 } else { 0 };
-                        rd_val.wrapping_add(rs_val).wrapping_add(c)
+                    LSL | LSR | ASR | ROR => {
+                        // Shift per registro: conta solo il byte basso di Rs
+                        let shift_type = match op {
+                            LSL => 0,
+                            LSR => 1,
+                            ASR => 2,
+                            _ => 3,
+                        };
+                        let (value, carry) =
+                            barrel_shift(rd_val, shift_type, rs_val & 0xFF, self.regs.flag_c());
+                        self.regs.set_flag_c(carry);
+                        value
                     }
-                    SBC => {
-                        let c = if self.regs.flag_c() { 0 } else { 1 };
-                        rd_val.wrapping_sub(rs_val).wrapping_sub(c)
+                    ADC | CMN => {
+                        let c = if op == ADC && self.regs.flag_c() {
+                            1
+                        } else {
+                            0
+                        };
+                        let res = rd_val.wrapping_add(rs_val).wrapping_add(c);
+                        // C: riporto oltre il bit 31
+                        self.regs
+                            .set_flag_c(rd_val as u64 + rs_val as u64 + c as u64 > 0xFFFFFFFF);
+                        self.regs
+                            .set_flag_v(((rd_val ^ res) & (rs_val ^ res) & 0x80000000) != 0);
+                        res
+                    }
+                    SBC | CMP | NEG => {
+                        // NEG Rd, Rs equivale a 0 - Rs
+                        let minuend = if op == NEG { 0 } else { rd_val };
+                        let borrow = if op == SBC && !self.regs.flag_c() {
+                            1
+                        } else {
+                            0
+                        };
+                        let res = minuend.wrapping_sub(rs_val).wrapping_sub(borrow);
+                        // C: 1 se non c'è stato prestito
+                        self.regs
+                            .set_flag_c(minuend as u64 >= rs_val as u64 + borrow as u64);
+                        self.regs
+                            .set_flag_v(((minuend ^ rs_val) & (minuend ^ res) & 0x80000000) != 0);
+                        res
                     }
-                    ROR => rd_val.rotate_right(rs_val & 0xFF),
                     TST => rd_val & rs_val,
-                    NEG => 0u32.wrapping_sub(rs_val),
-                    CMP => rd_val.wrapping_sub(rs_val),
-                    CMN => rd_val.wrapping_add(rs_val),
                     ORR => rd_val | rs_val,
+                    // Come per MULS in ARM, C viene "distrutto" sull'hardware:
+                    // qui resta invariato. V non modificato.
                     MUL => rd_val.wrapping_mul(rs_val),
                     BIC => rd_val & !rs_val,
                     MVN => !rs_val,
@@ -476,26 +569,25 @@ impl ARM7TDMI {
                 self.regs.set_flag_n((result & 0x80000000) != 0);
                 self.regs.set_flag_z(result == 0);
 
-                if op == CMP || op == CMN {
-                    if op == CMP {
-                        self.regs.set_flag_c(rd_val >= rs_val);
-                    } else {
-                        self.regs
-                            .set_flag_c((rd_val as u64 + rs_val as u64) > 0xFFFFFFFF);
-                    }
+                // MUL Rd, Rs equivale a MULS Rd, Rs, Rd: il moltiplicatore è Rd
+                // 1S + mI, con m = 1-4 in base ai byte significativi di Rd
+                if op == MUL {
+                    1 + multiply_internal_cycles(rd_val)
+                } else {
+                    1
                 }
-
-                1
             }
 
             ThumbInstruction::HiRegisterOps { op, h1, h2, rs, rd } => {
                 let rd_idx = (rd as usize) | (if h1 { 8 } else { 0 });
                 let rs_idx = (rs as usize) | (if h2 { 8 } else { 0 });
+                let rd_val = self.thumb_operand(rd_idx);
+                let rs_val = self.thumb_operand(rs_idx);
 
                 match op {
                     0 => {
                         // ADD
-                        let result = self.regs.r[rd_idx].wrapping_add(self.regs.r[rs_idx]);
+                        let result = rd_val.wrapping_add(rs_val);
                         if rd_idx == 15 {
                             self.regs.set_pc(result & !1);
                         } else {
@@ -504,21 +596,16 @@ impl ARM7TDMI {
                     }
                     1 => {
                         // CMP
-                        let result = self.regs.r[rd_idx].wrapping_sub(self.regs.r[rs_idx]);
+                        let result = rd_val.wrapping_sub(rs_val);
                         self.regs.set_flag_n((result & 0x80000000) != 0);
                         self.regs.set_flag_z(result == 0);
+                        self.regs.set_flag_c(rd_val >= rs_val);
                         self.regs
-                            .set_flag_c(self.regs.r[rd_idx] >= self.regs.r[rs_idx]);
-                        self.regs.set_flag_v(
-                            ((self.regs.r[rd_idx] ^ self.regs.r[rs_idx])
-                                & (self.regs.r[rd_idx] ^ result)
-                                & 0x80000000)
-                                != 0,
-                        );
+                            .set_flag_v(((rd_val ^ rs_val) & (rd_val ^ result) & 0x80000000) != 0);
                     }
                     2 => {
                         // MOV
-                        let value = self.regs.r[rs_idx];
+                        let value = rs_val;
                         if rd_idx == 15 {
                             self.regs.set_pc(value & !1);
                         } else {
@@ -527,7 +614,7 @@ impl ARM7TDMI {
                     }
                     3 => {
                         // BX
-                        let target = self.regs.r[rs_idx];
+                        let target = rs_val;
                         if (target & 1) != 0 {
                             self.regs.set_pc(target & !1);
                             self.regs.set_thumb(true);
@@ -543,9 +630,10 @@ impl ARM7TDMI {
             }
 
             ThumbInstruction::LoadPcRelative { rd, offset } => {
-                let pc = self.regs.pc() & !2;
+                // Base: indirizzo dell'istruzione + 4, allineato a word
+                let pc = self.thumb_operand(15) & !3;
                 let address = pc.wrapping_add((offset as u32) << 2);
-                let value = bus.read_word(address & !3);
+                let value = read_word_rotated(bus, address);
                 self.regs.r[rd as usize] = value;
                 3
             }
@@ -562,7 +650,7 @@ impl ARM7TDMI {
                     let value = if byte {
                         bus.read_byte(address) as u32
                     } else {
-                        bus.read_word(address & !3)
+                        read_word_rotated(bus, address)
                     };
                     self.regs.r[rd as usize] = value;
                 } else {
@@ -598,7 +686,7 @@ impl ARM7TDMI {
                     let value = if byte {
                         bus.read_byte(address) as u32
                     } else {
-                        bus.read_word(address & !3)
+                        read_word_rotated(bus, address)
                     };
                     self.regs.r[rd as usize] = value;
                 } else {
@@ -640,7 +728,7 @@ impl ARM7TDMI {
                 let sp = self.regs.r[13];
                 let address = sp.wrapping_add((offset as u32) << 2);
                 if load {
-                    self.regs.r[rd as usize] = bus.read_word(address & !3);
+                    self.regs.r[rd as usize] = read_word_rotated(bus, address);
                 } else {
                     bus.write_word(address & !3, self.regs.r[rd as usize]);
                 }
@@ -655,7 +743,7 @@ impl ARM7TDMI {
                 let base = if sp {
                     self.regs.r[13]
                 } else {
-                    self.regs.pc() & !2
+                    self.thumb_operand(15) & !2
                 };
                 self.regs.r[rd as usize] = base.wrapping_add((offset as u32) << 2);
                 1
@@ -675,6 +763,21 @@ impl ARM7TDMI {
                 let mut sp = self.regs.r[13];
                 let mut cycles = 0;
 
+                // Lista vuota (quirk ARMv4): trasferisce solo R15, ma SP
+                // si sposta di 0x40 come per 16 registri
+                if rlist == 0 && !r {
+                    if load {
+                        let pc = bus.read_word(sp);
+                        self.regs.set_pc(pc & !1);
+                        self.regs.r[13] = sp.wrapping_add(0x40);
+                    } else {
+                        sp = sp.wrapping_sub(0x40);
+                        bus.write_word(sp, self.thumb_operand(15).wrapping_add(2));
+                        self.regs.r[13] = sp;
+                    }
+                    return 1;
+                }
+
                 if load {
                     // POP
                     for i in 0..8 {
@@ -685,6 +788,8 @@ impl ARM7TDMI {
                         }
                     }
                     if r {
+                        // Su ARMv4 POP {PC} non cambia stato: il bit 0
+                        // viene ignorato e si resta in THUMB (serve BX)
                         let pc = bus.read_word(sp);
                         self.regs.set_pc(pc & !1);
                         sp = sp.wrapping_add(4);
@@ -733,7 +838,7 @@ impl ARM7TDMI {
             ThumbInstruction::ConditionalBranch { cond, offset } => {
                 let condition = crate::arm::Condition::from_opcode((cond as u32) << 28);
                 if condition.check(self.regs.cpsr) {
-                    let pc = self.regs.pc();
+                    let pc = self.thumb_operand(15);
                     let offset_ext = ((offset as i32) << 1) as u32;
                     self.regs.set_pc(pc.wrapping_add(offset_ext));
                     return 3;
@@ -742,7 +847,7 @@ impl ARM7TDMI {
             }
 
             ThumbInstruction::UnconditionalBranch { offset } => {
-                let pc = self.regs.pc();
+                let pc = self.thumb_operand(15);
                 let offset_val = ((offset as i32) << 1) as u32;
                 self.regs.set_pc(pc.wrapping_add(offset_val));
                 3
@@ -753,17 +858,18 @@ impl ARM7TDMI {
                 offset,
             } => {
                 if first_instruction {
-                    // Prima istruzione: LR = PC + (offset << 12)
-                    let pc = self.regs.pc();
+                    // Prima istruzione: LR = PC + (offset << 12), con PC = indirizzo + 4
+                    let pc = self.thumb_operand(15);
                     let mut off = offset as i32;
                     if off & 0x400 != 0 {
                         off |= !0x7FF;
                     }
                     self.regs.r[14] = pc.wrapping_add((off << 12) as u32);
                 } else {
-                    // Seconda istruzione: PC = LR + (offset << 1), LR = next instruction
+                    // Seconda istruzione: PC = LR + (offset << 1), LR = istruzione
+                    // successiva con il bit 0 alzato per tornare in THUMB
                     let lr = self.regs.r[14];
-                    let next_pc = self.regs.pc().wrapping_sub(2);
+                    let next_pc = self.regs.pc();
                     self.regs.set_pc(lr.wrapping_add((offset as u32) << 1));
                     self.regs.r[14] = next_pc | 1;
                 }
@@ -810,11 +916,26 @@ impl ARM7TDMI {
             }
 
             ThumbInstruction::Undefined => {
-                // Istruzione non riconosciuta
-                1
+                // Istruzione non riconosciuta: undefined instruction exception
+                self.raise_undefined();
+                3
             }
         }
     }
+    /// Valore di un registro usato come operando in THUMB
+    ///
+    /// Durante l'esecuzione R15 contiene già l'indirizzo dell'istruzione
+    /// successiva (istruzione + 2), ma per il prefetch il valore visibile
+    /// è istruzione + 4.
+    #[inline]
+    fn thumb_operand(&self, idx: usize) -> u32 {
+        if idx == 15 {
+            self.regs.pc().wrapping_add(2)
+        } else {
+            self.regs.r[idx]
+        }
+    }
+
     /// Gestisci interrupt IRQ
     pub fn request_interrupt(&mut self) {
         if self.regs.cpsr & (1 << 7) == 0 {
@@ -823,24 +944,76 @@ impl ARM7TDMI {
         }
     }
 
+    /// Entra nel handler IRQ (vettore 0x18)
+    ///
+    /// Chiamata tra due istruzioni: PC è già l'indirizzo della prossima
+    /// istruzione da eseguire, sia in ARM che in THUMB. LR = PC + 4, così
+    /// il handler ritorna con `SUBS PC, LR, #4` all'istruzione interrotta.
     fn handle_irq(&mut self) {
-        use crate::registers::Mode;
+        let lr = self.regs.pc().wrapping_add(4);
+        self.enter_exception(Mode::IRQ, 0x0000_0018, lr, false);
+    }
+
+    /// Gestisci interrupt FIQ
+    pub fn request_fiq(&mut self) {
+        if self.regs.cpsr & (1 << 6) == 0 {
+            // FIQ non disabilitati
+            let lr = self.regs.pc().wrapping_add(4);
+            self.enter_exception(Mode::FIQ, 0x0000_001C, lr, true);
+        }
+    }
+
+    /// Undefined instruction exception (vettore 0x04)
+    ///
+    /// Chiamata dopo il fetch: PC punta già all'istruzione successiva,
+    /// che è l'indirizzo di ritorno sia in ARM che in THUMB.
+    pub fn raise_undefined(&mut self) {
+        let lr = self.regs.pc();
+        self.enter_exception(Mode::Undefined, 0x0000_0004, lr, false);
+    }
+
+    /// Prefetch abort (vettore 0x0C)
+    ///
+    /// PC punta all'istruzione che non è stato possibile leggere;
+    /// il handler ritorna con `SUBS PC, LR, #4`.
+    pub fn raise_prefetch_abort(&mut self) {
+        let lr = self.regs.pc().wrapping_add(4);
+        self.enter_exception(Mode::Abort, 0x0000_000C, lr, false);
+    }
+
+    /// Data abort (vettore 0x10)
+    ///
+    /// Chiamata durante l'esecuzione dell'istruzione che ha causato l'abort
+    /// (PC = istruzione + 4 in ARM, + 2 in THUMB): LR = istruzione + 8,
+    /// il handler ritorna con `SUBS PC, LR, #8`.
+    pub fn raise_data_abort(&mut self) {
+        let offset = if self.regs.is_thumb() { 6 } else { 4 };
+        let lr = self.regs.pc().wrapping_add(offset);
+        self.enter_exception(Mode::Abort, 0x0000_0010, lr, false);
+    }
 
+    /// Entra in un'eccezione
+    ///
+    /// Salva CPSR nello SPSR della nuova modalità, imposta LR, disabilita
+    /// IRQ (e FIQ se richiesto), passa ad ARM state e salta al vettore.
+    fn enter_exception(&mut self, mode: Mode, vector: u32, lr: u32, disable_fiq: bool) {
         // Salva stato corrente
         let old_cpsr = self.regs.cpsr;
-        let pc = self.regs.pc();
 
-        // Passa a modalità IRQ
-        self.regs.change_mode(Mode::IRQ);
+        // Passa alla nuova modalità
+        self.regs.change_mode(mode);
         self.regs.set_spsr(old_cpsr);
-        self.regs.set_lr(pc.wrapping_add(4));
+        self.regs.set_lr(lr);
 
         // Disabilita IRQ e passa ad ARM state
         self.regs.cpsr |= 1 << 7; // Disable IRQ
+        if disable_fiq {
+            self.regs.cpsr |= 1 << 6; // Disable FIQ
+        }
         self.regs.cpsr &= !(1 << 5); // ARM state
 
-        // Salta al vettore IRQ
-        self.regs.set_pc(0x0000_0018);
+        // Salta al vettore
+        self.regs.set_pc(vector);
     }
 }
 
@@ -849,3 +1022,24 @@ impl Default for ARM7TDMI {
         Self::new()
     }
 }
+
+/// Cicli interni (m) di una moltiplicazione in base al moltiplicatore
+///
+/// Il moltiplicatore termina appena i byte alti restanti di Rs sono tutti
+/// 0 o tutti 1: m = 1 se i bit 31-8 sono uguali, 2 per i bit 31-16,
+/// 3 per i bit 31-24, altrimenti 4.
+fn multiply_internal_cycles(multiplier: u32) -> u32 {
+    let significant = |mask: u32| {
+        let high = multiplier & mask;
+        high == 0 || high == mask
+    };
+    if significant(0xFFFF_FF00) {
+        1
+    } else if significant(0xFFFF_0000) {
+        2
+    } else if significant(0xFF00_0000) {
+        3
+    } else {
+        4
+    }
+}
@@ -146,10 +146,10 @@ mod tests {
 
         cpu.step(&mut bus);
 
-        // PC iniziale 0, legge istruzione, incrementa a 4
+        // Base del salto = istruzione + 8 (prefetch)
         // Branch con offset 1 word = 4 byte
-        // Nuovo PC = 4 + 4 = 8
-        assert_eq!(cpu.regs.pc(), 8);
+        // Nuovo PC = 0 + 8 + 4 = 12
+        assert_eq!(cpu.regs.pc(), 12);
     }
 
     #[test]
@@ -375,7 +375,1007 @@ mod tests {
 
         cpu.step(&mut bus);
 
-        // PC dopo step = 2, branch offset 2*2 = 4, quindi PC finale = 2+4 = 6
-        assert_eq!(cpu.regs.pc(), 6);
+        // PC visibile = istruzione + 4, branch offset 2*2 = 4, quindi PC finale = 4+4 = 8
+        assert_eq!(cpu.regs.pc(), 8);
+    }
+
+    #[test]
+    fn test_ldr_unaligned_rotation() {
+        // LDR da indirizzo non allineato: word allineata ruotata di 8 * (addr & 3)
+        struct MemBus {
+            instructions: Vec<u32>,
+        }
+
+        impl MemoryBus for MemBus {
+            fn read_word(&mut self, addr: u32) -> u32 {
+                if addr < (self.instructions.len() * 4) as u32 {
+                    self.instructions[(addr / 4) as usize]
+                } else if addr == 0x0300_0000 {
+                    0x1122_3344
+                } else {
+                    0
+                }
+            }
+            fn read_byte(&mut self, _: u32) -> u8 {
+                0
+            }
+            fn read_halfword(&mut self, _: u32) -> u16 {
+                0
+            }
+            fn write_byte(&mut self, _: u32, _: u8) {}
+            fn write_halfword(&mut self, _: u32, _: u16) {}
+            fn write_word(&mut self, _: u32, _: u32) {}
+        }
+
+        let mut cpu = ARM7TDMI::new();
+        cpu.regs.r[1] = 0x0300_0002;
+
+        let mut bus = MemBus {
+            instructions: vec![0xE591_2000], // LDR R2, [R1]
+        };
+
+        cpu.step(&mut bus);
+        assert_eq!(cpu.regs.r[2], 0x3344_1122);
+    }
+
+    #[test]
+    fn test_thumb_ldr_unaligned_rotation() {
+        // THUMB: LDR R2, [R1, R0] con indirizzo base+2
+        struct MemBus;
+
+        impl MemoryBus for MemBus {
+            fn read_halfword(&mut self, _: u32) -> u16 {
+                0x580A // LDR R2, [R1, R0]
+            }
+            fn read_word(&mut self, addr: u32) -> u32 {
+                if addr == 0x0300_0000 {
+                    0xAABB_CCDD
+                } else {
+                    0
+                }
+            }
+            fn read_byte(&mut self, _: u32) -> u8 {
+                0
+            }
+            fn write_byte(&mut self, _: u32, _: u8) {}
+            fn write_halfword(&mut self, _: u32, _: u16) {}
+            fn write_word(&mut self, _: u32, _: u32) {}
+        }
+
+        let mut cpu = ARM7TDMI::new();
+        cpu.regs.set_thumb(true);
+        cpu.regs.r[0] = 2;
+        cpu.regs.r[1] = 0x0300_0000;
+
+        cpu.step(&mut MemBus);
+        assert_eq!(cpu.regs.r[2], 0xCCDD_AABB);
+    }
+
+    #[test]
+    fn test_request_fiq() {
+        use crate::registers::Mode;
+
+        let mut cpu = ARM7TDMI::new();
+        cpu.regs.set_pc(0x0800_0100);
+        cpu.regs.r[8] = 0x1111_1111;
+        cpu.regs.r[13] = 0x0300_7F00;
+        cpu.regs.r8_fiq = 0x2222_2222;
+        cpu.regs.r13_fiq = 0x0300_7E00;
+        let old_cpsr = cpu.regs.cpsr;
+
+        cpu.request_fiq();
+
+        // Registri banked FIQ attivi e vettore 0x1C
+        assert_eq!(cpu.regs.mode, Mode::FIQ);
+        assert_eq!(cpu.regs.r[8], 0x2222_2222);
+        assert_eq!(cpu.regs.r[13], 0x0300_7E00);
+        assert_eq!(cpu.regs.pc(), 0x1C);
+        assert_eq!(cpu.regs.lr(), 0x0800_0104);
+        assert_eq!(cpu.regs.spsr_fiq, old_cpsr);
+        assert_ne!(cpu.regs.cpsr & (1 << 7), 0);
+        assert_ne!(cpu.regs.cpsr & (1 << 6), 0);
+        assert!(!cpu.regs.is_thumb());
+
+        // FIQ disabilitati: nessun effetto
+        cpu.request_fiq();
+        assert_eq!(cpu.regs.pc(), 0x1C);
+
+        // Ritorno alla modalità precedente: registri utente ripristinati
+        cpu.regs.change_mode(Mode::System);
+        assert_eq!(cpu.regs.r[8], 0x1111_1111);
+        assert_eq!(cpu.regs.r[13], 0x0300_7F00);
+    }
+
+    #[test]
+    fn test_undefined_instruction_exception() {
+        use crate::registers::Mode;
+
+        struct TestBus;
+        impl MemoryBus for TestBus {
+            fn read_word(&mut self, _: u32) -> u32 {
+                0xE7F0_00F0 // Undefined (ARM)
+            }
+            fn read_halfword(&mut self, _: u32) -> u16 {
+                0xDE00 // Undefined (THUMB)
+            }
+            fn read_byte(&mut self, _: u32) -> u8 {
+                0
+            }
+            fn write_byte(&mut self, _: u32, _: u8) {}
+            fn write_halfword(&mut self, _: u32, _: u16) {}
+            fn write_word(&mut self, _: u32, _: u32) {}
+        }
+
+        // ARM: LR = istruzione + 4
+        let mut cpu = ARM7TDMI::new();
+        cpu.regs.set_pc(0x0800_0000);
+        let old_cpsr = cpu.regs.cpsr;
+        cpu.step(&mut TestBus);
+
+        assert_eq!(cpu.regs.mode, Mode::Undefined);
+        assert_eq!(cpu.regs.pc(), 0x04);
+        assert_eq!(cpu.regs.lr(), 0x0800_0004);
+        assert_eq!(cpu.regs.spsr_und, old_cpsr);
+        assert_ne!(cpu.regs.cpsr & (1 << 7), 0);
+
+        // Condizione 0xF (NV): undefined anche se l'istruzione è valida
+        struct NvBus;
+        impl MemoryBus for NvBus {
+            fn read_word(&mut self, _: u32) -> u32 {
+                0xF3A0_0042 // MOV r0, #0x42 con condizione NV
+            }
+            fn read_halfword(&mut self, _: u32) -> u16 {
+                0
+            }
+            fn read_byte(&mut self, _: u32) -> u8 {
+                0
+            }
+            fn write_byte(&mut self, _: u32, _: u8) {}
+            fn write_halfword(&mut self, _: u32, _: u16) {}
+            fn write_word(&mut self, _: u32, _: u32) {}
+        }
+
+        let mut cpu = ARM7TDMI::new();
+        cpu.regs.set_pc(0x0800_0000);
+        cpu.step(&mut NvBus);
+
+        assert_eq!(cpu.regs.r[0], 0);
+        assert_eq!(cpu.regs.mode, Mode::Undefined);
+        assert_eq!(cpu.regs.pc(), 0x04);
+        assert_eq!(cpu.regs.lr(), 0x0800_0004);
+
+        // THUMB: LR = istruzione + 2, ritorno in ARM state
+        let mut cpu = ARM7TDMI::new();
+        cpu.regs.set_thumb(true);
+        cpu.regs.set_pc(0x0800_0000);
+        cpu.step(&mut TestBus);
+
+        assert_eq!(cpu.regs.mode, Mode::Undefined);
+        assert_eq!(cpu.regs.pc(), 0x04);
+        assert_eq!(cpu.regs.lr(), 0x0800_0002);
+        assert!(!cpu.regs.is_thumb());
+        assert_ne!(cpu.regs.spsr_und & (1 << 5), 0);
+    }
+
+    #[test]
+    fn test_abort_vectors() {
+        use crate::registers::Mode;
+
+        let mut cpu = ARM7TDMI::new();
+        cpu.regs.set_pc(0x0800_0004);
+        cpu.raise_data_abort();
+        assert_eq!(cpu.regs.mode, Mode::Abort);
+        assert_eq!(cpu.regs.pc(), 0x10);
+        assert_eq!(cpu.regs.lr(), 0x0800_0008);
+
+        let mut cpu = ARM7TDMI::new();
+        cpu.regs.set_pc(0x0800_0000);
+        cpu.raise_prefetch_abort();
+        assert_eq!(cpu.regs.mode, Mode::Abort);
+        assert_eq!(cpu.regs.pc(), 0x0C);
+        assert_eq!(cpu.regs.lr(), 0x0800_0004);
+    }
+
+    #[test]
+    fn test_thumb_pc_operand_offset() {
+        struct TestBus {
+            instructions: Vec<u16>,
+        }
+
+        impl MemoryBus for TestBus {
+            fn read_halfword(&mut self, addr: u32) -> u16 {
+                self.instructions[(addr / 2) as usize]
+            }
+            fn read_word(&mut self, addr: u32) -> u32 {
+                addr | 0xAA00_0000 // Ritorna l'indirizzo letto
+            }
+            fn read_byte(&mut self, _: u32) -> u8 {
+                0
+            }
+            fn write_byte(&mut self, _: u32, _: u8) {}
+            fn write_halfword(&mut self, _: u32, _: u16) {}
+            fn write_word(&mut self, _: u32, _: u32) {}
+        }
+
+        let mut cpu = ARM7TDMI::new();
+        cpu.regs.set_thumb(true);
+        let mut bus = TestBus {
+            instructions: vec![
+                0xA001, // 0x00: ADD R0, PC, #4  -> (0x04 & !2) + 4 = 0x08
+                0xA101, // 0x02: ADD R1, PC, #4  -> (0x06 & !2) + 4 = 0x08
+                0x4A01, // 0x04: LDR R2, [PC, #4] -> [(0x08 & !2) + 4] = [0x0C]
+                0x467B, // 0x06: MOV R3, PC      -> 0x0A
+            ],
+        };
+
+        cpu.step(&mut bus);
+        assert_eq!(cpu.regs.r[0], 0x08);
+
+        cpu.step(&mut bus);
+        assert_eq!(cpu.regs.r[1], 0x08);
+
+        cpu.step(&mut bus);
+        assert_eq!(cpu.regs.r[2], 0xAA00_000C);
+
+        cpu.step(&mut bus);
+        assert_eq!(cpu.regs.r[3], 0x0A);
+    }
+
+    #[test]
+    fn test_thumb_ldr_literal_pool() {
+        struct RamBus {
+            memory: Vec<u8>,
+        }
+
+        impl MemoryBus for RamBus {
+            fn read_byte(&mut self, addr: u32) -> u8 {
+                self.memory[addr as usize]
+            }
+            fn read_halfword(&mut self, addr: u32) -> u16 {
+                let a = addr as usize;
+                u16::from_le_bytes([self.memory[a], self.memory[a + 1]])
+            }
+            fn read_word(&mut self, addr: u32) -> u32 {
+                let a = addr as usize;
+                u32::from_le_bytes(self.memory[a..a + 4].try_into().unwrap())
+            }
+            fn write_byte(&mut self, _: u32, _: u8) {}
+            fn write_halfword(&mut self, _: u32, _: u16) {}
+            fn write_word(&mut self, _: u32, _: u32) {}
+        }
+
+        let mut memory = vec![0u8; 0x20];
+        // 0x00: LDR R0, [PC, #4] -> [(0x00 + 4) + 4] = [0x08]
+        memory[0x00..0x02].copy_from_slice(&0x4801u16.to_le_bytes());
+        // 0x02: LDR R1, [PC, #4] -> [((0x02 + 4) & !3) + 4] = [0x08]
+        memory[0x02..0x04].copy_from_slice(&0x4901u16.to_le_bytes());
+        // 0x04: LDR R2, [PC, #8] -> [(0x04 + 4) + 8] = [0x10]
+        memory[0x04..0x06].copy_from_slice(&0x4A02u16.to_le_bytes());
+        // Literal pool
+        memory[0x08..0x0C].copy_from_slice(&0x1234_5678u32.to_le_bytes());
+        memory[0x0C..0x10].copy_from_slice(&0xDEAD_BEEFu32.to_le_bytes());
+        memory[0x10..0x14].copy_from_slice(&0xCAFE_F00Du32.to_le_bytes());
+
+        let mut cpu = ARM7TDMI::new();
+        cpu.regs.set_thumb(true);
+        let mut bus = RamBus { memory };
+
+        cpu.step(&mut bus);
+        cpu.step(&mut bus);
+        cpu.step(&mut bus);
+        assert_eq!(cpu.regs.r[0], 0x1234_5678);
+        assert_eq!(cpu.regs.r[1], 0x1234_5678);
+        assert_eq!(cpu.regs.r[2], 0xCAFE_F00D);
+        assert_eq!(cpu.regs.pc(), 0x06);
+    }
+
+    struct BlockBus {
+        memory: std::collections::HashMap<u32, u32>,
+        instructions: Vec<u32>,
+    }
+
+    impl MemoryBus for BlockBus {
+        fn read_word(&mut self, addr: u32) -> u32 {
+            if addr < (self.instructions.len() * 4) as u32 {
+                self.instructions[(addr / 4) as usize]
+            } else {
+                *self.memory.get(&(addr & !3)).unwrap_or(&0)
+            }
+        }
+        fn write_word(&mut self, addr: u32, value: u32) {
+            self.memory.insert(addr & !3, value);
+        }
+        fn read_byte(&mut self, _: u32) -> u8 {
+            0
+        }
+        fn read_halfword(&mut self, _: u32) -> u16 {
+            0
+        }
+        fn write_byte(&mut self, _: u32, _: u8) {}
+        fn write_halfword(&mut self, _: u32, _: u16) {}
+    }
+
+    #[test]
+    fn test_ldm_s_bit_restores_cpsr() {
+        use crate::registers::Mode;
+
+        let mut cpu = ARM7TDMI::new();
+        cpu.regs.r[13] = 0x0300_7F00; // SP_sys
+        cpu.regs.change_mode(Mode::IRQ);
+        cpu.regs.r[13] = 0x0300_7FA0; // SP_irq
+        cpu.regs.spsr_irq = 0x8000_001F; // System, flag N
+
+        let mut bus = BlockBus {
+            memory: std::collections::HashMap::new(),
+            instructions: vec![0xE8FD_800F], // LDMFD SP!, {R0-R3, PC}^
+        };
+        for (i, value) in [1, 2, 3, 4, 0x0800_0100].iter().enumerate() {
+            bus.memory.insert(0x0300_7FA0 + i as u32 * 4, *value);
+        }
+
+        cpu.step(&mut bus);
+
+        assert_eq!(&cpu.regs.r[0..4], &[1, 2, 3, 4]);
+        assert_eq!(cpu.regs.pc(), 0x0800_0100);
+        assert_eq!(cpu.regs.cpsr, 0x8000_001F);
+        assert_eq!(cpu.regs.mode, Mode::System);
+        assert_eq!(cpu.regs.r[13], 0x0300_7F00);
+        assert_eq!(cpu.regs.r13_irq, 0x0300_7FB4);
+    }
+
+    #[test]
+    fn test_irq_return_resumes_next_instruction() {
+        use crate::registers::Mode;
+
+        // ARM: IRQ dopo la prima istruzione, il handler a 0x18 ritorna subito
+        let mut instructions = vec![0; 7];
+        instructions[0] = 0xE3A0_0001; // MOV R0, #1
+        instructions[1] = 0xE3A0_1002; // MOV R1, #2
+        instructions[6] = 0xE25E_F004; // SUBS PC, LR, #4
+        let mut bus = BlockBus {
+            memory: std::collections::HashMap::new(),
+            instructions,
+        };
+
+        let mut cpu = ARM7TDMI::new();
+        cpu.regs.set_flag_n(true);
+        let old_cpsr = cpu.regs.cpsr;
+
+        cpu.step(&mut bus);
+        cpu.request_interrupt();
+        assert_eq!(cpu.regs.mode, Mode::IRQ);
+        assert_eq!(cpu.regs.pc(), 0x18);
+        assert_eq!(cpu.regs.lr(), 0x08);
+
+        cpu.step(&mut bus);
+        assert_eq!(cpu.regs.pc(), 0x04);
+        assert_eq!(cpu.regs.cpsr, old_cpsr);
+        assert_eq!(cpu.regs.mode, Mode::System);
+
+        cpu.step(&mut bus);
+        assert_eq!(cpu.regs.r[0], 1);
+        assert_eq!(cpu.regs.r[1], 2);
+        assert_eq!(cpu.regs.pc(), 0x08);
+
+        // THUMB: stesso LR = prossima istruzione + 4, ritorno in THUMB state
+        struct ThumbBus;
+        impl MemoryBus for ThumbBus {
+            fn read_word(&mut self, _: u32) -> u32 {
+                0xE25E_F004 // SUBS PC, LR, #4 (handler)
+            }
+            fn read_halfword(&mut self, _: u32) -> u16 {
+                0x3001 // ADDS R0, #1
+            }
+            fn read_byte(&mut self, _: u32) -> u8 {
+                0
+            }
+            fn write_byte(&mut self, _: u32, _: u8) {}
+            fn write_halfword(&mut self, _: u32, _: u16) {}
+            fn write_word(&mut self, _: u32, _: u32) {}
+        }
+
+        let mut cpu = ARM7TDMI::new();
+        cpu.regs.set_thumb(true);
+        cpu.regs.set_pc(0x0800_0000);
+
+        cpu.step(&mut ThumbBus);
+        cpu.request_interrupt();
+        assert_eq!(cpu.regs.lr(), 0x0800_0006);
+        assert!(!cpu.regs.is_thumb());
+
+        cpu.step(&mut ThumbBus);
+        assert!(cpu.regs.is_thumb());
+        assert_eq!(cpu.regs.pc(), 0x0800_0002);
+
+        cpu.step(&mut ThumbBus);
+        assert_eq!(cpu.regs.r[0], 2);
+        assert_eq!(cpu.regs.pc(), 0x0800_0004);
+    }
+
+    #[test]
+    fn test_stm_s_bit_uses_user_bank() {
+        use crate::registers::Mode;
+
+        let mut cpu = ARM7TDMI::new();
+        cpu.regs.r[13] = 0x0000_AAAA; // SP_sys
+        cpu.regs.r[14] = 0x0000_BBBB; // LR_sys
+        cpu.regs.change_mode(Mode::IRQ);
+        cpu.regs.r[13] = 0x1111;
+        cpu.regs.r[14] = 0x2222;
+        cpu.regs.r[0] = 0x0300_0000;
+
+        let mut bus = BlockBus {
+            memory: std::collections::HashMap::new(),
+            instructions: vec![0xE8C0_6000], // STMIA R0, {R13, R14}^
+        };
+
+        cpu.step(&mut bus);
+
+        assert_eq!(bus.memory.get(&0x0300_0000), Some(&0x0000_AAAA));
+        assert_eq!(bus.memory.get(&0x0300_0004), Some(&0x0000_BBBB));
+        assert_eq!(cpu.regs.mode, Mode::IRQ);
+    }
+
+    #[test]
+    fn test_stmdb_address_order() {
+        let mut cpu = ARM7TDMI::new();
+        cpu.regs.r[0] = 0xAAAA;
+        cpu.regs.r[1] = 0xBBBB;
+        cpu.regs.r[13] = 0x0300_0010;
+
+        let mut bus = BlockBus {
+            memory: std::collections::HashMap::new(),
+            instructions: vec![0xE92D_0003], // STMDB SP!, {R0, R1}
+        };
+
+        cpu.step(&mut bus);
+
+        assert_eq!(bus.memory.get(&0x0300_0008), Some(&0xAAAA));
+        assert_eq!(bus.memory.get(&0x0300_000C), Some(&0xBBBB));
+        assert_eq!(cpu.regs.r[13], 0x0300_0008);
+    }
+
+    #[test]
+    fn test_trace_reports_state_before_each_instruction() {
+        use crate::cpu::TraceEntry;
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let mut cpu = ARM7TDMI::new();
+        let mut bus = BlockBus {
+            memory: std::collections::HashMap::new(),
+            instructions: vec![
+                0xE3A0_0001, // MOV R0, #1
+                0xE280_1002, // ADD R1, R0, #2
+                0xE1A0_2081, // MOV R2, R1, LSL #1
+            ],
+        };
+
+        let entries: Rc<RefCell<Vec<TraceEntry>>> = Rc::default();
+        let sink = Rc::clone(&entries);
+        cpu.set_trace(Some(Box::new(move |entry| sink.borrow_mut().push(*entry))));
+
+        for _ in 0..3 {
+            cpu.step(&mut bus);
+        }
+
+        // Senza callback non vengono prodotte altre entry
+        cpu.set_trace(None);
+        cpu.step(&mut bus);
+
+        let entries = entries.borrow();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(
+            entries.iter().map(|e| e.pc).collect::<Vec<_>>(),
+            vec![0, 4, 8]
+        );
+        assert_eq!(entries[1].opcode, 0xE280_1002);
+        assert!(!entries[0].thumb);
+
+        // Registri prima dell'esecuzione di ogni istruzione
+        assert_eq!(&entries[0].regs[0..3], &[0, 0, 0]);
+        assert_eq!(&entries[1].regs[0..3], &[1, 0, 0]);
+        assert_eq!(&entries[2].regs[0..3], &[1, 3, 0]);
+        assert_eq!(entries[2].regs[15], 8);
+        assert_eq!(entries[2].cpsr, cpu.regs.cpsr);
+        assert_eq!(cpu.regs.r[2], 6);
+        assert!(entries[0].to_string().ends_with("| 00000000: E3A00001"));
+    }
+
+    #[test]
+    fn test_multiply_cycles_depend_on_rs() {
+        struct TestBus {
+            instructions: Vec<u32>,
+        }
+
+        impl MemoryBus for TestBus {
+            fn read_word(&mut self, addr: u32) -> u32 {
+                self.instructions[(addr / 4) as usize]
+            }
+            fn read_halfword(&mut self, _: u32) -> u16 {
+                0
+            }
+            fn read_byte(&mut self, _: u32) -> u8 {
+                0
+            }
+            fn write_byte(&mut self, _: u32, _: u8) {}
+            fn write_halfword(&mut self, _: u32, _: u16) {}
+            fn write_word(&mut self, _: u32, _: u32) {}
+        }
+
+        let mut bus = TestBus {
+            instructions: vec![
+                0xE000_0291, // MUL R0, R1, R2
+                0xE000_0291, // MUL R0, R1, R2
+                0xE020_3291, // MLA R0, R1, R2, R3
+                0xE010_0291, // MULS R0, R1, R2
+            ],
+        };
+
+        let mut cpu = ARM7TDMI::new();
+        cpu.regs.r[1] = 7;
+
+        // Rs piccolo: un solo ciclo interno
+        cpu.regs.r[2] = 3;
+        let small = cpu.step(&mut bus);
+        assert_eq!(cpu.regs.r[0], 21);
+
+        // Rs con tutti i byte significativi: quattro cicli interni
+        cpu.regs.r[2] = 0x1234_5678;
+        let large = cpu.step(&mut bus);
+        assert!(small < large);
+        assert_eq!(large - small, 3);
+
+        // Rs negativo piccolo (bit alti tutti a 1) costa come uno positivo
+        cpu.regs.r[2] = 0xFFFF_FFFE;
+        cpu.regs.r[3] = 100;
+        let mla = cpu.step(&mut bus);
+        assert_eq!(mla, small + 1);
+        assert_eq!(cpu.regs.r[0], 86);
+
+        // MULS: N/Z aggiornati, C invariato
+        cpu.regs.r[2] = 0;
+        cpu.regs.set_flag_c(true);
+        cpu.step(&mut bus);
+        assert!(cpu.regs.flag_z());
+        assert!(cpu.regs.flag_c());
+    }
+
+    #[test]
+    fn test_thumb_shift_amounts_0_32_33() {
+        struct TestBus {
+            instructions: Vec<u16>,
+        }
+
+        impl MemoryBus for TestBus {
+            fn read_halfword(&mut self, addr: u32) -> u16 {
+                self.instructions[(addr / 2) as usize]
+            }
+            fn read_byte(&mut self, _: u32) -> u8 {
+                0
+            }
+            fn read_word(&mut self, _: u32) -> u32 {
+                0
+            }
+            fn write_byte(&mut self, _: u32, _: u8) {}
+            fn write_halfword(&mut self, _: u32, _: u16) {}
+            fn write_word(&mut self, _: u32, _: u32) {}
+        }
+
+        let mut bus = TestBus {
+            instructions: vec![
+                0x4088, // LSL R0, R1
+                0x40C8, // LSR R0, R1
+                0x40C8, // LSR R0, R1
+                0x4088, // LSL R0, R1
+                0x4088, // LSL R0, R1
+                0x4108, // ASR R0, R1
+                0x0808, // LSR R0, R1, #0 (= #32)
+                0x0008, // LSL R0, R1, #0
+            ],
+        };
+
+        let mut cpu = ARM7TDMI::new();
+        cpu.regs.set_thumb(true);
+
+        // Shift di 0: valore e carry invariati
+        cpu.regs.r[0] = 0x8000_0001;
+        cpu.regs.r[1] = 0;
+        cpu.regs.set_flag_c(true);
+        cpu.step(&mut bus);
+        assert_eq!(cpu.regs.r[0], 0x8000_0001);
+        assert!(cpu.regs.flag_c());
+
+        // LSR #32: risultato 0, carry = bit 31
+        cpu.regs.r[1] = 32;
+        cpu.step(&mut bus);
+        assert_eq!(cpu.regs.r[0], 0);
+        assert!(cpu.regs.flag_c());
+        assert!(cpu.regs.flag_z());
+
+        // LSR #33: risultato 0, carry 0
+        cpu.regs.r[0] = 0x8000_0000;
+        cpu.regs.r[1] = 33;
+        cpu.step(&mut bus);
+        assert_eq!(cpu.regs.r[0], 0);
+        assert!(!cpu.regs.flag_c());
+
+        // LSL #32: risultato 0, carry = bit 0
+        cpu.regs.r[0] = 1;
+        cpu.regs.r[1] = 32;
+        cpu.step(&mut bus);
+        assert_eq!(cpu.regs.r[0], 0);
+        assert!(cpu.regs.flag_c());
+
+        // LSL #33: risultato 0, carry 0
+        cpu.regs.r[0] = 1;
+        cpu.regs.r[1] = 33;
+        cpu.step(&mut bus);
+        assert_eq!(cpu.regs.r[0], 0);
+        assert!(!cpu.regs.flag_c());
+
+        // ASR #33: tutti i bit uguali al segno, carry = bit 31
+        cpu.regs.r[0] = 0x8000_0000;
+        cpu.step(&mut bus);
+        assert_eq!(cpu.regs.r[0], 0xFFFF_FFFF);
+        assert!(cpu.regs.flag_c());
+        assert!(cpu.regs.flag_n());
+
+        // Immediato: LSR #0 significa LSR #32
+        cpu.regs.r[1] = 0x8000_0000;
+        cpu.regs.set_flag_c(false);
+        cpu.step(&mut bus);
+        assert_eq!(cpu.regs.r[0], 0);
+        assert!(cpu.regs.flag_c());
+
+        // Immediato: LSL #0 copia il valore senza toccare il carry
+        cpu.regs.r[1] = 0x1234;
+        cpu.step(&mut bus);
+        assert_eq!(cpu.regs.r[0], 0x1234);
+        assert!(cpu.regs.flag_c());
+    }
+
+    #[test]
+    fn test_thumb_adc_sbc_neg_flags() {
+        struct TestBus {
+            instructions: Vec<u16>,
+        }
+
+        impl MemoryBus for TestBus {
+            fn read_halfword(&mut self, addr: u32) -> u16 {
+                self.instructions[(addr / 2) as usize]
+            }
+            fn read_byte(&mut self, _: u32) -> u8 {
+                0
+            }
+            fn read_word(&mut self, _: u32) -> u32 {
+                0
+            }
+            fn write_byte(&mut self, _: u32, _: u8) {}
+            fn write_halfword(&mut self, _: u32, _: u16) {}
+            fn write_word(&mut self, _: u32, _: u32) {}
+        }
+
+        let mut bus = TestBus {
+            instructions: vec![
+                0x4148, // ADC R0, R1
+                0x4190, // SBC R0, R2
+                0x4248, // NEG R0, R1
+                0x4248, // NEG R0, R1
+            ],
+        };
+
+        let mut cpu = ARM7TDMI::new();
+        cpu.regs.set_thumb(true);
+
+        // ADC: 0xFFFFFFFF + 1 = 0 con riporto
+        cpu.regs.r[0] = 0xFFFF_FFFF;
+        cpu.regs.r[1] = 1;
+        cpu.regs.set_flag_c(false);
+        cpu.step(&mut bus);
+        assert_eq!(cpu.regs.r[0], 0);
+        assert!(cpu.regs.flag_z());
+        assert!(cpu.regs.flag_c());
+        assert!(!cpu.regs.flag_v());
+
+        // SBC con C=1 (nessun prestito precedente): 0 - 1 presta, C=0
+        cpu.regs.r[2] = 1;
+        cpu.step(&mut bus);
+        assert_eq!(cpu.regs.r[0], 0xFFFF_FFFF);
+        assert!(!cpu.regs.flag_c());
+        assert!(cpu.regs.flag_n());
+
+        // NEG 0x80000000: overflow con segno
+        cpu.regs.r[1] = 0x8000_0000;
+        cpu.step(&mut bus);
+        assert_eq!(cpu.regs.r[0], 0x8000_0000);
+        assert!(cpu.regs.flag_v());
+        assert!(!cpu.regs.flag_c());
+
+        // NEG 0: nessun prestito, C=1
+        cpu.regs.r[1] = 0;
+        cpu.step(&mut bus);
+        assert_eq!(cpu.regs.r[0], 0);
+        assert!(cpu.regs.flag_c());
+        assert!(!cpu.regs.flag_v());
+    }
+
+    #[test]
+    fn test_thumb_mul_result_and_cycles() {
+        struct TestBus {
+            instructions: Vec<u16>,
+        }
+
+        impl MemoryBus for TestBus {
+            fn read_halfword(&mut self, addr: u32) -> u16 {
+                self.instructions[(addr / 2) as usize]
+            }
+            fn read_byte(&mut self, _: u32) -> u8 {
+                0
+            }
+            fn read_word(&mut self, _: u32) -> u32 {
+                0
+            }
+            fn write_byte(&mut self, _: u32, _: u8) {}
+            fn write_halfword(&mut self, _: u32, _: u16) {}
+            fn write_word(&mut self, _: u32, _: u32) {}
+        }
+
+        let mut bus = TestBus {
+            instructions: vec![
+                0x4348, // MUL R0, R1
+                0x4348, // MUL R0, R1
+            ],
+        };
+
+        let mut cpu = ARM7TDMI::new();
+        cpu.regs.set_thumb(true);
+
+        cpu.regs.r[0] = 6;
+        cpu.regs.r[1] = 7;
+        cpu.regs.set_flag_c(true);
+        let small = cpu.step(&mut bus);
+        assert_eq!(cpu.regs.r[0], 42);
+        assert!(small > 1);
+        assert!(cpu.regs.flag_c());
+
+        // Moltiplicatore (Rd) grande: più cicli interni
+        cpu.regs.r[0] = 0x0123_4567;
+        cpu.regs.r[1] = 0;
+        let large = cpu.step(&mut bus);
+        assert_eq!(cpu.regs.r[0], 0);
+        assert!(cpu.regs.flag_z());
+        assert_eq!(large, small + 3);
+    }
+
+    #[test]
+    fn test_arm_adds_carry() {
+        struct TestBus {
+            instructions: Vec<u32>,
+        }
+
+        impl MemoryBus for TestBus {
+            fn read_word(&mut self, addr: u32) -> u32 {
+                self.instructions[(addr / 4) as usize]
+            }
+            fn read_halfword(&mut self, _: u32) -> u16 {
+                0
+            }
+            fn read_byte(&mut self, _: u32) -> u8 {
+                0
+            }
+            fn write_byte(&mut self, _: u32, _: u8) {}
+            fn write_halfword(&mut self, _: u32, _: u16) {}
+            fn write_word(&mut self, _: u32, _: u32) {}
+        }
+
+        let mut bus = TestBus {
+            instructions: vec![
+                0xE090_2001, // ADDS R2, R0, R1
+                0xE090_2001, // ADDS R2, R0, R1
+                0xE090_2001, // ADDS R2, R0, R1
+            ],
+        };
+        let mut cpu = ARM7TDMI::new();
+
+        // 0xFFFFFFFF + 1: risultato 0 con carry
+        cpu.regs.r[0] = 0xFFFF_FFFF;
+        cpu.regs.r[1] = 1;
+        cpu.step(&mut bus);
+        assert_eq!(cpu.regs.r[2], 0);
+        assert!(cpu.regs.flag_c());
+        assert!(cpu.regs.flag_z());
+        assert!(!cpu.regs.flag_v());
+
+        // 1 + 1: nessun carry
+        cpu.regs.r[0] = 1;
+        cpu.step(&mut bus);
+        assert_eq!(cpu.regs.r[2], 2);
+        assert!(!cpu.regs.flag_c());
+
+        // 0xFFFFFFFF + 0: somma esatta a 32 bit, nessun carry
+        cpu.regs.r[0] = 0xFFFF_FFFF;
+        cpu.regs.r[1] = 0;
+        cpu.step(&mut bus);
+        assert_eq!(cpu.regs.r[2], 0xFFFF_FFFF);
+        assert!(!cpu.regs.flag_c());
+        assert!(cpu.regs.flag_n());
+    }
+
+    #[test]
+    fn test_banked_sp_write_keeps_user_sp() {
+        use crate::registers::Mode;
+
+        let mut cpu = ARM7TDMI::new();
+        cpu.regs.change_mode(Mode::User);
+        cpu.regs.r[13] = 0x0300_7F00;
+
+        // SP dell'IRQ scritto da User: lo stack visibile non cambia
+        cpu.regs.write_banked(Mode::IRQ, 13, 0x0300_7FA0);
+        assert_eq!(cpu.regs.sp(), 0x0300_7F00);
+        assert_eq!(cpu.regs.read_banked(Mode::IRQ, 13), 0x0300_7FA0);
+        assert_eq!(cpu.regs.read_banked(Mode::System, 13), 0x0300_7F00);
+
+        // Entrando in IRQ si vede il nuovo SP, e quello User resta salvato
+        cpu.regs.change_mode(Mode::IRQ);
+        assert_eq!(cpu.regs.sp(), 0x0300_7FA0);
+        assert_eq!(cpu.regs.read_banked(Mode::User, 13), 0x0300_7F00);
+
+        // R8 FIQ separato da quello degli altri modi
+        cpu.regs.r[8] = 8;
+        cpu.regs.write_banked(Mode::FIQ, 8, 0x88);
+        assert_eq!(cpu.regs.read_banked(Mode::Supervisor, 8), 8);
+
+        let snapshot = cpu.dump_registers();
+        assert_eq!(snapshot.mode, Mode::IRQ);
+        assert_eq!(snapshot.visible[13], 0x0300_7FA0);
+        assert_eq!(snapshot.irq[0], 0x0300_7FA0);
+        assert_eq!(snapshot.usr[5], 0x0300_7F00);
+        assert_eq!(snapshot.usr[0], 8);
+        assert_eq!(snapshot.fiq[0], 0x88);
+    }
+
+    #[test]
+    fn test_arm_branch_link() {
+        const NOP: u32 = 0xE1A0_0000; // MOV r0, r0
+
+        let mut instructions = vec![NOP; 0x20];
+        instructions[0x04 / 4] = 0xEB00_000D; // 0x04: BL 0x40
+        instructions[0x40 / 4] = 0xEBFF_FFF2; // 0x40: BL 0x10
+        instructions[0x44 / 4] = 0xEAFF_FFFE; // 0x44: B . (su sé stessa)
+        let mut bus = BlockBus {
+            memory: std::collections::HashMap::new(),
+            instructions,
+        };
+
+        let mut cpu = ARM7TDMI::new();
+        cpu.step(&mut bus); // NOP
+        cpu.step(&mut bus);
+        assert_eq!(cpu.regs.pc(), 0x40);
+        assert_eq!(cpu.regs.r[14], 0x08);
+
+        // All'indietro
+        cpu.step(&mut bus);
+        assert_eq!(cpu.regs.pc(), 0x10);
+        assert_eq!(cpu.regs.r[14], 0x44);
+
+        cpu.regs.set_pc(0x44);
+        cpu.step(&mut bus);
+        assert_eq!(cpu.regs.pc(), 0x44);
+    }
+
+    #[test]
+    fn test_thumb_long_branch_link() {
+        struct TestBus {
+            memory: Vec<u16>,
+        }
+
+        impl MemoryBus for TestBus {
+            fn read_halfword(&mut self, addr: u32) -> u16 {
+                self.memory[(addr / 2) as usize]
+            }
+            fn read_byte(&mut self, _: u32) -> u8 {
+                0
+            }
+            fn read_word(&mut self, _: u32) -> u32 {
+                0
+            }
+            fn write_byte(&mut self, _: u32, _: u8) {}
+            fn write_halfword(&mut self, _: u32, _: u16) {}
+            fn write_word(&mut self, _: u32, _: u32) {}
+        }
+
+        // Coppia BL con offset a 23 bit relativo a indirizzo + 4
+        fn bl(from: u32, to: u32) -> [u16; 2] {
+            let offset = to.wrapping_sub(from + 4) as i32;
+            [
+                0xF000 | ((offset >> 12) & 0x7FF) as u16,
+                0xF800 | ((offset >> 1) & 0x7FF) as u16,
+            ]
+        }
+
+        let mut memory = vec![0u16; 0x800];
+        // 0x100: BL 0x400 (in avanti)
+        memory[0x80..0x82].copy_from_slice(&bl(0x100, 0x400));
+        // 0x400: BL 0x040 (all'indietro)
+        memory[0x200..0x202].copy_from_slice(&bl(0x400, 0x040));
+
+        let mut cpu = ARM7TDMI::new();
+        cpu.regs.set_thumb(true);
+        cpu.regs.set_pc(0x100);
+        let mut bus = TestBus { memory };
+
+        cpu.step(&mut bus);
+        cpu.step(&mut bus);
+        assert_eq!(cpu.regs.pc(), 0x400);
+        assert_eq!(cpu.regs.r[14], 0x105);
+
+        cpu.step(&mut bus);
+        cpu.step(&mut bus);
+        assert_eq!(cpu.regs.pc(), 0x040);
+        assert_eq!(cpu.regs.r[14], 0x405);
+    }
+
+    #[test]
+    fn test_thumb_push_pop_pc_and_empty_list() {
+        struct RamBus {
+            memory: Vec<u8>,
+        }
+
+        impl MemoryBus for RamBus {
+            fn read_byte(&mut self, addr: u32) -> u8 {
+                self.memory[addr as usize]
+            }
+            fn read_halfword(&mut self, addr: u32) -> u16 {
+                let a = addr as usize;
+                u16::from_le_bytes([self.memory[a], self.memory[a + 1]])
+            }
+            fn read_word(&mut self, addr: u32) -> u32 {
+                let a = addr as usize;
+                u32::from_le_bytes(self.memory[a..a + 4].try_into().unwrap())
+            }
+            fn write_byte(&mut self, addr: u32, value: u8) {
+                self.memory[addr as usize] = value;
+            }
+            fn write_halfword(&mut self, addr: u32, value: u16) {
+                let a = addr as usize;
+                self.memory[a..a + 2].copy_from_slice(&value.to_le_bytes());
+            }
+            fn write_word(&mut self, addr: u32, value: u32) {
+                let a = addr as usize;
+                self.memory[a..a + 4].copy_from_slice(&value.to_le_bytes());
+            }
+        }
+
+        let mut bus = RamBus {
+            memory: vec![0u8; 0x400],
+        };
+        bus.write_halfword(0x000, 0xBD00); // POP {PC}
+        bus.write_halfword(0x100, 0xBC00); // POP {} (lista vuota)
+        bus.write_halfword(0x180, 0xB400); // PUSH {} (lista vuota)
+        bus.write_word(0x200, 0x0000_0100);
+        bus.write_word(0x204, 0x0000_0181);
+
+        let mut cpu = ARM7TDMI::new();
+        cpu.regs.set_thumb(true);
+        cpu.regs.r[13] = 0x200;
+
+        // POP {PC} con bit 0 a zero: su ARMv4 nessun cambio di stato
+        cpu.step(&mut bus);
+        assert_eq!(cpu.regs.pc(), 0x100);
+        assert!(cpu.regs.is_thumb());
+        assert_eq!(cpu.regs.r[13], 0x204);
+
+        // POP {}: carica solo PC, SP avanza di 0x40
+        cpu.step(&mut bus);
+        assert_eq!(cpu.regs.pc(), 0x180);
+        assert!(cpu.regs.is_thumb());
+        assert_eq!(cpu.regs.r[13], 0x244);
+
+        // PUSH {}: salva PC + 6, SP scende di 0x40
+        cpu.step(&mut bus);
+        assert_eq!(cpu.regs.r[13], 0x204);
+        assert_eq!(bus.read_word(0x204), 0x186);
+        assert_eq!(cpu.regs.pc(), 0x182);
     }
 }
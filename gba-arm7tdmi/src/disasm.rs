@@ -0,0 +1,524 @@
+// Disassembler ARM/THUMB
+//
+// Converte le istruzioni in testo leggibile (mnemonici in minuscolo,
+// sintassi UAL semplificata), per il debugger e per i log di trace.
+// `pc` è l'indirizzo dell'istruzione: serve a calcolare i target dei
+// branch e dei load PC-relative (PC letto = pc + 8 in ARM, pc + 4 in THUMB).
+//
+// Le forme che il decoder ARM non distingue ancora (MRS/MSR, transfer di
+// halfword, SWP, multiply long) vengono riconosciute direttamente dai bit.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::arm::{decode_arm, ArmInstruction};
+use crate::thumb::{decode_thumb, ThumbInstruction};
+
+/// Suffissi delle condizioni (AL non ha suffisso)
+const CONDITIONS: [&str; 16] = [
+    "eq", "ne", "cs", "cc", "mi", "pl", "vs", "vc", "hi", "ls", "ge", "lt", "gt", "le", "", "nv",
+];
+
+/// Mnemonici Data Processing (indice = opcode)
+const DATA_PROCESSING: [&str; 16] = [
+    "and", "eor", "sub", "rsb", "add", "adc", "sbc", "rsc", "tst", "teq", "cmp", "cmn", "orr",
+    "mov", "bic", "mvn",
+];
+
+/// Mnemonici THUMB Format 4 (indice = op)
+const THUMB_ALU: [&str; 16] = [
+    "and", "eor", "lsl", "lsr", "asr", "adc", "sbc", "ror", "tst", "neg", "cmp", "cmn", "orr",
+    "mul", "bic", "mvn",
+];
+
+const SHIFTS: [&str; 4] = ["lsl", "lsr", "asr", "ror"];
+
+/// Nome di un registro (r13-r15 con gli alias sp/lr/pc)
+fn reg(r: u32) -> String {
+    match r & 0xF {
+        13 => "sp".to_string(),
+        14 => "lr".to_string(),
+        15 => "pc".to_string(),
+        r => format!("r{}", r),
+    }
+}
+
+/// Immediato: decimale se piccolo, altrimenti esadecimale
+fn imm(value: u32) -> String {
+    if value < 10 {
+        format!("#{}", value)
+    } else {
+        format!("#0x{:x}", value)
+    }
+}
+
+/// Offset con segno per gli indirizzamenti
+fn signed_imm(add: bool, value: u32) -> String {
+    if add {
+        imm(value)
+    } else {
+        format!("#-{}", &imm(value)[1..])
+    }
+}
+
+/// Lista di registri, es. "{r0, r1, lr}"
+fn reg_list(list: u16) -> String {
+    let regs: Vec<String> = (0..16)
+        .filter(|i| list & (1 << i) != 0)
+        .map(|i| reg(i as u32))
+        .collect();
+    format!("{{{}}}", regs.join(", "))
+}
+
+/// Target assoluto di un branch
+fn target(base: u32, offset: i32) -> String {
+    format!("0x{:08x}", base.wrapping_add(offset as u32))
+}
+
+/// Registro con shift (operand2 ARM o offset registro di LDR/STR)
+fn shifted_register(operand: u32) -> String {
+    let rm = reg(operand & 0xF);
+    let shift_type = ((operand >> 5) & 3) as usize;
+
+    if operand & (1 << 4) != 0 {
+        return format!("{}, {} {}", rm, SHIFTS[shift_type], reg(operand >> 8));
+    }
+
+    let amount = (operand >> 7) & 0x1F;
+    match (shift_type, amount) {
+        (0, 0) => rm,
+        (3, 0) => format!("{}, rrx", rm),
+        (_, 0) => format!("{}, {} #32", rm, SHIFTS[shift_type]),
+        _ => format!("{}, {} #{}", rm, SHIFTS[shift_type], amount),
+    }
+}
+
+//==============================================================================
+// ARM
+//==============================================================================
+
+/// Disassembla un'istruzione ARM all'indirizzo `pc`
+pub fn disassemble_arm(instr: u32, pc: u32) -> String {
+    let cond = CONDITIONS[(instr >> 28) as usize];
+
+    if let Some(text) = disassemble_arm_misc(instr, cond) {
+        return text;
+    }
+
+    match decode_arm(instr) {
+        ArmInstruction::DataProcessing {
+            opcode,
+            set_flags,
+            rn,
+            rd,
+            operand2,
+            immediate,
+        } => {
+            let op2 = if immediate {
+                imm((operand2 & 0xFF).rotate_right((operand2 >> 8) * 2))
+            } else {
+                shifted_register(operand2)
+            };
+            let name = DATA_PROCESSING[opcode as usize];
+            let s = if set_flags { "s" } else { "" };
+
+            match opcode {
+                // TST/TEQ/CMP/CMN: S implicito, nessuna destinazione
+                0x8..=0xB => format!("{}{} {}, {}", name, cond, reg(rn as u32), op2),
+                // MOV/MVN: nessun primo operando
+                0xD | 0xF => format!("{}{}{} {}, {}", name, cond, s, reg(rd as u32), op2),
+                _ => format!(
+                    "{}{}{} {}, {}, {}",
+                    name,
+                    cond,
+                    s,
+                    reg(rd as u32),
+                    reg(rn as u32),
+                    op2
+                ),
+            }
+        }
+
+        ArmInstruction::Branch { link, offset } => {
+            let l = if link { "l" } else { "" };
+            format!("b{}{} {}", l, cond, target(pc.wrapping_add(8), offset))
+        }
+
+        ArmInstruction::BranchExchange { rn } => format!("bx{} {}", cond, reg(rn as u32)),
+
+        ArmInstruction::SingleDataTransfer {
+            load,
+            byte,
+            pre_index,
+            add,
+            writeback,
+            rn,
+            rd,
+            offset,
+            immediate,
+        } => {
+            let name = if load { "ldr" } else { "str" };
+            let b = if byte { "b" } else { "" };
+            // Post-indexed con W = accesso in modalità utente
+            let t = if !pre_index && writeback { "t" } else { "" };
+
+            let offset = if immediate {
+                (offset != 0).then(|| signed_imm(add, offset))
+            } else {
+                let sign = if add { "" } else { "-" };
+                Some(format!("{}{}", sign, shifted_register(offset)))
+            };
+
+            let address = address_mode(reg(rn as u32), offset, pre_index, writeback);
+            format!("{}{}{}{} {}, {}", name, cond, b, t, reg(rd as u32), address)
+        }
+
+        ArmInstruction::BlockDataTransfer {
+            load,
+            pre_index,
+            add,
+            user_mode,
+            writeback,
+            rn,
+            register_list,
+        } => {
+            let caret = if user_mode { "^" } else { "" };
+
+            // STMDB sp! / LDMIA sp! sono push/pop
+            if rn == 13 && writeback && !user_mode && (pre_index != load) && (add == load) {
+                let name = if load { "pop" } else { "push" };
+                return format!("{}{} {}", name, cond, reg_list(register_list));
+            }
+
+            let name = if load { "ldm" } else { "stm" };
+            let mode = match (pre_index, add) {
+                (false, true) => "ia",
+                (true, true) => "ib",
+                (false, false) => "da",
+                (true, false) => "db",
+            };
+            let wb = if writeback { "!" } else { "" };
+            format!(
+                "{}{}{} {}{}, {}{}",
+                name,
+                cond,
+                mode,
+                reg(rn as u32),
+                wb,
+                reg_list(register_list),
+                caret
+            )
+        }
+
+        ArmInstruction::Multiply {
+            accumulate,
+            set_flags,
+            rd,
+            rn,
+            rs,
+            rm,
+        } => {
+            let s = if set_flags { "s" } else { "" };
+            let (rd, rm, rs) = (reg(rd as u32), reg(rm as u32), reg(rs as u32));
+            if accumulate {
+                format!(
+                    "mla{}{} {}, {}, {}, {}",
+                    cond,
+                    s,
+                    rd,
+                    rm,
+                    rs,
+                    reg(rn as u32)
+                )
+            } else {
+                format!("mul{}{} {}, {}, {}", cond, s, rd, rm, rs)
+            }
+        }
+
+        ArmInstruction::SWI { comment } => format!("swi{} #0x{:x}", cond, comment),
+
+        ArmInstruction::Undefined => format!("undefined 0x{:08x}", instr),
+    }
+}
+
+/// Forme ARM riconosciute solo dai bit grezzi
+fn disassemble_arm_misc(instr: u32, cond: &str) -> Option<String> {
+    let rd = reg(instr >> 12);
+    let rn = reg(instr >> 16);
+    let psr = if instr & (1 << 22) != 0 {
+        "spsr"
+    } else {
+        "cpsr"
+    };
+
+    // MRS: xxxx 0001 0p00 1111 dddd 0000 0000 0000
+    if instr & 0x0FBF_0FFF == 0x010F_0000 {
+        return Some(format!("mrs{} {}, {}", cond, rd, psr));
+    }
+
+    // MSR: xxxx 0011 0p10 ffff 1111 iiii iiii iiii (immediato)
+    //      xxxx 0001 0p10 ffff 1111 0000 0000 mmmm (registro)
+    if instr & 0x0FB0_F000 == 0x0320_F000 || instr & 0x0FB0_FFF0 == 0x0120_F000 {
+        let fields: String = [(19, 'f'), (18, 's'), (17, 'x'), (16, 'c')]
+            .iter()
+            .filter(|(bit, _)| instr & (1 << bit) != 0)
+            .map(|&(_, f)| f)
+            .collect();
+        let operand = if instr & (1 << 25) != 0 {
+            imm((instr & 0xFF).rotate_right(((instr >> 8) & 0xF) * 2))
+        } else {
+            reg(instr)
+        };
+        return Some(format!("msr{} {}_{}, {}", cond, psr, fields, operand));
+    }
+
+    // SWP: xxxx 0001 0b00 nnnn dddd 0000 1001 mmmm
+    if instr & 0x0FB0_0FF0 == 0x0100_0090 {
+        let b = if instr & (1 << 22) != 0 { "b" } else { "" };
+        return Some(format!("swp{}{} {}, {}, [{}]", cond, b, rd, reg(instr), rn));
+    }
+
+    // Multiply long: xxxx 0000 1uas hhhh llll ssss 1001 mmmm
+    if instr & 0x0F80_00F0 == 0x0080_0090 {
+        let sign = if instr & (1 << 22) != 0 { "s" } else { "u" };
+        let op = if instr & (1 << 21) != 0 {
+            "mlal"
+        } else {
+            "mull"
+        };
+        let s = if instr & (1 << 20) != 0 { "s" } else { "" };
+        return Some(format!(
+            "{}{}{}{} {}, {}, {}, {}",
+            sign,
+            op,
+            cond,
+            s,
+            rd,
+            rn,
+            reg(instr),
+            reg(instr >> 8)
+        ));
+    }
+
+    // Halfword/signed transfer: xxxx 000p uiwl nnnn dddd oooo 1sh1 oooo
+    if instr & 0x0E00_0090 == 0x0000_0090 && instr & 0x60 != 0 {
+        let load = instr & (1 << 20) != 0;
+        let name = match (load, (instr >> 5) & 3) {
+            (false, _) => "strh",
+            (true, 1) => "ldrh",
+            (true, 2) => "ldrsb",
+            _ => "ldrsh",
+        };
+        let pre_index = instr & (1 << 24) != 0;
+        let add = instr & (1 << 23) != 0;
+        let writeback = instr & (1 << 21) != 0;
+
+        let offset = if instr & (1 << 22) != 0 {
+            let value = ((instr >> 4) & 0xF0) | (instr & 0xF);
+            (value != 0).then(|| signed_imm(add, value))
+        } else {
+            let sign = if add { "" } else { "-" };
+            Some(format!("{}{}", sign, reg(instr)))
+        };
+
+        let address = address_mode(rn, offset, pre_index, writeback);
+        return Some(format!("{}{} {}, {}", name, cond, rd, address));
+    }
+
+    None
+}
+
+/// Indirizzamento "[rn, off]{!}" (pre) o "[rn], off" (post)
+fn address_mode(rn: String, offset: Option<String>, pre_index: bool, writeback: bool) -> String {
+    match (offset, pre_index) {
+        (None, _) => format!("[{}]", rn),
+        (Some(off), true) => {
+            let wb = if writeback { "!" } else { "" };
+            format!("[{}, {}]{}", rn, off, wb)
+        }
+        (Some(off), false) => format!("[{}], {}", rn, off),
+    }
+}
+
+//==============================================================================
+// THUMB
+//==============================================================================
+
+/// Disassembla un'istruzione THUMB all'indirizzo `pc`
+///
+/// Le due metà di un BL sono mostrate separatamente: la prima riporta
+/// la parte alta del target (in LR), la seconda l'offset basso.
+pub fn disassemble_thumb(instr: u16, pc: u32) -> String {
+    let r = |n: u8| reg(n as u32);
+
+    match decode_thumb(instr) {
+        ThumbInstruction::MoveShiftedRegister { op, offset, rs, rd } => {
+            let amount = if offset == 0 && op != 0 {
+                32
+            } else {
+                offset as u32
+            };
+            format!("{} {}, {}, #{}", SHIFTS[op as usize], r(rd), r(rs), amount)
+        }
+
+        ThumbInstruction::AddSubtract {
+            sub,
+            immediate,
+            rn_offset,
+            rs,
+            rd,
+        } => {
+            let name = if sub { "sub" } else { "add" };
+            let operand = if immediate {
+                imm(rn_offset as u32)
+            } else {
+                r(rn_offset)
+            };
+            format!("{} {}, {}, {}", name, r(rd), r(rs), operand)
+        }
+
+        ThumbInstruction::AluImmediate { op, rd, offset } => {
+            let name = ["mov", "cmp", "add", "sub"][op as usize];
+            format!("{} {}, {}", name, r(rd), imm(offset as u32))
+        }
+
+        ThumbInstruction::AluOperation { op, rs, rd } => {
+            format!("{} {}, {}", THUMB_ALU[op as usize], r(rd), r(rs))
+        }
+
+        ThumbInstruction::HiRegisterOps { op, h1, h2, rs, rd } => {
+            let rd = rd + if h1 { 8 } else { 0 };
+            let rs = rs + if h2 { 8 } else { 0 };
+            match op {
+                0 => format!("add {}, {}", r(rd), r(rs)),
+                1 => format!("cmp {}, {}", r(rd), r(rs)),
+                2 => format!("mov {}, {}", r(rd), r(rs)),
+                _ => format!("bx {}", r(rs)),
+            }
+        }
+
+        ThumbInstruction::LoadPcRelative { rd, offset } => {
+            let offset = (offset as u32) << 2;
+            let address = (pc.wrapping_add(4) & !2).wrapping_add(offset);
+            format!("ldr {}, [pc, {}] ; 0x{:08x}", r(rd), imm(offset), address)
+        }
+
+        ThumbInstruction::LoadStoreRegOffset {
+            load,
+            byte,
+            ro,
+            rb,
+            rd,
+        } => {
+            let name = if load { "ldr" } else { "str" };
+            let b = if byte { "b" } else { "" };
+            format!("{}{} {}, [{}, {}]", name, b, r(rd), r(rb), r(ro))
+        }
+
+        ThumbInstruction::LoadStoreSignExtended {
+            h,
+            sign,
+            ro,
+            rb,
+            rd,
+        } => {
+            let name = match (sign, h) {
+                (false, false) => "strh",
+                (false, true) => "ldrh",
+                (true, false) => "ldsb",
+                (true, true) => "ldsh",
+            };
+            format!("{} {}, [{}, {}]", name, r(rd), r(rb), r(ro))
+        }
+
+        ThumbInstruction::LoadStoreImmOffset {
+            load,
+            byte,
+            offset,
+            rb,
+            rd,
+        } => {
+            let name = if load { "ldr" } else { "str" };
+            let (b, offset) = if byte {
+                ("b", offset as u32)
+            } else {
+                ("", (offset as u32) << 2)
+            };
+            let address = address_mode(r(rb), (offset != 0).then(|| imm(offset)), true, false);
+            format!("{}{} {}, {}", name, b, r(rd), address)
+        }
+
+        ThumbInstruction::LoadStoreHalfword {
+            load,
+            offset,
+            rb,
+            rd,
+        } => {
+            let name = if load { "ldrh" } else { "strh" };
+            let offset = (offset as u32) << 1;
+            let address = address_mode(r(rb), (offset != 0).then(|| imm(offset)), true, false);
+            format!("{} {}, {}", name, r(rd), address)
+        }
+
+        ThumbInstruction::LoadStoreSpRelative { load, rd, offset } => {
+            let name = if load { "ldr" } else { "str" };
+            let offset = (offset as u32) << 2;
+            let address = address_mode(reg(13), (offset != 0).then(|| imm(offset)), true, false);
+            format!("{} {}, {}", name, r(rd), address)
+        }
+
+        ThumbInstruction::LoadAddress { sp, rd, offset } => {
+            let base = if sp { "sp" } else { "pc" };
+            format!("add {}, {}, {}", r(rd), base, imm((offset as u32) << 2))
+        }
+
+        ThumbInstruction::AddOffsetSp { sub, offset } => {
+            let name = if sub { "sub" } else { "add" };
+            format!("{} sp, {}", name, imm((offset as u32) << 2))
+        }
+
+        ThumbInstruction::PushPop {
+            load,
+            r: extra,
+            rlist,
+        } => {
+            let mut list = rlist as u16;
+            if extra {
+                list |= if load { 1 << 15 } else { 1 << 14 };
+            }
+            let name = if load { "pop" } else { "push" };
+            format!("{} {}", name, reg_list(list))
+        }
+
+        ThumbInstruction::LoadStoreMultiple { load, rb, rlist } => {
+            let name = if load { "ldmia" } else { "stmia" };
+            format!("{} {}!, {}", name, r(rb), reg_list(rlist as u16))
+        }
+
+        ThumbInstruction::ConditionalBranch { cond, offset } => format!(
+            "b{} {}",
+            CONDITIONS[cond as usize],
+            target(pc.wrapping_add(4), (offset as i32) << 1)
+        ),
+
+        ThumbInstruction::SoftwareInterrupt { comment } => format!("swi #0x{:x}", comment),
+
+        ThumbInstruction::UnconditionalBranch { offset } => {
+            format!("b {}", target(pc.wrapping_add(4), (offset as i32) << 1))
+        }
+
+        ThumbInstruction::LongBranchLink { offset, .. } => {
+            // H = 0: prima metà (parte alta dell'offset), H = 1: seconda
+            if instr & (1 << 11) == 0 {
+                // Estende il segno dell'offset a 11 bit
+                let high = ((offset as i32) << 21) >> 9;
+                format!("bl (lr = {})", target(pc.wrapping_add(4), high))
+            } else {
+                format!("bl (pc = lr + {})", imm((offset as u32) << 1))
+            }
+        }
+
+        ThumbInstruction::Undefined => format!("undefined 0x{:04x}", instr),
+    }
+}
@@ -0,0 +1,101 @@
+use crate::disasm::{disassemble_arm, disassemble_thumb};
+
+const PC: u32 = 0x0800_0000;
+
+fn arm(instr: u32) -> String {
+    disassemble_arm(instr, PC)
+}
+
+fn thumb(instr: u16) -> String {
+    disassemble_thumb(instr, PC)
+}
+
+#[test]
+fn test_arm_data_processing() {
+    assert_eq!(arm(0xE3A00403), "mov r0, #0x3000000");
+    assert_eq!(arm(0x10812003), "addne r2, r1, r3");
+    assert_eq!(arm(0xE1A01102), "mov r1, r2, lsl #2");
+    assert_eq!(arm(0xE0910312), "adds r0, r1, r2, lsl r3");
+    assert_eq!(arm(0xE1A00060), "mov r0, r0, rrx");
+    assert_eq!(arm(0xE1A00020), "mov r0, r0, lsr #32");
+    assert_eq!(arm(0xE3500000), "cmp r0, #0");
+}
+
+#[test]
+fn test_arm_branches() {
+    assert_eq!(arm(0xEAFFFFFE), "b 0x08000000");
+    assert_eq!(arm(0xEB00003E), "bl 0x08000100");
+    assert_eq!(arm(0x0AFFFFFC), "beq 0x07fffff8");
+    assert_eq!(arm(0xE12FFF1E), "bx lr");
+    assert_eq!(arm(0xEF000005), "swi #0x5");
+}
+
+#[test]
+fn test_arm_load_store() {
+    assert_eq!(arm(0xE5910004), "ldr r0, [r1, #4]");
+    assert_eq!(arm(0xE5910000), "ldr r0, [r1]");
+    assert_eq!(arm(0xE4912004), "ldr r2, [r1], #4");
+    assert_eq!(arm(0xE5310004), "ldr r0, [r1, #-4]!");
+    assert_eq!(arm(0xE5C10000), "strb r0, [r1]");
+    assert_eq!(arm(0xE7910102), "ldr r0, [r1, r2, lsl #2]");
+    assert_eq!(arm(0xE1D000B2), "ldrh r0, [r0, #2]");
+    assert_eq!(arm(0xE19100D2), "ldrsb r0, [r1, r2]");
+    assert_eq!(arm(0xE1020091), "swp r0, r1, [r2]");
+}
+
+#[test]
+fn test_arm_register_lists() {
+    assert_eq!(arm(0xE92D4010), "push {r4, lr}");
+    assert_eq!(arm(0xE8BD8010), "pop {r4, pc}");
+    assert_eq!(arm(0xE8900006), "ldmia r0, {r1, r2}");
+    assert_eq!(arm(0xE9A0000F), "stmib r0!, {r0, r1, r2, r3}");
+    assert_eq!(arm(0xE8D08000), "ldmia r0, {pc}^");
+}
+
+#[test]
+fn test_arm_multiply_and_psr() {
+    assert_eq!(arm(0xE0000291), "mul r0, r1, r2");
+    assert_eq!(arm(0xE0203291), "mla r0, r1, r2, r3");
+    assert_eq!(arm(0xE0810392), "umull r0, r1, r2, r3");
+    assert_eq!(arm(0xE10F0000), "mrs r0, cpsr");
+    assert_eq!(arm(0xE169F000), "msr spsr_fc, r0");
+}
+
+#[test]
+fn test_thumb_alu_and_shifts() {
+    assert_eq!(thumb(0x0088), "lsl r0, r1, #2");
+    assert_eq!(thumb(0x0808), "lsr r0, r1, #32");
+    assert_eq!(thumb(0x1888), "add r0, r1, r2");
+    assert_eq!(thumb(0x1E48), "sub r0, r1, #1");
+    assert_eq!(thumb(0x2010), "mov r0, #0x10");
+    assert_eq!(thumb(0x4350), "mul r0, r2");
+    assert_eq!(thumb(0x4770), "bx lr");
+    assert_eq!(thumb(0x46C0), "mov r8, r8");
+}
+
+#[test]
+fn test_thumb_load_store() {
+    assert_eq!(thumb(0x6848), "ldr r0, [r1, #4]");
+    assert_eq!(thumb(0x7808), "ldrb r0, [r1]");
+    assert_eq!(thumb(0x8848), "ldrh r0, [r1, #2]");
+    assert_eq!(thumb(0x5888), "ldr r0, [r1, r2]");
+    assert_eq!(thumb(0x5E88), "ldsh r0, [r1, r2]");
+    assert_eq!(thumb(0x9801), "ldr r0, [sp, #4]");
+    assert_eq!(
+        disassemble_thumb(0x4801, PC + 2),
+        "ldr r0, [pc, #4] ; 0x08000008"
+    );
+}
+
+#[test]
+fn test_thumb_branches_and_lists() {
+    assert_eq!(thumb(0xD0FE), "beq 0x08000000");
+    assert_eq!(thumb(0xE7FE), "b 0x08000000");
+    assert_eq!(thumb(0xB510), "push {r4, lr}");
+    assert_eq!(thumb(0xBD10), "pop {r4, pc}");
+    assert_eq!(thumb(0xC806), "ldmia r0!, {r1, r2}");
+    assert_eq!(thumb(0xB082), "sub sp, #8");
+    assert_eq!(thumb(0xDF05), "swi #0x5");
+    assert_eq!(thumb(0xF000), "bl (lr = 0x08000004)");
+    assert_eq!(thumb(0xF87E), "bl (pc = lr + #0xfc)");
+}
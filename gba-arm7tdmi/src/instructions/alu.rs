@@ -65,9 +65,10 @@ pub fn execute_data_processing(
         // ADD: Rd = Rn + Op2
         data_processing::ADD => {
             let (res, overflow) = add_with_flags(rn_value, operand2, false);
+            // Carry = la somma senza segno supera 32 bit
             (
                 Some(res),
-                (res as u64) != ((rn_value as u64) + (operand2 as u64)),
+                rn_value.checked_add(operand2).is_none(),
                 overflow,
             )
         }
@@ -160,6 +161,14 @@ pub fn execute_data_processing(
     // Scrivi risultato nel registro destinazione (se presente)
     if let Some(value) = result {
         if rd == 15 {
+            if set_flags {
+                // Ritorno da eccezione (es. SUBS PC, LR, #4): CPSR = SPSR,
+                // il PC si allinea allo stato (ARM/THUMB) ripristinato
+                regs.restore_cpsr();
+                let mask = if regs.is_thumb() { !1 } else { !3 };
+                regs.set_pc(value & mask);
+                return 1;
+            }
             // Scrittura in PC
             regs.set_pc(value & !3); // Allinea a 4 byte
         } else {
@@ -190,9 +199,9 @@ fn add_with_flags(a: u32, b: u32, carry: bool) -> (u32, bool) {
     (result, overflow)
 }
 
-/// Sottrazione con rilevamento overflow
-fn sub_with_flags(a: u32, b: u32, carry: bool) -> (u32, bool) {
-    let c = if carry { 0 } else { 1 };
+/// Sottrazione con rilevamento overflow (`borrow` = NOT carry per SBC/RSC)
+fn sub_with_flags(a: u32, b: u32, borrow: bool) -> (u32, bool) {
+    let c = if borrow { 1 } else { 0 };
     let result = a.wrapping_sub(b).wrapping_sub(c);
 
     // Overflow: segni diversi e risultato con segno diverso da 'a'
@@ -269,7 +278,10 @@ pub fn decode_operand2(operand2: u32, immediate: bool, regs: &Registers) -> (u32
 }
 
 /// Barrel shifter (shift/rotate con carry out)
-fn barrel_shift(value: u32, shift_type: u32, amount: u32, carry_in: bool) -> (u32, bool) {
+///
+/// `amount` è la quantità effettiva, come negli shift per registro: 0 lascia
+/// valore e carry invariati, quantità ≥ 32 seguono le regole ARM7TDMI.
+pub fn barrel_shift(value: u32, shift_type: u32, amount: u32, carry_in: bool) -> (u32, bool) {
     if amount == 0 {
         return (value, carry_in);
     }
@@ -277,31 +289,39 @@ fn barrel_shift(value: u32, shift_type: u32, amount: u32, carry_in: bool) -> (u3
     match shift_type {
         0 => {
             // LSL (Logical Shift Left)
-            let result = value << amount;
-            let carry = if amount <= 32 {
-                (value & (1 << (32 - amount))) != 0
-            } else {
-                false
-            };
-            (result, carry)
+            match amount {
+                1..=31 => (value << amount, (value & (1 << (32 - amount))) != 0),
+                32 => (0, (value & 1) != 0),
+                _ => (0, false),
+            }
         }
         1 => {
             // LSR (Logical Shift Right)
-            let result = value >> amount;
-            let carry = (value & (1 << (amount - 1))) != 0;
-            (result, carry)
+            match amount {
+                1..=31 => (value >> amount, (value & (1 << (amount - 1))) != 0),
+                32 => (0, (value & 0x8000_0000) != 0),
+                _ => (0, false),
+            }
         }
         2 => {
-            // ASR (Arithmetic Shift Right)
-            let result = ((value as i32) >> amount) as u32;
-            let carry = (value & (1 << (amount - 1))) != 0;
-            (result, carry)
+            // ASR (Arithmetic Shift Right): oltre 31 resta solo il segno
+            if amount < 32 {
+                let result = ((value as i32) >> amount) as u32;
+                (result, (value & (1 << (amount - 1))) != 0)
+            } else {
+                let sign = (value & 0x8000_0000) != 0;
+                (if sign { 0xFFFF_FFFF } else { 0 }, sign)
+            }
         }
         3 => {
-            // ROR (Rotate Right)
-            let result = value.rotate_right(amount);
-            let carry = (value & (1 << (amount - 1))) != 0;
-            (result, carry)
+            // ROR (Rotate Right): multipli di 32 lasciano il valore, carry = bit 31
+            let rotate = amount & 31;
+            if rotate == 0 {
+                (value, (value & 0x8000_0000) != 0)
+            } else {
+                let result = value.rotate_right(rotate);
+                (result, (value & (1 << (rotate - 1))) != 0)
+            }
         }
         _ => (value, carry_in),
     }
@@ -15,21 +15,23 @@ use crate::registers::Registers;
 /// # Arguments
 /// * `regs` - Registri CPU
 /// * `offset` - Offset signed (già moltiplicato per 4) da aggiungere al PC
-/// * `link` - Se true, salva PC in R14 (LR) prima del salto
+/// * `link` - Se true, salva l'indirizzo di ritorno in R14 (LR)
+///
+/// Va chiamata con PC = indirizzo dell'istruzione + 4 (già avanzato dal
+/// fetch): la base del salto è istruzione + 8, come con il prefetch.
 ///
 /// # Returns
 /// Numero di cicli usati (2S+1N = 3 cicli)
 pub fn execute_branch(regs: &mut Registers, offset: i32, link: bool) -> u32 {
     let pc = regs.pc();
 
-    // Se BL, salva indirizzo ritorno in LR (R14)
+    // Se BL, salva indirizzo ritorno in LR (R14): istruzione dopo BL
     if link {
-        regs.r[14] = pc.wrapping_sub(4); // PC-4 = istruzione dopo BL
+        regs.r[14] = pc;
     }
 
-    // Calcola nuovo PC: PC corrente è già +8 (prefetch)
-    // quindi sommiamo l'offset a PC che è già avanzato
-    let new_pc = (pc as i32).wrapping_add(offset) as u32;
+    // Nuovo PC: istruzione + 8 + offset
+    let new_pc = pc.wrapping_add(4).wrapping_add(offset as u32);
     regs.set_pc(new_pc & !3); // Allinea a 4 byte (ARM mode)
 
     3 // Branch costa 2S+1N = 3 cicli
@@ -8,6 +8,14 @@
 
 use crate::{cpu::MemoryBus, registers::Registers};
 
+/// Legge una word con la semantica LDR per indirizzi non allineati
+///
+/// L'ARM7 legge la word allineata e la ruota a destra di `8 * (addr & 3)` bit.
+pub fn read_word_rotated<M: MemoryBus>(bus: &mut M, address: u32) -> u32 {
+    let value = bus.read_word(address & !3);
+    value.rotate_right((address & 3) * 8)
+}
+
 /// Parametri per Single Data Transfer (LDR/STR)
 pub struct SingleDataTransferParams {
     pub load: bool,
@@ -58,7 +66,7 @@ pub fn execute_single_data_transfer<M: MemoryBus>(
         let value = if params.byte {
             bus.read_byte(address) as u32
         } else {
-            bus.read_word(address & !3) // Word allineato
+            read_word_rotated(bus, address)
         };
 
         if params.rd == 15 {
@@ -104,6 +112,8 @@ pub struct BlockDataTransferParams {
     pub pre_index: bool,
     pub add: bool,
     pub writeback: bool,
+    /// Bit S: banco User (senza R15) o ripristino CPSR (LDM con R15)
+    pub user_mode: bool,
     pub rn: u8,
     pub register_list: u16,
 }
@@ -124,26 +134,28 @@ pub fn execute_block_data_transfer<M: MemoryBus>(
     bus: &mut M,
     params: &BlockDataTransferParams,
 ) -> u32 {
-    let mut address = regs.r[params.rn as usize];
+    let base = regs.r[params.rn as usize];
     let count = params.register_list.count_ones();
 
-    // Calcola indirizzo iniziale per decremento
-    if !params.add {
-        address = address.wrapping_sub(count * 4);
-    }
+    // I registri sono sempre trasferiti dall'indirizzo più basso:
+    // per il decremento si parte da base - 4n e si procede in avanti
+    let (mut address, increment_before) = if params.add {
+        (base, params.pre_index)
+    } else {
+        (base.wrapping_sub(count * 4), !params.pre_index)
+    };
+
+    // Bit S: con R15 in un LDM ripristina CPSR, altrimenti usa il banco User
+    let has_pc = (params.register_list & (1 << 15)) != 0;
+    let user_bank = params.user_mode && !(params.load && has_pc);
 
     let mut cycles = 0;
 
     // Trasferisci ogni registro nella lista
     for i in 0..16 {
         if (params.register_list & (1 << i)) != 0 {
-            // Pre-increment se richiesto
-            if params.pre_index {
-                address = if params.add {
-                    address.wrapping_add(4)
-                } else {
-                    address.wrapping_sub(4)
-                };
+            if increment_before {
+                address = address.wrapping_add(4);
             }
 
             // Esegui load/store
@@ -151,21 +163,24 @@ pub fn execute_block_data_transfer<M: MemoryBus>(
                 let value = bus.read_word(address);
                 if i == 15 {
                     regs.set_pc(value & !3);
+                } else if user_bank {
+                    regs.set_user_reg(i, value);
                 } else {
                     regs.r[i] = value;
                 }
             } else {
-                let value = if i == 15 { regs.pc() + 12 } else { regs.r[i] };
+                let value = if i == 15 {
+                    regs.pc() + 12
+                } else if user_bank {
+                    regs.user_reg(i)
+                } else {
+                    regs.r[i]
+                };
                 bus.write_word(address, value);
             }
 
-            // Post-increment se non pre
-            if !params.pre_index {
-                address = if params.add {
-                    address.wrapping_add(4)
-                } else {
-                    address.wrapping_sub(4)
-                };
+            if !increment_before {
+                address = address.wrapping_add(4);
             }
 
             cycles += 1;
@@ -182,6 +197,11 @@ pub fn execute_block_data_transfer<M: MemoryBus>(
         regs.r[params.rn as usize] = final_address;
     }
 
+    // LDM con R15 e bit S: ritorno da eccezione
+    if params.load && has_pc && params.user_mode {
+        regs.restore_cpsr();
+    }
+
     // Cicli: nS + 1N + 1I (LDM) o (n-1)S + 2N (STM)
     if params.load {
         cycles + 2
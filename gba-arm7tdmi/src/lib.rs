@@ -1,10 +1,19 @@
+// Il core non usa std: gira anche su target embedded (solo `alloc`)
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
 pub mod arm;
 pub mod cpu;
 #[cfg(test)]
 mod cpu_tests;
+pub mod disasm;
+#[cfg(test)]
+mod disasm_tests;
 pub mod instructions;
 pub mod registers;
 pub mod thumb;
 
-pub use cpu::ARM7TDMI;
-pub use registers::{CpuState, Mode, Registers};
+pub use cpu::{TraceCallback, TraceEntry, ARM7TDMI};
+pub use disasm::{disassemble_arm, disassemble_thumb};
+pub use registers::{CpuState, Mode, RegisterSnapshot, Registers};
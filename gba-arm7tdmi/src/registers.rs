@@ -86,6 +86,14 @@ pub struct Registers {
     pub r: [u32; 16],
 
     // Registri banked per diverse modalità
+    pub r8_usr: u32,
+    pub r9_usr: u32,
+    pub r10_usr: u32,
+    pub r11_usr: u32,
+    pub r12_usr: u32,
+    pub r13_usr: u32, // SP_usr/sys
+    pub r14_usr: u32, // LR_usr/sys
+
     pub r8_fiq: u32,
     pub r9_fiq: u32,
     pub r10_fiq: u32,
@@ -124,6 +132,13 @@ impl Registers {
     pub fn new() -> Self {
         Self {
             r: [0; 16],
+            r8_usr: 0,
+            r9_usr: 0,
+            r10_usr: 0,
+            r11_usr: 0,
+            r12_usr: 0,
+            r13_usr: 0,
+            r14_usr: 0,
             r8_fiq: 0,
             r9_fiq: 0,
             r10_fiq: 0,
@@ -295,6 +310,7 @@ impl Registers {
         }
 
         // Salva registri banked correnti
+        // R8-R12 sono condivisi da tutte le modalità tranne FIQ
         match self.mode {
             Mode::FIQ => {
                 self.r8_fiq = self.r[8];
@@ -305,6 +321,19 @@ impl Registers {
                 self.r13_fiq = self.r[13];
                 self.r14_fiq = self.r[14];
             }
+            _ => {
+                self.r8_usr = self.r[8];
+                self.r9_usr = self.r[9];
+                self.r10_usr = self.r[10];
+                self.r11_usr = self.r[11];
+                self.r12_usr = self.r[12];
+            }
+        }
+        match self.mode {
+            Mode::User | Mode::System => {
+                self.r13_usr = self.r[13];
+                self.r14_usr = self.r[14];
+            }
             Mode::Supervisor => {
                 self.r13_svc = self.r[13];
                 self.r14_svc = self.r[14];
@@ -321,7 +350,7 @@ impl Registers {
                 self.r13_und = self.r[13];
                 self.r14_und = self.r[14];
             }
-            _ => {}
+            Mode::FIQ => {}
         }
 
         // Carica registri banked nuovi
@@ -335,6 +364,19 @@ impl Registers {
                 self.r[13] = self.r13_fiq;
                 self.r[14] = self.r14_fiq;
             }
+            _ => {
+                self.r[8] = self.r8_usr;
+                self.r[9] = self.r9_usr;
+                self.r[10] = self.r10_usr;
+                self.r[11] = self.r11_usr;
+                self.r[12] = self.r12_usr;
+            }
+        }
+        match new_mode {
+            Mode::User | Mode::System => {
+                self.r[13] = self.r13_usr;
+                self.r[14] = self.r14_usr;
+            }
             Mode::Supervisor => {
                 self.r[13] = self.r13_svc;
                 self.r[14] = self.r14_svc;
@@ -351,7 +393,7 @@ impl Registers {
                 self.r[13] = self.r13_und;
                 self.r[14] = self.r14_und;
             }
-            _ => {}
+            Mode::FIQ => {}
         }
 
         self.mode = new_mode;
@@ -381,6 +423,176 @@ impl Registers {
             _ => {}
         }
     }
+
+    /// Ripristina CPSR da SPSR (ritorno da eccezione)
+    ///
+    /// Cambia anche modalità, così i registri banked vengono scambiati.
+    pub fn restore_cpsr(&mut self) {
+        let spsr = self.spsr();
+        if let Some(mode) = Mode::from_bits(spsr) {
+            self.change_mode(mode);
+        }
+        self.cpsr = spsr;
+    }
+
+    /// Verifica se il registro `index` di `mode` è quello visibile ora
+    fn is_visible_in(&self, mode: Mode, index: usize) -> bool {
+        let user_bank = |m: Mode| matches!(m, Mode::User | Mode::System);
+        match index {
+            8..=12 => (self.mode == Mode::FIQ) == (mode == Mode::FIQ),
+            13 | 14 => self.mode == mode || (user_bank(self.mode) && user_bank(mode)),
+            _ => true,
+        }
+    }
+
+    /// Legge il registro `index` (0-15) come lo vede la modalità `mode`
+    ///
+    /// Se il registro non è quello visibile si legge la copia banked:
+    /// R8-R12 esistono solo in versione FIQ, R13-R14 per ogni modalità.
+    pub fn read_banked(&self, mode: Mode, index: usize) -> u32 {
+        if self.is_visible_in(mode, index) {
+            return self.r[index];
+        }
+        match (mode, index) {
+            (Mode::FIQ, 8) => self.r8_fiq,
+            (Mode::FIQ, 9) => self.r9_fiq,
+            (Mode::FIQ, 10) => self.r10_fiq,
+            (Mode::FIQ, 11) => self.r11_fiq,
+            (Mode::FIQ, 12) => self.r12_fiq,
+            (Mode::FIQ, 13) => self.r13_fiq,
+            (Mode::FIQ, 14) => self.r14_fiq,
+            (_, 8) => self.r8_usr,
+            (_, 9) => self.r9_usr,
+            (_, 10) => self.r10_usr,
+            (_, 11) => self.r11_usr,
+            (_, 12) => self.r12_usr,
+            (Mode::Supervisor, 13) => self.r13_svc,
+            (Mode::Supervisor, 14) => self.r14_svc,
+            (Mode::Abort, 13) => self.r13_abt,
+            (Mode::Abort, 14) => self.r14_abt,
+            (Mode::IRQ, 13) => self.r13_irq,
+            (Mode::IRQ, 14) => self.r14_irq,
+            (Mode::Undefined, 13) => self.r13_und,
+            (Mode::Undefined, 14) => self.r14_und,
+            (_, 13) => self.r13_usr,
+            _ => self.r14_usr,
+        }
+    }
+
+    /// Scrive il registro `index` (0-15) della modalità `mode`, senza
+    /// toccare le copie delle altre modalità
+    pub fn write_banked(&mut self, mode: Mode, index: usize, value: u32) {
+        if self.is_visible_in(mode, index) {
+            self.r[index] = value;
+            return;
+        }
+        match (mode, index) {
+            (Mode::FIQ, 8) => self.r8_fiq = value,
+            (Mode::FIQ, 9) => self.r9_fiq = value,
+            (Mode::FIQ, 10) => self.r10_fiq = value,
+            (Mode::FIQ, 11) => self.r11_fiq = value,
+            (Mode::FIQ, 12) => self.r12_fiq = value,
+            (Mode::FIQ, 13) => self.r13_fiq = value,
+            (Mode::FIQ, 14) => self.r14_fiq = value,
+            (_, 8) => self.r8_usr = value,
+            (_, 9) => self.r9_usr = value,
+            (_, 10) => self.r10_usr = value,
+            (_, 11) => self.r11_usr = value,
+            (_, 12) => self.r12_usr = value,
+            (Mode::Supervisor, 13) => self.r13_svc = value,
+            (Mode::Supervisor, 14) => self.r14_svc = value,
+            (Mode::Abort, 13) => self.r13_abt = value,
+            (Mode::Abort, 14) => self.r14_abt = value,
+            (Mode::IRQ, 13) => self.r13_irq = value,
+            (Mode::IRQ, 14) => self.r14_irq = value,
+            (Mode::Undefined, 13) => self.r13_und = value,
+            (Mode::Undefined, 14) => self.r14_und = value,
+            (_, 13) => self.r13_usr = value,
+            _ => self.r14_usr = value,
+        }
+    }
+
+    /// Tutti i registri, visibili e banked
+    pub fn snapshot(&self) -> RegisterSnapshot {
+        let bank = |mode: Mode, first: usize| -> [u32; 7] {
+            core::array::from_fn(|i| self.read_banked(mode, first + i))
+        };
+        let sp_lr = |mode: Mode| [self.read_banked(mode, 13), self.read_banked(mode, 14)];
+        RegisterSnapshot {
+            visible: self.r,
+            mode: self.mode,
+            cpsr: self.cpsr,
+            usr: bank(Mode::User, 8),
+            fiq: bank(Mode::FIQ, 8),
+            svc: sp_lr(Mode::Supervisor),
+            abt: sp_lr(Mode::Abort),
+            irq: sp_lr(Mode::IRQ),
+            und: sp_lr(Mode::Undefined),
+            spsr_fiq: self.spsr_fiq,
+            spsr_svc: self.spsr_svc,
+            spsr_abt: self.spsr_abt,
+            spsr_irq: self.spsr_irq,
+            spsr_und: self.spsr_und,
+        }
+    }
+
+    /// Legge un registro del banco User/System indipendentemente dalla modalità
+    pub fn user_reg(&self, index: usize) -> u32 {
+        match (self.mode, index) {
+            (Mode::User | Mode::System, _) => self.r[index],
+            (Mode::FIQ, 8) => self.r8_usr,
+            (Mode::FIQ, 9) => self.r9_usr,
+            (Mode::FIQ, 10) => self.r10_usr,
+            (Mode::FIQ, 11) => self.r11_usr,
+            (Mode::FIQ, 12) => self.r12_usr,
+            (_, 13) => self.r13_usr,
+            (_, 14) => self.r14_usr,
+            _ => self.r[index],
+        }
+    }
+
+    /// Scrive un registro del banco User/System indipendentemente dalla modalità
+    pub fn set_user_reg(&mut self, index: usize, value: u32) {
+        match (self.mode, index) {
+            (Mode::User | Mode::System, _) => self.r[index] = value,
+            (Mode::FIQ, 8) => self.r8_usr = value,
+            (Mode::FIQ, 9) => self.r9_usr = value,
+            (Mode::FIQ, 10) => self.r10_usr = value,
+            (Mode::FIQ, 11) => self.r11_usr = value,
+            (Mode::FIQ, 12) => self.r12_usr = value,
+            (_, 13) => self.r13_usr = value,
+            (_, 14) => self.r14_usr = value,
+            _ => self.r[index] = value,
+        }
+    }
+}
+
+/// Fotografia di tutti i registri della CPU, visibili e banked
+///
+/// Pensata per debugger e scripting: non serve conoscere il layout del
+/// banking per leggere lo stack di una modalità diversa da quella corrente.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegisterSnapshot {
+    /// R0-R15 come li vede la modalità corrente
+    pub visible: [u32; 16],
+    /// Modalità corrente
+    pub mode: Mode,
+    pub cpsr: u32,
+    /// R8-R14 del banco User/System
+    pub usr: [u32; 7],
+    /// R8-R14 del banco FIQ
+    pub fiq: [u32; 7],
+    /// R13-R14 (SP, LR) delle altre modalità privilegiate
+    pub svc: [u32; 2],
+    pub abt: [u32; 2],
+    pub irq: [u32; 2],
+    pub und: [u32; 2],
+    /// SPSR delle modalità con eccezione
+    pub spsr_fiq: u32,
+    pub spsr_svc: u32,
+    pub spsr_abt: u32,
+    pub spsr_irq: u32,
+    pub spsr_und: u32,
 }
 
 impl Default for Registers {
@@ -171,7 +171,7 @@ pub enum ThumbInstruction {
     /// Format 19: Long branch with link
     /// BL label (first or second instruction)
     LongBranchLink {
-        first_instruction: bool, // Bit 11 (1=first H, 0=second L)
+        first_instruction: bool, // Bit 11 (0=first H, 1=second L)
         offset: u16,             // Bits 0-10
     },
 
@@ -333,10 +333,10 @@ pub fn decode_thumb(instruction: u16) -> ThumbInstruction {
         };
     }
 
-    // Format 16: Conditional branch (1101xxxx, but not 1111)
+    // Format 16: Conditional branch (1101xxxx, but not 1110/1111)
     if (instruction & 0xF000) == 0xD000 {
         let cond = ((instruction >> 8) & 0xF) as u8;
-        if cond != 0xF {
+        if cond < 0xE {
             return ThumbInstruction::ConditionalBranch {
                 cond,
                 offset: (instruction & 0xFF) as i8,
@@ -357,7 +357,7 @@ pub fn decode_thumb(instruction: u16) -> ThumbInstruction {
     // Format 19: Long branch with link (1111x)
     if (instruction & 0xF000) == 0xF000 {
         return ThumbInstruction::LongBranchLink {
-            first_instruction: (instruction & (1 << 11)) != 0,
+            first_instruction: (instruction & (1 << 11)) == 0,
             offset: instruction & 0x7FF,
         };
     }
@@ -4,4 +4,6 @@
 #[path = "apu_impl/mod.rs"]
 mod apu_impl;
 
-pub use apu_impl::APU;
+#[cfg(feature = "std")]
+pub use apu_impl::WavRecorder;
+pub use apu_impl::{AudioResampler, AudioSink, APU, CYCLES_PER_SAMPLE, NATIVE_SAMPLE_RATE};
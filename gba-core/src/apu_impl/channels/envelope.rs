@@ -0,0 +1,135 @@
+// Envelope e length counter condivisi dai canali PSG
+//
+// Entrambi avanzano solo sui clock del frame sequencer: l'envelope a 64 Hz,
+// il length counter a 256 Hz.
+
+/// Envelope del volume (registro NRx2: bit 8-10 periodo, 11 direzione, 12-15 volume iniziale)
+#[derive(Debug, Default, Clone)]
+pub struct Envelope {
+    volume: u8,
+    timer: u8,
+}
+
+impl Envelope {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ricarica il volume iniziale al trigger del canale
+    pub fn trigger(&mut self, reg: u16) {
+        self.volume = ((reg >> 12) & 0x0F) as u8;
+        self.timer = 0;
+    }
+
+    /// Volume corrente (0-15)
+    pub fn volume(&self) -> u8 {
+        self.volume
+    }
+
+    /// Clock a 64 Hz: ogni `periodo` clock il volume sale o scende di 1
+    pub fn clock(&mut self, reg: u16) {
+        let period = ((reg >> 8) & 0x07) as u8;
+        if period == 0 {
+            return;
+        }
+
+        self.timer += 1;
+        if self.timer < period {
+            return;
+        }
+        self.timer = 0;
+
+        let increase = reg & 0x0800 != 0;
+        if increase && self.volume < 15 {
+            self.volume += 1;
+        } else if !increase && self.volume > 0 {
+            self.volume -= 1;
+        }
+    }
+}
+
+/// Length counter: spegne il canale dopo `max - length` clock a 256 Hz
+#[derive(Debug, Clone)]
+pub struct LengthCounter {
+    counter: u32,
+    max: u32,
+}
+
+impl LengthCounter {
+    /// `max` = 64 per square e noise, 256 per il canale wave
+    pub fn new(max: u32) -> Self {
+        Self { counter: 0, max }
+    }
+
+    /// Carica la durata scritta nel registro (NRx1)
+    pub fn load(&mut self, length: u32) {
+        self.counter = self.max - (length & (self.max - 1));
+    }
+
+    /// Al trigger un counter scaduto riparte dal massimo
+    pub fn trigger(&mut self) {
+        if self.counter == 0 {
+            self.counter = self.max;
+        }
+    }
+
+    /// Clock a 256 Hz; ritorna true quando il counter arriva a 0
+    pub fn clock(&mut self, enabled: bool) -> bool {
+        if !enabled || self.counter == 0 {
+            return false;
+        }
+        self.counter -= 1;
+        self.counter == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_envelope_period_and_direction() {
+        let mut env = Envelope::new();
+
+        // Volume 2, decrease, periodo 2
+        env.trigger(0x2200);
+        env.clock(0x2200);
+        assert_eq!(env.volume(), 2);
+        env.clock(0x2200);
+        assert_eq!(env.volume(), 1);
+
+        // Increase, periodo 1: si ferma a 15
+        env.trigger(0xE900);
+        for _ in 0..4 {
+            env.clock(0xE900);
+        }
+        assert_eq!(env.volume(), 15);
+
+        // Periodo 0: envelope fermo
+        env.trigger(0x5000);
+        env.clock(0x5000);
+        assert_eq!(env.volume(), 5);
+    }
+
+    #[test]
+    fn test_length_counter_expires() {
+        let mut length = LengthCounter::new(64);
+        length.load(60);
+
+        // Senza length enable non scende
+        assert!(!length.clock(false));
+
+        assert!(!length.clock(true));
+        assert!(!length.clock(true));
+        assert!(!length.clock(true));
+        assert!(length.clock(true));
+        assert!(!length.clock(true));
+
+        // Trigger dopo la scadenza: durata massima
+        length.trigger();
+        for _ in 0..63 {
+            assert!(!length.clock(true));
+        }
+        assert!(length.clock(true));
+    }
+}
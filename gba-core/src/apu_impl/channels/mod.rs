@@ -1,5 +1,6 @@
 // Canali audio GB
 
+mod envelope;
 mod square;
 mod wave;
 mod noise;
@@ -1,7 +1,9 @@
 // Noise Channel (Channel 4)
 
+use super::envelope::{Envelope, LengthCounter};
+
 /// Noise Channel con LFSR
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct NoiseChannel {
     // === Registri ===
     length_envelope: u16, // SOUND4CNT_L
@@ -11,8 +13,8 @@ pub struct NoiseChannel {
     enabled: bool,
     lfsr: u16, // Linear Feedback Shift Register
     frequency_timer: u32,
-    envelope_volume: u8,
-    envelope_timer: u32,
+    envelope: Envelope,
+    length: LengthCounter,
 }
 
 impl NoiseChannel {
@@ -23,8 +25,8 @@ impl NoiseChannel {
             enabled: false,
             lfsr: 0x7FFF,
             frequency_timer: 0,
-            envelope_volume: 0,
-            envelope_timer: 0,
+            envelope: Envelope::new(),
+            length: LengthCounter::new(64),
         }
     }
     
@@ -32,11 +34,11 @@ impl NoiseChannel {
         // 0x04000078-0x0400007D
         let offset = addr & 0x0F;
         
+        // Lunghezza e trigger sono write-only: si leggono a 0
         match offset {
-            0x8 => self.length_envelope as u8,
             0x9 => (self.length_envelope >> 8) as u8,
             0xC => self.frequency as u8,
-            0xD => (self.frequency >> 8) as u8,
+            0xD => (self.frequency >> 8) as u8 & 0x40,
             _ => 0,
         }
     }
@@ -45,7 +47,10 @@ impl NoiseChannel {
         let offset = addr & 0x0F;
         
         match offset {
-            0x8 => self.length_envelope = (self.length_envelope & 0xFF00) | value as u16,
+            0x8 => {
+                self.length_envelope = (self.length_envelope & 0xFF00) | value as u16;
+                self.length.load((value & 0x3F) as u32);
+            }
             0x9 => self.length_envelope = (self.length_envelope & 0x00FF) | ((value as u16) << 8),
             0xC => self.frequency = (self.frequency & 0xFF00) | value as u16,
             0xD => {
@@ -61,14 +66,61 @@ impl NoiseChannel {
     fn trigger(&mut self) {
         self.enabled = true;
         self.lfsr = 0x7FFF;
-        self.envelope_volume = (self.length_envelope >> 12) as u8 & 0x0F;
+        self.envelope.trigger(self.length_envelope);
         self.frequency_timer = 0;
-        self.envelope_timer = 0;
+        self.length.trigger();
     }
-    
-    pub fn step(&mut self) {
+
+    /// Periodo dello shift clock in cicli CPU
+    ///
+    /// Frequenza = 524288 Hz / r / 2^(s+1), con r=0 trattato come 0.5
+    pub fn period(&self) -> u32 {
+        let ratio = (self.frequency & 0x07) as u32;
+        let shift = ((self.frequency >> 4) & 0x0F) as u32;
+        let base = if ratio == 0 { 32 } else { 64 * ratio };
+        base << shift
+    }
+
+    /// Modalità 7-bit del LFSR (bit 3 di SOUND4CNT_H)
+    fn is_7bit(&self) -> bool {
+        self.frequency & 0x08 != 0
+    }
+
+    /// Avanza il LFSR di uno shift
+    fn clock_lfsr(&mut self) {
+        let xor = (self.lfsr & 1) ^ ((self.lfsr >> 1) & 1);
+        self.lfsr = (self.lfsr >> 1) | (xor << 14);
+        if self.is_7bit() {
+            self.lfsr = (self.lfsr & !0x40) | (xor << 6);
+        }
+    }
+
+    /// Avanza il canale di `cycles` cicli
+    pub fn advance(&mut self, cycles: u32) {
+        if !self.enabled {
+            return;
+        }
+
+        // Frequency timer: shift del LFSR
+        let period = self.period();
+        self.frequency_timer += cycles;
+        while self.frequency_timer >= period {
+            self.frequency_timer -= period;
+            self.clock_lfsr();
+        }
+    }
+
+    /// Clock del length counter a 256 Hz (bit 14 di SOUND4CNT_H)
+    pub fn clock_length(&mut self) {
+        if self.length.clock(self.frequency & 0x4000 != 0) {
+            self.enabled = false;
+        }
+    }
+
+    /// Clock dell'envelope a 64 Hz (bit 8-10 step time, bit 11 direzione)
+    pub fn clock_envelope(&mut self) {
         if self.enabled {
-            // TODO: LFSR stepping e frequency timer
+            self.envelope.clock(self.length_envelope);
         }
     }
     
@@ -77,11 +129,12 @@ impl NoiseChannel {
         if !self.enabled {
             0
         } else {
-            // Output basato su bit 0 del LFSR
-            if (self.lfsr & 1) != 0 {
-                self.envelope_volume as i8
+            // Output = bit 0 del LFSR invertito
+            let volume = self.envelope.volume() as i8;
+            if (self.lfsr & 1) == 0 {
+                volume
             } else {
-                -(self.envelope_volume as i8)
+                -volume
             }
         }
     }
@@ -119,7 +172,65 @@ mod tests {
         ch.write_byte(0x0400007D, 0x80);
         
         assert!(ch.is_enabled());
-        assert_eq!(ch.envelope_volume, 10);
+        assert_eq!(ch.envelope.volume(), 10);
         assert_eq!(ch.lfsr, 0x7FFF);
     }
+
+    #[test]
+    fn test_read_masks_write_only_bits() {
+        let mut ch = NoiseChannel::new();
+
+        ch.write_byte(0x04000078, 0x3F);
+        ch.write_byte(0x04000079, 0xA3);
+        ch.write_byte(0x0400007C, 0x5B);
+        ch.write_byte(0x0400007D, 0x40);
+
+        assert_eq!(ch.read_byte(0x04000078), 0x00);
+        assert_eq!(ch.read_byte(0x04000079), 0xA3);
+        assert_eq!(ch.read_byte(0x0400007C), 0x5B);
+        assert_eq!(ch.read_byte(0x0400007D), 0x40);
+    }
+
+    #[test]
+    fn test_period() {
+        let mut ch = NoiseChannel::new();
+
+        // r=0, s=0: 32 cicli
+        assert_eq!(ch.period(), 32);
+
+        // r=3, s=2: 64 * 3 << 2
+        ch.frequency = 0x0023;
+        assert_eq!(ch.period(), 768);
+    }
+
+    #[test]
+    fn test_lfsr_7bit_period() {
+        let mut ch = NoiseChannel::new();
+
+        // Volume 15, LFSR 7-bit, r=1, s=0 (64 cicli per shift)
+        ch.length_envelope = 0xF000;
+        ch.write_byte(0x0400007C, 0x09);
+        ch.write_byte(0x0400007D, 0x80);
+
+        // Nessuno shift prima della fine del periodo
+        for _ in 0..63 {
+            ch.advance(1);
+        }
+        assert_eq!(ch.lfsr, 0x7FFF);
+        ch.advance(1);
+        assert_ne!(ch.lfsr, 0x7FFF);
+
+        // Raccogli lo stream di bit per 2 periodi (127 shift ciascuno)
+        let mut bits = Vec::new();
+        for _ in 0..254 {
+            bits.push(ch.get_sample() > 0);
+            for _ in 0..64 {
+                ch.advance(1);
+            }
+        }
+
+        assert!(bits.contains(&true));
+        assert!(bits.contains(&false));
+        assert_eq!(bits[..127], bits[127..]);
+    }
 }
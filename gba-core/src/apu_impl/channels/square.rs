@@ -1,7 +1,9 @@
 // Square Wave Channel (Channel 1 e 2)
 
+use super::envelope::{Envelope, LengthCounter};
+
 /// Square Wave Channel
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SquareChannel {
     /// Ha sweep? (true per CH1, false per CH2)
     has_sweep: bool,
@@ -15,10 +17,10 @@ pub struct SquareChannel {
     enabled: bool,
     phase: u32,
     frequency_timer: u32,
-    envelope_volume: u8,
-    envelope_timer: u32,
+    envelope: Envelope,
     sweep_timer: u32,
     shadow_frequency: u32,
+    length: LengthCounter,
 }
 
 impl SquareChannel {
@@ -31,36 +33,55 @@ impl SquareChannel {
             enabled: false,
             phase: 0,
             frequency_timer: 0,
-            envelope_volume: 0,
-            envelope_timer: 0,
+            envelope: Envelope::new(),
             sweep_timer: 0,
             shadow_frequency: 0,
+            length: LengthCounter::new(64),
+        }
+    }
+
+    /// Normalizza l'offset del registro
+    ///
+    /// CH2 non ha sweep: SOUND2CNT_L sta a 0x68 e SOUND2CNT_H a 0x6C,
+    /// che corrispondono a duty/envelope e frequency di CH1 (0x62 e 0x64).
+    fn register_offset(&self, addr: u32) -> u32 {
+        let offset = addr & 0x0F;
+        if self.has_sweep {
+            offset
+        } else {
+            match offset {
+                0x8..=0x9 => offset - 0x6,
+                0xC..=0xD => offset - 0x8,
+                _ => 0xF,
+            }
         }
     }
 
     pub fn read_byte(&self, addr: u32) -> u8 {
         // CH1: 0x04000060-0x04000065
         // CH2: 0x04000068-0x0400006D
-        let offset = addr & 0x0F;
+        // Lunghezza, frequenza e trigger sono write-only: si leggono a 0
+        let offset = self.register_offset(addr);
 
         match offset {
-            0x0 => self.sweep_reg as u8,
-            0x1 => (self.sweep_reg >> 8) as u8,
-            0x2 => self.duty_envelope as u8,
+            0x0 => self.sweep_reg as u8 & 0x7F,
+            0x2 => self.duty_envelope as u8 & 0xC0,
             0x3 => (self.duty_envelope >> 8) as u8,
-            0x4 => self.frequency as u8,
-            0x5 => (self.frequency >> 8) as u8,
+            0x5 => (self.frequency >> 8) as u8 & 0x40,
             _ => 0,
         }
     }
 
     pub fn write_byte(&mut self, addr: u32, value: u8) {
-        let offset = addr & 0x0F;
+        let offset = self.register_offset(addr);
 
         match offset {
             0x0 => self.sweep_reg = (self.sweep_reg & 0xFF00) | value as u16,
             0x1 => self.sweep_reg = (self.sweep_reg & 0x00FF) | ((value as u16) << 8),
-            0x2 => self.duty_envelope = (self.duty_envelope & 0xFF00) | value as u16,
+            0x2 => {
+                self.duty_envelope = (self.duty_envelope & 0xFF00) | value as u16;
+                self.length.load((value & 0x3F) as u32);
+            }
             0x3 => self.duty_envelope = (self.duty_envelope & 0x00FF) | ((value as u16) << 8),
             0x4 => self.frequency = (self.frequency & 0xFF00) | value as u16,
             0x5 => {
@@ -77,23 +98,106 @@ impl SquareChannel {
     fn trigger(&mut self) {
         self.enabled = true;
         self.phase = 0;
-        self.envelope_volume = (self.duty_envelope >> 12) as u8 & 0x0F;
+        self.envelope.trigger(self.duty_envelope);
         self.frequency_timer = 0;
-        self.envelope_timer = 0;
         self.sweep_timer = 0;
+        self.length.trigger();
 
         if self.has_sweep {
             self.shadow_frequency = (self.frequency & 0x7FF) as u32;
+
+            // Overflow check immediato se shift != 0
+            if self.sweep_shift() != 0 && self.calculate_sweep() > 2047 {
+                self.enabled = false;
+            }
+        }
+    }
+
+    /// Frequenza corrente (11 bit, include l'effetto dello sweep)
+    pub fn current_frequency(&self) -> u32 {
+        if self.has_sweep && self.enabled {
+            self.shadow_frequency
+        } else {
+            (self.frequency & 0x7FF) as u32
+        }
+    }
+
+    /// Sweep shift (bit 0-2 di SOUND1CNT_L)
+    fn sweep_shift(&self) -> u32 {
+        (self.sweep_reg & 0x07) as u32
+    }
+
+    /// Calcola la prossima frequenza dello sweep
+    fn calculate_sweep(&self) -> u32 {
+        let delta = self.shadow_frequency >> self.sweep_shift();
+        if self.sweep_reg & 0x08 != 0 {
+            // Decrease
+            self.shadow_frequency.saturating_sub(delta)
+        } else {
+            // Increase
+            self.shadow_frequency + delta
+        }
+    }
+
+    /// Clock dello sweep a 128 Hz dal frame sequencer (solo CH1)
+    pub fn clock_sweep(&mut self) {
+        if !self.has_sweep || !self.enabled {
+            return;
+        }
+
+        // Sweep time: bit 4-6 di SOUND1CNT_L (0 = disabilitato)
+        let sweep_time = ((self.sweep_reg >> 4) & 0x07) as u32;
+        if sweep_time == 0 {
+            return;
+        }
+
+        self.sweep_timer += 1;
+        if self.sweep_timer < sweep_time {
+            return;
+        }
+        self.sweep_timer = 0;
+
+        let new_frequency = self.calculate_sweep();
+        if new_frequency > 2047 {
+            // Overflow: il canale si spegne
+            self.enabled = false;
+        } else if self.sweep_shift() != 0 {
+            self.shadow_frequency = new_frequency;
+            self.frequency = (self.frequency & !0x7FF) | new_frequency as u16;
+        }
+    }
+
+    /// Avanza il canale di `cycles` cicli
+    pub fn advance(&mut self, cycles: u32) {
+        if !self.enabled {
+            return;
+        }
+
+        // Frequency timer: avanza la fase del duty cycle
+        // Periodo di uno step = (2048 - freq) * 16 cicli
+        let period = (2048 - self.current_frequency()) * 16;
+        self.frequency_timer += cycles;
+        if self.frequency_timer >= period {
+            let steps = self.frequency_timer / period;
+            self.frequency_timer %= period;
+            self.phase = (self.phase + steps) % 8;
+        }
+    }
+
+    /// Clock del length counter a 256 Hz (bit 14 di SOUNDxCNT_X)
+    pub fn clock_length(&mut self) {
+        if self.length.clock(self.frequency & 0x4000 != 0) {
+            self.enabled = false;
         }
     }
 
-    /// Avanza il canale di un ciclo
-    pub fn step(&mut self) {
+    /// Clock dell'envelope a 64 Hz (bit 8-10 step time, bit 11 direzione)
+    pub fn clock_envelope(&mut self) {
         if self.enabled {
-            // TODO: Implementare frequency timer, envelope, sweep
-            // Per ora placeholder
+            self.envelope.clock(self.duty_envelope);
         }
     }
+
     /// Genera un sample audio (-15 a +15)
     pub fn get_sample(&self) -> i8 {
         if !self.enabled {
@@ -111,10 +215,11 @@ impl SquareChannel {
             let pattern = duty_patterns[duty];
             let phase_index = (self.phase % 8) as usize;
 
+            let volume = self.envelope.volume() as i8;
             if pattern[phase_index] != 0 {
-                self.envelope_volume as i8
+                volume
             } else {
-                -(self.envelope_volume as i8)
+                -volume
             }
         }
     }
@@ -146,10 +251,31 @@ mod tests {
         ch.duty_envelope = 0xF000; // Volume 15
 
         // Trigger
-        ch.write_byte(0x04000065, 0x80);
+        ch.write_byte(0x0400006D, 0x80);
 
         assert!(ch.is_enabled());
-        assert_eq!(ch.envelope_volume, 15);
+        assert_eq!(ch.envelope.volume(), 15);
+    }
+
+    #[test]
+    fn test_read_masks_write_only_bits() {
+        let mut ch = SquareChannel::new(true);
+
+        ch.write_byte(0x04000060, 0xFF);
+        ch.write_byte(0x04000062, 0xFF);
+        ch.write_byte(0x04000063, 0xF7);
+        ch.write_byte(0x04000064, 0xFF);
+        ch.write_byte(0x04000065, 0x47);
+
+        // Sweep: bit 0-6; duty sì, lunghezza no; envelope intero
+        assert_eq!(ch.read_byte(0x04000060), 0x7F);
+        assert_eq!(ch.read_byte(0x04000061), 0x00);
+        assert_eq!(ch.read_byte(0x04000062), 0xC0);
+        assert_eq!(ch.read_byte(0x04000063), 0xF7);
+
+        // Della frequenza resta solo il bit di length enable
+        assert_eq!(ch.read_byte(0x04000064), 0x00);
+        assert_eq!(ch.read_byte(0x04000065), 0x40);
     }
 
     #[test]
@@ -167,4 +293,83 @@ mod tests {
         ch.phase = 1;
         assert_eq!(ch.get_sample(), -10); // pattern[1] = 0
     }
+
+    #[test]
+    fn test_sweep_increase_overflow() {
+        let mut ch = SquareChannel::new(true);
+
+        // Sweep time 1, increase, shift 1
+        ch.write_byte(0x04000060, 0x11);
+        ch.duty_envelope = 0xF000;
+
+        // Frequenza iniziale 1024 + trigger
+        ch.write_byte(0x04000064, 0x00);
+        ch.write_byte(0x04000065, 0x84);
+        assert!(ch.is_enabled());
+        assert_eq!(ch.current_frequency(), 1024);
+
+        // Primo step dello sweep: 1024 + 512 = 1536
+        ch.clock_sweep();
+        assert_eq!(ch.current_frequency(), 1536);
+        assert!(ch.is_enabled());
+
+        // Secondo step: 1536 + 768 = 2304 > 2047 -> canale disabilitato
+        ch.clock_sweep();
+        assert!(!ch.is_enabled());
+    }
+
+    #[test]
+    fn test_square_wave_symmetric() {
+        let mut ch = SquareChannel::new(false);
+
+        // Volume 8, duty 50%, frequenza 2047 (16 cicli per step di fase)
+        ch.write_byte(0x04000068, 0x80);
+        ch.write_byte(0x04000069, 0x80);
+        ch.write_byte(0x0400006C, 0xFF);
+        ch.write_byte(0x0400006D, 0x87);
+        assert!(ch.is_enabled());
+        assert_eq!(ch.current_frequency(), 2047);
+
+        // Un periodo completo = 8 step * 16 cicli
+        let mut high = 0;
+        let mut low = 0;
+        for _ in 0..128 {
+            match ch.get_sample() {
+                8 => high += 1,
+                -8 => low += 1,
+                s => panic!("unexpected sample {}", s),
+            }
+            ch.advance(1);
+        }
+
+        assert_eq!(high, 64);
+        assert_eq!(low, 64);
+        assert_eq!(ch.phase, 0);
+    }
+
+    #[test]
+    fn test_envelope_decrease() {
+        let mut ch = SquareChannel::new(false);
+
+        // Volume 2, decrease, step time 1
+        ch.duty_envelope = 0x2100;
+        ch.trigger();
+
+        ch.clock_envelope();
+        assert_eq!(ch.envelope.volume(), 1);
+    }
+
+    #[test]
+    fn test_length_counter_disables() {
+        let mut ch = SquareChannel::new(false);
+
+        // Length 63 -> 1 tick, length enable + trigger
+        ch.write_byte(0x04000068, 0x3F);
+        ch.write_byte(0x04000069, 0xF0);
+        ch.write_byte(0x0400006D, 0xC0);
+        assert!(ch.is_enabled());
+
+        ch.clock_length();
+        assert!(!ch.is_enabled());
+    }
 }
@@ -1,20 +1,35 @@
 // Wave Output Channel (Channel 3)
+//
+// Sul GBA la Wave RAM ha due banchi da 32 sample a 4 bit (16 byte ciascuno).
+// SOUND3CNT_L bit 6 sceglie il banco in riproduzione, mentre la CPU accede
+// sempre all'altro; con il bit 5 (dimensione 64) i due banchi vengono
+// riprodotti uno dopo l'altro come un'unica forma d'onda da 64 sample.
+
+use super::envelope::LengthCounter;
+
+/// Sample a 4 bit per banco
+const SAMPLES_PER_BANK: usize = 32;
+
+/// Byte di Wave RAM per banco
+const BANK_SIZE: usize = 16;
 
 /// Wave Output Channel con Wave RAM
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct WaveChannel {
     // === Registri ===
     control: u16,       // SOUND3CNT_L
     length_volume: u16, // SOUND3CNT_H
     frequency: u16,     // SOUND3CNT_X
 
-    /// Wave RAM - 32 sample * 4-bit (16 byte)
-    wave_ram: [u8; 16],
+    /// Wave RAM - 2 banchi * 32 sample * 4-bit (32 byte)
+    wave_ram: [u8; 2 * BANK_SIZE],
 
     // === State ===
     enabled: bool,
     frequency_timer: u32,
+    /// Sample corrente, 0-63 su entrambi i banchi
     sample_index: usize,
+    length: LengthCounter,
 }
 
 impl WaveChannel {
@@ -23,10 +38,11 @@ impl WaveChannel {
             control: 0,
             length_volume: 0,
             frequency: 0,
-            wave_ram: [0; 16],
+            wave_ram: [0; 2 * BANK_SIZE],
             enabled: false,
             frequency_timer: 0,
             sample_index: 0,
+            length: LengthCounter::new(256),
         }
     }
 
@@ -34,13 +50,11 @@ impl WaveChannel {
         // 0x04000070-0x04000075
         let offset = addr & 0x0F;
 
+        // Lunghezza, frequenza e trigger sono write-only: si leggono a 0
         match offset {
-            0x0 => self.control as u8,
-            0x1 => (self.control >> 8) as u8,
-            0x2 => self.length_volume as u8,
-            0x3 => (self.length_volume >> 8) as u8,
-            0x4 => self.frequency as u8,
-            0x5 => (self.frequency >> 8) as u8,
+            0x0 => self.control as u8 & 0xE0,
+            0x3 => (self.length_volume >> 8) as u8 & 0xE0,
+            0x5 => (self.frequency >> 8) as u8 & 0x40,
             _ => 0,
         }
     }
@@ -49,9 +63,18 @@ impl WaveChannel {
         let offset = addr & 0x0F;
 
         match offset {
-            0x0 => self.control = (self.control & 0xFF00) | value as u16,
+            0x0 => {
+                self.control = (self.control & 0xFF00) | value as u16;
+                // Bit 7 = DAC: spegnerlo ferma subito il canale
+                if !self.dac_enabled() {
+                    self.enabled = false;
+                }
+            }
             0x1 => self.control = (self.control & 0x00FF) | ((value as u16) << 8),
-            0x2 => self.length_volume = (self.length_volume & 0xFF00) | value as u16,
+            0x2 => {
+                self.length_volume = (self.length_volume & 0xFF00) | value as u16;
+                self.length.load(value as u32);
+            }
             0x3 => self.length_volume = (self.length_volume & 0x00FF) | ((value as u16) << 8),
             0x4 => self.frequency = (self.frequency & 0xFF00) | value as u16,
             0x5 => {
@@ -64,69 +87,117 @@ impl WaveChannel {
         }
     }
 
+    /// Legge la Wave RAM dal banco non in riproduzione
     pub fn read_wave_ram(&self, addr: u32) -> u8 {
         let index = (addr - 0x04000090) as usize;
-        if index < 16 {
-            self.wave_ram[index]
+        if index < BANK_SIZE {
+            self.wave_ram[self.cpu_bank() * BANK_SIZE + index]
         } else {
             0
         }
     }
 
+    /// Scrive la Wave RAM nel banco non in riproduzione
     pub fn write_wave_ram(&mut self, addr: u32, value: u8) {
         let index = (addr - 0x04000090) as usize;
-        if index < 16 {
-            self.wave_ram[index] = value;
+        if index < BANK_SIZE {
+            self.wave_ram[self.cpu_bank() * BANK_SIZE + index] = value;
         }
     }
 
+    /// Bit 7 di SOUND3CNT_L: DAC acceso
+    fn dac_enabled(&self) -> bool {
+        (self.control >> 7) & 1 != 0
+    }
+
+    /// Bit 6 di SOUND3CNT_L: banco in riproduzione
+    fn play_bank(&self) -> usize {
+        ((self.control >> 6) & 1) as usize
+    }
+
+    /// Banco visibile alla CPU (l'altro)
+    fn cpu_bank(&self) -> usize {
+        self.play_bank() ^ 1
+    }
+
+    /// Bit 5 di SOUND3CNT_L: forma d'onda da 64 sample (due banchi)
+    fn is_64_samples(&self) -> bool {
+        (self.control >> 5) & 1 != 0
+    }
+
     fn trigger(&mut self) {
-        // Bit 7 di control = channel enable
-        let channel_enabled = (self.control >> 7) & 1 != 0;
-        self.enabled = channel_enabled;
-        self.sample_index = 0;
+        self.enabled = self.dac_enabled();
+        self.sample_index = self.play_bank() * SAMPLES_PER_BANK;
         self.frequency_timer = 0;
+        self.length.trigger();
+    }
+
+    /// Periodo di un sample in cicli CPU
+    ///
+    /// Frequenza sample = 2097152 Hz / (2048 - freq)
+    pub fn period(&self) -> u32 {
+        (2048 - (self.frequency & 0x7FF) as u32) * 8
+    }
+
+    /// Passa al sample successivo
+    fn advance_sample(&mut self) {
+        self.sample_index = if self.is_64_samples() {
+            (self.sample_index + 1) % (2 * SAMPLES_PER_BANK)
+        } else {
+            // Resta nel banco selezionato
+            let bank_start = self.play_bank() * SAMPLES_PER_BANK;
+            bank_start + (self.sample_index + 1 - bank_start) % SAMPLES_PER_BANK
+        };
     }
 
-    pub fn step(&mut self) {
-        if self.enabled {
-            // TODO: Frequency timer e sample playback
+    /// Avanza il canale di `cycles` cicli
+    pub fn advance(&mut self, cycles: u32) {
+        if !self.enabled {
+            return;
+        }
+
+        // Frequency timer: avanza la posizione nella Wave RAM
+        let period = self.period();
+        self.frequency_timer += cycles;
+        while self.frequency_timer >= period {
+            self.frequency_timer -= period;
+            self.advance_sample();
         }
     }
 
-    /// Genera un sample audio
+    /// Clock del length counter a 256 Hz (bit 14 di SOUND3CNT_X)
+    pub fn clock_length(&mut self) {
+        if self.length.clock(self.frequency & 0x4000 != 0) {
+            self.enabled = false;
+        }
+    }
+
+    /// Genera un sample audio (-15 a +15)
     pub fn get_sample(&self) -> i8 {
         if !self.enabled {
-            0
+            return 0;
+        }
+
+        // Sample 4-bit: nibble alto per primo
+        let byte = self.wave_ram[(self.sample_index / 2) % self.wave_ram.len()];
+        let sample_4bit = if self.sample_index.is_multiple_of(2) {
+            (byte >> 4) & 0x0F
         } else {
-            // Leggi sample 4-bit da Wave RAM
-            let byte_index = self.sample_index / 2;
-            let nibble_high = self.sample_index.is_multiple_of(2);
-
-            if byte_index >= 16 {
-                0
-            } else {
-                let byte = self.wave_ram[byte_index];
-                let sample_4bit = if nibble_high {
-                    (byte >> 4) & 0x0F
-                } else {
-                    byte & 0x0F
-                };
-
-                // Volume control: bit 13-14 di length_volume
-                let volume_code = (self.length_volume >> 13) & 0x03;
-                let shift = match volume_code {
-                    0 => 4, // Mute (shift right 4 = /16)
-                    1 => 0, // 100%
-                    2 => 1, // 50%
-                    3 => 2, // 25%
-                    _ => 0,
-                };
-
-                // Converti 4-bit (0-15) a signed (-8 a +7)
-                let signed = (sample_4bit as i8) - 8;
-                signed >> shift
-            }
+            byte & 0x0F
+        };
+
+        // Converti 0-15 a -15..+15, stessa scala dei canali square e noise
+        let signed = (sample_4bit as i8) * 2 - 15;
+
+        // Volume: bit 15 forza il 75%, altrimenti bit 13-14
+        if self.length_volume & 0x8000 != 0 {
+            return (signed as i16 * 3 / 4) as i8;
+        }
+        match (self.length_volume >> 13) & 0x03 {
+            0 => 0,          // Mute
+            1 => signed,     // 100%
+            2 => signed / 2, // 50%
+            _ => signed / 4, // 25%
         }
     }
 
@@ -160,6 +231,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_read_masks_write_only_bits() {
+        let mut ch = WaveChannel::new();
+
+        ch.write_byte(0x04000070, 0xFF);
+        ch.write_byte(0x04000072, 0xFF);
+        ch.write_byte(0x04000073, 0x60);
+        ch.write_byte(0x04000074, 0xFF);
+        ch.write_byte(0x04000075, 0x47);
+
+        // Banco e DAC; volume ma non lunghezza; solo length enable
+        assert_eq!(ch.read_byte(0x04000070), 0xE0);
+        assert_eq!(ch.read_byte(0x04000072), 0x00);
+        assert_eq!(ch.read_byte(0x04000073), 0x60);
+        assert_eq!(ch.read_byte(0x04000074), 0x00);
+        assert_eq!(ch.read_byte(0x04000075), 0x40);
+    }
+
     #[test]
     fn test_trigger() {
         let mut ch = WaveChannel::new();
@@ -172,4 +261,97 @@ mod tests {
 
         assert!(ch.is_enabled());
     }
+
+    /// Carica una rampa 0-15 (due volte) nel banco visibile alla CPU
+    fn load_ramp(ch: &mut WaveChannel) {
+        for i in 0..16u32 {
+            let hi = ((2 * i) % 16) as u8;
+            let lo = ((2 * i + 1) % 16) as u8;
+            ch.write_wave_ram(0x04000090 + i, (hi << 4) | lo);
+        }
+    }
+
+    #[test]
+    fn test_playback_follows_ramp() {
+        let mut ch = WaveChannel::new();
+
+        // Banco 1 visibile alla CPU mentre è selezionato il banco 0
+        load_ramp(&mut ch);
+
+        // DAC on, banco 0, volume 100%, freq 2047 (8 cicli per sample)
+        ch.write_byte(0x04000070, 0x80);
+        ch.write_byte(0x04000073, 0x20);
+        ch.write_byte(0x04000074, 0xFF);
+        ch.write_byte(0x04000075, 0x87);
+        assert_eq!(ch.period(), 8);
+
+        // Il banco 0 è vuoto: sample 0 -> -15
+        assert_eq!(ch.get_sample(), -15);
+
+        // Passa al banco 1 (la CPU ora vede il banco 0) e riparte
+        ch.write_byte(0x04000070, 0xC0);
+        ch.write_byte(0x04000075, 0x87);
+
+        for step in 0..32 {
+            let expected = (step % 16) as i8 * 2 - 15;
+            assert_eq!(ch.get_sample(), expected, "sample {}", step);
+            for _ in 0..ch.period() {
+                ch.advance(1);
+            }
+        }
+
+        // Modalità 32 sample: torna all'inizio dello stesso banco
+        assert_eq!(ch.get_sample(), -15);
+    }
+
+    #[test]
+    fn test_64_sample_mode_plays_both_banks() {
+        let mut ch = WaveChannel::new();
+
+        // Banco 1 = rampa, banco 0 = tutti 0xF
+        load_ramp(&mut ch);
+        ch.write_byte(0x04000070, 0x40);
+        for i in 0..16 {
+            ch.write_wave_ram(0x04000090 + i, 0xFF);
+        }
+
+        // DAC on, 64 sample, parte dal banco 0
+        ch.write_byte(0x04000070, 0xA0);
+        ch.write_byte(0x04000073, 0x20);
+        ch.write_byte(0x04000074, 0xFF);
+        ch.write_byte(0x04000075, 0x87);
+
+        let mut samples = Vec::new();
+        for _ in 0..64 {
+            samples.push(ch.get_sample());
+            for _ in 0..ch.period() {
+                ch.advance(1);
+            }
+        }
+
+        assert!(samples[..32].iter().all(|&s| s == 15));
+        assert_eq!(samples[32], -15);
+        assert_eq!(samples[47], 15);
+        // Dopo 64 sample ricomincia dal banco 0
+        assert_eq!(ch.get_sample(), 15);
+    }
+
+    #[test]
+    fn test_volume_shift() {
+        let mut ch = WaveChannel::new();
+        ch.write_byte(0x04000070, 0x40);
+        ch.write_wave_ram(0x04000090, 0xF0);
+        ch.write_byte(0x04000070, 0x80);
+        ch.write_byte(0x04000075, 0x80);
+
+        let volumes = [(0x00, 0), (0x20, 15), (0x40, 7), (0x60, 3), (0x80, 11)];
+        for (nr32, expected) in volumes {
+            ch.write_byte(0x04000073, nr32);
+            assert_eq!(ch.get_sample(), expected, "NR32 {:#04x}", nr32);
+        }
+
+        // Spegnere il DAC ferma il canale
+        ch.write_byte(0x04000070, 0x00);
+        assert!(!ch.is_enabled());
+    }
 }
@@ -1,12 +1,14 @@
 // Direct Sound A/B (DMA Audio)
 
 /// Direct Sound Channel (A o B)
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct DirectSound {
     /// FIFO buffer 32-byte
     fifo: [i8; 32],
     read_pos: usize,
     write_pos: usize,
+    /// Sample corrente in uscita (tenuto fino al prossimo overflow del timer)
+    current: i8,
 }
 
 impl DirectSound {
@@ -15,6 +17,7 @@ impl DirectSound {
             fifo: [0; 32],
             read_pos: 0,
             write_pos: 0,
+            current: 0,
         }
     }
 
@@ -35,6 +38,30 @@ impl DirectSound {
         }
     }
 
+    /// Numero di byte presenti nel FIFO
+    pub fn len(&self) -> usize {
+        (self.write_pos + 32 - self.read_pos) % 32
+    }
+
+    /// Verifica se il FIFO è vuoto
+    pub fn is_empty(&self) -> bool {
+        self.read_pos == self.write_pos
+    }
+
+    /// Sample corrente verso il mixer
+    pub fn sample(&self) -> i8 {
+        self.current
+    }
+
+    /// Overflow del timer associato: passa al sample successivo.
+    /// Ritorna true se il FIFO è sceso a metà e serve una DMA di ricarica
+    pub fn timer_overflow(&mut self) -> bool {
+        if !self.is_empty() {
+            self.current = self.read_sample();
+        }
+        self.len() <= 16
+    }
+
     /// Resetta il FIFO
     pub fn reset_fifo(&mut self) {
         self.read_pos = 0;
@@ -115,4 +142,32 @@ mod tests {
         // FIFO ha spazio (10 consumati + 10 nuovi = 20/32)
         assert!(ds.has_space());
     }
+
+    #[test]
+    fn test_timer_overflow_latches_sample() {
+        let mut ds = DirectSound::new();
+        for i in 0..20 {
+            ds.write_sample(i as i8 + 1);
+        }
+
+        // Il sample cambia solo all'overflow del timer
+        assert_eq!(ds.sample(), 0);
+        assert!(!ds.timer_overflow(), "19 byte: nessuna richiesta DMA");
+        assert_eq!(ds.sample(), 1);
+        assert_eq!(ds.sample(), 1);
+
+        // Sotto metà FIFO serve la ricarica
+        for _ in 0..2 {
+            ds.timer_overflow();
+        }
+        assert!(ds.timer_overflow());
+        assert_eq!(ds.len(), 16);
+
+        // FIFO vuoto: resta l'ultimo sample
+        let mut empty = DirectSound::new();
+        empty.write_sample(-5);
+        empty.timer_overflow();
+        assert!(empty.timer_overflow());
+        assert_eq!(empty.sample(), -5);
+    }
 }
@@ -0,0 +1,88 @@
+// Frame Sequencer - Clock a bassa frequenza dei canali PSG
+//
+// Un contatore a 512 Hz scorre 8 step; ogni step attiva alcune unità:
+//
+//   step:      0  1  2  3  4  5  6  7
+//   length:    x     x     x     x        (256 Hz)
+//   sweep:           x           x        (128 Hz)
+//   envelope:                       x     (64 Hz)
+
+/// Cicli CPU per step del frame sequencer (16.78 MHz / 512 Hz)
+pub const FRAME_SEQUENCER_CYCLES: u32 = 32768;
+
+/// Unità da clockare in questo ciclo
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SequencerClocks {
+    pub length: bool,
+    pub sweep: bool,
+    pub envelope: bool,
+}
+
+/// Frame sequencer dell'APU
+#[derive(Debug, Default, Clone)]
+pub struct FrameSequencer {
+    timer: u32,
+    step: u8,
+}
+
+impl FrameSequencer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cicli CPU mancanti al prossimo step
+    pub fn cycles_until_step(&self) -> u32 {
+        FRAME_SEQUENCER_CYCLES - self.timer
+    }
+
+    /// Avanza di `cycles` cicli CPU, al massimo fino al prossimo step;
+    /// a fine periodo ritorna le unità da clockare
+    pub fn advance(&mut self, cycles: u32) -> SequencerClocks {
+        debug_assert!(cycles <= self.cycles_until_step());
+        self.timer += cycles;
+        if self.timer < FRAME_SEQUENCER_CYCLES {
+            return SequencerClocks::default();
+        }
+        self.timer = 0;
+
+        let step = self.step;
+        self.step = (self.step + 1) % 8;
+        SequencerClocks {
+            length: step.is_multiple_of(2),
+            sweep: step == 2 || step == 6,
+            envelope: step == 7,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_rates() {
+        let mut seq = FrameSequencer::new();
+        let (mut length, mut sweep, mut envelope) = (0, 0, 0);
+
+        // Un ciclo completo di 8 step = 1/64 di secondo
+        for _ in 0..8 * FRAME_SEQUENCER_CYCLES {
+            let clocks = seq.advance(1);
+            length += clocks.length as u32;
+            sweep += clocks.sweep as u32;
+            envelope += clocks.envelope as u32;
+        }
+
+        assert_eq!((length, sweep, envelope), (4, 2, 1));
+        assert_eq!(seq.step, 0);
+    }
+
+    #[test]
+    fn test_no_clock_before_period() {
+        let mut seq = FrameSequencer::new();
+        for _ in 0..FRAME_SEQUENCER_CYCLES - 1 {
+            assert_eq!(seq.advance(1), SequencerClocks::default());
+        }
+        assert!(seq.advance(1).length);
+        assert_eq!(seq.step, 1);
+    }
+}
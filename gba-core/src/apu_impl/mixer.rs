@@ -4,17 +4,20 @@ use super::channels::{SquareChannel, WaveChannel, NoiseChannel};
 use super::direct_sound::DirectSound;
 use super::registers::SoundRegisters;
 
+/// Unità del mixer per step del DAC (Direct Sound al 100% = sample * 4)
+const MIXER_UNITS_PER_DAC_STEP: i32 = 8;
+
 /// Mixa tutti i 6 canali audio (4 GB + 2 Direct Sound)
-/// Ritorna sample stereo (left, right) in formato i16
+/// Ritorna sample stereo (left, right) in step del DAC, prima del bias
 pub fn mix_audio(
     ch1: &mut SquareChannel,
     ch2: &mut SquareChannel,
     ch3: &mut WaveChannel,
     ch4: &mut NoiseChannel,
-    dsa: &mut DirectSound,
-    dsb: &mut DirectSound,
+    dsa: &DirectSound,
+    dsb: &DirectSound,
     regs: &SoundRegisters,
-) -> (i16, i16) {
+) -> (i32, i32) {
     let mut left: i32 = 0;
     let mut right: i32 = 0;
     
@@ -82,8 +85,9 @@ pub fn mix_audio(
     }
     
     // === Mix Direct Sound A ===
+    // Il sample avanza solo all'overflow del timer scelto in SOUNDCNT_H
     
-    let dsa_sample = dsa.read_sample() as i32;
+    let dsa_sample = dsa.sample() as i32;
     let dsa_vol = if (regs.soundcnt_h >> 2) & 1 != 0 { 4 } else { 2 }; // 100% o 50%
     
     if (regs.soundcnt_h >> 9) & 1 != 0 { // Left enable
@@ -95,7 +99,7 @@ pub fn mix_audio(
     
     // === Mix Direct Sound B ===
     
-    let dsb_sample = dsb.read_sample() as i32;
+    let dsb_sample = dsb.sample() as i32;
     let dsb_vol = if (regs.soundcnt_h >> 3) & 1 != 0 { 4 } else { 2 };
     
     if (regs.soundcnt_h >> 13) & 1 != 0 { // Left enable
@@ -105,12 +109,9 @@ pub fn mix_audio(
         right += dsb_sample * dsb_vol * 8;
     }
     
-    // === Clamp e converti a i16 ===
-    
-    let left_final = left.clamp(-32768, 32767) as i16;
-    let right_final = right.clamp(-32768, 32767) as i16;
+    // === Scala al DAC (bias e clamp in SoundRegisters::apply_bias) ===
     
-    (left_final, right_final)
+    (left / MIXER_UNITS_PER_DAC_STEP, right / MIXER_UNITS_PER_DAC_STEP)
 }
 
 #[cfg(test)]
@@ -123,14 +124,41 @@ mod tests {
         let mut ch2 = SquareChannel::new(false);
         let mut ch3 = WaveChannel::new();
         let mut ch4 = NoiseChannel::new();
-        let mut dsa = DirectSound::new();
-        let mut dsb = DirectSound::new();
+        let dsa = DirectSound::new();
+        let dsb = DirectSound::new();
         let regs = SoundRegisters::new();
         
         // Tutti i canali disabilitati
-        let (left, right) = mix_audio(&mut ch1, &mut ch2, &mut ch3, &mut ch4, &mut dsa, &mut dsb, &regs);
+        let (left, right) = mix_audio(&mut ch1, &mut ch2, &mut ch3, &mut ch4, &dsa, &dsb, &regs);
         
         assert_eq!(left, 0);
         assert_eq!(right, 0);
     }
+    
+    #[test]
+    fn test_direct_sound_stereo_routing() {
+        let mut ch1 = SquareChannel::new(true);
+        let mut ch2 = SquareChannel::new(false);
+        let mut ch3 = WaveChannel::new();
+        let mut ch4 = NoiseChannel::new();
+        let mut dsa = DirectSound::new();
+        let mut dsb = DirectSound::new();
+        let mut regs = SoundRegisters::new();
+        
+        dsa.write_sample(100);
+        dsa.timer_overflow();
+        dsb.write_sample(-50);
+        dsb.timer_overflow();
+        
+        // A al 100% solo a sinistra, B al 50% solo a destra
+        regs.soundcnt_h = 0x0200 | 0x1000 | 0x0004;
+        let (left, right) = mix_audio(&mut ch1, &mut ch2, &mut ch3, &mut ch4, &dsa, &dsb, &regs);
+        assert_eq!(left, 100 * 4);
+        assert_eq!(right, -50 * 2);
+        
+        // A su entrambi i lati al 50%
+        regs.soundcnt_h = 0x0300;
+        let (left, right) = mix_audio(&mut ch1, &mut ch2, &mut ch3, &mut ch4, &dsa, &dsb, &regs);
+        assert_eq!((left, right), (100 * 2, 100 * 2));
+    }
 }
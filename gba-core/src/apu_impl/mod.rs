@@ -2,21 +2,42 @@
 //
 // Struttura modulare per gestire l'audio del GBA:
 // - channels/: I 4 canali GB (square1, square2, wave, noise)
+// - frame_sequencer.rs: Clock di length, sweep ed envelope (512 Hz)
 // - direct_sound.rs: Direct Sound A/B (DMA audio)
 // - mixer.rs: Mixing dei 6 canali
 // - registers.rs: Registri audio (SOUNDCNT_L/H/X, SOUNDBIAS)
+// - resampler.rs: Conversione al sample rate dell'host
+// - sink.rs: Destinazioni dei sample (trait AudioSink, registrazione WAV)
 
 mod channels;
 mod direct_sound;
+mod frame_sequencer;
 mod mixer;
 mod registers;
+mod resampler;
+mod sink;
 
 pub use registers::SoundRegisters;
+pub use resampler::AudioResampler;
+pub use sink::AudioSink;
+#[cfg(feature = "std")]
+pub use sink::WavRecorder;
+use alloc::vec::Vec;
 use channels::{SquareChannel, WaveChannel, NoiseChannel};
 use direct_sound::DirectSound;
+use frame_sequencer::FrameSequencer;
+
+/// Sample rate nativo dell'APU (SOUNDBIAS default)
+pub const NATIVE_SAMPLE_RATE: u32 = 32768;
+
+/// Cicli CPU per sample nativo (16.78 MHz / 32768 Hz)
+pub const CYCLES_PER_SAMPLE: u32 = 512;
+
+/// Latenza di default del buffer audio in uscita
+const DEFAULT_LATENCY_MS: u32 = 100;
 
 /// GBA Audio Processing Unit
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct APU {
     /// Registri audio condivisi
     registers: SoundRegisters,
@@ -41,6 +62,15 @@ pub struct APU {
     
     /// Frame counter per timing
     frame_counter: u64,
+
+    /// Clock di length counter, sweep ed envelope dei canali GB
+    frame_sequencer: FrameSequencer,
+
+    /// Resampler verso il sample rate dell'host
+    resampler: AudioResampler,
+
+    /// Cicli accumulati dall'ultimo sample generato
+    sample_cycles: u32,
 }
 
 impl APU {
@@ -55,6 +85,106 @@ impl APU {
             direct_sound_a: DirectSound::new(),
             direct_sound_b: DirectSound::new(),
             frame_counter: 0,
+            frame_sequencer: FrameSequencer::new(),
+            resampler: AudioResampler::new(
+                NATIVE_SAMPLE_RATE,
+                NATIVE_SAMPLE_RATE,
+                DEFAULT_LATENCY_MS,
+            ),
+            sample_cycles: 0,
+        }
+    }
+
+    /// Imposta il sample rate del device audio
+    pub fn set_output_rate(&mut self, hz: u32) {
+        self.resampler.set_output_rate(hz);
+    }
+
+    /// Sample rate del device audio
+    pub fn output_rate(&self) -> u32 {
+        self.resampler.output_rate()
+    }
+
+    /// Imposta la latenza del buffer audio in uscita
+    pub fn set_latency(&mut self, latency_ms: u32) {
+        self.resampler.set_latency(latency_ms);
+    }
+
+    /// Genera un sample nativo e lo accoda al resampler
+    pub fn queue_sample(&mut self) {
+        let sample = self.generate_sample();
+        self.resampler.push(sample);
+    }
+
+    /// Avanza l'APU di `cycles` cicli CPU
+    ///
+    /// Genera un sample nativo ogni `CYCLES_PER_SAMPLE` cicli (o meno,
+    /// se SOUNDBIAS seleziona un sample rate più alto). I cicli vengono
+    /// consumati a blocchi fino al prossimo step del frame sequencer o
+    /// al prossimo sample, non uno alla volta.
+    pub fn tick(&mut self, cycles: u32) {
+        let mut remaining = cycles;
+        while remaining > 0 {
+            let cycles_per_sample = self.registers.cycles_per_sample();
+            let until_sample = cycles_per_sample.saturating_sub(self.sample_cycles).max(1);
+            let chunk = remaining
+                .min(until_sample)
+                .min(self.frame_sequencer.cycles_until_step());
+
+            self.advance(chunk);
+            remaining -= chunk;
+
+            self.sample_cycles += chunk;
+            while self.sample_cycles >= cycles_per_sample {
+                self.sample_cycles -= cycles_per_sample;
+                self.queue_sample();
+            }
+        }
+    }
+
+    /// Copia dello stato emulato per i save state
+    ///
+    /// Canali PSG, frame sequencer, FIFO Direct Sound e registri; il
+    /// resampler con i sample già pronti per l'host resta fuori.
+    pub(crate) fn snapshot(&self) -> Self {
+        Self {
+            registers: self.registers.clone(),
+            channel1: self.channel1.clone(),
+            channel2: self.channel2.clone(),
+            channel3: self.channel3.clone(),
+            channel4: self.channel4.clone(),
+            direct_sound_a: self.direct_sound_a.clone(),
+            direct_sound_b: self.direct_sound_b.clone(),
+            frame_counter: self.frame_counter,
+            frame_sequencer: self.frame_sequencer.clone(),
+            resampler: AudioResampler::new(NATIVE_SAMPLE_RATE, NATIVE_SAMPLE_RATE, 0),
+            sample_cycles: self.sample_cycles,
+        }
+    }
+
+    /// Ripristina uno stato catturato con `snapshot`, mantenendo il
+    /// resampler (e quindi rate e latenza) dell'host
+    pub(crate) fn restore(&mut self, state: &Self) {
+        let resampler = core::mem::replace(&mut self.resampler, AudioResampler::new(1, 1, 0));
+        *self = state.snapshot();
+        self.resampler = resampler;
+        self.resampler.set_input_rate(self.registers.sample_rate());
+    }
+
+    /// Numero di sample stereo pronti al rate dell'host
+    pub fn pending_samples(&self) -> usize {
+        self.resampler.len()
+    }
+
+    /// Svuota i sample pronti al rate dell'host (stereo interleaved)
+    pub fn drain_samples(&mut self, out: &mut Vec<i16>) {
+        self.resampler.drain_into(out);
+    }
+
+    /// Passa i sample pronti al rate dell'host a `sink`
+    pub fn drain_to_sink(&mut self, sink: &mut dyn AudioSink) {
+        while let Some((left, right)) = self.resampler.pop() {
+            sink.push_sample(left, right);
         }
     }
     
@@ -105,6 +235,11 @@ impl APU {
             0x04000080..=0x04000089 => {
                 self.registers.write_byte(addr, value);
                 
+                // SOUNDBIAS bit 14-15: nuovo sample rate nativo
+                if addr == 0x04000089 {
+                    self.resampler.set_input_rate(self.registers.sample_rate());
+                }
+                
                 // Reset FIFO se richiesto
                 if addr == 0x04000083 {
                     if value & 0x08 != 0 {
@@ -116,6 +251,10 @@ impl APU {
                 }
             }
             
+            // FIFO Direct Sound (scritti da CPU o DMA sound)
+            0x040000A0..=0x040000A3 => self.write_fifo_a(value as i8),
+            0x040000A4..=0x040000A7 => self.write_fifo_b(value as i8),
+            
             _ => {}
         }
     }
@@ -143,6 +282,20 @@ impl APU {
         self.direct_sound_b.write_sample(value);
     }
     
+    /// Overflow del timer 0 o 1: i FIFO agganciati a quel timer
+    /// (SOUNDCNT_H bit 10 per A, bit 14 per B) passano al sample successivo.
+    /// Ritorna (A, B): true se il FIFO va ricaricato via DMA
+    pub fn timer_overflow(&mut self, timer: usize) -> (bool, bool) {
+        let soundcnt_h = self.registers.soundcnt_h;
+        let fifo_a = soundcnt_h & 0x0300 != 0 && (soundcnt_h >> 10) as usize & 1 == timer;
+        let fifo_b = soundcnt_h & 0x3000 != 0 && (soundcnt_h >> 14) as usize & 1 == timer;
+        
+        (
+            fifo_a && self.direct_sound_a.timer_overflow(),
+            fifo_b && self.direct_sound_b.timer_overflow(),
+        )
+    }
+    
     /// Genera un sample audio stereo (left, right)
     /// Chiamato a 32768 Hz (sample rate default)
     pub fn generate_sample(&mut self) -> (i16, i16) {
@@ -151,28 +304,63 @@ impl APU {
         }
         
         // Mix tutti i canali
-        mixer::mix_audio(
+        let (left, right) = mixer::mix_audio(
             &mut self.channel1,
             &mut self.channel2,
             &mut self.channel3,
             &mut self.channel4,
-            &mut self.direct_sound_a,
-            &mut self.direct_sound_b,
+            &self.direct_sound_a,
+            &self.direct_sound_b,
             &self.registers,
-        )
+        );
+        
+        // Bias e clamp del DAC
+        (self.registers.apply_bias(left), self.registers.apply_bias(right))
     }
     
     /// Avanza l'APU di un ciclo
     pub fn step(&mut self) {
-        self.frame_counter += 1;
+        self.advance(1);
+    }
+
+    /// Avanza canali e frame sequencer di `cycles` cicli, senza
+    /// superare il prossimo step del sequencer
+    fn advance(&mut self, cycles: u32) {
+        self.frame_counter += cycles as u64;
         
         // Step sui canali se abilitati
         if self.registers.is_master_enabled() {
-            self.channel1.step();
-            self.channel2.step();
-            self.channel3.step();
-            self.channel4.step();
+            self.channel1.advance(cycles);
+            self.channel2.advance(cycles);
+            self.channel3.advance(cycles);
+            self.channel4.advance(cycles);
+
+            let clocks = self.frame_sequencer.advance(cycles);
+            if clocks.length {
+                self.channel1.clock_length();
+                self.channel2.clock_length();
+                self.channel3.clock_length();
+                self.channel4.clock_length();
+            }
+            if clocks.sweep {
+                self.channel1.clock_sweep();
+            }
+            if clocks.envelope {
+                self.channel1.clock_envelope();
+                self.channel2.clock_envelope();
+                self.channel4.clock_envelope();
+            }
         }
+
+        self.update_channel_status();
+    }
+
+    /// Aggiorna i bit 0-3 di SOUNDCNT_X (canali attivi)
+    fn update_channel_status(&mut self) {
+        self.registers.set_channel_status(0, self.channel1.is_enabled());
+        self.registers.set_channel_status(1, self.channel2.is_enabled());
+        self.registers.set_channel_status(2, self.channel3.is_enabled());
+        self.registers.set_channel_status(3, self.channel4.is_enabled());
     }
 }
 
@@ -219,4 +407,213 @@ mod tests {
         apu.write_halfword(0x04000080, 0x1234);
         assert_eq!(apu.read_halfword(0x04000080), 0x1234);
     }
+
+    #[test]
+    fn test_output_rate() {
+        let mut apu = APU::new();
+        assert_eq!(apu.output_rate(), NATIVE_SAMPLE_RATE);
+
+        apu.set_output_rate(48000);
+        assert_eq!(apu.output_rate(), 48000);
+
+        // 1 secondo di audio, limitato dalla latenza di default (100 ms a 48 kHz)
+        for _ in 0..NATIVE_SAMPLE_RATE {
+            apu.queue_sample();
+        }
+        let mut out = Vec::new();
+        apu.drain_samples(&mut out);
+        assert_eq!(out.len(), 4800 * 2);
+    }
+
+    #[test]
+    fn test_length_counter_silences_after_sequencer_ticks() {
+        use frame_sequencer::FRAME_SEQUENCER_CYCLES;
+
+        let mut apu = APU::new();
+        apu.write_byte(0x04000084, 0x80);
+
+        // Channel 2: length 61 (3 clock a 256 Hz), volume 15, length enable
+        apu.write_byte(0x04000068, 0x3D);
+        apu.write_byte(0x04000069, 0xF0);
+        apu.write_byte(0x0400006D, 0xC0);
+
+        // Il length viene clockato negli step pari: dopo 4 step del
+        // sequencer (step 0 e 2) il canale suona ancora
+        for _ in 0..4 * FRAME_SEQUENCER_CYCLES {
+            apu.step();
+        }
+        assert!(apu.channel2.is_enabled());
+        assert_eq!(apu.channel2.get_sample().abs(), 15);
+
+        // Il terzo clock (step 4) lo spegne
+        for _ in 0..FRAME_SEQUENCER_CYCLES {
+            apu.step();
+        }
+        assert!(!apu.channel2.is_enabled());
+        assert_eq!(apu.channel2.get_sample(), 0);
+        assert_eq!(apu.read_byte(0x04000084) & 0x0F, 0x00);
+    }
+
+    #[test]
+    fn test_channel_status_bits() {
+        let mut apu = APU::new();
+        apu.write_byte(0x04000084, 0x80);
+
+        // Trigger channel 2 con length 63 (1 tick) e length enable
+        apu.write_byte(0x04000068, 0x3F);
+        apu.write_byte(0x04000069, 0xF0);
+        apu.write_byte(0x0400006D, 0xC0);
+
+        apu.step();
+        assert_eq!(apu.read_byte(0x04000084) & 0x0F, 0x02);
+
+        // Scaduto il length counter il bit torna a 0
+        for _ in 0..65536 {
+            apu.step();
+        }
+        assert_eq!(apu.read_byte(0x04000084) & 0x0F, 0x00);
+        assert_eq!(apu.read_byte(0x04000084) & 0x80, 0x80);
+    }
+
+    #[test]
+    fn test_channel_right_disabled() {
+        let mut apu = APU::new();
+        apu.write_byte(0x04000084, 0x80);
+
+        // Volume GB 100%, volume L/R 7, channel 2 solo a sinistra
+        apu.write_halfword(0x04000080, 0x2077);
+        apu.write_halfword(0x04000082, 0x0002);
+
+        // Channel 2: volume 15, duty 50%, trigger
+        apu.write_byte(0x04000068, 0x80);
+        apu.write_byte(0x04000069, 0xF0);
+        apu.write_byte(0x0400006D, 0x80);
+
+        let (left, right) = apu.generate_sample();
+        assert_ne!(left, 0);
+        assert_eq!(right, 0);
+    }
+
+    #[test]
+    fn test_tick_generates_samples() {
+        let mut apu = APU::new();
+
+        apu.tick(CYCLES_PER_SAMPLE - 1);
+        assert_eq!(apu.pending_samples(), 0);
+
+        apu.tick(1);
+        assert_eq!(apu.pending_samples(), 1);
+
+        apu.tick(CYCLES_PER_SAMPLE * 10);
+        assert_eq!(apu.pending_samples(), 11);
+    }
+
+    #[test]
+    fn test_tick_in_blocks_matches_single_cycles() {
+        use frame_sequencer::FRAME_SEQUENCER_CYCLES;
+
+        let setup = || {
+            let mut apu = APU::new();
+            apu.write_byte(0x04000084, 0x80);
+            apu.write_halfword(0x04000080, 0xFF77);
+            // Channel 1: length ed envelope decrescente
+            apu.write_halfword(0x04000062, 0xF13A);
+            apu.write_halfword(0x04000064, 0xC700);
+            // Channel 4: noise 7 bit con length
+            apu.write_halfword(0x04000078, 0xA120);
+            apu.write_halfword(0x0400007C, 0xC019);
+            apu
+        };
+        let mut single = setup();
+        let mut blocks = setup();
+
+        let total = 9 * FRAME_SEQUENCER_CYCLES + 123;
+        for _ in 0..total {
+            single.tick(1);
+        }
+        // Blocchi che scavalcano sample e step del sequencer
+        let mut done = 0;
+        while done < total {
+            let chunk = (total - done).min(40_000);
+            blocks.tick(chunk);
+            done += chunk;
+        }
+
+        let (mut a, mut b) = (Vec::new(), Vec::new());
+        single.drain_samples(&mut a);
+        blocks.drain_samples(&mut b);
+        assert_eq!(a, b);
+        assert_eq!(single.read_byte(0x04000084), blocks.read_byte(0x04000084));
+    }
+
+    #[test]
+    fn test_soundbias_shifts_silence() {
+        let mut apu = APU::new();
+        apu.write_byte(0x04000084, 0x80);
+
+        // Bias di default: il silenzio resta a 0
+        assert_eq!(apu.generate_sample(), (0, 0));
+
+        // Bias 0x300: DC a +0x100 step del DAC
+        apu.write_halfword(0x04000088, 0x0300);
+        assert_eq!(apu.generate_sample(), (0x100 * 64, 0x100 * 64));
+
+        // Bias 0: il silenzio va al minimo del DAC
+        apu.write_halfword(0x04000088, 0x0000);
+        assert_eq!(apu.generate_sample(), (i16::MIN, i16::MIN));
+    }
+
+    #[test]
+    fn test_soundbias_clamps_output() {
+        let mut apu = APU::new();
+        apu.write_byte(0x04000084, 0x80);
+        apu.write_halfword(0x04000088, 0x03FE);
+
+        // Direct Sound A e B al 100% su entrambi i lati, sample massimo
+        apu.write_halfword(0x04000082, 0x330C);
+        apu.write_fifo_a(127);
+        apu.write_fifo_b(127);
+        apu.timer_overflow(0);
+        let (left, right) = apu.generate_sample();
+        assert_eq!(left, (0x3FF - 0x200) * 64);
+        assert_eq!(right, left);
+
+        apu.write_halfword(0x04000088, 0x0000);
+        apu.write_fifo_a(-128);
+        apu.write_fifo_b(-128);
+        apu.timer_overflow(0);
+        assert_eq!(apu.generate_sample(), (i16::MIN, i16::MIN));
+    }
+
+    #[test]
+    fn test_soundbias_sample_rate() {
+        let mut apu = APU::new();
+
+        // 65536 Hz: un sample ogni 256 cicli, resampler senza conversione
+        apu.set_output_rate(65536);
+        apu.write_halfword(0x04000088, 0x4200);
+        apu.tick(CYCLES_PER_SAMPLE * 4);
+        assert_eq!(apu.pending_samples(), 8);
+    }
+
+    #[test]
+    fn test_fifo_follows_selected_timer() {
+        let mut apu = APU::new();
+        apu.write_byte(0x04000084, 0x80);
+
+        // FIFO A al 100%, solo sinistra, agganciato al timer 1
+        apu.write_halfword(0x04000082, 0x0604);
+        apu.write_halfword(0x040000A0, 0x1020);
+        let silence = apu.generate_sample();
+
+        // Il timer 0 non tocca il FIFO A
+        assert_eq!(apu.timer_overflow(0), (false, false));
+        assert_eq!(apu.generate_sample(), silence);
+
+        // Overflow del timer 1: primo byte in uscita, FIFO da ricaricare
+        assert_eq!(apu.timer_overflow(1), (true, false));
+        let (left, right) = apu.generate_sample();
+        assert_eq!(left, silence.0 + 0x20 * 4 * 64);
+        assert_eq!(right, silence.1);
+    }
 }
@@ -1,7 +1,16 @@
 // Registri di controllo audio
 
+/// Bit di SOUNDBIAS con il livello di bias (bit 0 non usato)
+const BIAS_LEVEL_MASK: u16 = 0x03FE;
+
+/// Uscita massima del DAC a 10 bit
+const DAC_MAX: i32 = 0x3FF;
+
+/// Livello centrale del DAC (bias di default)
+const DAC_CENTER: i32 = 0x200;
+
 /// Sound Control Registers
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SoundRegisters {
     /// SOUNDCNT_L (0x04000080) - DMG Sound Control/Mixing
     /// Bit 0-2: Sound 1-4 Right Volume (0-7)
@@ -30,8 +39,8 @@ pub struct SoundRegisters {
     soundcnt_x: u16,
     
     /// SOUNDBIAS (0x04000088) - Sound PWM Control
-    /// Bit 0-9: Bias Level (default 0x200)
-    /// Bit 14-15: Sampling Rate (0=32768Hz default)
+    /// Bit 1-9: Bias Level (default 0x200)
+    /// Bit 14-15: Sampling Rate (0=32768Hz, 1=65536Hz, 2=131072Hz, 3=262144Hz)
     pub soundbias: u16,
 }
 
@@ -76,13 +85,36 @@ impl SoundRegisters {
         }
     }
     
+    /// Livello di bias aggiunto all'uscita mixata (0-0x3FE)
+    pub fn bias_level(&self) -> u16 {
+        self.soundbias & BIAS_LEVEL_MASK
+    }
+    
+    /// Sample rate selezionato dai bit 14-15 di SOUNDBIAS
+    pub fn sample_rate(&self) -> u32 {
+        32768 << (self.soundbias >> 14)
+    }
+    
+    /// Cicli CPU per sample al sample rate selezionato
+    pub fn cycles_per_sample(&self) -> u32 {
+        512 >> (self.soundbias >> 14)
+    }
+    
+    /// Applica bias e clamp del DAC a un sample mixato (in step del DAC)
+    ///
+    /// L'uscita del DAC (0-0x3FF) viene riportata a i16 centrata su 0x200:
+    /// con il bias di default il silenzio resta 0.
+    pub fn apply_bias(&self, mixed: i32) -> i16 {
+        let dac = (mixed + self.bias_level() as i32).clamp(0, DAC_MAX);
+        ((dac - DAC_CENTER) * 64) as i16
+    }
+    
     /// Verifica se master audio è abilitato
     pub fn is_master_enabled(&self) -> bool {
         (self.soundcnt_x & 0x80) != 0
     }
     
     /// Aggiorna status bit per un canale (0-3)
-    #[allow(dead_code)]
     pub fn set_channel_status(&mut self, channel: u8, enabled: bool) {
         if channel < 4 {
             if enabled {
@@ -150,4 +182,19 @@ mod tests {
         assert_eq!(left, 7);
         assert_eq!(right, 7);
     }
+    
+    #[test]
+    fn test_soundbias_fields() {
+        let mut regs = SoundRegisters::new();
+        assert_eq!(regs.bias_level(), 0x200);
+        assert_eq!(regs.sample_rate(), 32768);
+        assert_eq!(regs.cycles_per_sample(), 512);
+        
+        // Bias 0x100, 262144 Hz (il bit 0 del bias non è usato)
+        regs.write_byte(0x04000088, 0x01);
+        regs.write_byte(0x04000089, 0xC1);
+        assert_eq!(regs.bias_level(), 0x100);
+        assert_eq!(regs.sample_rate(), 262144);
+        assert_eq!(regs.cycles_per_sample(), 64);
+    }
 }
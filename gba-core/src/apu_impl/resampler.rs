@@ -0,0 +1,172 @@
+// Audio Resampler - Conversione dal sample rate nativo a quello dell'host
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+/// Resampler stereo con interpolazione lineare
+///
+/// Riceve sample al rate nativo dell'APU e produce sample al rate
+/// richiesto dal device audio, accumulandoli in un ring buffer la cui
+/// capacità è determinata dalla latenza configurata.
+#[derive(Debug, Clone)]
+pub struct AudioResampler {
+    input_rate: u32,
+    output_rate: u32,
+    latency_ms: u32,
+
+    /// Posizione frazionaria tra il sample precedente e quello corrente
+    position: f64,
+    previous: (i16, i16),
+
+    /// Ring buffer dei sample in uscita
+    buffer: VecDeque<(i16, i16)>,
+    capacity: usize,
+}
+
+impl AudioResampler {
+    /// Crea un resampler da `input_rate` a `output_rate` Hz
+    pub fn new(input_rate: u32, output_rate: u32, latency_ms: u32) -> Self {
+        let capacity = Self::capacity_for(output_rate, latency_ms);
+        Self {
+            input_rate,
+            output_rate,
+            latency_ms,
+            position: 0.0,
+            previous: (0, 0),
+            buffer: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    fn capacity_for(output_rate: u32, latency_ms: u32) -> usize {
+        ((output_rate as u64 * latency_ms as u64) / 1000).max(1) as usize
+    }
+
+    /// Sample rate in uscita
+    pub fn output_rate(&self) -> u32 {
+        self.output_rate
+    }
+
+    /// Sample rate in ingresso
+    pub fn input_rate(&self) -> u32 {
+        self.input_rate
+    }
+
+    /// Cambia il sample rate in ingresso (rate nativo dell'APU)
+    pub fn set_input_rate(&mut self, hz: u32) {
+        self.input_rate = hz.max(1);
+    }
+
+    /// Cambia il sample rate in uscita (svuota il buffer)
+    pub fn set_output_rate(&mut self, hz: u32) {
+        self.output_rate = hz.max(1);
+        self.capacity = Self::capacity_for(self.output_rate, self.latency_ms);
+        self.position = 0.0;
+        self.buffer.clear();
+    }
+
+    /// Cambia la latenza del ring buffer
+    pub fn set_latency(&mut self, latency_ms: u32) {
+        self.latency_ms = latency_ms;
+        self.capacity = Self::capacity_for(self.output_rate, latency_ms);
+        while self.buffer.len() > self.capacity {
+            self.buffer.pop_front();
+        }
+    }
+
+    /// Aggiunge un sample al rate nativo
+    ///
+    /// Produce zero o più sample in uscita. Se il buffer è pieno,
+    /// i sample più vecchi vengono scartati.
+    pub fn push(&mut self, sample: (i16, i16)) {
+        let step = self.input_rate as f64 / self.output_rate as f64;
+
+        while self.position < 1.0 {
+            let t = self.position;
+            let left = lerp(self.previous.0, sample.0, t);
+            let right = lerp(self.previous.1, sample.1, t);
+
+            if self.buffer.len() >= self.capacity {
+                self.buffer.pop_front();
+            }
+            self.buffer.push_back((left, right));
+
+            self.position += step;
+        }
+
+        self.position -= 1.0;
+        self.previous = sample;
+    }
+
+    /// Estrae il prossimo sample in uscita
+    pub fn pop(&mut self) -> Option<(i16, i16)> {
+        self.buffer.pop_front()
+    }
+
+    /// Svuota il buffer in `out` come sample stereo interleaved (L, R, L, R, ...)
+    pub fn drain_into(&mut self, out: &mut Vec<i16>) {
+        out.reserve(self.buffer.len() * 2);
+        for (left, right) in self.buffer.drain(..) {
+            out.push(left);
+            out.push(right);
+        }
+    }
+
+    /// Numero di sample stereo pronti
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Verifica se il buffer è vuoto
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+}
+
+fn lerp(a: i16, b: i16, t: f64) -> i16 {
+    libm::round(a as f64 + (b as f64 - a as f64) * t) as i16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resample_sine_32768_to_48000() {
+        let mut resampler = AudioResampler::new(32768, 48000, 2000);
+
+        // 1 secondo di sinusoide a 440 Hz
+        for i in 0..32768 {
+            let t = i as f64 / 32768.0;
+            let value = ((t * 440.0 * core::f64::consts::TAU).sin() * 16000.0) as i16;
+            resampler.push((value, value));
+        }
+
+        let produced = resampler.len() as i64;
+        assert!((produced - 48000).abs() <= 1, "produced {}", produced);
+
+        // L'interpolazione resta nei limiti dell'input
+        while let Some((left, right)) = resampler.pop() {
+            assert_eq!(left, right);
+            assert!(left.abs() <= 16000);
+        }
+    }
+
+    #[test]
+    fn test_latency_caps_buffer() {
+        let mut resampler = AudioResampler::new(32768, 48000, 10);
+
+        for _ in 0..32768 {
+            resampler.push((100, -100));
+        }
+
+        // 10 ms a 48000 Hz = 480 sample
+        assert_eq!(resampler.len(), 480);
+
+        let mut out = Vec::new();
+        resampler.drain_into(&mut out);
+        assert_eq!(out.len(), 960);
+        assert_eq!(&out[out.len() - 2..], &[100, -100]);
+        assert!(resampler.is_empty());
+    }
+}
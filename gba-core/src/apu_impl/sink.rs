@@ -0,0 +1,117 @@
+// Audio Sink - Destinazione dei sample prodotti dall'emulatore
+//
+// Invece di chiamare `drain_audio` a ogni frame, il frontend può
+// installare un sink con `GbaEmulator::set_audio_sink`: l'emulatore gli
+// passa i sample (al rate dell'host) alla fine di ogni `run_frame` e
+// `run_cycles`. Lo stesso meccanismo serve per SDL2, cpal, un sink nullo
+// o la registrazione su file (`WavRecorder`).
+
+#[cfg(feature = "std")]
+use std::fs::File;
+#[cfg(feature = "std")]
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+#[cfg(feature = "std")]
+use std::path::Path;
+
+/// Destinazione dei sample audio stereo
+pub trait AudioSink {
+    /// Riceve un sample stereo al rate di uscita dell'APU
+    fn push_sample(&mut self, left: i16, right: i16);
+}
+
+/// Registra l'audio in un file WAV (PCM 16 bit stereo)
+///
+/// I sample vengono scritti man mano; le dimensioni nell'header sono
+/// completate da `finish`, oppure al drop ignorando gli errori.
+#[cfg(feature = "std")]
+pub struct WavRecorder {
+    writer: BufWriter<File>,
+    sample_rate: u32,
+    samples: u32,
+    /// Primo errore di scrittura, riportato da `finish`
+    error: Option<io::Error>,
+    finished: bool,
+}
+
+#[cfg(feature = "std")]
+impl WavRecorder {
+    /// Crea il file WAV in `path` con il sample rate dato
+    pub fn create<P: AsRef<Path>>(path: P, sample_rate: u32) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        write_wav_header(&mut writer, sample_rate, 0)?;
+        Ok(Self {
+            writer,
+            sample_rate,
+            samples: 0,
+            error: None,
+            finished: false,
+        })
+    }
+
+    /// Sample stereo registrati finora
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+
+    /// Completa l'header e chiude il file
+    pub fn finish(mut self) -> io::Result<()> {
+        self.finished = true;
+        self.finalize()
+    }
+
+    fn finalize(&mut self) -> io::Result<()> {
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        self.writer.seek(SeekFrom::Start(0))?;
+        write_wav_header(&mut self.writer, self.sample_rate, self.samples)?;
+        self.writer.flush()
+    }
+}
+
+#[cfg(feature = "std")]
+impl AudioSink for WavRecorder {
+    fn push_sample(&mut self, left: i16, right: i16) {
+        if self.error.is_some() {
+            return;
+        }
+        let [l0, l1] = left.to_le_bytes();
+        let [r0, r1] = right.to_le_bytes();
+        match self.writer.write_all(&[l0, l1, r0, r1]) {
+            Ok(()) => self.samples += 1,
+            Err(error) => self.error = Some(error),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Drop for WavRecorder {
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = self.finalize();
+        }
+    }
+}
+
+/// Header RIFF/WAVE di 44 byte per `samples` sample stereo a 16 bit
+#[cfg(feature = "std")]
+fn write_wav_header(writer: &mut impl Write, sample_rate: u32, samples: u32) -> io::Result<()> {
+    const CHANNELS: u16 = 2;
+    const BITS_PER_SAMPLE: u16 = 16;
+    let block_align = CHANNELS * BITS_PER_SAMPLE / 8;
+    let data_size = samples * block_align as u32;
+
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&(36 + data_size).to_le_bytes())?;
+    writer.write_all(b"WAVE")?;
+    writer.write_all(b"fmt ")?;
+    writer.write_all(&16u32.to_le_bytes())?;
+    writer.write_all(&1u16.to_le_bytes())?; // PCM
+    writer.write_all(&CHANNELS.to_le_bytes())?;
+    writer.write_all(&sample_rate.to_le_bytes())?;
+    writer.write_all(&(sample_rate * block_align as u32).to_le_bytes())?;
+    writer.write_all(&block_align.to_le_bytes())?;
+    writer.write_all(&BITS_PER_SAMPLE.to_le_bytes())?;
+    writer.write_all(b"data")?;
+    writer.write_all(&data_size.to_le_bytes())
+}
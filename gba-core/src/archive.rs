@@ -0,0 +1,128 @@
+// Archive - ROM compresse (.zip e .gz)
+//
+// Le ROM circolano spesso compresse. Qui c'è solo l'adattatore verso
+// Cartridge: gzip e zip li decomprimono flate2 e zip (feature `std`), questo
+// modulo sceglie il formato dai magic byte, trova la ROM nell'archivio e
+// traduce gli errori. Il 7z (LZMA) viene solo riconosciuto, per dare un
+// errore chiaro invece di "header non valido".
+
+#[cfg(feature = "std")]
+use crate::cartridge::MAX_ROM_SIZE;
+use alloc::string::String;
+use alloc::vec::Vec;
+use thiserror::Error;
+
+/// Errori di estrazione di una ROM compressa
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ArchiveError {
+    #[error("Corrupt archive: {0}")]
+    Corrupt(&'static str),
+
+    #[error("Unsupported archive: {0}")]
+    Unsupported(&'static str),
+
+    #[error("Archive contains no .gba file")]
+    NoRom,
+
+    #[error("Archive contains more than one ROM: {}", .0.join(", "))]
+    MultipleRoms(Vec<String>),
+
+    #[error("Decompressed ROM is too large (max 32 MB)")]
+    TooLarge,
+}
+
+/// Magic gzip con metodo deflate (l'unico definito)
+const GZIP_MAGIC: [u8; 3] = [0x1F, 0x8B, 0x08];
+/// Local file header zip
+const ZIP_MAGIC: [u8; 4] = *b"PK\x03\x04";
+/// Signature 7z
+const SEVEN_ZIP_MAGIC: [u8; 6] = [b'7', b'z', 0xBC, 0xAF, 0x27, 0x1C];
+
+/// Estrae la ROM se `data` è un archivio riconosciuto (dai magic byte, non
+/// dall'estensione); altrimenti restituisce i dati così come sono
+pub fn extract_rom(data: Vec<u8>) -> Result<Vec<u8>, ArchiveError> {
+    if data.starts_with(&GZIP_MAGIC) {
+        gunzip(&data)
+    } else if data.starts_with(&ZIP_MAGIC) {
+        unzip_rom(&data)
+    } else if data.starts_with(&SEVEN_ZIP_MAGIC) {
+        Err(ArchiveError::Unsupported("7z archives are not supported"))
+    } else {
+        Ok(data)
+    }
+}
+
+/// Decomprime un file .gz (flate2 verifica CRC32 e lunghezza del trailer)
+#[cfg(feature = "std")]
+fn gunzip(data: &[u8]) -> Result<Vec<u8>, ArchiveError> {
+    read_rom(flate2::read::GzDecoder::new(data), "invalid gzip stream")
+}
+
+/// Estrae l'unico file .gba di uno zip
+#[cfg(feature = "std")]
+fn unzip_rom(data: &[u8]) -> Result<Vec<u8>, ArchiveError> {
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(data)).map_err(zip_error)?;
+
+    let mut roms = Vec::new();
+    for index in 0..archive.len() {
+        let entry = archive.by_index_raw(index).map_err(zip_error)?;
+        if !entry.is_dir() && entry.name().to_ascii_lowercase().ends_with(".gba") {
+            roms.push((index, entry.name().to_string()));
+        }
+    }
+
+    let index = match roms.as_slice() {
+        [] => return Err(ArchiveError::NoRom),
+        [(index, _)] => *index,
+        _ => {
+            return Err(ArchiveError::MultipleRoms(
+                roms.into_iter().map(|(_, name)| name).collect(),
+            ))
+        }
+    };
+
+    let entry = archive.by_index(index).map_err(zip_error)?;
+    if entry.size() > MAX_ROM_SIZE as u64 {
+        return Err(ArchiveError::TooLarge);
+    }
+    // zip verifica il CRC32 a fine lettura
+    read_rom(entry, "invalid zip entry")
+}
+
+/// Legge una ROM decompressa, fermandosi oltre `MAX_ROM_SIZE`
+#[cfg(feature = "std")]
+fn read_rom(reader: impl std::io::Read, corrupt: &'static str) -> Result<Vec<u8>, ArchiveError> {
+    use std::io::Read;
+
+    let mut rom = Vec::new();
+    reader
+        .take(MAX_ROM_SIZE as u64 + 1)
+        .read_to_end(&mut rom)
+        .map_err(|_| ArchiveError::Corrupt(corrupt))?;
+    if rom.len() > MAX_ROM_SIZE {
+        return Err(ArchiveError::TooLarge);
+    }
+    Ok(rom)
+}
+
+#[cfg(feature = "std")]
+fn zip_error(error: zip::result::ZipError) -> ArchiveError {
+    use zip::result::ZipError;
+
+    match error {
+        ZipError::UnsupportedArchive(reason) => ArchiveError::Unsupported(reason),
+        ZipError::InvalidArchive(reason) => ArchiveError::Corrupt(reason),
+        _ => ArchiveError::Corrupt("unreadable zip archive"),
+    }
+}
+
+/// Senza `std` non ci sono decompressori: solo ROM non compresse
+#[cfg(not(feature = "std"))]
+fn gunzip(_data: &[u8]) -> Result<Vec<u8>, ArchiveError> {
+    Err(ArchiveError::Unsupported("gzip needs the `std` feature"))
+}
+
+#[cfg(not(feature = "std"))]
+fn unzip_rom(_data: &[u8]) -> Result<Vec<u8>, ArchiveError> {
+    Err(ArchiveError::Unsupported("zip needs the `std` feature"))
+}
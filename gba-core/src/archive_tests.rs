@@ -0,0 +1,182 @@
+use crate::archive::{extract_rom, ArchiveError};
+use crate::cartridge::{Cartridge, LoadError};
+use crate::cartridge_tests::valid_rom;
+#[cfg(feature = "std")]
+use crate::checksum::crc32;
+
+/// Stream DEFLATE di soli blocchi non compressi
+#[cfg(feature = "std")]
+fn stored_deflate(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut chunks = data.chunks(0x100).peekable();
+    while let Some(chunk) = chunks.next() {
+        out.push(u8::from(chunks.peek().is_none()));
+        out.extend_from_slice(&(chunk.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(!(chunk.len() as u16)).to_le_bytes());
+        out.extend_from_slice(chunk);
+    }
+    out
+}
+
+/// File .gz con nome del file originale nell'header
+#[cfg(feature = "std")]
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x1F, 0x8B, 0x08, 0x08, 0, 0, 0, 0, 0, 0xFF];
+    out.extend_from_slice(b"game.gba\0");
+    out.extend_from_slice(&stored_deflate(data));
+    out.extend_from_slice(&crc32(data).to_le_bytes());
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out
+}
+
+/// Zip con voci deflate (blocchi non compressi) e directory centrale
+#[cfg(feature = "std")]
+fn zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut central = Vec::new();
+
+    for (name, data) in files {
+        let compressed = stored_deflate(data);
+        let mut fields = Vec::new();
+        fields.extend_from_slice(&[20, 0, 0, 0, 8, 0, 0, 0, 0, 0]);
+        fields.extend_from_slice(&crc32(data).to_le_bytes());
+        fields.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+        fields.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        fields.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        fields.extend_from_slice(&[0, 0]);
+
+        central.extend_from_slice(b"PK\x01\x02\x14\x00");
+        central.extend_from_slice(&fields);
+        central.extend_from_slice(&[0; 10]);
+        central.extend_from_slice(&(out.len() as u32).to_le_bytes());
+        central.extend_from_slice(name.as_bytes());
+
+        out.extend_from_slice(b"PK\x03\x04");
+        out.extend_from_slice(&fields);
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(&compressed);
+    }
+
+    let central_offset = out.len() as u32;
+    out.extend_from_slice(&central);
+    out.extend_from_slice(b"PK\x05\x06\0\0\0\0");
+    out.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    out.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    out.extend_from_slice(&(central.len() as u32).to_le_bytes());
+    out.extend_from_slice(&central_offset.to_le_bytes());
+    out.extend_from_slice(&[0, 0]);
+    out
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_gzip_rom_loads_cartridge() {
+    let cartridge = Cartridge::from_file_bytes(gzip(&valid_rom()), true).unwrap();
+    assert_eq!(cartridge.rom, valid_rom());
+    assert_eq!(cartridge.header.title, "TEST");
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_gzip_crc_mismatch() {
+    let mut data = gzip(&valid_rom());
+    let crc_pos = data.len() - 8;
+    data[crc_pos] ^= 0xFF;
+    assert!(matches!(extract_rom(data), Err(ArchiveError::Corrupt(_))));
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_gzip_compressed_rom() {
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+
+    // ROM ripetitiva: il deflate usa codici Huffman e back-reference
+    let mut rom = valid_rom();
+    rom.extend((0..0x4000u32).map(|i| (i % 251) as u8));
+    let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::best());
+    encoder.write_all(&rom).unwrap();
+    let data = encoder.finish().unwrap();
+    assert!(data.len() < rom.len() / 4);
+
+    assert_eq!(extract_rom(data), Ok(rom));
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_zip_compressed_rom() {
+    use std::io::Write;
+    use zip::write::{SimpleFileOptions, ZipWriter};
+
+    let rom = valid_rom();
+    let mut writer = ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    writer
+        .add_directory("roms/", SimpleFileOptions::default())
+        .unwrap();
+    writer
+        .start_file("roms/game.gba", SimpleFileOptions::default())
+        .unwrap();
+    writer.write_all(&rom).unwrap();
+    let data = writer.finish().unwrap().into_inner();
+
+    assert_eq!(extract_rom(data), Ok(rom));
+}
+
+#[test]
+#[cfg(not(feature = "std"))]
+fn test_archives_need_std() {
+    let data = vec![0x1F, 0x8B, 0x08, 0, 0, 0, 0, 0, 0, 0xFF];
+    assert!(matches!(
+        extract_rom(data),
+        Err(ArchiveError::Unsupported(_))
+    ));
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_zip_extracts_single_rom() {
+    let rom = valid_rom();
+    let data = zip(&[("readme.txt", b"Have fun!"), ("Game.GBA", &rom)]);
+
+    let cartridge = Cartridge::from_file_bytes(data, true).unwrap();
+    assert_eq!(cartridge.rom, rom);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_zip_with_several_roms() {
+    let rom = valid_rom();
+    let data = zip(&[("game (E).gba", &rom), ("game (U).gba", &rom)]);
+
+    assert_eq!(
+        extract_rom(data),
+        Err(ArchiveError::MultipleRoms(vec![
+            "game (E).gba".to_string(),
+            "game (U).gba".to_string(),
+        ]))
+    );
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_zip_without_rom() {
+    let data = zip(&[("readme.txt", b"Have fun!")]);
+    assert_eq!(extract_rom(data), Err(ArchiveError::NoRom));
+}
+
+#[test]
+fn test_7z_reports_unsupported() {
+    let mut data = vec![b'7', b'z', 0xBC, 0xAF, 0x27, 0x1C];
+    data.extend_from_slice(&[0; 32]);
+
+    let result = Cartridge::from_file_bytes(data, true);
+    assert!(matches!(
+        result,
+        Err(LoadError::Archive(ArchiveError::Unsupported(_)))
+    ));
+}
+
+#[test]
+fn test_plain_rom_passes_through() {
+    assert_eq!(extract_rom(valid_rom()), Ok(valid_rom()));
+}
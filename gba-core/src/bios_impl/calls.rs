@@ -1,5 +1,7 @@
 /// BIOS Call implementations
 /// These are high-level emulation of GBA BIOS functions
+use super::constants::*;
+
 /// Division result
 #[derive(Debug, Clone, Copy)]
 pub struct DivResult {
@@ -14,13 +16,24 @@ pub struct SqrtResult {
     pub result: u16,
 }
 
-/// SoftReset - Reset most of the system
-pub fn soft_reset() {
-    // In real hardware, this would:
-    // - Clear 0x03007F00-0x03007FFF (256 bytes)
-    // - Clear most I/O registers
-    // - Jump to address in ROM header
-    // We handle this at emulator level
+/// SoftReset - Clear the top of IWRAM and pick the restart address
+///
+/// The byte at 0x03007FFA selects the entry point: 0 restarts the ROM
+/// at 0x08000000, anything else the multiboot image at 0x02000000. The
+/// last 0x200 bytes of IWRAM (0x03007E00-0x03007FFF, flag included) are
+/// cleared. CPU registers and stacks are reset by the emulator.
+pub fn soft_reset(iwram: &mut [u8]) -> u32 {
+    // Offset inside the 32 KB of IWRAM
+    let flag_offset = (SOFT_RESET_FLAG & 0x7FFF) as usize;
+    let entry = if iwram.get(flag_offset).copied().unwrap_or(0) != 0 {
+        SOFT_RESET_EWRAM_ENTRY
+    } else {
+        SOFT_RESET_ROM_ENTRY
+    };
+
+    let len = iwram.len();
+    iwram[len.saturating_sub(SOFT_RESET_CLEAR_SIZE)..].fill(0);
+    entry
 }
 
 /// Div - Signed division
@@ -45,7 +58,7 @@ pub fn div(numerator: i32, denominator: i32) -> DivResult {
 
 /// Sqrt - Integer square root
 pub fn sqrt(value: u32) -> SqrtResult {
-    let result = (value as f64).sqrt() as u16;
+    let result = libm::sqrt(value as f64) as u16;
     SqrtResult { result }
 }
 
@@ -54,7 +67,7 @@ pub fn arctan(x: i16) -> i16 {
     // Simple linear approximation for tan^-1(x/2^14)
     // Real BIOS uses a lookup table
     let x_f = (x as f64) / 16384.0;
-    let result = x_f.atan() * 16384.0 / std::f64::consts::PI;
+    let result = libm::atan(x_f) * 16384.0 / core::f64::consts::PI;
     result as i16
 }
 
@@ -63,9 +76,9 @@ pub fn arctan2(x: i16, y: i16) -> u16 {
     if x == 0 && y == 0 {
         return 0;
     }
-    let angle = (y as f64).atan2(x as f64);
+    let angle = libm::atan2(y as f64, x as f64);
     // Convert to 0-FFFF range (0-360 degrees)
-    let normalized = ((angle + std::f64::consts::PI) / (2.0 * std::f64::consts::PI)) * 65536.0;
+    let normalized = ((angle + core::f64::consts::PI) / (2.0 * core::f64::consts::PI)) * 65536.0;
     normalized as u16
 }
 
@@ -234,3 +247,76 @@ where
         }
     }
 }
+
+/// Decoded size from a BIOS compression header (bits 8-31)
+fn decoded_size(data: &[u8]) -> usize {
+    let header = data
+        .iter()
+        .take(4)
+        .enumerate()
+        .fold(0u32, |acc, (i, &b)| acc | (b as u32) << (i * 8));
+    (header >> 8) as usize
+}
+
+/// Diff8bitUnFilterWram - 8-bit delta decoding
+///
+/// `data` is the source stream starting with its 4-byte header. Each
+/// output byte is the previous output plus the next difference; bytes
+/// are passed to `write_byte(addr, value)` starting at `dest`.
+pub fn diff_8bit_unfilter<W>(data: &[u8], dest: u32, mut write_byte: W)
+where
+    W: FnMut(u32, u8),
+{
+    let size = decoded_size(data);
+    let mut value = 0u8;
+    for (i, &diff) in data.iter().skip(4).take(size).enumerate() {
+        value = value.wrapping_add(diff);
+        write_byte(dest.wrapping_add(i as u32), value);
+    }
+}
+
+/// Diff8bitUnFilterVram - same as the WRAM version, written in 16-bit
+/// units since VRAM ignores byte writes
+pub fn diff_8bit_unfilter_vram<W>(data: &[u8], dest: u32, mut write_halfword: W)
+where
+    W: FnMut(u32, u16),
+{
+    let mut low = 0u8;
+    diff_8bit_unfilter(data, dest, |addr, value| {
+        if addr & 1 == 0 {
+            low = value;
+        } else {
+            write_halfword(addr & !1, u16::from_le_bytes([low, value]));
+        }
+    });
+}
+
+/// Diff16bitUnFilter - 16-bit delta decoding
+///
+/// Like the 8-bit version with little-endian halfword differences.
+pub fn diff_16bit_unfilter<W>(data: &[u8], dest: u32, mut write_halfword: W)
+where
+    W: FnMut(u32, u16),
+{
+    let size = decoded_size(data);
+    let mut value = 0u16;
+    for (i, diff) in data
+        .get(4..)
+        .unwrap_or(&[])
+        .chunks_exact(2)
+        .take(size / 2)
+        .enumerate()
+    {
+        value = value.wrapping_add(u16::from_le_bytes([diff[0], diff[1]]));
+        write_halfword(dest.wrapping_add(i as u32 * 2), value);
+    }
+}
+
+/// MidiKey2Freq - Sample rate for a MIDI key
+///
+/// `sample_rate` is the WaveData frequency field (Hz * 1024, played back
+/// at key 60); the result is `sample_rate / 2^((180 - key - fine/256) / 12)`.
+pub fn midi_key_to_freq(sample_rate: u32, key: u32, fine: u32) -> u32 {
+    let semitones = 180.0 - key as f64 - fine as f64 / 256.0;
+    (sample_rate as f64 / libm::exp2(semitones / 12.0)) as u32
+}
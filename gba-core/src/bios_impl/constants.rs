@@ -38,3 +38,23 @@ pub const SWI_SOUND_DRIVER_VSYNC_ON: u8 = 0x29;
 /// CPU Set control flags
 pub const CPUSET_FILL: u32 = 1 << 24;  // Fill mode (vs copy)
 pub const CPUSET_32BIT: u32 = 1 << 26; // 32-bit transfer (vs 16-bit)
+
+/// BIOS IF mirror in IWRAM, set by the game's IRQ handler for IntrWait
+pub const BIOS_IF_MIRROR: u32 = 0x03007FF8;
+
+/// SoundDriverInit stores the SoundArea pointer here
+pub const SOUND_AREA_POINTER: u32 = 0x03007FF0;
+
+/// IRQ exception vector, handled by the BIOS dispatcher
+pub const IRQ_VECTOR: u32 = 0x00000018;
+/// Pointer to the game's IRQ handler, called by the BIOS dispatcher
+pub const BIOS_IRQ_HANDLER: u32 = 0x03007FFC;
+/// Address in the BIOS the game's IRQ handler returns to
+pub const BIOS_IRQ_RETURN: u32 = 0x00000138;
+
+/// SoftReset: IWRAM flag choosing the restart address (0 = ROM, else EWRAM)
+pub const SOFT_RESET_FLAG: u32 = 0x03007FFA;
+pub const SOFT_RESET_ROM_ENTRY: u32 = 0x08000000;
+pub const SOFT_RESET_EWRAM_ENTRY: u32 = 0x02000000;
+/// SoftReset clears the last 0x200 bytes of IWRAM
+pub const SOFT_RESET_CLEAR_SIZE: usize = 0x200;
@@ -7,10 +7,15 @@ pub use calls::*;
 pub use constants::*;
 
 /// BIOS state and handler
+#[derive(Clone)]
 pub struct Bios {
     // BIOS state (if needed for stateful operations)
     pub halted: bool,
     pub waiting_for_interrupt: bool,
+    /// Interrupts IntrWait is waiting for (r1)
+    pub wait_mask: u16,
+    /// IF bits already raised when IntrWait was called with r0 = 1
+    stale_if: u16,
 }
 
 impl Bios {
@@ -18,6 +23,8 @@ impl Bios {
         Self {
             halted: false,
             waiting_for_interrupt: false,
+            wait_mask: 0,
+            stale_if: 0,
         }
     }
 
@@ -25,16 +32,63 @@ impl Bios {
     pub fn reset(&mut self) {
         self.halted = false;
         self.waiting_for_interrupt = false;
+        self.wait_mask = 0;
+        self.stale_if = 0;
+    }
+
+    /// Start IntrWait (SWI 0x04) / VBlankIntrWait (SWI 0x05)
+    ///
+    /// With `discard_old` (r0 = 1) the `mask` bits are cleared from the
+    /// BIOS IF mirror at 0x03007FF8 and interrupts already raised in IF
+    /// don't count: only a new one ends the wait. Returns true if the
+    /// call returns immediately.
+    pub fn start_intr_wait(
+        &mut self,
+        discard_old: bool,
+        mask: u16,
+        if_: u16,
+        mirror: &mut u16,
+    ) -> bool {
+        if discard_old {
+            *mirror &= !mask;
+            self.stale_if = if_ & mask;
+        } else {
+            self.stale_if = 0;
+        }
+        self.wait_mask = mask;
+        self.waiting_for_interrupt = true;
+        self.poll_intr_wait(if_, mirror)
+    }
+
+    /// Check whether a pending IntrWait is satisfied
+    ///
+    /// Wakes on a `wait_mask` bit set in the IF mirror (written by the
+    /// game's IRQ handler) or newly raised in IF. The satisfied bits are
+    /// cleared from the mirror, as the real BIOS does.
+    pub fn poll_intr_wait(&mut self, if_: u16, mirror: &mut u16) -> bool {
+        if !self.waiting_for_interrupt {
+            return true;
+        }
+
+        // A stale bit acknowledged in IF counts again when raised anew
+        self.stale_if &= if_;
+        let raised = if_ & !self.stale_if;
+        let flags = (*mirror | raised) & self.wait_mask;
+        if flags == 0 {
+            return false;
+        }
+
+        *mirror &= !flags;
+        self.waiting_for_interrupt = false;
+        true
     }
 
     /// Handle SWI call
     /// Returns tuple: (should_halt, should_wait_interrupt)
     pub fn handle_swi(&mut self, swi_number: u8) -> (bool, bool) {
         match swi_number {
-            SWI_SOFT_RESET => {
-                calls::soft_reset();
-                (false, false)
-            }
+            // Needs the CPU registers: done by the emulator with calls::soft_reset
+            SWI_SOFT_RESET => (false, false),
             SWI_HALT => {
                 self.halted = true;
                 (true, false)
@@ -52,9 +106,14 @@ impl Bios {
             // Memory operations - handled by CPU with memory callbacks
             SWI_CPU_SET | SWI_CPU_FAST_SET => (false, false),
             // Decompression - handled by CPU with memory callbacks
+            // Delta decoding - done by the emulator with calls::diff_*_unfilter
+            SWI_DIFF_8BIT_UNCOMP_WRAM | SWI_DIFF_8BIT_UNCOMP_VRAM | SWI_DIFF_16BIT_UNCOMP => {
+                (false, false)
+            }
             SWI_BIT_UNPACK | SWI_LZ77_UNCOMP_WRAM | SWI_LZ77_UNCOMP_VRAM | SWI_RL_UNCOMP_WRAM
             | SWI_RL_UNCOMP_VRAM => (false, false),
-            // Sound driver - stub for now
+            // Sound driver - done by the emulator: the mixer is not emulated,
+            // only MidiKey2Freq, SoundBias and SoundDriverInit have effects
             SWI_SOUND_BIAS
             | SWI_SOUND_DRIVER_INIT
             | SWI_SOUND_DRIVER_MODE
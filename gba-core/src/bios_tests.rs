@@ -173,9 +173,19 @@ fn test_cpuset_flags() {
 }
 
 #[test]
-fn test_soft_reset_no_panic() {
-    // Just verify it doesn't panic
-    soft_reset();
+fn test_soft_reset_clears_iwram_top_and_picks_entry() {
+    let mut iwram = vec![0xAA; 0x8000];
+
+    // Flag clear: restart from ROM, last 0x200 bytes cleared
+    iwram[0x7FFA] = 0;
+    assert_eq!(soft_reset(&mut iwram), SOFT_RESET_ROM_ENTRY);
+    assert_eq!(iwram[0x7DFF], 0xAA);
+    assert!(iwram[0x7E00..].iter().all(|&b| b == 0));
+
+    // Flag set: restart from EWRAM
+    iwram[0x7FFA] = 1;
+    assert_eq!(soft_reset(&mut iwram), SOFT_RESET_EWRAM_ENTRY);
+    assert_eq!(iwram[0x7FFA], 0);
 }
 
 #[test]
@@ -186,3 +196,91 @@ fn test_bios_unknown_swi() {
     assert!(!should_halt);
     assert!(!should_wait);
 }
+
+#[test]
+fn test_intr_wait_discards_old_flags() {
+    let mut bios = Bios::new();
+    let mut mirror = 0x0001;
+
+    // r0 = 1: the VBlank already in the mirror and in IF doesn't count
+    assert!(!bios.start_intr_wait(true, 0x0001, 0x0001, &mut mirror));
+    assert_eq!(mirror, 0);
+    assert!(bios.is_waiting());
+    assert!(!bios.poll_intr_wait(0x0001, &mut mirror));
+
+    // The IRQ handler acknowledges IF and sets the mirror bit
+    mirror |= 0x0001;
+    assert!(bios.poll_intr_wait(0x0000, &mut mirror));
+    assert_eq!(mirror, 0);
+    assert!(!bios.is_waiting());
+}
+
+#[test]
+fn test_intr_wait_returns_on_old_flag() {
+    let mut bios = Bios::new();
+    let mut mirror = 0x0009;
+
+    // r0 = 0: a flag already set in the mirror returns immediately
+    assert!(bios.start_intr_wait(false, 0x0008, 0x0000, &mut mirror));
+    assert_eq!(mirror, 0x0001);
+    assert!(!bios.is_waiting());
+}
+
+#[test]
+fn test_diff_8bit_unfilter_accumulates() {
+    // Header: 8-bit diff (0x81), 5 bytes
+    let data = [0x81, 0x05, 0x00, 0x00, 10, 5, 0xFF, 0, 0xF7];
+    let mut out = Vec::new();
+    diff_8bit_unfilter(&data, 0x02000000, |addr, value| out.push((addr, value)));
+
+    assert_eq!(
+        out,
+        vec![
+            (0x02000000, 10),
+            (0x02000001, 15),
+            (0x02000002, 14),
+            (0x02000003, 14),
+            (0x02000004, 5),
+        ]
+    );
+}
+
+#[test]
+fn test_diff_8bit_unfilter_vram_writes_halfwords() {
+    let data = [0x81, 0x04, 0x00, 0x00, 1, 1, 1, 1];
+    let mut out = Vec::new();
+    diff_8bit_unfilter_vram(&data, 0x06000000, |addr, value| out.push((addr, value)));
+
+    assert_eq!(out, vec![(0x06000000, 0x0201), (0x06000002, 0x0403)]);
+}
+
+#[test]
+fn test_diff_16bit_unfilter_accumulates() {
+    // Header: 16-bit diff (0x82), 6 bytes = 3 halfwords
+    let data = [0x82, 0x06, 0x00, 0x00, 0x00, 0x10, 0x01, 0x00, 0xFF, 0xFF];
+    let mut out = Vec::new();
+    diff_16bit_unfilter(&data, 0x02000000, |addr, value| out.push((addr, value)));
+
+    assert_eq!(
+        out,
+        vec![
+            (0x02000000, 0x1000),
+            (0x02000002, 0x1001),
+            (0x02000004, 0x1000)
+        ]
+    );
+}
+
+#[test]
+fn test_midi_key_to_freq() {
+    // 44100 Hz WaveData (* 1024) plays at its own rate on key 60
+    let sample_rate = 44100 << 10;
+    assert_eq!(midi_key_to_freq(sample_rate, 60, 0), 44100);
+
+    // One octave up and down
+    assert_eq!(midi_key_to_freq(sample_rate, 72, 0), 88200);
+    assert_eq!(midi_key_to_freq(sample_rate, 48, 0), 22050);
+
+    // Half a semitone via the fine adjust: 44100 * 2^(1/24)
+    assert_eq!(midi_key_to_freq(sample_rate, 60, 128), 45392);
+}
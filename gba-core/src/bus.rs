@@ -1,13 +1,31 @@
 use crate::apu::APU;
+use crate::debugger::{MemoryAccess, MemoryWatch, WatchHit, Watchpoint};
 use crate::dma::DMA;
+use crate::gpio::Gpio;
 use crate::input::InputController;
-use crate::interrupt::InterruptController;
-use crate::memory::Memory;
+use crate::interrupt::{InterruptController, InterruptFlags, PowerState};
+use crate::memory::{vram_offset, Memory};
 use crate::ppu::PPU;
-use crate::save::SaveController;
+use crate::save::{SaveController, SAVE_BUS_END, SRAM_START};
+use crate::serial::Serial;
 use crate::timer::Timer;
+use crate::timing::MemoryTiming;
+use alloc::vec::Vec;
 use gba_arm7tdmi::cpu::MemoryBus;
 
+/// Internal Memory Control: registro non documentato fuori dal blocco I/O
+/// principale (wait state della EWRAM, vedi timing.rs)
+pub const MEMCNT: u32 = 0x04000800;
+
+/// Keypad Interrupt Control: tasti selezionati (bit 0-9), IRQ (bit 14),
+/// condizione AND/OR (bit 15)
+pub const KEYCNT: u32 = 0x04000132;
+
+/// Registri I/O: 0x04000000-0x040003FF più Internal Memory Control
+fn is_io_register(addr: u32) -> bool {
+    (0x04000000..0x04000400).contains(&addr) || addr & !3 == MEMCNT
+}
+
 /// Bus principale del sistema GBA
 pub struct Bus {
     pub memory: Memory,
@@ -16,8 +34,29 @@ pub struct Bus {
     pub timer: Timer,
     pub dma: DMA,
     pub save: SaveController,
+    pub gpio: Gpio,
+    pub serial: Serial,
     pub interrupt: InterruptController,
     pub input: InputController,
+    pub timing: MemoryTiming,
+
+    /// POSTFLG (0x04000300)
+    pub postflg: u8,
+    /// Stato richiesto scrivendo HALTCNT (0x04000301)
+    pub power: PowerState,
+
+    /// Watchpoint del debugger e ultimo accesso che li ha toccati
+    pub watchpoints: Vec<Watchpoint>,
+    pub watch_hit: Option<WatchHit>,
+
+    /// Callback sugli accessi alla memoria e PC dell'istruzione in corso
+    memory_watches: Vec<MemoryWatch>,
+    pub(crate) access_pc: u32,
+
+    /// Trasferimento DMA in corso: gli accessi non sono wait state della CPU
+    in_dma: bool,
+    /// Accessi della CPU alla memoria video rallentati mentre il PPU disegna
+    video_contention: bool,
 }
 
 impl Bus {
@@ -29,11 +68,55 @@ impl Bus {
             timer: Timer::new(),
             dma: DMA::new(),
             save: SaveController::new(),
+            gpio: Gpio::new(),
+            serial: Serial::new(),
             interrupt: InterruptController::new(),
             input: InputController::new(),
+            timing: MemoryTiming::new(),
+            postflg: 0,
+            power: PowerState::Running,
+            watchpoints: Vec::new(),
+            watch_hit: None,
+            memory_watches: Vec::new(),
+            access_pc: 0,
+            in_dma: false,
+            video_contention: false,
+        }
+    }
+
+    /// Abilita l'attesa della CPU sugli accessi a palette, VRAM e OAM
+    /// mentre il PPU disegna (più accurato, disattivato di default)
+    pub fn set_video_contention(&mut self, enabled: bool) {
+        self.video_contention = enabled;
+    }
+
+    /// Richiede l'IRQ keypad se lo stato dei tasti soddisfa KEYCNT
+    ///
+    /// Con il bit 15 a 0 basta uno dei tasti selezionati, a 1 servono
+    /// tutti.
+    pub fn check_keypad_irq(&mut self) {
+        let keycnt = self.peek_halfword(KEYCNT);
+        let selected = keycnt & 0x03FF;
+        if keycnt & (1 << 14) == 0 || selected == 0 {
+            return;
+        }
+
+        let pressed = !self.input.read_keyinput() & selected;
+        let hit = if keycnt & (1 << 15) != 0 {
+            pressed == selected
+        } else {
+            pressed != 0
+        };
+        if hit {
+            self.interrupt.request(InterruptFlags::KEYPAD);
         }
     }
 
+    /// Indica se la contesa della memoria video è abilitata
+    pub fn video_contention(&self) -> bool {
+        self.video_contention
+    }
+
     pub fn load_bios(&mut self, bios: Vec<u8>) {
         self.memory.load_bios(bios);
     }
@@ -41,159 +124,369 @@ impl Bus {
     pub fn load_rom(&mut self, rom: Vec<u8>) {
         self.memory.load_rom(rom);
     }
+
+    /// Registra una callback sugli accessi a un intervallo di indirizzi
+    pub fn add_memory_watch(&mut self, watch: MemoryWatch) {
+        self.memory_watches.push(watch);
+    }
+
+    /// Rimuove tutte le callback sugli accessi
+    pub fn clear_memory_watches(&mut self) {
+        self.memory_watches.clear();
+    }
 }
 
 impl MemoryBus for Bus {
     fn read_byte(&mut self, addr: u32) -> u8 {
-        // SRAM/Flash (0x0E000000-0x0E00FFFF)
-        if (0x0E000000..=0x0E00FFFF).contains(&addr) {
-            return self.save.read_byte(addr - 0x0E000000);
+        self.record_access(addr, 1, false);
+        self.check_watchpoints(addr, 1, false);
+        if !self.memory_watches.is_empty() {
+            let value = self.peek_byte(addr) as u32;
+            self.notify_memory_watches(addr, 1, value, false);
         }
 
-        // OAM: 0x07000000-0x070003FF
-        if (0x07000000..0x07000400).contains(&addr) {
-            let offset = (addr - 0x07000000) as usize;
+        // SRAM/Flash (0x0E000000-0x0FFFFFFF, bus a 8 bit)
+        if (SRAM_START..=SAVE_BUS_END).contains(&addr) {
+            return self.save.read_bus(addr, 1) as u8;
+        }
+
+        // OAM: 0x07000000-0x070003FF, mirror ogni 1 KB
+        if (0x07000000..0x08000000).contains(&addr) {
+            let offset = (addr & 0x3FF) as usize;
             return self.ppu.read_oam_byte(offset);
         }
 
-        // Palette RAM: 0x05000000-0x050003FF
-        if (0x05000000..0x05000400).contains(&addr) {
-            let offset = (addr - 0x05000000) as usize;
+        // Palette RAM: 0x05000000-0x050003FF, mirror ogni 1 KB
+        if (0x05000000..0x06000000).contains(&addr) {
+            let offset = (addr & 0x3FF) as usize;
             return self.ppu.read_palette_byte(offset);
         }
 
         // I/O Registers: 0x04000000-0x040003FE
-        if (0x04000000..0x04000400).contains(&addr) {
+        if is_io_register(addr) {
             return self.read_io_byte(addr);
         }
+
+        // GPIO della cartuccia, se il gioco l'ha reso leggibile
+        if Gpio::covers(addr) && self.gpio.readable() {
+            return (self.gpio.read_halfword(addr) >> ((addr & 1) * 8)) as u8;
+        }
         self.memory.read_byte(addr)
     }
 
     fn read_halfword(&mut self, addr: u32) -> u16 {
-        // OAM
-        if (0x07000000..0x07000400).contains(&addr) {
-            return self.ppu.read_oam_halfword((addr - 0x07000000) as usize);
-        }
-
-        // Palette RAM
-        if (0x05000000..0x05000400).contains(&addr) {
-            return self.ppu.read_palette_halfword((addr - 0x05000000) as usize);
-        }
-
-        // I/O Registers
-        if (0x04000000..0x04000400).contains(&addr) {
-            return self.read_io_halfword(addr);
-        }
-        self.memory.read_halfword(addr)
+        self.record_access(addr, 2, false);
+        self.load_halfword(addr)
     }
 
     fn read_word(&mut self, addr: u32) -> u32 {
-        // OAM
-        if (0x07000000..0x07000400).contains(&addr) {
-            let low = self.read_halfword(addr);
-            let high = self.read_halfword(addr + 2);
-            return (low as u32) | ((high as u32) << 16);
-        }
-
-        // Palette RAM
-        if (0x05000000..0x05000400).contains(&addr) {
-            let low = self.read_halfword(addr);
-            let high = self.read_halfword(addr + 2);
-            return (low as u32) | ((high as u32) << 16);
-        }
-
-        // I/O Registers
-        if (0x04000000..0x04000400).contains(&addr) {
-            let low = self.read_io_halfword(addr);
-            let high = self.read_io_halfword(addr + 2);
-            return (low as u32) | ((high as u32) << 16);
-        }
-        self.memory.read_word(addr)
+        self.record_access(addr, 4, false);
+        self.load_word(addr)
     }
 
     fn write_byte(&mut self, addr: u32, value: u8) {
-        // SRAM/Flash (0x0E000000-0x0E00FFFF)
-        if (0x0E000000..=0x0E00FFFF).contains(&addr) {
-            self.save.write_byte(addr - 0x0E000000, value);
+        self.record_access(addr, 1, false);
+        self.check_watchpoints(addr, 1, true);
+        if !self.memory_watches.is_empty() {
+            self.notify_memory_watches(addr, 1, value as u32, true);
+        }
+
+        // SRAM/Flash (0x0E000000-0x0FFFFFFF, bus a 8 bit)
+        if (SRAM_START..=SAVE_BUS_END).contains(&addr) {
+            self.save.write_bus(addr, 1, value as u32);
             return;
         }
 
         // OAM
-        if (0x07000000..0x07000400).contains(&addr) {
-            let offset = (addr - 0x07000000) as usize;
+        if (0x07000000..0x08000000).contains(&addr) {
+            let offset = (addr & 0x3FF) as usize;
             self.ppu.write_oam_byte(offset, value);
             return;
         }
 
         // Palette RAM
-        if (0x05000000..0x05000400).contains(&addr) {
-            let offset = (addr - 0x05000000) as usize;
+        if (0x05000000..0x06000000).contains(&addr) {
+            let offset = (addr & 0x3FF) as usize;
             self.ppu.write_palette_byte(offset, value);
             return;
         }
 
+        // VRAM: 0x06000000-0x06017FFF e mirror
+        if (0x06000000..0x07000000).contains(&addr) {
+            self.write_vram_byte(addr, value);
+            return;
+        }
+
         // I/O Registers
-        if (0x04000000..0x04000400).contains(&addr) {
+        if is_io_register(addr) {
             self.write_io_byte(addr, value);
             return;
         }
+
+        // GPIO della cartuccia (rumble)
+        if Gpio::covers(addr) {
+            self.gpio.write_byte(addr, value);
+            return;
+        }
         self.memory.write_byte(addr, value);
     }
 
     fn write_halfword(&mut self, addr: u32, value: u16) {
+        self.record_access(addr, 2, false);
+        self.check_watchpoints(addr, 2, true);
+        if !self.memory_watches.is_empty() {
+            self.notify_memory_watches(addr, 2, value as u32, true);
+        }
+
+        // SRAM/Flash: arriva solo il byte selezionato dall'indirizzo
+        if (SRAM_START..=SAVE_BUS_END).contains(&addr) {
+            self.save.write_bus(addr, 2, value as u32);
+            return;
+        }
+
         // OAM
-        if (0x07000000..0x07000400).contains(&addr) {
-            let offset = (addr - 0x07000000) as usize;
+        if (0x07000000..0x08000000).contains(&addr) {
+            let offset = (addr & 0x3FF) as usize;
             self.ppu.write_oam_halfword(offset, value);
             return;
         }
 
         // Palette RAM
-        if (0x05000000..0x05000400).contains(&addr) {
-            let offset = (addr - 0x05000000) as usize;
+        if (0x05000000..0x06000000).contains(&addr) {
+            let offset = (addr & 0x3FF) as usize;
             self.ppu.write_palette_halfword(offset, value);
             return;
         }
 
         // I/O Registers
-        if (0x04000000..0x04000400).contains(&addr) {
+        if is_io_register(addr) {
             self.write_io_halfword(addr, value);
             return;
         }
+
+        // GPIO della cartuccia (rumble)
+        if Gpio::covers(addr) {
+            self.gpio.write_halfword(addr, value);
+            return;
+        }
         self.memory.write_halfword(addr, value);
     }
 
     fn write_word(&mut self, addr: u32, value: u32) {
+        self.record_access(addr, 4, false);
+        self.check_watchpoints(addr, 4, true);
+        if !self.memory_watches.is_empty() {
+            self.notify_memory_watches(addr, 4, value, true);
+        }
+
+        // SRAM/Flash: arriva solo il byte selezionato dall'indirizzo
+        if (SRAM_START..=SAVE_BUS_END).contains(&addr) {
+            self.save.write_bus(addr, 4, value);
+            return;
+        }
+
         // OAM
-        if (0x07000000..0x07000400).contains(&addr) {
-            self.write_halfword(addr, value as u16);
-            self.write_halfword(addr + 2, (value >> 16) as u16);
+        if (0x07000000..0x08000000).contains(&addr) {
+            let offset = (addr & 0x3FF) as usize;
+            self.ppu.write_oam_halfword(offset, value as u16);
+            self.ppu
+                .write_oam_halfword(offset + 2, (value >> 16) as u16);
             return;
         }
 
         // Palette RAM
-        if (0x05000000..0x05000400).contains(&addr) {
-            self.write_halfword(addr, value as u16);
-            self.write_halfword(addr + 2, (value >> 16) as u16);
+        if (0x05000000..0x06000000).contains(&addr) {
+            let offset = (addr & 0x3FF) as usize;
+            self.ppu.write_palette_halfword(offset, value as u16);
+            self.ppu
+                .write_palette_halfword(offset + 2, (value >> 16) as u16);
             return;
         }
 
         // I/O Registers
-        if (0x04000000..0x04000400).contains(&addr) {
+        if is_io_register(addr) {
             self.write_io_halfword(addr, value as u16);
             self.write_io_halfword(addr + 2, (value >> 16) as u16);
             return;
         }
+
+        // GPIO della cartuccia (rumble)
+        if Gpio::covers(addr) {
+            self.gpio.write_halfword(addr, value as u16);
+            self.gpio.write_halfword(addr + 2, (value >> 16) as u16);
+            return;
+        }
         self.memory.write_word(addr, value);
     }
+
+    fn fetch_halfword(&mut self, addr: u32) -> u16 {
+        self.record_access(addr, 2, true);
+        self.load_halfword(addr)
+    }
+
+    fn fetch_word(&mut self, addr: u32) -> u32 {
+        self.record_access(addr, 4, true);
+        self.load_word(addr)
+    }
 }
 
 impl Bus {
+    /// Conteggia i wait state degli accessi a ROM, SRAM e aree a 16 bit
+    fn record_access(&mut self, addr: u32, size: u32, fetch: bool) {
+        if self.in_dma {
+            return;
+        }
+        match addr >> 24 {
+            0x02 | 0x05 | 0x06 => self.timing.access_ram(addr, size),
+            0x08..=0x0D => self.timing.access_rom(addr, size, fetch),
+            0x0E..=0x0F => self.timing.access_sram(),
+            _ => {}
+        }
+
+        if self.video_contention && (0x05..=0x07).contains(&(addr >> 24)) && self.ppu.is_drawing() {
+            self.timing.access_video_stall(addr, size);
+        }
+    }
+
+    /// Lettura halfword di dati o di istruzioni, già conteggiata nei wait state
+    fn load_halfword(&mut self, addr: u32) -> u16 {
+        self.check_watchpoints(addr, 2, false);
+        if !self.memory_watches.is_empty() {
+            let value = self.peek_halfword(addr) as u32;
+            self.notify_memory_watches(addr, 2, value, false);
+        }
+
+        // SRAM/Flash: il byte indirizzato ripetuto su entrambe le metà
+        if (SRAM_START..=SAVE_BUS_END).contains(&addr) {
+            return self.save.read_bus(addr, 2) as u16;
+        }
+
+        // OAM
+        if (0x07000000..0x08000000).contains(&addr) {
+            return self.ppu.read_oam_halfword((addr & 0x3FF) as usize);
+        }
+
+        // Palette RAM
+        if (0x05000000..0x06000000).contains(&addr) {
+            return self.ppu.read_palette_halfword((addr & 0x3FF) as usize);
+        }
+
+        // I/O Registers
+        if is_io_register(addr) {
+            return self.read_io_halfword(addr);
+        }
+
+        // GPIO della cartuccia, se il gioco l'ha reso leggibile
+        if Gpio::covers(addr) && self.gpio.readable() {
+            return self.gpio.read_halfword(addr);
+        }
+        self.memory.read_halfword(addr)
+    }
+
+    /// Lettura word di dati o di istruzioni, già conteggiata nei wait state
+    fn load_word(&mut self, addr: u32) -> u32 {
+        self.check_watchpoints(addr, 4, false);
+        if !self.memory_watches.is_empty() {
+            let value = self.peek_word(addr);
+            self.notify_memory_watches(addr, 4, value, false);
+        }
+
+        // SRAM/Flash: il byte indirizzato ripetuto su tutta la word
+        if (SRAM_START..=SAVE_BUS_END).contains(&addr) {
+            return self.save.read_bus(addr, 4);
+        }
+
+        // OAM
+        if (0x07000000..0x08000000).contains(&addr) {
+            let offset = (addr & 0x3FC) as usize;
+            let low = self.ppu.read_oam_halfword(offset);
+            let high = self.ppu.read_oam_halfword(offset + 2);
+            return (low as u32) | ((high as u32) << 16);
+        }
+
+        // Palette RAM
+        if (0x05000000..0x06000000).contains(&addr) {
+            let offset = (addr & 0x3FC) as usize;
+            let low = self.ppu.read_palette_halfword(offset);
+            let high = self.ppu.read_palette_halfword(offset + 2);
+            return (low as u32) | ((high as u32) << 16);
+        }
+
+        // I/O Registers
+        if is_io_register(addr) {
+            let low = self.read_io_halfword(addr);
+            let high = self.read_io_halfword(addr + 2);
+            return (low as u32) | ((high as u32) << 16);
+        }
+
+        // GPIO della cartuccia: DATA+DIRECTION, o CONTROL e la ROM che segue
+        if Gpio::covers(addr) && self.gpio.readable() {
+            let addr = addr & !3;
+            let low = self.gpio.read_halfword(addr);
+            let high = if Gpio::covers(addr + 2) {
+                self.gpio.read_halfword(addr + 2)
+            } else {
+                self.memory.read_halfword(addr + 2)
+            };
+            return (low as u32) | ((high as u32) << 16);
+        }
+        self.memory.read_word(addr)
+    }
+
+    /// Registra l'accesso se tocca un watchpoint del debugger
+    fn check_watchpoints(&mut self, addr: u32, size: u32, write: bool) {
+        if self.watchpoints.is_empty() || self.watch_hit.is_some() {
+            return;
+        }
+        let hit = self
+            .watchpoints
+            .iter()
+            .any(|w| w.kind.matches(write) && w.addr.wrapping_sub(addr) < size);
+        if hit {
+            self.watch_hit = Some(WatchHit { addr, write });
+        }
+    }
+
+    /// Riporta un accesso alle callback che ne coprono l'indirizzo
+    fn notify_memory_watches(&mut self, addr: u32, size: u32, value: u32, write: bool) {
+        let access = MemoryAccess {
+            pc: self.access_pc,
+            addr,
+            size,
+            value,
+            write,
+        };
+        for watch in &mut self.memory_watches {
+            if watch.covers(addr, size) {
+                watch.notify(&access);
+            }
+        }
+    }
+
+    /// Scrivi byte in VRAM
+    ///
+    /// La VRAM ha un bus a 16 bit: nell'area BG il byte viene duplicato
+    /// in entrambe le metà dell'halfword, nell'area OBJ la scrittura è ignorata.
+    fn write_vram_byte(&mut self, addr: u32, value: u8) {
+        let offset = vram_offset(addr);
+        if offset >= self.ppu.obj_vram_start() {
+            return;
+        }
+        let duplicated = ((value as u16) << 8) | (value as u16);
+        self.memory.write_halfword(addr & !1, duplicated);
+    }
+
     /// Leggi I/O register (halfword)
     fn read_io_halfword(&mut self, addr: u32) -> u16 {
+        self.peek_io_halfword(addr)
+    }
+
+    /// Valore di un I/O register senza effetti collaterali
+    fn peek_io_halfword(&self, addr: u32) -> u16 {
         match addr & !1 {
             // PPU registers
             0x04000000 => self.ppu.read_register(addr), // DISPCNT
+            0x04000002 => self.ppu.read_register(addr), // GREENSWAP
             0x04000004 => self.ppu.read_register(addr), // DISPSTAT
             0x04000006 => self.ppu.read_register(addr), // VCOUNT
             0x04000008 => self.ppu.read_register(addr), // BG0CNT
@@ -214,9 +507,20 @@ impl Bus {
             0x04000202 => self.interrupt.if_,        // IF
             0x04000208 => self.interrupt.ime as u16, // IME
 
+            // Wait state control
+            0x04000204 => self.timing.read_waitcnt(), // WAITCNT
+            MEMCNT => self.timing.read_memcnt() as u16,
+            0x04000802 => (self.timing.read_memcnt() >> 16) as u16,
+
+            // POSTFLG (HALTCNT è write-only)
+            0x04000300 => self.postflg as u16,
+
             // Input
             0x04000130 => self.input.read_keyinput(), // KEYINPUT
 
+            // Serial (0x04000120-0x0400015A, tranne KEYINPUT/KEYCNT)
+            0x04000120..=0x0400012E | 0x04000134..=0x0400015A => self.serial.read_register(addr),
+
             // APU registers (0x04000060-0x040000AE)
             0x04000060..=0x040000AE => self.apu.read_halfword(addr),
 
@@ -227,8 +531,10 @@ impl Bus {
             0x040000B0..=0x040000DE => self.dma.read_register(addr) as u16,
 
             _ => {
-                // Altri I/O non implementati
-                0
+                // Altri I/O non implementati: rileggono l'ultimo valore scritto
+                let offset = (addr & 0x3FE) as usize;
+                let io = &self.memory.io_registers;
+                u16::from_le_bytes([io[offset], io[offset + 1]])
             }
         }
     }
@@ -238,7 +544,9 @@ impl Bus {
         match addr & !1 {
             // PPU registers
             0x04000000 => self.ppu.write_register(addr, value), // DISPCNT
+            0x04000002 => self.ppu.write_register(addr, value), // GREENSWAP
             0x04000004 => self.ppu.write_register(addr, value), // DISPSTAT
+            0x04000006 => self.ppu.write_register(addr, value), // VCOUNT (sola lettura)
             0x04000008 => self.ppu.write_register(addr, value), // BG0CNT
             0x0400000A => self.ppu.write_register(addr, value), // BG1CNT
             0x0400000C => self.ppu.write_register(addr, value), // BG2CNT
@@ -251,12 +559,32 @@ impl Bus {
             0x0400001A => self.ppu.write_register(addr, value), // BG2VOFS
             0x0400001C => self.ppu.write_register(addr, value), // BG3HOFS
             0x0400001E => self.ppu.write_register(addr, value), // BG3VOFS
+            // BG2/BG3 affine: matrice e reference point (write-only)
+            0x04000020..=0x0400003E => self.ppu.write_register(addr, value),
 
             // Interrupt registers
             0x04000200 => self.interrupt.ie = value,
-            0x04000202 => self.interrupt.if_ = value,
+            // IF: scrivere 1 riconosce (azzera) l'interrupt
+            0x04000202 => self.interrupt.if_ &= !value,
             0x04000208 => self.interrupt.ime = (value & 0x01) != 0,
 
+            // Wait state control
+            0x04000204 => self.timing.write_waitcnt(value),
+            MEMCNT => {
+                let high = self.timing.read_memcnt() & 0xFFFF_0000;
+                self.timing.write_memcnt(high | value as u32);
+            }
+            0x04000802 => {
+                let low = self.timing.read_memcnt() & 0xFFFF;
+                self.timing.write_memcnt(low | (value as u32) << 16);
+            }
+
+            // POSTFLG + HALTCNT
+            0x04000300 => {
+                self.postflg = value as u8 & 1;
+                self.write_haltcnt((value >> 8) as u8);
+            }
+
             // APU registers (0x04000060-0x040000AE)
             0x04000060..=0x040000AE => self.apu.write_halfword(addr, value),
 
@@ -266,12 +594,38 @@ impl Bus {
             // DMA registers (0x040000B0-0x040000DE)
             0x040000B0..=0x040000DE => self.dma.write_register(addr, value as u32, true),
 
+            // KEYCNT: la condizione può essere già soddisfatta
+            KEYCNT => {
+                let offset = (KEYCNT & 0x3FE) as usize;
+                self.memory.io_registers[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+                self.check_keypad_irq();
+            }
+
+            // Serial (0x04000120-0x0400015A, tranne KEYINPUT/KEYCNT)
+            0x04000120..=0x0400012E | 0x04000134..=0x0400015A => {
+                let irq = self.serial.write_register(addr, value);
+                if irq {
+                    self.interrupt.request(InterruptFlags::SERIAL);
+                }
+            }
+
             _ => {
-                // Altri I/O non implementati
+                // Altri I/O non implementati: il valore viene solo memorizzato
+                let offset = (addr & 0x3FE) as usize;
+                self.memory.io_registers[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
             }
         }
     }
 
+    /// HALTCNT: bit 7 = 0 Halt, 1 Stop
+    fn write_haltcnt(&mut self, value: u8) {
+        self.power = if value & 0x80 != 0 {
+            PowerState::Stop
+        } else {
+            PowerState::Halt
+        };
+    }
+
     /// Leggi I/O register (byte)
     fn read_io_byte(&mut self, addr: u32) -> u8 {
         let halfword = self.read_io_halfword(addr & !1);
@@ -284,6 +638,24 @@ impl Bus {
 
     /// Scrivi I/O register (byte)
     fn write_io_byte(&mut self, addr: u32, value: u8) {
+        // POSTFLG e HALTCNT sono registri a 8 bit: niente read-modify-write
+        match addr {
+            0x04000300 => {
+                self.postflg = value & 1;
+                return;
+            }
+            0x04000301 => {
+                self.write_haltcnt(value);
+                return;
+            }
+            // IF: azzera solo i bit del byte scritto
+            0x04000202 | 0x04000203 => {
+                self.interrupt.if_ &= !((value as u16) << ((addr & 1) * 8));
+                return;
+            }
+            _ => {}
+        }
+
         let aligned = addr & !1;
         let current = self.read_io_halfword(aligned);
         let new_value = if addr & 1 == 0 {
@@ -295,6 +667,97 @@ impl Bus {
     }
 }
 
+//==============================================================================
+// DMA
+//==============================================================================
+
+impl Bus {
+    /// Esegue i trasferimenti DMA attivi e richiede gli IRQ di fine trasferimento
+    ///
+    /// Ritorna i cicli di bus spesi dal DMA, durante i quali la CPU è ferma.
+    pub fn run_dma(&mut self) -> u32 {
+        if !self.dma.is_active() {
+            return 0;
+        }
+
+        // Un'unità alla volta: il trasferimento può scrivere nei registri
+        // DMA (es. riarmare un canale) e le scritture restano valide
+        let mut cycles = 0;
+        let mut running = None;
+        self.in_dma = true;
+        while let Some(unit) = self.dma.next_unit(&self.timing, running) {
+            cycles += unit.cycles;
+            running = Some(unit.channel);
+
+            if unit.is_32bit {
+                let value = self.read_word(unit.source & !3);
+                self.write_word(unit.dest & !3, value);
+            } else {
+                let value = self.read_halfword(unit.source & !1);
+                self.write_halfword(unit.dest & !1, value);
+            }
+
+            // DMA0-3 = bit 8-11 di IF
+            if self.dma.complete_unit(unit.channel) {
+                self.interrupt.request(InterruptFlags::from_bits_truncate(
+                    1 << (8 + unit.channel),
+                ));
+            }
+        }
+        self.in_dma = false;
+
+        cycles
+    }
+}
+
+//==============================================================================
+// ISPEZIONE MEMORIA
+//==============================================================================
+// Letture per debugger, cheat search e viewer: non contano wait state, non
+// fanno scattare watchpoint e non modificano lo stato dei componenti.
+//==============================================================================
+
+impl Bus {
+    /// Leggi un byte senza effetti collaterali
+    pub fn peek_byte(&self, addr: u32) -> u8 {
+        match addr {
+            SRAM_START..=SAVE_BUS_END => self.save.read_bus(addr, 1) as u8,
+            0x07000000..=0x07FFFFFF => self.ppu.read_oam_byte((addr & 0x3FF) as usize),
+            0x05000000..=0x05FFFFFF => self.ppu.read_palette_byte((addr & 0x3FF) as usize),
+            _ if is_io_register(addr) => {
+                let halfword = self.peek_io_halfword(addr & !1);
+                (halfword >> ((addr & 1) * 8)) as u8
+            }
+            _ => self.memory.read_byte(addr),
+        }
+    }
+
+    /// Leggi un halfword senza effetti collaterali
+    pub fn peek_halfword(&self, addr: u32) -> u16 {
+        if (SRAM_START..=SAVE_BUS_END).contains(&addr) {
+            return self.save.read_bus(addr, 2) as u16;
+        }
+        let addr = addr & !1;
+        (self.peek_byte(addr) as u16) | ((self.peek_byte(addr + 1) as u16) << 8)
+    }
+
+    /// Leggi una word senza effetti collaterali
+    pub fn peek_word(&self, addr: u32) -> u32 {
+        if (SRAM_START..=SAVE_BUS_END).contains(&addr) {
+            return self.save.read_bus(addr, 4);
+        }
+        let addr = addr & !3;
+        (self.peek_halfword(addr) as u32) | ((self.peek_halfword(addr + 2) as u32) << 16)
+    }
+
+    /// Copia `len` byte a partire da `start` senza effetti collaterali
+    pub fn dump_region(&self, start: u32, len: usize) -> Vec<u8> {
+        (0..len)
+            .map(|i| self.peek_byte(start.wrapping_add(i as u32)))
+            .collect()
+    }
+}
+
 impl Default for Bus {
     fn default() -> Self {
         Self::new()
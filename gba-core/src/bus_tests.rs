@@ -0,0 +1,286 @@
+use crate::bus::Bus;
+use crate::debugger::{MemoryAccess, MemoryWatch, WatchKind, Watchpoint};
+use crate::save::SaveType;
+use gba_arm7tdmi::cpu::MemoryBus;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[test]
+fn test_peek_matches_bus_reads() {
+    let mut bus = Bus::new();
+    bus.write_word(0x0200_0000, 0xDEAD_BEEF);
+    bus.write_halfword(0x0500_0002, 0x7FFF);
+    bus.write_halfword(0x0700_0000, 0x1234);
+
+    assert_eq!(bus.peek_word(0x0200_0000), 0xDEAD_BEEF);
+    assert_eq!(bus.peek_halfword(0x0200_0002), 0xDEAD);
+    assert_eq!(bus.peek_byte(0x0200_0001), 0xBE);
+    assert_eq!(bus.peek_halfword(0x0500_0002), 0x7FFF);
+    assert_eq!(bus.peek_halfword(0x0700_0000), 0x1234);
+    assert_eq!(bus.peek_word(0x0200_0000), bus.read_word(0x0200_0000));
+}
+
+#[test]
+fn test_peek_io_has_no_side_effects() {
+    let mut bus = Bus::new();
+    bus.write_halfword(0x0400_0004, 0x0018); // DISPSTAT: IRQ VBlank + HBlank
+    let dispstat = bus.ppu.dispstat;
+    let vcount = bus.ppu.scanline;
+
+    bus.watchpoints.push(Watchpoint {
+        addr: 0x0400_0004,
+        kind: WatchKind::Read,
+    });
+
+    let value = bus.peek_word(0x0400_0004);
+
+    // Impostazioni + flag V-counter (VCOUNT = 0 = valore in DISPSTAT)
+    assert_eq!(value & 0xFFFF, 0x001C);
+    assert_eq!(value >> 16, vcount as u32);
+    assert_eq!(bus.ppu.dispstat, dispstat);
+    assert_eq!(bus.ppu.scanline, vcount);
+    assert!(bus.watch_hit.is_none());
+    assert_eq!(bus.timing.end_step(0), 0);
+}
+
+#[test]
+fn test_dump_region() {
+    let mut bus = Bus::new();
+    for (i, byte) in [0x11u8, 0x22, 0x33, 0x44].iter().enumerate() {
+        bus.write_byte(0x0300_0010 + i as u32, *byte);
+    }
+
+    assert_eq!(
+        bus.dump_region(0x0300_000F, 6),
+        vec![0, 0x11, 0x22, 0x33, 0x44, 0]
+    );
+    assert!(bus.dump_region(0x0300_0000, 0).is_empty());
+}
+
+#[test]
+fn test_vram_mirrors() {
+    let mut bus = Bus::new();
+
+    // Gli ultimi 32 KB di ogni blocco da 128 KB rispecchiano l'area OBJ
+    bus.write_halfword(0x0601_8010, 0xBEEF);
+    assert_eq!(bus.read_halfword(0x0601_0010), 0xBEEF);
+    bus.write_word(0x0601_4000, 0x1234_5678);
+    assert_eq!(bus.read_word(0x0601_C000), 0x1234_5678);
+
+    // L'intera VRAM si ripete ogni 128 KB
+    bus.write_halfword(0x0600_0100, 0x7FFF);
+    assert_eq!(bus.read_halfword(0x0602_0100), 0x7FFF);
+    assert_eq!(bus.read_halfword(0x06FE_0100), 0x7FFF);
+}
+
+#[test]
+fn test_vram_byte_write_to_obj_mirror_is_ignored() {
+    let mut bus = Bus::new();
+
+    // In Mode 3 l'area OBJ parte da 0x06014000 (mirror 0x0601C000)
+    bus.write_halfword(0x0400_0000, 0x0003);
+    bus.write_byte(0x0601_C000, 0xAA);
+    bus.write_byte(0x0601_8000, 0x55);
+
+    assert_eq!(bus.read_byte(0x0601_4000), 0);
+    assert_eq!(bus.read_halfword(0x0601_0000), 0x5555);
+}
+
+#[test]
+fn test_unhandled_io_reads_back_written_value() {
+    let mut bus = Bus::new();
+
+    // KEYCNT non ha un registro proprio in `Input`: il bus lo tiene nel
+    // backing store e lo rilegge da lì
+    bus.write_halfword(0x0400_0132, 0xC3FF);
+    assert_eq!(bus.read_halfword(0x0400_0132), 0xC3FF);
+
+    // Scritture a byte su un indirizzo non usato
+    bus.write_byte(0x0400_0057, 0xAB);
+    bus.write_byte(0x0400_0056, 0xCD);
+    assert_eq!(bus.read_halfword(0x0400_0056), 0xABCD);
+    assert_eq!(bus.peek_halfword(0x0400_0056), 0xABCD);
+
+    // Word che copre due halfword non gestiti
+    bus.write_word(0x0400_0058, 0x1234_5678);
+    assert_eq!(bus.read_word(0x0400_0058), 0x1234_5678);
+}
+
+#[test]
+fn test_memory_watch_reports_ewram_accesses() {
+    let mut bus = Bus::new();
+    let log: Rc<RefCell<Vec<MemoryAccess>>> = Rc::default();
+    let sink = Rc::clone(&log);
+    bus.add_memory_watch(MemoryWatch::new(0x0200_1000..=0x0200_10FF, move |access| {
+        sink.borrow_mut().push(*access)
+    }));
+    bus.access_pc = 0x0800_0124;
+
+    bus.write_halfword(0x0200_1010, 0xBEEF);
+    bus.write_word(0x0200_0000, 0x1234_5678); // Fuori dall'intervallo
+    let value = bus.read_byte(0x0200_1011);
+
+    assert_eq!(value, 0xBE);
+    assert_eq!(
+        *log.borrow(),
+        [
+            MemoryAccess {
+                pc: 0x0800_0124,
+                addr: 0x0200_1010,
+                size: 2,
+                value: 0xBEEF,
+                write: true,
+            },
+            MemoryAccess {
+                pc: 0x0800_0124,
+                addr: 0x0200_1011,
+                size: 1,
+                value: 0xBE,
+                write: false,
+            },
+        ]
+    );
+
+    // Solo gli accessi che toccano l'intervallo
+    bus.write_word(0x0200_0FFC, 0);
+    bus.read_word(0x0200_10FC);
+    assert_eq!(log.borrow().len(), 3);
+
+    bus.clear_memory_watches();
+    bus.write_byte(0x0200_1000, 1);
+    assert_eq!(log.borrow().len(), 3);
+}
+
+#[test]
+fn test_word_access_to_oam_and_palette_is_one_event() {
+    let mut bus = Bus::new();
+    let log: Rc<RefCell<Vec<MemoryAccess>>> = Rc::default();
+    let sink = Rc::clone(&log);
+    bus.add_memory_watch(MemoryWatch::new(0x0500_0000..=0x07FF_FFFF, move |access| {
+        sink.borrow_mut().push(*access)
+    }));
+
+    bus.write_word(0x0500_0010, 0x7FFF_001F);
+    bus.write_word(0x0700_0008, 0x1234_5678);
+    assert_eq!(bus.timing.end_step(0), 1);
+
+    assert_eq!(bus.read_word(0x0500_0010), 0x7FFF_001F);
+    assert_eq!(bus.read_word(0x0700_0008), 0x1234_5678);
+    // Palette: bus a 16 bit, un solo wait state per la word
+    assert_eq!(bus.timing.end_step(0), 1);
+
+    let log = log.borrow();
+    assert_eq!(log.len(), 4);
+    assert!(log.iter().all(|access| access.size == 4));
+    assert_eq!(log[2].addr, 0x0500_0010);
+    assert_eq!(log[2].value, 0x7FFF_001F);
+    assert_eq!(log[3].addr, 0x0700_0008);
+}
+
+#[test]
+fn test_sram_is_an_8bit_mirrored_bus() {
+    let mut bus = Bus::new();
+    bus.save.force_save_type(SaveType::Sram);
+
+    bus.write_byte(0x0E00_0010, 0x12);
+    assert_eq!(bus.read_word(0x0E00_0010), 0x1212_1212);
+    assert_eq!(bus.read_halfword(0x0E00_0010), 0x1212);
+    assert_eq!(bus.peek_word(0x0E00_0010), 0x1212_1212);
+
+    // La finestra da 64 KB si ripete fino a 0x0FFFFFFF
+    assert_eq!(bus.read_byte(0x0E01_0010), 0x12);
+    assert_eq!(bus.read_byte(0x0FFF_0010), 0x12);
+
+    // Scritture larghe: arriva solo il byte della lane indirizzata
+    bus.write_word(0x0E00_0021, 0xAABB_CCDD);
+    assert_eq!(bus.read_byte(0x0E00_0021), 0xCC);
+    bus.write_halfword(0x0E00_0030, 0x3456);
+    assert_eq!(bus.read_byte(0x0E00_0030), 0x56);
+}
+
+#[test]
+fn test_work_ram_mirrors() {
+    let mut bus = Bus::new();
+
+    // IWRAM ogni 32 KB, fino in fondo alla regione
+    bus.write_word(0x0300_0000, 0x1234_5678);
+    assert_eq!(bus.read_word(0x0300_8000), 0x1234_5678);
+    bus.write_word(0x03FF_FFFC, 0x0800_0100);
+    assert_eq!(bus.read_word(0x0300_7FFC), 0x0800_0100);
+
+    // EWRAM ogni 256 KB
+    bus.write_halfword(0x0204_0010, 0xBEEF);
+    assert_eq!(bus.read_halfword(0x0200_0010), 0xBEEF);
+}
+
+#[test]
+fn test_palette_and_oam_mirrors() {
+    let mut bus = Bus::new();
+
+    bus.write_halfword(0x0500_0402, 0x7FFF);
+    assert_eq!(bus.read_halfword(0x0500_0002), 0x7FFF);
+    assert_eq!(bus.ppu.read_palette_halfword(2), 0x7FFF);
+
+    bus.write_halfword(0x0700_0000, 0x1234);
+    assert_eq!(bus.read_halfword(0x07FF_FC00), 0x1234);
+    assert_eq!(bus.peek_halfword(0x0700_0400), 0x1234);
+}
+
+#[test]
+fn test_if_write_acknowledges_interrupts() {
+    use crate::interrupt::InterruptFlags;
+
+    let mut bus = Bus::new();
+    bus.interrupt.request(InterruptFlags::VBLANK);
+    bus.interrupt.request(InterruptFlags::TIMER0);
+    bus.interrupt.request(InterruptFlags::DMA0);
+
+    bus.write_halfword(0x0400_0202, InterruptFlags::VBLANK.bits());
+    assert_eq!(
+        bus.interrupt.if_,
+        (InterruptFlags::TIMER0 | InterruptFlags::DMA0).bits()
+    );
+
+    // Scrittura a byte: l'altra metà di IF resta invariata
+    bus.write_byte(0x0400_0203, (InterruptFlags::DMA0.bits() >> 8) as u8);
+    assert_eq!(bus.interrupt.if_, InterruptFlags::TIMER0.bits());
+}
+
+#[test]
+fn test_fetch_uses_the_same_dispatch_as_reads() {
+    let mut bus = Bus::new();
+    bus.write_word(0x0500_0100, 0xE1A0_0000);
+
+    // Palette con mirror ogni 1 KB, come le letture dati
+    assert_eq!(bus.fetch_word(0x0500_0500), 0xE1A0_0000);
+    assert_eq!(bus.fetch_halfword(0x0500_0502), 0xE1A0);
+    assert_eq!(bus.fetch_word(0x0500_0500), bus.read_word(0x0500_0500));
+
+    bus.watchpoints.push(Watchpoint {
+        addr: 0x0500_0102,
+        kind: WatchKind::Read,
+    });
+    bus.fetch_halfword(0x0500_0102);
+    assert!(bus.watch_hit.is_some());
+}
+
+#[test]
+fn test_dma_register_writes_during_transfer_are_kept() {
+    let mut bus = Bus::new();
+
+    // DMA0: sorgente/destinazione pronte, il controllo lo scrive DMA3
+    bus.write_word(0x0200_0100, 0xCAFE_BABE);
+    bus.write_word(0x0400_00B0, 0x0200_0100);
+    bus.write_word(0x0400_00B4, 0x0200_0200);
+
+    // Parola copiata in DMA0CNT: count 1, enable, immediato, 32 bit
+    bus.write_word(0x0200_0000, 0x8400_0001);
+    bus.write_word(0x0400_00D4, 0x0200_0000);
+    bus.write_word(0x0400_00D8, 0x0400_00B8);
+    bus.write_word(0x0400_00DC, 0x8400_0001);
+
+    bus.run_dma();
+
+    // DMA0 armato durante il trasferimento di DMA3 ha girato davvero
+    assert_eq!(bus.read_word(0x0200_0200), 0xCAFE_BABE);
+}
@@ -1,19 +1,64 @@
+use crate::archive::{self, ArchiveError};
+use crate::checksum::{crc32, sha1};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
 use std::fs;
+#[cfg(feature = "std")]
 use std::path::{Path, PathBuf};
 use thiserror::Error;
 
-#[derive(Error, Debug)]
-pub enum CartridgeError {
-    #[error("Failed to load ROM: {0}")]
-    LoadError(String),
+/// Dimensione massima di una ROM (spazio di indirizzi WS0, 32 MB)
+pub const MAX_ROM_SIZE: usize = 32 * 1024 * 1024;
+
+/// Dimensione del BIOS del GBA (16 KB)
+pub const BIOS_SIZE: usize = 0x4000;
 
+/// Errori di caricamento di ROM e BIOS
+#[derive(Error, Debug)]
+pub enum LoadError {
     #[error("Invalid ROM size")]
     InvalidSize,
 
+    #[error("ROM is too large: {0} bytes (max 32 MB)")]
+    RomTooLarge(usize),
+
+    #[error("Invalid ROM header: Nintendo logo does not match (not a GBA ROM?)")]
+    InvalidLogo,
+
+    #[error("Invalid ROM header: entry point {0:#010X} is not an ARM branch")]
+    InvalidEntryPoint(u32),
+
+    #[error("Invalid BIOS size: {0} bytes (expected 16 KB)")]
+    InvalidBiosSize(usize),
+
+    #[error("Multiboot image is too large: {0} bytes (max 256 KB)")]
+    MultibootTooLarge(usize),
+
+    #[error("Compressed ROM: {0}")]
+    Archive(#[from] ArchiveError),
+
+    #[cfg(feature = "std")]
     #[error("IO Error: {0}")]
     IoError(#[from] std::io::Error),
 }
 
+/// Logo Nintendo compresso presente a 0x04-0x9F in ogni ROM ufficiale
+///
+/// Il BIOS lo confronta all'avvio e si blocca se non coincide.
+pub const NINTENDO_LOGO: [u8; 156] = [
+    0x24, 0xFF, 0xAE, 0x51, 0x69, 0x9A, 0xA2, 0x21, 0x3D, 0x84, 0x82, 0x0A, 0x84, 0xE4, 0x09, 0xAD,
+    0x11, 0x24, 0x8B, 0x98, 0xC0, 0x81, 0x7F, 0x21, 0xA3, 0x52, 0xBE, 0x19, 0x93, 0x09, 0xCE, 0x20,
+    0x10, 0x46, 0x4A, 0x4A, 0xF8, 0x27, 0x31, 0xEC, 0x58, 0xC7, 0xE8, 0x33, 0x82, 0xE3, 0xCE, 0xBF,
+    0x85, 0xF4, 0xDF, 0x94, 0xCE, 0x4B, 0x09, 0xC1, 0x94, 0x56, 0x8A, 0xC0, 0x13, 0x72, 0xA7, 0xFC,
+    0x9F, 0x84, 0x4D, 0x73, 0xA3, 0xCA, 0x9A, 0x61, 0x58, 0x97, 0xA3, 0x27, 0xFC, 0x03, 0x98, 0x76,
+    0x23, 0x1D, 0xC7, 0x61, 0x03, 0x04, 0xAE, 0x56, 0xBF, 0x38, 0x84, 0x00, 0x40, 0xA7, 0x0E, 0xFD,
+    0xFF, 0x52, 0xFE, 0x03, 0x6F, 0x95, 0x30, 0xF1, 0x97, 0xFB, 0xC0, 0x85, 0x60, 0xD6, 0x80, 0x25,
+    0xA9, 0x63, 0xBE, 0x03, 0x01, 0x4E, 0x38, 0xE2, 0xF9, 0xA2, 0x34, 0xFF, 0xBB, 0x3E, 0x03, 0x44,
+    0x78, 0x00, 0x90, 0xCB, 0x88, 0x11, 0x3A, 0x94, 0x65, 0xC0, 0x7C, 0x63, 0x87, 0xF0, 0x3C, 0xAF,
+    0xD6, 0x25, 0xE4, 0x8B, 0x38, 0x0A, 0xAC, 0x72, 0x21, 0xD4, 0xF8, 0x07,
+];
+
 /// Informazioni header ROM GBA
 #[derive(Debug, Clone)]
 pub struct RomHeader {
@@ -26,30 +71,96 @@ pub struct RomHeader {
 pub struct Cartridge {
     pub rom: Vec<u8>,
     pub header: RomHeader,
+    /// File da cui è stata caricata (vicino alla ROM c'è il .sav)
+    #[cfg(feature = "std")]
     pub rom_path: Option<PathBuf>,
+    /// Hash della ROM calcolati al caricamento
+    crc32: u32,
+    sha1: [u8; 20],
 }
 
 impl Cartridge {
-    /// Carica una ROM da file
-    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, CartridgeError> {
-        let rom = fs::read(path.as_ref())?;
+    /// Carica una ROM da file, verificando logo ed entry point
+    #[cfg(feature = "std")]
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, LoadError> {
+        Self::load_with_validation(path, true)
+    }
+
+    /// Carica una ROM da file (anche .zip/.gz); `validate` = false accetta
+    /// header non standard (homebrew senza logo)
+    #[cfg(feature = "std")]
+    pub fn load_with_validation<P: AsRef<Path>>(
+        path: P,
+        validate: bool,
+    ) -> Result<Self, LoadError> {
+        let data = fs::read(path.as_ref())?;
+        let mut cartridge = Self::from_file_bytes(data, validate)?;
+        cartridge.rom_path = Some(path.as_ref().to_path_buf());
+        Ok(cartridge)
+    }
+
+    /// Crea una cartuccia dal contenuto di un file ROM: un .gba così com'è,
+    /// oppure un .zip/.gz da cui viene estratta la ROM
+    pub fn from_file_bytes(data: Vec<u8>, validate: bool) -> Result<Self, LoadError> {
+        Self::from_bytes(archive::extract_rom(data)?, validate)
+    }
 
+    /// Crea una cartuccia da un'immagine ROM in memoria
+    pub fn from_bytes(rom: Vec<u8>, validate: bool) -> Result<Self, LoadError> {
         if rom.len() < 0xC0 {
-            return Err(CartridgeError::InvalidSize);
+            return Err(LoadError::InvalidSize);
+        }
+        if rom.len() > MAX_ROM_SIZE {
+            return Err(LoadError::RomTooLarge(rom.len()));
+        }
+
+        if validate {
+            Self::validate_header(&rom)?;
         }
 
         let header = Self::parse_header(&rom)?;
-        let rom_path = Some(path.as_ref().to_path_buf());
 
         Ok(Self {
+            crc32: crc32(&rom),
+            sha1: sha1(&rom),
             rom,
             header,
-            rom_path,
+            #[cfg(feature = "std")]
+            rom_path: None,
         })
     }
 
+    /// CRC32 della ROM (come nei database No-Intro)
+    pub fn crc32(&self) -> u32 {
+        self.crc32
+    }
+
+    /// SHA-1 della ROM
+    pub fn sha1(&self) -> [u8; 20] {
+        self.sha1
+    }
+
+    /// Controlla il logo Nintendo e che l'entry point sia un branch ARM
+    pub fn validate_header(rom: &[u8]) -> Result<(), LoadError> {
+        if rom.len() < 0xC0 {
+            return Err(LoadError::InvalidSize);
+        }
+
+        if rom[0x04..0xA0] != NINTENDO_LOGO {
+            return Err(LoadError::InvalidLogo);
+        }
+
+        // Entry point @ 0x00: B incondizionato (cond = AL, bit 27-25 = 101)
+        let entry = u32::from_le_bytes([rom[0], rom[1], rom[2], rom[3]]);
+        if entry >> 24 != 0xEA {
+            return Err(LoadError::InvalidEntryPoint(entry));
+        }
+
+        Ok(())
+    }
+
     /// Parse dell'header ROM
-    fn parse_header(rom: &[u8]) -> Result<RomHeader, CartridgeError> {
+    fn parse_header(rom: &[u8]) -> Result<RomHeader, LoadError> {
         // Title @ 0xA0-0xAB
         let title_bytes = &rom[0xA0..0xAC];
         let title = String::from_utf8_lossy(title_bytes)
@@ -0,0 +1,81 @@
+use crate::cartridge::{Cartridge, LoadError, BIOS_SIZE, MAX_ROM_SIZE, NINTENDO_LOGO};
+use crate::GbaEmulator;
+
+/// ROM minima con header valido: B 0x080000C0, logo e titolo
+///
+/// Condivisa con `archive_tests`.
+pub(crate) fn valid_rom() -> Vec<u8> {
+    let mut rom = vec![0u8; 0x200];
+    rom[0..4].copy_from_slice(&0xEA00_002Eu32.to_le_bytes());
+    rom[0x04..0xA0].copy_from_slice(&NINTENDO_LOGO);
+    rom[0xA0..0xA4].copy_from_slice(b"TEST");
+    rom[0xAC..0xB0].copy_from_slice(b"ABCE");
+    rom
+}
+
+#[test]
+fn test_valid_header_passes() {
+    let cartridge = Cartridge::from_bytes(valid_rom(), true).unwrap();
+    assert_eq!(cartridge.header.title, "TEST");
+    assert_eq!(cartridge.header.game_code, "ABCE");
+    #[cfg(feature = "std")]
+    assert!(cartridge.rom_path.is_none());
+}
+
+#[test]
+fn test_zeroed_header_fails() {
+    let result = Cartridge::from_bytes(vec![0u8; 0x200], true);
+    assert!(matches!(result, Err(LoadError::InvalidLogo)));
+}
+
+#[test]
+fn test_entry_point_must_be_branch() {
+    let mut rom = valid_rom();
+    rom[0..4].copy_from_slice(&0xE3A0_0000u32.to_le_bytes()); // MOV R0, #0
+
+    let result = Cartridge::from_bytes(rom, true);
+    assert!(matches!(
+        result,
+        Err(LoadError::InvalidEntryPoint(0xE3A0_0000))
+    ));
+}
+
+#[test]
+fn test_validation_can_be_bypassed() {
+    // Homebrew senza logo: accettato se la validazione è disattivata
+    let cartridge = Cartridge::from_bytes(vec![0u8; 0x200], false).unwrap();
+    assert_eq!(cartridge.rom.len(), 0x200);
+
+    // La dimensione minima resta obbligatoria
+    let result = Cartridge::from_bytes(vec![0u8; 0x40], false);
+    assert!(matches!(result, Err(LoadError::InvalidSize)));
+}
+
+#[test]
+fn test_oversized_rom_rejected() {
+    let mut rom = valid_rom();
+    rom.resize(MAX_ROM_SIZE + 1, 0);
+
+    let result = Cartridge::from_bytes(rom, true);
+    assert!(matches!(result, Err(LoadError::RomTooLarge(size)) if size == MAX_ROM_SIZE + 1));
+
+    // 32 MB esatti sono ancora validi
+    let mut rom = valid_rom();
+    rom.resize(MAX_ROM_SIZE, 0);
+    assert!(Cartridge::from_bytes(rom, true).is_ok());
+}
+
+#[test]
+fn test_bios_must_be_16kb() {
+    let mut emu = GbaEmulator::new();
+
+    let result = emu.load_bios(vec![0; 0x1000]);
+    assert!(matches!(result, Err(LoadError::InvalidBiosSize(0x1000))));
+    let result = emu.load_bios(vec![0; BIOS_SIZE + 1]);
+    assert!(matches!(result, Err(LoadError::InvalidBiosSize(0x4001))));
+    // Un BIOS rifiutato lascia attivo l'HLE
+    assert!(emu.bios_hle());
+
+    emu.load_bios(vec![0; BIOS_SIZE]).unwrap();
+    assert!(!emu.bios_hle());
+}
@@ -0,0 +1,187 @@
+// Cheat Engine - Codici GameShark / Action Replay v3
+//
+// Formato dei codici: "XXXXXXXX YYYYYYYY" (indirizzo + valore, 8+8 hex),
+// una riga per codice. I codici AR v3 sono cifrati con TEA e vengono
+// decifrati al momento del parsing.
+//
+// Opcode supportati (dopo la decifratura), con `a0aaaaa` = indirizzo reale:
+// - 00aaaaaa xxxxxxyy: [a0aaaaa..+xxxxxx] = yy (8 bit, fill)
+// - 02aaaaaa xxxxyyyy: [a0aaaaa..+xxxx*2] = yyyy (16 bit, fill)
+// - 04aaaaaa yyyyyyyy: [a0aaaaa] = yyyyyyyy (32 bit)
+// - 08aaaaaa 000000yy: IF [a0aaaaa] == yy THEN riga successiva
+// - 0Aaaaaaa 0000yyyy: IF [a0aaaaa] == yyyy THEN riga successiva
+// - 0Caaaaaa yyyyyyyy: IF [a0aaaaa] == yyyyyyyy THEN riga successiva
+
+use crate::bus::Bus;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use gba_arm7tdmi::cpu::MemoryBus;
+use thiserror::Error;
+
+/// Seed TEA per Action Replay v3
+const AR_V3_SEEDS: [u32; 4] = [0x7AA9648F, 0x7FAE6994, 0xC0EFAAD5, 0x42712C57];
+
+/// Delta TEA
+const TEA_DELTA: u32 = 0x9E3779B9;
+
+#[derive(Error, Debug)]
+pub enum CheatError {
+    #[error("Invalid cheat code: {0}")]
+    InvalidFormat(String),
+
+    #[error("Unsupported cheat opcode: {0:08X}")]
+    UnsupportedOpcode(u32),
+}
+
+/// Singola operazione di un cheat (già decifrata)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheatOp {
+    Write8 { addr: u32, value: u8, count: u32 },
+    Write16 { addr: u32, value: u16, count: u32 },
+    Write32 { addr: u32, value: u32 },
+    IfEqual8 { addr: u32, value: u8 },
+    IfEqual16 { addr: u32, value: u16 },
+    IfEqual32 { addr: u32, value: u32 },
+}
+
+/// Cheat composto da una o più righe di codice
+#[derive(Debug, Clone)]
+pub struct Cheat {
+    pub code: String,
+    pub enabled: bool,
+    ops: Vec<CheatOp>,
+}
+
+impl Cheat {
+    /// Parsa un codice Action Replay v3 (cifrato)
+    pub fn parse(code: &str) -> Result<Self, CheatError> {
+        let mut ops = Vec::new();
+
+        for line in code.lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (address, value) = parse_line(line)?;
+            let (address, value) = decrypt_ar_v3(address, value);
+            ops.push(decode_op(address, value)?);
+        }
+
+        if ops.is_empty() {
+            return Err(CheatError::InvalidFormat(code.to_string()));
+        }
+
+        Ok(Self {
+            code: code.to_string(),
+            enabled: true,
+            ops,
+        })
+    }
+
+    /// Operazioni decodificate
+    pub fn ops(&self) -> &[CheatOp] {
+        &self.ops
+    }
+
+    /// Applica il cheat alla memoria (chiamato ogni frame)
+    pub fn apply(&self, bus: &mut Bus) {
+        if !self.enabled {
+            return;
+        }
+
+        let mut skip_next = false;
+
+        for op in &self.ops {
+            if skip_next {
+                skip_next = false;
+                continue;
+            }
+
+            match *op {
+                CheatOp::Write8 { addr, value, count } => {
+                    for i in 0..count {
+                        bus.write_byte(addr.wrapping_add(i), value);
+                    }
+                }
+                CheatOp::Write16 { addr, value, count } => {
+                    for i in 0..count {
+                        bus.write_halfword(addr.wrapping_add(i * 2), value);
+                    }
+                }
+                CheatOp::Write32 { addr, value } => bus.write_word(addr, value),
+                CheatOp::IfEqual8 { addr, value } => skip_next = bus.read_byte(addr) != value,
+                CheatOp::IfEqual16 { addr, value } => skip_next = bus.read_halfword(addr) != value,
+                CheatOp::IfEqual32 { addr, value } => skip_next = bus.read_word(addr) != value,
+            }
+        }
+    }
+}
+
+/// Parsa una riga "XXXXXXXX YYYYYYYY" (spazio opzionale)
+fn parse_line(line: &str) -> Result<(u32, u32), CheatError> {
+    let hex: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    if hex.len() != 16 {
+        return Err(CheatError::InvalidFormat(line.to_string()));
+    }
+
+    let address = u32::from_str_radix(&hex[..8], 16)
+        .map_err(|_| CheatError::InvalidFormat(line.to_string()))?;
+    let value = u32::from_str_radix(&hex[8..], 16)
+        .map_err(|_| CheatError::InvalidFormat(line.to_string()))?;
+
+    Ok((address, value))
+}
+
+/// Decifra una riga Action Replay v3 (TEA, 32 round)
+pub fn decrypt_ar_v3(mut address: u32, mut value: u32) -> (u32, u32) {
+    let seeds = AR_V3_SEEDS;
+    let mut sum = TEA_DELTA.wrapping_mul(32);
+
+    for _ in 0..32 {
+        value = value.wrapping_sub(
+            (address << 4).wrapping_add(seeds[2])
+                ^ address.wrapping_add(sum)
+                ^ (address >> 5).wrapping_add(seeds[3]),
+        );
+        address = address.wrapping_sub(
+            (value << 4).wrapping_add(seeds[0])
+                ^ value.wrapping_add(sum)
+                ^ (value >> 5).wrapping_add(seeds[1]),
+        );
+        sum = sum.wrapping_sub(TEA_DELTA);
+    }
+
+    (address, value)
+}
+
+/// Decodifica una riga decifrata in un'operazione
+fn decode_op(address: u32, value: u32) -> Result<CheatOp, CheatError> {
+    // Indirizzo: aaaaaa -> a0aaaaa
+    let field = address & 0x00FF_FFFF;
+    let addr = ((field & 0x00F0_0000) << 4) | (field & 0x000F_FFFF);
+
+    match address >> 24 {
+        0x00 if address != 0 => Ok(CheatOp::Write8 {
+            addr,
+            value: value as u8,
+            count: (value >> 8) + 1,
+        }),
+        0x02 => Ok(CheatOp::Write16 {
+            addr,
+            value: value as u16,
+            count: (value >> 16) + 1,
+        }),
+        0x04 => Ok(CheatOp::Write32 { addr, value }),
+        0x08 => Ok(CheatOp::IfEqual8 {
+            addr,
+            value: value as u8,
+        }),
+        0x0A => Ok(CheatOp::IfEqual16 {
+            addr,
+            value: value as u16,
+        }),
+        0x0C => Ok(CheatOp::IfEqual32 { addr, value }),
+        _ => Err(CheatError::UnsupportedOpcode(address)),
+    }
+}
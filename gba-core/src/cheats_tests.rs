@@ -0,0 +1,99 @@
+use crate::cheats::{decrypt_ar_v3, Cheat, CheatError, CheatOp};
+use crate::GbaEmulator;
+use gba_arm7tdmi::cpu::MemoryBus;
+
+const AR_V3_SEEDS: [u32; 4] = [0x7AA9648F, 0x7FAE6994, 0xC0EFAAD5, 0x42712C57];
+
+/// Cifra una riga AR v3 (inverso di `decrypt_ar_v3`)
+fn encrypt_ar_v3(mut address: u32, mut value: u32) -> (u32, u32) {
+    let seeds = AR_V3_SEEDS;
+    let mut sum: u32 = 0;
+
+    for _ in 0..32 {
+        sum = sum.wrapping_add(0x9E3779B9);
+        address = address.wrapping_add(
+            (value << 4).wrapping_add(seeds[0])
+                ^ value.wrapping_add(sum)
+                ^ (value >> 5).wrapping_add(seeds[1]),
+        );
+        value = value.wrapping_add(
+            (address << 4).wrapping_add(seeds[2])
+                ^ address.wrapping_add(sum)
+                ^ (address >> 5).wrapping_add(seeds[3]),
+        );
+    }
+
+    (address, value)
+}
+
+fn encrypted_line(address: u32, value: u32) -> String {
+    let (a, v) = encrypt_ar_v3(address, value);
+    format!("{:08X} {:08X}", a, v)
+}
+
+#[test]
+fn test_decrypt_roundtrip() {
+    let (a, v) = encrypt_ar_v3(0x0020_0000, 0x0000_0063);
+    assert_ne!((a, v), (0x0020_0000, 0x0000_0063));
+    assert_eq!(decrypt_ar_v3(a, v), (0x0020_0000, 0x0000_0063));
+}
+
+#[test]
+fn test_parse_write8() {
+    let cheat = Cheat::parse(&encrypted_line(0x0020_0000, 0x0000_0063)).unwrap();
+    assert_eq!(
+        cheat.ops(),
+        &[CheatOp::Write8 {
+            addr: 0x0200_0000,
+            value: 0x63,
+            count: 1,
+        }]
+    );
+}
+
+#[test]
+fn test_parse_invalid() {
+    assert!(matches!(
+        Cheat::parse("1234"),
+        Err(CheatError::InvalidFormat(_))
+    ));
+    assert!(matches!(
+        Cheat::parse("ZZZZZZZZ 00000000"),
+        Err(CheatError::InvalidFormat(_))
+    ));
+    assert!(matches!(
+        Cheat::parse(&encrypted_line(0xFE00_0000, 0)),
+        Err(CheatError::UnsupportedOpcode(_))
+    ));
+}
+
+#[test]
+fn test_cheat_writes_ewram_after_frame() {
+    let mut emu = GbaEmulator::new();
+
+    emu.add_cheat(&encrypted_line(0x0020_0000, 0x0000_0063))
+        .unwrap();
+    emu.run_frame();
+
+    assert_eq!(emu.bus.read_byte(0x0200_0000), 0x63);
+}
+
+#[test]
+fn test_conditional_skips_next_line() {
+    let mut emu = GbaEmulator::new();
+
+    // IF [0x03000000] == 0x12 THEN [0x03000004] = 0xBEEF
+    let code = format!(
+        "{}\n{}",
+        encrypted_line(0x0830_0000, 0x0000_0012),
+        encrypted_line(0x0230_0004, 0x0000_BEEF)
+    );
+    let cheat = Cheat::parse(&code).unwrap();
+
+    cheat.apply(&mut emu.bus);
+    assert_eq!(emu.bus.read_halfword(0x0300_0004), 0);
+
+    emu.bus.write_byte(0x0300_0000, 0x12);
+    cheat.apply(&mut emu.bus);
+    assert_eq!(emu.bus.read_halfword(0x0300_0004), 0xBEEF);
+}
@@ -0,0 +1,54 @@
+use crate::cartridge::Cartridge;
+use crate::checksum::{crc32, sha1};
+use crate::GbaEmulator;
+
+/// SHA-1 in esadecimale, per confrontarlo con i valori di riferimento
+fn hex(digest: [u8; 20]) -> String {
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[test]
+fn test_crc32_reference_values() {
+    assert_eq!(crc32(b""), 0);
+    assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    assert_eq!(
+        crc32(b"The quick brown fox jumps over the lazy dog"),
+        0x414F_A339
+    );
+}
+
+#[test]
+fn test_sha1_reference_values() {
+    assert_eq!(hex(sha1(b"")), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+    assert_eq!(
+        hex(sha1(b"abc")),
+        "a9993e364706816aba3e25717850c26c9cd0d89d"
+    );
+    // Due blocchi: il padding non sta nel primo
+    assert_eq!(
+        hex(sha1(
+            b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+        )),
+        "84983e441c3bd26ebaae4aa1f95129e5e54670f1"
+    );
+}
+
+#[test]
+fn test_cartridge_hashes_fixed_pattern() {
+    // 1 KB con byte 0x00..0xFF ripetuti
+    let rom: Vec<u8> = (0..1024).map(|i| i as u8).collect();
+    let cartridge = Cartridge::from_bytes(rom.clone(), false).unwrap();
+
+    assert_eq!(cartridge.crc32(), 0xB70B_4C26);
+    assert_eq!(
+        hex(cartridge.sha1()),
+        "5b00669c480d5cffbdfa8bdba99561160f2d1b77"
+    );
+
+    // Esposti anche dall'emulatore dopo il caricamento
+    let mut emu = GbaEmulator::new();
+    assert_eq!(emu.rom_crc32(), None);
+    emu.load_cartridge(cartridge);
+    assert_eq!(emu.rom_crc32(), Some(0xB70B_4C26));
+    assert_eq!(emu.rom_sha1().map(hex), Some(hex(sha1(&rom))));
+}
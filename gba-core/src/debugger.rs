@@ -0,0 +1,222 @@
+// Debugger - Esecuzione passo-passo con breakpoint e watchpoint
+//
+// Il debugger possiede l'emulatore e lo fa avanzare un'istruzione alla
+// volta. I breakpoint sono controllati sull'indirizzo del prossimo fetch
+// (PC), i watchpoint vengono registrati dal bus sui percorsi di
+// lettura/scrittura e segnalati al termine dell'istruzione che li ha
+// toccati.
+
+use crate::emulator::GbaEmulator;
+use alloc::boxed::Box;
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+use core::ops::RangeInclusive;
+use gba_arm7tdmi::{disassemble_arm, disassemble_thumb};
+
+/// Istruzioni eseguite al massimo da `continue_until_break`
+/// (circa 100 frame a 4 cicli per istruzione)
+pub const DEFAULT_STEP_LIMIT: u64 = 7_000_000;
+
+/// Tipo di accesso sorvegliato da un watchpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchKind {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl WatchKind {
+    /// Verifica se un accesso (`write` = scrittura) fa scattare il watchpoint
+    pub fn matches(self, write: bool) -> bool {
+        match self {
+            WatchKind::Read => !write,
+            WatchKind::Write => write,
+            WatchKind::ReadWrite => true,
+        }
+    }
+}
+
+/// Watchpoint su un singolo indirizzo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub addr: u32,
+    pub kind: WatchKind,
+}
+
+/// Accesso che ha fatto scattare un watchpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchHit {
+    pub addr: u32,
+    pub write: bool,
+}
+
+/// Accesso alla memoria riportato a un `MemoryWatch`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryAccess {
+    /// Istruzione in esecuzione (per il DMA, l'ultima eseguita dalla CPU)
+    pub pc: u32,
+    pub addr: u32,
+    /// Dimensione in byte (1, 2 o 4)
+    pub size: u32,
+    pub value: u32,
+    pub write: bool,
+}
+
+/// Callback chiamata dal bus per ogni accesso a un intervallo di indirizzi
+///
+/// A differenza dei watchpoint non ferma l'esecuzione: serve a tracciare
+/// chi legge o scrive una zona di memoria (reverse engineering, ROM hack).
+pub struct MemoryWatch {
+    start: u32,
+    end: u32,
+    callback: Box<dyn FnMut(&MemoryAccess)>,
+}
+
+impl MemoryWatch {
+    pub fn new(range: RangeInclusive<u32>, callback: impl FnMut(&MemoryAccess) + 'static) -> Self {
+        Self {
+            start: *range.start(),
+            end: *range.end(),
+            callback: Box::new(callback),
+        }
+    }
+
+    /// Verifica se l'accesso di `size` byte a `addr` tocca l'intervallo
+    pub fn covers(&self, addr: u32, size: u32) -> bool {
+        let last = addr.wrapping_add(size - 1);
+        addr <= self.end && last >= self.start
+    }
+
+    /// Riporta l'accesso alla callback
+    pub fn notify(&mut self, access: &MemoryAccess) {
+        (self.callback)(access);
+    }
+}
+
+/// Motivo per cui l'esecuzione si è fermata
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// Eseguita una singola istruzione
+    Step,
+    /// Il PC ha raggiunto un breakpoint
+    Breakpoint(u32),
+    /// L'istruzione all'indirizzo `pc` ha toccato un watchpoint
+    Watchpoint { pc: u32, hit: WatchHit },
+    /// Raggiunto il limite di istruzioni senza fermarsi
+    StepLimit,
+}
+
+/// Debugger passo-passo
+pub struct Debugger {
+    emulator: GbaEmulator,
+    breakpoints: BTreeSet<u32>,
+    step_limit: u64,
+}
+
+impl Debugger {
+    pub fn new(emulator: GbaEmulator) -> Self {
+        Self {
+            emulator,
+            breakpoints: BTreeSet::new(),
+            step_limit: DEFAULT_STEP_LIMIT,
+        }
+    }
+
+    /// Emulatore sotto debug
+    pub fn emulator(&self) -> &GbaEmulator {
+        &self.emulator
+    }
+
+    /// Emulatore sotto debug (mutabile)
+    pub fn emulator_mut(&mut self) -> &mut GbaEmulator {
+        &mut self.emulator
+    }
+
+    /// Restituisce l'emulatore rimuovendo i watchpoint
+    pub fn into_inner(mut self) -> GbaEmulator {
+        self.emulator.bus.watchpoints.clear();
+        self.emulator.bus.watch_hit = None;
+        self.emulator
+    }
+
+    /// Indirizzo della prossima istruzione
+    pub fn pc(&self) -> u32 {
+        self.emulator.cpu.regs.pc()
+    }
+
+    /// Disassembla la prossima istruzione
+    ///
+    /// Usa le letture di ispezione del bus (nessun watchpoint né wait state).
+    pub fn disassemble_next(&self) -> String {
+        let pc = self.pc();
+        let bus = &self.emulator.bus;
+        if self.emulator.cpu.regs.is_thumb() {
+            disassemble_thumb(bus.peek_halfword(pc), pc)
+        } else {
+            disassemble_arm(bus.peek_word(pc), pc)
+        }
+    }
+
+    pub fn add_breakpoint(&mut self, addr: u32) {
+        self.breakpoints.insert(addr);
+    }
+
+    pub fn remove_breakpoint(&mut self, addr: u32) -> bool {
+        self.breakpoints.remove(&addr)
+    }
+
+    pub fn breakpoints(&self) -> impl Iterator<Item = u32> + '_ {
+        self.breakpoints.iter().copied()
+    }
+
+    pub fn add_watchpoint(&mut self, addr: u32, kind: WatchKind) {
+        let watchpoints = &mut self.emulator.bus.watchpoints;
+        watchpoints.retain(|w| w.addr != addr);
+        watchpoints.push(Watchpoint { addr, kind });
+    }
+
+    pub fn remove_watchpoint(&mut self, addr: u32) -> bool {
+        let watchpoints = &mut self.emulator.bus.watchpoints;
+        let len = watchpoints.len();
+        watchpoints.retain(|w| w.addr != addr);
+        watchpoints.len() != len
+    }
+
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        &self.emulator.bus.watchpoints
+    }
+
+    /// Imposta il numero massimo di istruzioni per `continue_until_break`
+    pub fn set_step_limit(&mut self, limit: u64) {
+        self.step_limit = limit.max(1);
+    }
+
+    /// Esegue una singola istruzione
+    ///
+    /// Ignora un eventuale breakpoint sul PC corrente, così da poter
+    /// ripartire dopo essersi fermati.
+    pub fn step(&mut self) -> StopReason {
+        let pc = self.pc();
+        self.emulator.bus.watch_hit = None;
+        self.emulator.step();
+
+        if let Some(hit) = self.emulator.bus.watch_hit.take() {
+            return StopReason::Watchpoint { pc, hit };
+        }
+        if self.breakpoints.contains(&self.pc()) {
+            return StopReason::Breakpoint(self.pc());
+        }
+        StopReason::Step
+    }
+
+    /// Esegue fino a un breakpoint, un watchpoint o al limite di istruzioni
+    pub fn continue_until_break(&mut self) -> StopReason {
+        for _ in 0..self.step_limit {
+            match self.step() {
+                StopReason::Step => {}
+                reason => return reason,
+            }
+        }
+        StopReason::StepLimit
+    }
+}
@@ -0,0 +1,91 @@
+use crate::debugger::{Debugger, StopReason, WatchHit, WatchKind};
+use crate::GbaEmulator;
+
+const ARM_NOP: u32 = 0xE1A00000; // MOV r0, r0
+
+fn debugger_with_rom(code: &[u32]) -> Debugger {
+    let mut rom = Vec::new();
+    for word in code {
+        rom.extend_from_slice(&word.to_le_bytes());
+    }
+
+    let mut emu = GbaEmulator::new();
+    emu.bus.load_rom(rom);
+    emu.reset();
+    Debugger::new(emu)
+}
+
+#[test]
+fn test_breakpoint_halts_at_pc() {
+    let mut debugger = debugger_with_rom(&[ARM_NOP; 0x100]);
+    debugger.add_breakpoint(0x08000100);
+
+    assert_eq!(
+        debugger.continue_until_break(),
+        StopReason::Breakpoint(0x08000100)
+    );
+    assert_eq!(debugger.pc(), 0x08000100);
+    assert_eq!(debugger.disassemble_next(), "mov r0, r0");
+    assert_eq!(debugger.emulator().cpu.regs.pc(), 0x08000100);
+}
+
+#[test]
+fn test_step_resumes_from_breakpoint() {
+    let mut debugger = debugger_with_rom(&[ARM_NOP; 4]);
+    debugger.add_breakpoint(0x08000004);
+
+    assert_eq!(debugger.step(), StopReason::Breakpoint(0x08000004));
+    // Ripartire dal breakpoint esegue l'istruzione invece di restare fermi
+    assert_eq!(debugger.step(), StopReason::Step);
+    assert_eq!(debugger.pc(), 0x08000008);
+
+    assert!(debugger.remove_breakpoint(0x08000004));
+    assert_eq!(debugger.breakpoints().count(), 0);
+}
+
+#[test]
+fn test_watchpoints() {
+    let mut debugger = debugger_with_rom(&[
+        0xE3A00403, // MOV r0, #0x03000000
+        0xE5801000, // STR r1, [r0]
+        0xE5902000, // LDR r2, [r0]
+        ARM_NOP,
+    ]);
+    debugger.add_watchpoint(0x03000002, WatchKind::Write);
+
+    assert_eq!(
+        debugger.continue_until_break(),
+        StopReason::Watchpoint {
+            pc: 0x08000004,
+            hit: WatchHit {
+                addr: 0x03000000,
+                write: true,
+            },
+        }
+    );
+
+    // La lettura non fa scattare un watchpoint di sola scrittura
+    debugger.set_step_limit(1);
+    assert_eq!(debugger.continue_until_break(), StopReason::StepLimit);
+
+    debugger.add_watchpoint(0x03000000, WatchKind::Read);
+    assert_eq!(debugger.watchpoints().len(), 2);
+}
+
+#[test]
+fn test_read_watchpoint() {
+    let mut debugger = debugger_with_rom(&[
+        0xE3A00403, // MOV r0, #0x03000000
+        0xE5801000, // STR r1, [r0]
+        0xE5902000, // LDR r2, [r0]
+    ]);
+    debugger.add_watchpoint(0x03000000, WatchKind::Read);
+
+    assert!(matches!(
+        debugger.continue_until_break(),
+        StopReason::Watchpoint { pc: 0x08000008, .. }
+    ));
+
+    let emu = debugger.into_inner();
+    assert!(emu.bus.watchpoints.is_empty());
+}
@@ -1,3 +1,4 @@
+use super::constants::{FIFO_A, FIFO_B, SOUND_FIFO_UNITS};
 use super::types::{DmaControl, DmaTiming};
 
 /// Single DMA channel
@@ -12,7 +13,7 @@ pub struct DmaChannel {
     // Internal state
     internal_source: u32,
     internal_dest: u32,
-    internal_count: u16,
+    internal_count: u32,
     pub active: bool,
 }
 
@@ -103,7 +104,7 @@ impl DmaChannel {
     fn reload(&mut self) {
         self.internal_source = self.source_addr;
         self.internal_dest = self.dest_addr;
-        self.internal_count = self.word_count;
+        self.internal_count = self.unit_count();
         
         // Check if should start immediately
         if DmaTiming::from_u8(self.control.timing) == DmaTiming::Immediate {
@@ -111,21 +112,59 @@ impl DmaChannel {
         }
     }
 
+    /// Sound DMA: DMA1/DMA2 with Special timing feeding a Direct Sound FIFO
+    ///
+    /// The hardware ignores the word count, size and destination control
+    /// bits in this mode: every request moves 4 words to the fixed FIFO.
+    pub fn is_sound_fifo(&self) -> bool {
+        matches!(self.channel_id, 1 | 2)
+            && DmaTiming::from_u8(self.control.timing) == DmaTiming::Special
+            && matches!(self.dest_addr, FIFO_A | FIFO_B)
+    }
+
+    /// 32-bit transfer (always for sound DMA)
+    pub fn transfer_32bit(&self) -> bool {
+        self.control.transfer_32bit || self.is_sound_fifo()
+    }
+
+    /// Bytes moved per unit
+    pub fn transfer_size(&self) -> u32 {
+        if self.transfer_32bit() { 4 } else { 2 }
+    }
+
+    /// Units to transfer for the current word count
+    fn unit_count(&self) -> u32 {
+        if self.is_sound_fifo() {
+            return SOUND_FIFO_UNITS;
+        }
+        // A count of 0 means the maximum (only reachable on DMA3: 0x10000)
+        if self.word_count == 0 {
+            0x10000
+        } else {
+            self.word_count as u32
+        }
+    }
+
     /// Trigger DMA transfer (for VBlank/HBlank/Special timing)
+    ///
+    /// Addresses were latched when the channel was enabled; a repeating
+    /// channel only reloads its count and keeps going from where it stopped.
     pub fn trigger(&mut self, timing: DmaTiming) {
         if !self.control.enabled {
             return;
         }
 
-        if DmaTiming::from_u8(self.control.timing) == timing {
-            // Reload if not in repeat mode or first trigger
-            if !self.active || !self.control.repeat {
-                self.reload();
-            }
+        if DmaTiming::from_u8(self.control.timing) == timing && !self.active {
+            self.internal_count = self.unit_count();
             self.active = true;
         }
     }
 
+    /// Disable the channel (end of video capture); a pending unit still completes
+    pub fn stop(&mut self) {
+        self.control.enabled = false;
+    }
+
     /// Perform one transfer unit, returns true if transfer complete
     pub fn step_transfer(&mut self) -> bool {
         if !self.active || self.internal_count == 0 {
@@ -135,7 +174,8 @@ impl DmaChannel {
         self.internal_count -= 1;
 
         // Update addresses based on control
-        let transfer_size = self.control.transfer_size();
+        let transfer_size = self.transfer_size();
+        let sound_fifo = self.is_sound_fifo();
         
         // Update source address
         match self.control.source_control {
@@ -146,8 +186,8 @@ impl DmaChannel {
             _ => {},
         }
 
-        // Update destination address
-        match self.control.dest_control {
+        // Update destination address (the FIFO never moves)
+        match if sound_fifo { 2 } else { self.control.dest_control } {
             0 => self.internal_dest = self.internal_dest.wrapping_add(transfer_size), // Increment
             1 => self.internal_dest = self.internal_dest.wrapping_sub(transfer_size), // Decrement
             2 => {}, // Fixed
@@ -162,8 +202,8 @@ impl DmaChannel {
                 self.internal_dest = self.dest_addr;
             }
 
-            // Disable if not repeat
-            if !self.control.repeat {
+            // Disable if not repeat (sound DMA always repeats)
+            if !self.control.repeat && !sound_fifo {
                 self.control.enabled = false;
                 self.active = false;
             } else {
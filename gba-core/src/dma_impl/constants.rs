@@ -24,6 +24,13 @@ pub const DMA3DAD: u32 = 0x040000D8;
 pub const DMA3CNT_L: u32 = 0x040000DC;
 pub const DMA3CNT_H: u32 = 0x040000DE;
 
+/// Direct Sound FIFOs, destinations of the sound DMA (DMA1/DMA2)
+pub const FIFO_A: u32 = 0x040000A0;
+pub const FIFO_B: u32 = 0x040000A4;
+
+/// Words moved by each sound DMA request (half of the 32-byte FIFO)
+pub const SOUND_FIFO_UNITS: u32 = 4;
+
 /// Number of DMA channels
 pub const DMA_CHANNEL_COUNT: usize = 4;
 
@@ -7,9 +7,36 @@ mod types;
 pub use constants::*;
 pub use types::{DmaControl, DmaTiming};
 
+use crate::timing::MemoryTiming;
 use channel::DmaChannel;
 
+/// Outcome of a `DMA::step` call
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DmaStepResult {
+    /// Bit n set = channel n finished with IRQ enabled
+    pub irq_flags: u8,
+    /// Cycles spent on the bus, during which the CPU is stalled
+    pub cycles: u32,
+}
+
+/// One unit (halfword or word) to move for a DMA channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DmaUnit {
+    pub channel: usize,
+    pub source: u32,
+    pub dest: u32,
+    pub is_32bit: bool,
+    /// Bus cycles of the unit, including the channel start-up cost
+    pub cycles: u32,
+}
+
+/// GamePak ROM/SRAM region (0x08000000-0x0FFFFFFF)
+fn is_gamepak(addr: u32) -> bool {
+    addr >= 0x0800_0000
+}
+
 /// DMA Controller (4 channels)
+#[derive(Clone)]
 pub struct DMA {
     channels: [DmaChannel; DMA_CHANNEL_COUNT],
 }
@@ -40,43 +67,96 @@ impl DMA {
         }
     }
 
-    /// Perform DMA transfers, returns IRQ flags
-    /// Should be called each frame/scanline
-    pub fn step<F>(&mut self, mut transfer_fn: F) -> u8
+    /// Sound FIFO request: DMA1/DMA2 with Special timing refill the FIFO
+    /// they point at when it drops to half full
+    pub fn trigger_sound_fifo(&mut self, fifo_addr: u32) {
+        for channel in &mut self.channels[1..=2] {
+            if channel.dest_addr == fifo_addr {
+                channel.trigger(DmaTiming::Special);
+            }
+        }
+    }
+
+    /// Video capture: DMA3 with Special timing copies one block per
+    /// visible scanline while the PPU is drawing
+    pub fn trigger_video_capture(&mut self) {
+        self.channels[3].trigger(DmaTiming::Special);
+    }
+
+    /// Video capture ends at VBlank and has to be re-enabled every frame
+    pub fn stop_video_capture(&mut self) {
+        let channel = &mut self.channels[3];
+        if DmaTiming::from_u8(channel.control.timing) == DmaTiming::Special {
+            channel.stop();
+        }
+    }
+
+    /// Perform DMA transfers, returns IRQ flags and the cycles the CPU is stalled
+    ///
+    /// Convenience loop over `next_unit`/`complete_unit` for callers whose
+    /// transfers can't write back to the DMA registers.
+    pub fn step<F>(&mut self, timing: &MemoryTiming, mut transfer_fn: F) -> DmaStepResult
     where
         F: FnMut(u32, u32, bool), // (source, dest, is_32bit)
     {
-        let mut irq_flags = 0u8;
+        let mut result = DmaStepResult::default();
+        let mut running: Option<usize> = None;
 
-        // Process channels in priority order (0 highest, 3 lowest)
-        for channel in &mut self.channels {
-            if !channel.active {
-                continue;
-            }
+        while let Some(unit) = self.next_unit(timing, running) {
+            result.cycles += unit.cycles;
+            running = Some(unit.channel);
 
-            // Perform all transfers for this channel
-            while channel.active {
-                let source = channel.current_source();
-                let dest = channel.current_dest();
-                let is_32bit = channel.control.transfer_32bit;
-
-                // Execute transfer callback
-                transfer_fn(source, dest, is_32bit);
-
-                // Step the channel
-                let complete = channel.step_transfer();
-
-                if complete {
-                    // Check if should generate IRQ
-                    if channel.should_irq() {
-                        irq_flags |= 1 << channel.channel_id;
-                    }
-                    break;
-                }
+            transfer_fn(unit.source, unit.dest, unit.is_32bit);
+
+            if self.complete_unit(unit.channel) {
+                result.irq_flags |= 1 << unit.channel;
             }
         }
 
-        irq_flags
+        result
+    }
+
+    /// Next transfer unit and its cost on the bus
+    ///
+    /// Channels are serviced one unit at a time, always picking the highest
+    /// priority active channel (0 highest, 3 lowest): a channel that becomes
+    /// active while a lower one is running takes over at the next unit.
+    /// `running` is the channel that transferred the previous unit.
+    ///
+    /// Each run of a channel costs 2 internal cycles (4 when both addresses
+    /// are in the GamePak), a non-sequential access for the first unit and
+    /// sequential accesses for the following ones, on both source and dest.
+    pub fn next_unit(&self, timing: &MemoryTiming, running: Option<usize>) -> Option<DmaUnit> {
+        let index = self.channels.iter().position(|ch| ch.active)?;
+        let channel = &self.channels[index];
+        let source = channel.current_source();
+        let dest = channel.current_dest();
+        let size = channel.transfer_size();
+
+        // Start (or resume after preemption) of a channel
+        let sequential = running == Some(index);
+        let mut cycles = timing.access_cycles(source, size, sequential)
+            + timing.access_cycles(dest, size, sequential);
+        if !sequential {
+            let both_gamepak = is_gamepak(source) && is_gamepak(dest);
+            cycles += if both_gamepak { 4 } else { 2 };
+        }
+
+        Some(DmaUnit {
+            channel: index,
+            source,
+            dest,
+            is_32bit: channel.transfer_32bit(),
+            cycles,
+        })
+    }
+
+    /// Advance `channel` past the unit just transferred
+    ///
+    /// Returns true when this ended the transfer with the IRQ enabled.
+    pub fn complete_unit(&mut self, channel: usize) -> bool {
+        let channel = &mut self.channels[channel];
+        channel.step_transfer() && channel.should_irq()
     }
 
     /// Read DMA register
@@ -104,19 +184,25 @@ impl DMA {
         }
 
         let offset = addr % 12;
+        let channel = &mut self.channels[channel_id];
         match offset {
-            0 => self.channels[channel_id].write_source(value),
-            4 => self.channels[channel_id].write_dest(value),
-            8 => {
-                if is_halfword {
-                    self.channels[channel_id].write_count(value as u16);
-                }
+            // Halfword writes update one half of the 32-bit address
+            0 if is_halfword => {
+                channel.write_source((channel.source_addr & 0xFFFF_0000) | (value & 0xFFFF))
+            }
+            2 if is_halfword => {
+                channel.write_source((channel.source_addr & 0x0000_FFFF) | (value << 16))
+            }
+            4 if is_halfword => {
+                channel.write_dest((channel.dest_addr & 0xFFFF_0000) | (value & 0xFFFF))
             }
-            10 => {
-                if is_halfword {
-                    self.channels[channel_id].write_control(value as u16);
-                }
+            6 if is_halfword => {
+                channel.write_dest((channel.dest_addr & 0x0000_FFFF) | (value << 16))
             }
+            0 => channel.write_source(value),
+            4 => channel.write_dest(value),
+            8 if is_halfword => channel.write_count(value as u16),
+            10 if is_halfword => channel.write_control(value as u16),
             _ => {}
         }
     }
@@ -1,4 +1,5 @@
 use crate::dma::*;
+use crate::timing::MemoryTiming;
 
 #[test]
 fn test_dma_creation() {
@@ -98,7 +99,7 @@ fn test_dma_immediate_trigger() {
     assert!(dma.is_active());
     
     // Execute transfers
-    dma.step(|_src, _dst, _is_32| {
+    dma.step(&MemoryTiming::new(), |_src, _dst, _is_32| {
         transfer_count += 1;
     });
     
@@ -153,7 +154,7 @@ fn test_dma_32bit_transfer() {
     dma.write_register(DMA0CNT_L, 1, true);
     dma.write_register(DMA0CNT_H, 0x8400, true); // Enable, 32-bit
     
-    dma.step(|_src, _dst, is_32| {
+    dma.step(&MemoryTiming::new(), |_src, _dst, is_32| {
         is_32bit_called = is_32;
     });
     
@@ -171,7 +172,7 @@ fn test_dma_address_increment() {
     dma.write_register(DMA0CNT_L, 3, true);
     dma.write_register(DMA0CNT_H, 0x8000, true); // 16-bit, increment both
     
-    dma.step(|src, dst, _| {
+    dma.step(&MemoryTiming::new(), |src, dst, _| {
         addresses.push((src, dst));
     });
     
@@ -193,7 +194,7 @@ fn test_dma_address_decrement() {
     dma.write_register(DMA0CNT_L, 3, true);
     dma.write_register(DMA0CNT_H, 0x80A0, true); // Decrement both (dest=01, src=01)
     
-    dma.step(|src, dst, _| {
+    dma.step(&MemoryTiming::new(), |src, dst, _| {
         addresses.push((src, dst));
     });
     
@@ -214,7 +215,7 @@ fn test_dma_address_fixed() {
     dma.write_register(DMA0CNT_L, 3, true);
     dma.write_register(DMA0CNT_H, 0x8140, true); // Fixed both (bits 5-6, 7-8 = 10)
     
-    dma.step(|src, dst, _| {
+    dma.step(&MemoryTiming::new(), |src, dst, _| {
         addresses.push((src, dst));
     });
     
@@ -234,7 +235,7 @@ fn test_dma_irq_flag() {
     dma.write_register(DMA0CNT_L, 2, true);
     dma.write_register(DMA0CNT_H, 0xC000, true); // Enable + IRQ
     
-    let irq_flags = dma.step(|_, _, _| {});
+    let irq_flags = dma.step(&MemoryTiming::new(), |_, _, _| {}).irq_flags;
     
     // Should have IRQ flag for channel 0
     assert_eq!(irq_flags & 1, 1);
@@ -250,7 +251,7 @@ fn test_dma_no_irq_when_disabled() {
     dma.write_register(DMA0CNT_L, 2, true);
     dma.write_register(DMA0CNT_H, 0x8000, true); // Enable, no IRQ
     
-    let irq_flags = dma.step(|_, _, _| {});
+    let irq_flags = dma.step(&MemoryTiming::new(), |_, _, _| {}).irq_flags;
     
     // Should have NO IRQ
     assert_eq!(irq_flags, 0);
@@ -268,7 +269,7 @@ fn test_dma_repeat_mode() {
     
     // First VBlank trigger
     dma.trigger(DmaTiming::VBlank);
-    dma.step(|_, _, _| {});
+    dma.step(&MemoryTiming::new(), |_, _, _| {});
     
     // Should still be enabled for repeat
     assert_eq!(dma.read_register(DMA1CNT_H) & 0x8000, 0x8000);
@@ -292,7 +293,7 @@ fn test_dma_priority() {
     assert_eq!(dma.active_channel(), Some(0));
     
     // Execute
-    dma.step(|_, _, _| {});
+    dma.step(&MemoryTiming::new(), |_, _, _| {});
     
     // Should complete
     assert!(!dma.is_active());
@@ -318,3 +319,80 @@ fn test_dma_reset() {
     assert_eq!(dma.read_register(DMA0SAD), 0);
     assert_eq!(dma.read_register(DMA0CNT_H) & 0x8000, 0);
 }
+
+#[test]
+fn test_dma_cycle_cost_depends_on_region() {
+    let timing = MemoryTiming::new();
+
+    // 10 word IWRAM -> IWRAM: 2I + 10 * (1 + 1)
+    let mut dma = DMA::new();
+    dma.write_register(DMA3SAD, 0x03000000, false);
+    dma.write_register(DMA3DAD, 0x03001000, false);
+    dma.write_register(DMA3CNT_L, 10, true);
+    dma.write_register(DMA3CNT_H, 0x8400, true); // Enable, immediate, 32-bit
+    let fast = dma.step(&timing, |_, _, _| {}).cycles;
+    assert_eq!(fast, 22);
+
+    // 10 word ROM -> EWRAM: wait states on both sides
+    dma.write_register(DMA3SAD, 0x08000000, false);
+    dma.write_register(DMA3DAD, 0x02000000, false);
+    dma.write_register(DMA3CNT_L, 10, true);
+    dma.write_register(DMA3CNT_H, 0x8400, true);
+    let slow = dma.step(&timing, |_, _, _| {}).cycles;
+    assert!(slow > fast);
+}
+
+#[test]
+fn test_dma_higher_priority_channel_runs_first() {
+    let mut dma = DMA::new();
+
+    // DMA3 and DMA0 active together: DMA0 completes first
+    dma.write_register(DMA3SAD, 0x03000000, false);
+    dma.write_register(DMA3DAD, 0x03001000, false);
+    dma.write_register(DMA3CNT_L, 3, true);
+    dma.write_register(DMA3CNT_H, 0x8000, true);
+    dma.write_register(DMA0SAD, 0x02000000, false);
+    dma.write_register(DMA0DAD, 0x02001000, false);
+    dma.write_register(DMA0CNT_L, 3, true);
+    dma.write_register(DMA0CNT_H, 0x8000, true);
+
+    let mut sources = Vec::new();
+    dma.step(&MemoryTiming::new(), |src, _, _| sources.push(src >> 24));
+
+    assert_eq!(sources, vec![0x02, 0x02, 0x02, 0x03, 0x03, 0x03]);
+}
+
+#[test]
+fn test_sound_fifo_dma_keeps_destination_fixed() {
+    let mut dma = DMA::new();
+
+    // Count, size and dest control are ignored: 16-bit, increment, 100 units
+    dma.write_register(DMA1SAD, 0x02000000, false);
+    dma.write_register(DMA1DAD, FIFO_A, false);
+    dma.write_register(DMA1CNT_L, 100, true);
+    dma.write_register(DMA1CNT_H, 0xB000, true); // Enable, Special, no repeat
+
+    let mut transfers = Vec::new();
+    dma.trigger_sound_fifo(FIFO_A);
+    dma.step(&MemoryTiming::new(), |src, dst, is_32bit| {
+        transfers.push((src, dst, is_32bit))
+    });
+
+    assert_eq!(
+        transfers,
+        vec![
+            (0x02000000, FIFO_A, true),
+            (0x02000004, FIFO_A, true),
+            (0x02000008, FIFO_A, true),
+            (0x0200000C, FIFO_A, true),
+        ]
+    );
+
+    // Still armed for the next FIFO request, continuing from the source
+    transfers.clear();
+    dma.trigger_sound_fifo(FIFO_A);
+    dma.step(&MemoryTiming::new(), |src, dst, _| transfers.push((src, dst, true)));
+    assert_eq!(transfers.len(), 4);
+    assert_eq!(transfers[0], (0x02000010, FIFO_A, true));
+    assert!(transfers.iter().all(|&(_, dst, _)| dst == FIFO_A));
+}
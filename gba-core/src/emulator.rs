@@ -1,6 +1,33 @@
+use crate::apu::AudioSink;
+use crate::bios::{
+    diff_16bit_unfilter, diff_8bit_unfilter, diff_8bit_unfilter_vram, midi_key_to_freq, soft_reset,
+    Bios, BIOS_IF_MIRROR, BIOS_IRQ_HANDLER, BIOS_IRQ_RETURN, IRQ_VECTOR, SOUND_AREA_POINTER,
+    SWI_DIFF_16BIT_UNCOMP, SWI_DIFF_8BIT_UNCOMP_VRAM, SWI_DIFF_8BIT_UNCOMP_WRAM, SWI_INTR_WAIT,
+    SWI_MIDI_KEY2FREQ, SWI_SOFT_RESET, SWI_SOUND_BIAS, SWI_SOUND_CHANNEL_CLEAR,
+    SWI_SOUND_DRIVER_INIT, SWI_SOUND_DRIVER_MAIN, SWI_SOUND_DRIVER_MODE, SWI_SOUND_DRIVER_VSYNC,
+    SWI_SOUND_DRIVER_VSYNC_OFF, SWI_SOUND_DRIVER_VSYNC_ON, SWI_VBLANK_INTR_WAIT,
+};
 use crate::bus::Bus;
-use crate::cartridge::Cartridge;
-use gba_arm7tdmi::ARM7TDMI;
+use crate::cartridge::{Cartridge, LoadError, BIOS_SIZE};
+use crate::cheats::{Cheat, CheatError};
+use crate::dma::{DmaTiming, FIFO_A, FIFO_B};
+use crate::input::{Button, ButtonState};
+use crate::interrupt::{InterruptFlags, PowerState};
+use crate::movie::{self, InputRecorder, Movie, MovieError, MoviePlayer};
+use crate::ppu::{present_rgb888, ColorCorrection, SCREEN_HEIGHT, SCREEN_WIDTH};
+use crate::rewind::{RewindBuffer, DEFAULT_REWIND_CAPACITY};
+#[cfg(feature = "std")]
+use crate::save::SaveFileError;
+use crate::save::{FlashChip, SaveType};
+use crate::savestate::SaveState;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use gba_arm7tdmi::arm::Condition;
+use gba_arm7tdmi::{Mode, ARM7TDMI};
+#[cfg(feature = "std")]
+use std::path::Path;
+#[cfg(feature = "std")]
+use std::time::Instant;
 
 //==============================================================================
 // EMULATORE GBA - COMPONENTE PRINCIPALE
@@ -30,25 +57,129 @@ use gba_arm7tdmi::ARM7TDMI;
 // - Riutilizzare codice in altri progetti
 //==============================================================================
 
+/// Entry point delle cartridge
+pub const CARTRIDGE_ENTRY: u32 = 0x0800_0000;
+
+/// Indirizzo di caricamento ed entry point delle ROM multiboot (EWRAM)
+pub const MULTIBOOT_ENTRY: u32 = 0x0200_0000;
+
+/// Dimensione massima di un'immagine multiboot (tutta la EWRAM)
+pub const MULTIBOOT_MAX_SIZE: usize = 0x40000;
+
+/// Cicli CPU per frame: 16.78 MHz a ~60 FPS
+pub const CYCLES_PER_FRAME: u32 = 280896;
+
+/// Limite suggerito per `skip_bios_intro`: 5 secondi emulati, più della
+/// durata dell'intro del BIOS
+pub const BIOS_INTRO_MAX_CYCLES: u64 = 300 * CYCLES_PER_FRAME as u64;
+
+/// Registro SOUNDBIAS (livello del bias in bit 1-9)
+const SOUNDBIAS: u32 = 0x04000088;
+
+/// Limiti del moltiplicatore di clock della CPU (vedi `set_clock_scale`)
+pub const MIN_CLOCK_SCALE: f32 = 0.25;
+pub const MAX_CLOCK_SCALE: f32 = 4.0;
+
+/// Statistiche dell'ultimo `run_frame`, per misurare le prestazioni
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameStats {
+    /// Istruzioni eseguite dalla CPU (esclusi i cicli in HALT)
+    pub instructions: u64,
+    /// Cicli emulati (circa 280896 per frame)
+    pub cycles: u64,
+    /// Tempo reale impiegato a emulare il frame (0 senza la feature `std`)
+    pub wall_time_ns: u64,
+}
+
 /// Emulatore GBA principale
 ///
 /// Coordina CPU, memoria, grafica e tutti i componenti del sistema
 pub struct GbaEmulator {
     pub cpu: ARM7TDMI,
     pub bus: Bus,
+    /// Stato delle SWI emulate ad alto livello (senza BIOS caricato)
+    pub bios: Bios,
+    bios_hle: bool,
+    /// Indirizzo da cui parte l'esecuzione dopo il reset
+    pub(crate) entry_point: u32,
+    /// Cicli totali dall'avvio (clock master)
+    pub(crate) total_cycles: u64,
+    /// Cicli eseguiti oltre il budget dell'ultima `run_cycles`
+    pub(crate) cycle_carry: u64,
+    /// Velocità della CPU rispetto alle periferiche e frazione di ciclo
+    /// periferico non ancora applicata
+    clock_scale: f32,
+    clock_remainder: f32,
+    cheats: Vec<Cheat>,
+    rewind: Option<RewindBuffer>,
+    /// Correzione colore dell'LCD applicata all'output (None = disattivata)
+    color_correction: Option<ColorCorrection>,
+    /// Registrazione e riproduzione dell'input (vedi movie.rs)
+    recorder: Option<InputRecorder>,
+    player: Option<MoviePlayer>,
+    frame_stats: FrameStats,
+    /// CRC32 e SHA-1 della cartuccia caricata
+    rom_hashes: Option<(u32, [u8; 20])>,
+    /// Ultimo frame completo in RGBA8888 (vedi `frame_rgba`)
+    frame_rgba: Vec<u8>,
+    /// Destinazione dell'audio (None = il frontend usa `drain_audio`)
+    audio_sink: Option<Box<dyn AudioSink>>,
 }
 
 impl GbaEmulator {
     pub fn new() -> Self {
-        Self {
+        let mut emulator = Self {
             cpu: ARM7TDMI::new(),
             bus: Bus::new(),
-        }
+            bios: Bios::new(),
+            bios_hle: true,
+            entry_point: CARTRIDGE_ENTRY,
+            total_cycles: 0,
+            cycle_carry: 0,
+            clock_scale: 1.0,
+            clock_remainder: 0.0,
+            cheats: Vec::new(),
+            rewind: None,
+            color_correction: None,
+            recorder: None,
+            player: None,
+            frame_stats: FrameStats::default(),
+            rom_hashes: None,
+            frame_rgba: [0, 0, 0, 0xFF].repeat(SCREEN_WIDTH * SCREEN_HEIGHT),
+            audio_sink: None,
+        };
+
+        // Senza BIOS (HLE, il default) gli stack partono già come li lascia
+        // il BIOS: anche il codice eseguito prima di reset() può fare PUSH
+        emulator.init_boot_stacks();
+        emulator
     }
 
-    /// Carica un BIOS
-    pub fn load_bios(&mut self, bios: Vec<u8>) {
+    /// Carica un BIOS (deve essere di 16 KB)
+    ///
+    /// Con un BIOS reale le SWI vengono eseguite dal suo codice invece
+    /// che emulate ad alto livello.
+    pub fn load_bios(&mut self, bios: Vec<u8>) -> Result<(), LoadError> {
+        if bios.len() != BIOS_SIZE {
+            return Err(LoadError::InvalidBiosSize(bios.len()));
+        }
         self.bus.load_bios(bios);
+        self.bios_hle = false;
+        Ok(())
+    }
+
+    /// Attiva o disattiva l'emulazione ad alto livello del BIOS
+    ///
+    /// Con l'HLE il reset salta direttamente al programma e SWI e IRQ
+    /// vengono gestiti dall'emulatore; senza, passano tutti dal codice del
+    /// BIOS caricato con `load_bios`.
+    pub fn set_bios_hle(&mut self, enabled: bool) {
+        self.bios_hle = enabled;
+    }
+
+    /// Indica se il BIOS è emulato ad alto livello
+    pub fn bios_hle(&self) -> bool {
+        self.bios_hle
     }
 
     /// Carica una cartridge
@@ -59,59 +190,707 @@ impl GbaEmulator {
         log::info!("Version: {}", cartridge.header.version);
 
         // Initialize save system with ROM data
-        let rom_path = cartridge.rom_path.clone();
-        self.bus.save.init_from_rom(&cartridge.rom, rom_path);
+        #[cfg(feature = "std")]
+        match cartridge.rom_path.clone() {
+            Some(rom_path) => self.bus.save.init_from_rom_file(&cartridge.rom, rom_path),
+            None => self.bus.save.init_from_rom(&cartridge.rom),
+        }
+        #[cfg(not(feature = "std"))]
+        self.bus.save.init_from_rom(&cartridge.rom);
 
         // Log save type
         let save_type = self.bus.save.save_type();
         log::info!("Save Type: {:?}", save_type);
+        #[cfg(feature = "std")]
         if let Some(save_path) = self.bus.save.save_path() {
             log::info!("Save Path: {}", save_path.display());
         }
 
+        self.rom_hashes = Some((cartridge.crc32(), cartridge.sha1()));
         self.bus.load_rom(cartridge.rom);
+        self.entry_point = CARTRIDGE_ENTRY;
+    }
+
+    /// CRC32 della cartuccia caricata (None senza cartuccia)
+    pub fn rom_crc32(&self) -> Option<u32> {
+        self.rom_hashes.map(|(crc32, _)| crc32)
+    }
+
+    /// SHA-1 della cartuccia caricata (None senza cartuccia)
+    pub fn rom_sha1(&self) -> Option<[u8; 20]> {
+        self.rom_hashes.map(|(_, sha1)| sha1)
+    }
+
+    /// Carica un'immagine multiboot (.mb) in EWRAM e la avvia
+    ///
+    /// Come il BIOS al termine del trasferimento multiboot: l'immagine
+    /// viene copiata a 0x02000000, gli stack vengono inizializzati e
+    /// l'esecuzione parte dall'inizio della EWRAM. Un'immagine più grande
+    /// della EWRAM viene rifiutata.
+    pub fn load_multiboot(&mut self, image: Vec<u8>) -> Result<(), LoadError> {
+        if image.len() > MULTIBOOT_MAX_SIZE {
+            return Err(LoadError::MultibootTooLarge(image.len()));
+        }
+
+        self.bus.memory.ewram[..image.len()].copy_from_slice(&image);
+        self.entry_point = MULTIBOOT_ENTRY;
+        self.reset();
+        Ok(())
+    }
+
+    /// Forza il tipo di salvataggio, ignorando il rilevamento automatico
+    ///
+    /// Va chiamato dopo `load_cartridge`: la memoria di salvataggio viene
+    /// ricreata e ricaricata dal file .sav, se presente.
+    pub fn set_save_type(&mut self, save_type: SaveType) {
+        log::info!("Save Type override: {:?}", save_type);
+        self.bus.save.force_save_type(save_type);
+    }
+
+    /// Forza il modello di chip Flash (e il tipo di salvataggio corrispondente)
+    pub fn set_flash_chip(&mut self, chip: FlashChip) {
+        log::info!("Flash chip override: {:?}", chip);
+        self.bus.save.force_flash_chip(chip);
+    }
+
+    /// Copia della memoria di salvataggio, da rendere persistente a cura
+    /// del frontend (None se il gioco non ha salvataggi)
+    ///
+    /// Senza la feature `std` è l'unico modo di conservare i salvataggi.
+    pub fn save_to_bytes(&mut self) -> Option<Vec<u8>> {
+        self.bus.save.save_to_bytes()
+    }
+
+    /// Ripristina la memoria di salvataggio da dati forniti dal frontend
+    ///
+    /// Va chiamato dopo `load_cartridge`.
+    pub fn load_from_bytes(&mut self, data: Vec<u8>) {
+        self.bus.save.load_from_bytes(data);
+    }
+
+    /// Indica se il salvataggio è cambiato dall'ultimo `save_to_bytes`
+    pub fn save_modified(&self) -> bool {
+        self.bus.save.is_modified()
+    }
+
+    /// Importa un file .sav scelto dall'utente al posto del salvataggio
+    ///
+    /// La dimensione deve corrispondere al tipo di salvataggio rilevato;
+    /// i dati importati verranno scritti nel .sav del gioco all'auto-save.
+    #[cfg(feature = "std")]
+    pub fn import_save(&mut self, path: &Path) -> Result<(), SaveFileError> {
+        self.bus.save.import_file(path)?;
+        log::info!("Save imported from {}", path.display());
+        Ok(())
+    }
+
+    /// Esporta una copia del salvataggio corrente in un file .sav
+    #[cfg(feature = "std")]
+    pub fn export_save(&self, path: &Path) -> Result<(), SaveFileError> {
+        self.bus.save.export_file(path)
+    }
+
+    /// Callback per il rumble della cartuccia (GPIO), chiamata a ogni
+    /// accensione o spegnimento del motore
+    pub fn set_rumble_handler(&mut self, handler: Box<dyn FnMut(bool)>) {
+        self.bus.gpio.set_rumble_handler(handler);
+    }
+
+    /// Livello di luce del sensore solare della cartuccia (Boktai), 0 = buio
+    pub fn set_solar_level(&mut self, level: u8) {
+        self.bus.gpio.set_solar_level(level);
+    }
+
+    /// Rotazione letta dal giroscopio della cartuccia (WarioWare Twisted)
+    pub fn set_gyro(&mut self, rotation: i16) {
+        self.bus.gpio.set_gyro(rotation);
+    }
+
+    /// Rallenta gli accessi della CPU alla memoria video durante il disegno
+    /// delle linee, come sull'hardware (più lento, disattivato di default)
+    pub fn set_video_contention(&mut self, enabled: bool) {
+        self.bus.set_video_contention(enabled);
+    }
+
+    /// Moltiplica la velocità della CPU rispetto a video, audio e timer
+    ///
+    /// Solo per debug e profiling: con 2.0 la CPU esegue il doppio delle
+    /// istruzioni per frame, con 0.5 la metà. Il timing risultante NON è
+    /// quello dell'hardware. Il valore è limitato a
+    /// `MIN_CLOCK_SCALE..=MAX_CLOCK_SCALE` (valori non finiti = 1.0) e non
+    /// finisce nei save state.
+    pub fn set_clock_scale(&mut self, scale: f32) {
+        self.clock_scale = if scale.is_finite() {
+            scale.clamp(MIN_CLOCK_SCALE, MAX_CLOCK_SCALE)
+        } else {
+            1.0
+        };
+        self.clock_remainder = 0.0;
+    }
+
+    /// Moltiplicatore di clock della CPU (1.0 = velocità reale)
+    pub fn clock_scale(&self) -> f32 {
+        self.clock_scale
     }
 
     /// Reset dell'emulatore
+    ///
+    /// Con il BIOS reale l'esecuzione riparte dal vettore di reset e passa
+    /// per l'intro del BIOS. In HLE (e per le immagini multiboot) si salta
+    /// direttamente al programma con lo stato lasciato dal BIOS al boot.
     pub fn reset(&mut self) {
         self.cpu.reset();
-        self.cpu.regs.set_pc(0x0800_0000); // Salta alla ROM
+        self.bios.reset();
+
+        if self.bios_hle || self.entry_point == MULTIBOOT_ENTRY {
+            self.init_boot_stacks();
+            self.cpu.regs.set_pc(self.entry_point); // Salta alla ROM (o alla EWRAM)
+        } else {
+            // Stato di reset dell'ARM7TDMI: Supervisor, IRQ e FIQ mascherati
+            self.cpu.regs.change_mode(Mode::Supervisor);
+            self.cpu.regs.cpsr |= (1 << 7) | (1 << 6);
+        }
+    }
+
+    /// Stack e modalità lasciati dal BIOS prima di saltare al programma
+    fn init_boot_stacks(&mut self) {
+        let regs = &mut self.cpu.regs;
+        regs.change_mode(Mode::Supervisor);
+        regs.r[13] = 0x0300_7FE0;
+        regs.change_mode(Mode::IRQ);
+        regs.r[13] = 0x0300_7FA0;
+        regs.change_mode(Mode::System);
+        regs.r[13] = 0x0300_7F00;
+    }
+
+    /// SoftReset (SWI 0x00) emulata ad alto livello
+    ///
+    /// Pulisce la fine della IWRAM, azzera i registri, reinizializza gli
+    /// stack di ogni modalità e riparte in ARM dalla ROM o dalla EWRAM
+    /// secondo il flag a 0x03007FFA.
+    fn hle_soft_reset(&mut self) {
+        let entry = soft_reset(&mut self.bus.memory.iwram);
+
+        self.init_boot_stacks();
+        let regs = &mut self.cpu.regs;
+        regs.r[..13].fill(0);
+        regs.set_lr(0);
+        regs.r14_svc = 0;
+        regs.r14_irq = 0;
+        regs.spsr_svc = 0;
+        regs.spsr_irq = 0;
+        regs.set_thumb(false);
+        regs.set_pc(entry);
+        self.cpu.halted = false;
+    }
+
+    /// Diff8bitUnFilter / Diff16bitUnFilter emulate ad alto livello
+    ///
+    /// r0 = sorgente (header + differenze), r1 = destinazione.
+    fn hle_diff_unfilter(&mut self, swi: u8) {
+        let source = self.cpu.regs.r[0];
+        let dest = self.cpu.regs.r[1];
+
+        // Legge prima tutto lo stream: la dimensione è nell'header
+        let size = self.bus.peek_word(source) >> 8;
+        let data: Vec<u8> = (0..4 + size)
+            .map(|i| self.bus.peek_byte(source.wrapping_add(i)))
+            .collect();
+
+        let memory = &mut self.bus.memory;
+        match swi {
+            SWI_DIFF_8BIT_UNCOMP_WRAM => {
+                diff_8bit_unfilter(&data, dest, |addr, value| memory.write_byte(addr, value))
+            }
+            SWI_DIFF_8BIT_UNCOMP_VRAM => diff_8bit_unfilter_vram(&data, dest, |addr, value| {
+                memory.write_halfword(addr, value)
+            }),
+            _ => diff_16bit_unfilter(&data, dest, |addr, value| {
+                memory.write_halfword(addr, value)
+            }),
+        }
     }
 
     /// Esegui un singolo frame
     pub fn run_frame(&mut self) {
-        // GBA: 16.78 MHz CPU, ~280896 cicli per frame (60 FPS)
-        const CYCLES_PER_FRAME: u32 = 280896;
+        // Snapshot per il rewind (stato all'inizio del frame)
+        if self.rewind.as_mut().is_some_and(|r| r.tick()) {
+            let state = self.save_state();
+            if let Some(rewind) = &mut self.rewind {
+                rewind.push(state);
+            }
+        }
+
+        self.update_movie();
 
+        #[cfg(feature = "std")]
+        let started = Instant::now();
         let mut frame_cycles = 0;
+        let mut instructions = 0;
 
         while frame_cycles < CYCLES_PER_FRAME {
-            let cycles = self.cpu.step(&mut self.bus);
-            frame_cycles += cycles;
+            if !self.cpu.halted {
+                instructions += 1;
+            }
+            frame_cycles += self.step();
+        }
+
+        #[cfg(feature = "std")]
+        let wall_time_ns = started.elapsed().as_nanos() as u64;
+        #[cfg(not(feature = "std"))]
+        let wall_time_ns = 0;
+        self.frame_stats = FrameStats {
+            instructions,
+            cycles: frame_cycles as u64,
+            wall_time_ns,
+        };
+        self.update_frame_rgba();
+
+        // Applica i cheat attivi
+        for cheat in &self.cheats {
+            cheat.apply(&mut self.bus);
+        }
+
+        // Auto-save at end of frame if save is modified
+        #[cfg(feature = "std")]
+        let _ = self.bus.save.auto_save();
+
+        self.flush_audio_sink();
+    }
+
+    /// Esegue CPU e periferiche per (almeno) `cycles` cicli
+    ///
+    /// L'ultima istruzione può sforare il budget: l'eccedenza viene
+    /// scalata dalla chiamata successiva, così N chiamate da `cycles`
+    /// restano allineate a N * `cycles` cicli totali. Ritorna i cicli
+    /// effettivamente eseguiti in questa chiamata.
+    pub fn run_cycles(&mut self, cycles: u64) -> u64 {
+        if self.cycle_carry >= cycles {
+            self.cycle_carry -= cycles;
+            return 0;
+        }
+
+        let budget = cycles - self.cycle_carry;
+        let mut elapsed = 0;
+        while elapsed < budget {
+            elapsed += self.step() as u64;
+        }
+
+        self.cycle_carry = elapsed - budget;
+        self.flush_audio_sink();
+        elapsed
+    }
+
+    /// Salta l'intro del BIOS reale eseguendolo a velocità piena
+    ///
+    /// Dopo `reset` con un BIOS caricato esegue istruzioni finché il PC non
+    /// arriva all'entry point del programma, al massimo per `max_cycles`
+    /// cicli. Non si presentano frame e l'audio prodotto viene scartato.
+    /// Ritorna `true` se l'entry point è stato raggiunto (subito in HLE).
+    pub fn skip_bios_intro(&mut self, max_cycles: u64) -> bool {
+        let mut elapsed = 0;
+        while self.cpu.regs.pc() != self.entry_point && elapsed < max_cycles {
+            elapsed += self.step() as u64;
+        }
+
+        self.bus.apu.drain_samples(&mut Vec::new());
+        self.cpu.regs.pc() == self.entry_point
+    }
+
+    /// Esegue finché il programma non si ferma, al massimo `max_frames` frame
+    ///
+    /// Le ROM di test segnalano la fine con un salto su sé stesso (`b .`)
+    /// o fermando la CPU con HALTCNT senza interrupt abilitati; il
+    /// risultato si legge poi con `result_word`. Ritorna `true` se il
+    /// programma si è fermato entro il limite.
+    pub fn run_until_halt_or(&mut self, max_frames: u32) -> bool {
+        let budget = max_frames as u64 * CYCLES_PER_FRAME as u64;
+        let mut elapsed = 0;
+
+        while elapsed < budget {
+            let pc = self.cpu.regs.pc();
+            elapsed += self.step() as u64;
 
-            // Step PPU con accesso alla VRAM
-            let vram_ptr = self.bus.memory.vram.as_ptr();
-            let vram_len = self.bus.memory.vram.len();
-            unsafe {
-                let vram_slice = std::slice::from_raw_parts(vram_ptr, vram_len);
-                self.bus.ppu.step(cycles, vram_slice);
+            let idle_loop = !self.cpu.halted && self.cpu.regs.pc() == pc;
+            let dead_halt = self.bus.power != PowerState::Running && self.bus.interrupt.ie == 0;
+            if idle_loop || dead_halt {
+                return true;
             }
+        }
+        false
+    }
 
-            // Gestione interrupt VBlank
-            if self.bus.ppu.in_vblank() && self.bus.ppu.scanline == 160 {
-                self.bus
-                    .interrupt
-                    .request(crate::interrupt::InterruptFlags::VBLANK);
+    /// Word all'indirizzo in cui una ROM di test scrive il risultato
+    pub fn result_word(&self, addr: u32) -> u32 {
+        self.bus.peek_word(addr)
+    }
+
+    /// Statistiche dell'ultimo frame eseguito con `run_frame`
+    pub fn last_frame_stats(&self) -> FrameStats {
+        self.frame_stats
+    }
+
+    /// Cicli totali emulati dall'avvio (wait state inclusi)
+    pub fn total_cycles(&self) -> u64 {
+        self.total_cycles
+    }
+
+    /// Esegui una singola istruzione CPU e avanza i componenti
+    ///
+    /// Ritorna i cicli consumati (wait state inclusi).
+    pub fn step(&mut self) -> u32 {
+        self.update_power_state();
+        self.update_intr_wait();
+        self.bus.access_pc = self.cpu.regs.pc();
+
+        let cycles = match self.hle_swi().or_else(|| self.hle_irq_return()) {
+            Some(cycles) => cycles,
+            None => {
+                let cycles = self.cpu.step(&mut self.bus);
+                // Wait state della cartridge (il prefetch avanza durante l'esecuzione)
+                cycles + self.bus.timing.end_step(cycles)
             }
+        };
+
+        // La CPU resta ferma mentre il DMA occupa il bus
+        let cycles = cycles + self.bus.run_dma();
+
+        // Overclock di debug: le periferiche vedono i cicli CPU scalati
+        let cycles = self.scale_cycles(cycles);
+
+        self.total_cycles += cycles as u64;
+
+        // In Stop anche video e audio sono fermi
+        if self.bus.power == PowerState::Stop {
+            return cycles;
+        }
 
-            // Gestione interrupt CPU
-            if self.bus.interrupt.pending() {
-                self.cpu.request_interrupt();
+        // Step PPU con accesso alla VRAM
+        let vram_ptr = self.bus.memory.vram.as_ptr();
+        let vram_len = self.bus.memory.vram.len();
+        let video = unsafe {
+            let vram_slice = core::slice::from_raw_parts(vram_ptr, vram_len);
+            self.bus.ppu.step(cycles, vram_slice)
+        };
+
+        // HBlank DMA e video capture: un trasferimento per ogni scanline
+        // visibile, all'inizio dell'HBlank
+        for _ in 0..video.drawn_lines {
+            self.bus.dma.trigger(DmaTiming::HBlank);
+            self.bus.dma.trigger_video_capture();
+        }
+
+        // Inizio VBlank: DMA VBlank, fine del video capture
+        if video.vblank_started {
+            self.bus.dma.trigger(DmaTiming::VBlank);
+            self.bus.dma.stop_video_capture();
+        }
+        self.bus
+            .interrupt
+            .request(InterruptFlags::from_bits_truncate(video.irq_flags));
+
+        // Step APU: genera i sample audio sincronizzati ai cicli CPU
+        self.bus.apu.tick(cycles);
+
+        // Timer: IRQ di overflow e clock dei FIFO Direct Sound (timer 0/1)
+        let timers = self.bus.timer.step(cycles);
+        self.bus
+            .interrupt
+            .request(InterruptFlags::from_bits_truncate(timers.irq_flags as u16));
+        for timer in 0..2 {
+            for _ in 0..timers.overflows[timer] {
+                let (refill_a, refill_b) = self.bus.apu.timer_overflow(timer);
+                if refill_a {
+                    self.bus.dma.trigger_sound_fifo(FIFO_A);
+                }
+                if refill_b {
+                    self.bus.dma.trigger_sound_fifo(FIFO_B);
+                }
             }
         }
 
-        // Auto-save at end of frame if save is modified
-        let _ = self.bus.save.auto_save();
+        // Gestione interrupt CPU
+        if self.bus.interrupt.pending() {
+            self.cpu.request_interrupt();
+            if self.bios_hle && self.cpu.regs.pc() == IRQ_VECTOR {
+                self.hle_irq();
+            }
+        }
+
+        cycles
+    }
+
+    /// Cicli CPU convertiti in cicli delle periferiche secondo `clock_scale`
+    fn scale_cycles(&mut self, cycles: u32) -> u32 {
+        if self.clock_scale == 1.0 {
+            return cycles;
+        }
+        let scaled = cycles as f32 / self.clock_scale + self.clock_remainder;
+        let whole = libm::floorf(scaled);
+        self.clock_remainder = scaled - whole;
+        whole as u32
+    }
+
+    /// Applica HALTCNT: ferma la CPU o la risveglia se c'è un interrupt
+    fn update_power_state(&mut self) {
+        if self.bus.power == PowerState::Running {
+            return;
+        }
+
+        if self.bus.interrupt.wakes(self.bus.power) {
+            self.bus.power = PowerState::Running;
+            self.cpu.halted = false;
+        } else {
+            self.cpu.halted = true;
+        }
+    }
+
+    /// IntrWait in corso: la CPU resta ferma finché l'attesa non è soddisfatta
+    fn update_intr_wait(&mut self) {
+        if !self.bios.waiting_for_interrupt {
+            return;
+        }
+
+        let if_ = self.bus.interrupt.if_;
+        let mut mirror = self.bus.memory.read_halfword(BIOS_IF_MIRROR);
+        let woken = self.bios.poll_intr_wait(if_, &mut mirror);
+        self.bus.memory.write_halfword(BIOS_IF_MIRROR, mirror);
+
+        self.cpu.halted = !woken || self.bus.power != PowerState::Running;
+    }
+
+    /// Dispatcher IRQ del BIOS emulato ad alto livello (vettore 0x18)
+    ///
+    /// Come il BIOS: salva r0-r3, r12 e LR sullo stack IRQ, carica
+    /// r0 = 0x04000000 e salta in ARM all'handler del gioco puntato da
+    /// 0x03007FFC, con LR che punta al ritorno nel BIOS. Gli interrupt in
+    /// attesa vengono copiati anche in 0x03007FF8 per le IntrWait.
+    fn hle_irq(&mut self) {
+        let pending = self.bus.interrupt.ie & self.bus.interrupt.if_;
+        let mirror = self.bus.memory.read_halfword(BIOS_IF_MIRROR);
+        self.bus
+            .memory
+            .write_halfword(BIOS_IF_MIRROR, mirror | pending);
+
+        // STMFD SP!, {r0-r3, r12, lr}
+        let regs = &mut self.cpu.regs;
+        let saved = [
+            regs.r[0],
+            regs.r[1],
+            regs.r[2],
+            regs.r[3],
+            regs.r[12],
+            regs.lr(),
+        ];
+        let sp = regs.sp().wrapping_sub(4 * saved.len() as u32);
+        for (i, value) in saved.into_iter().enumerate() {
+            self.bus.memory.write_word(sp + 4 * i as u32, value);
+        }
+
+        let handler = self.bus.memory.read_word(BIOS_IRQ_HANDLER);
+        let regs = &mut self.cpu.regs;
+        regs.r[13] = sp;
+        regs.r[0] = 0x0400_0000;
+        regs.set_lr(BIOS_IRQ_RETURN);
+        regs.set_pc(handler & !3);
+    }
+
+    /// Ritorno dall'handler IRQ del gioco nel BIOS emulato (0x138)
+    ///
+    /// Come il BIOS: LDMFD SP!, {r0-r3, r12, lr} e SUBS PC, LR, #4, cioè
+    /// ripristina i registri salvati da `hle_irq` e torna all'istruzione
+    /// interrotta con il CPSR (modalità e stato THUMB) di prima dell'IRQ.
+    fn hle_irq_return(&mut self) -> Option<u32> {
+        if !self.bios_hle || self.cpu.halted || self.cpu.regs.pc() != BIOS_IRQ_RETURN {
+            return None;
+        }
+
+        let sp = self.cpu.regs.sp();
+        let memory = &self.bus.memory;
+        let saved: Vec<u32> = (0..6).map(|i| memory.read_word(sp + 4 * i)).collect();
+
+        let regs = &mut self.cpu.regs;
+        regs.r[..4].copy_from_slice(&saved[..4]);
+        regs.r[12] = saved[4];
+        regs.r[13] = sp.wrapping_add(24);
+        regs.restore_cpsr();
+        regs.set_pc(saved[5].wrapping_sub(4));
+
+        Some(3)
+    }
+
+    /// Emula ad alto livello la SWI all'indirizzo del PC, se supportata
+    ///
+    /// Senza BIOS il vettore 0x08 non contiene codice: IntrWait e
+    /// VBlankIntrWait vengono eseguite qui, saltando l'istruzione SWI.
+    /// Ritorna i cicli consumati, o None se l'istruzione va eseguita
+    /// dalla CPU.
+    fn hle_swi(&mut self) -> Option<u32> {
+        if !self.bios_hle || self.cpu.halted {
+            return None;
+        }
+
+        let pc = self.cpu.regs.pc();
+        let (swi, size) = if self.cpu.regs.is_thumb() {
+            let instr = self.bus.peek_halfword(pc);
+            if instr & 0xFF00 != 0xDF00 {
+                return None;
+            }
+            (instr as u8, 2)
+        } else {
+            let instr = self.bus.peek_word(pc);
+            if instr & 0x0F00_0000 != 0x0F00_0000
+                || !Condition::from_opcode(instr).check(self.cpu.regs.cpsr)
+            {
+                return None;
+            }
+            ((instr >> 16) as u8, 4)
+        };
+
+        if swi == SWI_SOFT_RESET {
+            self.bios.handle_swi(swi);
+            self.hle_soft_reset();
+            return Some(3);
+        }
+
+        if matches!(
+            swi,
+            SWI_DIFF_8BIT_UNCOMP_WRAM | SWI_DIFF_8BIT_UNCOMP_VRAM | SWI_DIFF_16BIT_UNCOMP
+        ) {
+            self.bios.handle_swi(swi);
+            self.hle_diff_unfilter(swi);
+            self.cpu.regs.set_pc(pc.wrapping_add(size));
+            return Some(3);
+        }
+
+        if matches!(
+            swi,
+            SWI_SOUND_BIAS
+                | SWI_SOUND_DRIVER_INIT
+                | SWI_SOUND_DRIVER_MODE
+                | SWI_SOUND_DRIVER_MAIN
+                | SWI_SOUND_DRIVER_VSYNC
+                | SWI_SOUND_CHANNEL_CLEAR
+                | SWI_MIDI_KEY2FREQ
+                | SWI_SOUND_DRIVER_VSYNC_OFF
+                | SWI_SOUND_DRIVER_VSYNC_ON
+        ) {
+            self.bios.handle_swi(swi);
+            self.hle_sound_driver(swi);
+            self.cpu.regs.set_pc(pc.wrapping_add(size));
+            return Some(3);
+        }
+
+        let (discard_old, mask) = match swi {
+            SWI_INTR_WAIT => (self.cpu.regs.r[0] != 0, self.cpu.regs.r[1] as u16),
+            SWI_VBLANK_INTR_WAIT => {
+                self.cpu.regs.r[0] = 1;
+                self.cpu.regs.r[1] = 1;
+                (true, InterruptFlags::VBLANK.bits())
+            }
+            _ => return None,
+        };
+
+        self.cpu.regs.set_pc(pc.wrapping_add(size));
+        // Il BIOS abilita IME prima di mettersi in attesa
+        self.bus.interrupt.ime = true;
+
+        let if_ = self.bus.interrupt.if_;
+        let mut mirror = self.bus.memory.read_halfword(BIOS_IF_MIRROR);
+        self.bios.handle_swi(swi);
+        if !self
+            .bios
+            .start_intr_wait(discard_old, mask, if_, &mut mirror)
+        {
+            self.cpu.halted = true;
+        }
+        self.bus.memory.write_halfword(BIOS_IF_MIRROR, mirror);
+
+        Some(3)
+    }
+
+    /// Funzioni del sound driver del BIOS emulate ad alto livello
+    ///
+    /// Il mixer del driver non è emulato (i giochi che lo usano suonano
+    /// comunque con il proprio codice): le chiamate ritornano subito,
+    /// lasciando solo gli effetti documentati su registri e memoria.
+    fn hle_sound_driver(&mut self, swi: u8) {
+        let regs = &mut self.cpu.regs;
+        match swi {
+            // r0 = WaveData, r1 = tasto MIDI, r2 = fine adjust -> r0 = frequenza
+            SWI_MIDI_KEY2FREQ => {
+                let sample_rate = self.bus.peek_word(regs.r[0].wrapping_add(4));
+                regs.r[0] = midi_key_to_freq(sample_rate, regs.r[1], regs.r[2]);
+            }
+            // Il BIOS ricorda la SoundArea (r0) per le chiamate successive
+            SWI_SOUND_DRIVER_INIT => {
+                self.bus.memory.write_word(SOUND_AREA_POINTER, regs.r[0]);
+            }
+            // Livello di SOUNDBIAS a 0x200 (r0 != 0) o a 0
+            SWI_SOUND_BIAS => {
+                let level = if regs.r[0] != 0 { 0x200 } else { 0 };
+                let bias = self.bus.apu.read_halfword(SOUNDBIAS);
+                self.bus
+                    .apu
+                    .write_halfword(SOUNDBIAS, (bias & !0x03FE) | level);
+            }
+            _ => {}
+        }
+    }
+
+    /// Cattura uno snapshot dello stato corrente
+    pub fn save_state(&self) -> SaveState {
+        SaveState::capture(self)
+    }
+
+    /// Ripristina uno snapshot
+    pub fn load_state(&mut self, state: &SaveState) {
+        state.restore(self);
+    }
+
+    /// Abilita il rewind con uno snapshot ogni `frames` frame
+    pub fn enable_rewind(&mut self, frames: usize) {
+        self.rewind = Some(RewindBuffer::new(frames, DEFAULT_REWIND_CAPACITY));
+    }
+
+    /// Limita la memoria del buffer di rewind (in byte, `None` = nessun limite)
+    ///
+    /// Ha effetto solo con il rewind abilitato.
+    pub fn set_rewind_memory_limit(&mut self, bytes: Option<usize>) {
+        if let Some(rewind) = &mut self.rewind {
+            rewind.set_memory_limit(bytes);
+        }
+    }
+
+    /// Accesso in lettura al buffer di rewind
+    pub fn rewind_buffer(&self) -> Option<&RewindBuffer> {
+        self.rewind.as_ref()
+    }
+
+    /// Disabilita il rewind e libera il buffer
+    pub fn disable_rewind(&mut self) {
+        self.rewind = None;
+    }
+
+    /// Torna allo snapshot precedente, ritorna false se non disponibile
+    pub fn rewind_one(&mut self) -> bool {
+        let Some(state) = self.rewind.as_mut().and_then(|r| r.pop()) else {
+            return false;
+        };
+        self.load_state(&state);
+        true
+    }
+
+    /// Aggiunge un cheat (Action Replay v3, una riga per codice)
+    pub fn add_cheat(&mut self, code: &str) -> Result<(), CheatError> {
+        let cheat = Cheat::parse(code)?;
+        self.cheats.push(cheat);
+        Ok(())
+    }
+
+    /// Rimuove tutti i cheat
+    pub fn clear_cheats(&mut self) {
+        self.cheats.clear();
     }
 
     /// Ottieni il framebuffer corrente
@@ -119,6 +898,176 @@ impl GbaEmulator {
         &self.bus.ppu.framebuffer
     }
 
+    /// Attiva o disattiva la correzione colore dell'LCD del GBA
+    pub fn set_color_correction(&mut self, enabled: bool) {
+        if enabled != self.color_correction.is_some() {
+            self.color_correction = enabled.then(ColorCorrection::new);
+        }
+    }
+
+    /// Correzione colore attiva
+    pub fn color_correction(&self) -> bool {
+        self.color_correction.is_some()
+    }
+
+    /// Colore RGB555 convertito per la visualizzazione (RGB888), con la
+    /// correzione colore se attiva
+    pub fn present_pixel(&self, pixel: u16) -> [u8; 3] {
+        present_rgb888(pixel, self.color_correction.as_ref())
+    }
+
+    /// Framebuffer corrente convertito in RGBA a 8 bit per canale
+    ///
+    /// Usa la stessa conversione della texture del frontend (correzione
+    /// colore inclusa); l'alpha è sempre 0xFF.
+    pub fn screenshot_rgba(&self) -> Vec<u8> {
+        self.bus
+            .ppu
+            .framebuffer
+            .iter()
+            .flat_map(|&pixel| {
+                let [r, g, b] = self.present_pixel(pixel);
+                [r, g, b, 0xFF]
+            })
+            .collect()
+    }
+
+    /// Ultimo frame completato da `run_frame`, in RGBA a 8 bit per canale
+    ///
+    /// Pronto da copiare in un canvas (ImageData) o in una texture RGBA:
+    /// 240*160*4 byte, alpha sempre 0xFF, correzione colore inclusa. Il
+    /// buffer è interno e viene aggiornato a fine frame, senza allocazioni.
+    pub fn frame_rgba(&self) -> &[u8] {
+        &self.frame_rgba
+    }
+
+    /// Converte il framebuffer nel buffer di `frame_rgba`
+    fn update_frame_rgba(&mut self) {
+        let correction = self.color_correction.as_ref();
+        let pixels = self.bus.ppu.framebuffer.iter();
+        for (out, &pixel) in self.frame_rgba.chunks_exact_mut(4).zip(pixels) {
+            let [r, g, b] = present_rgb888(pixel, correction);
+            out.copy_from_slice(&[r, g, b, 0xFF]);
+        }
+    }
+
+    /// Hash stabile del framebuffer corrente (vedi frame_hash.rs)
+    pub fn frame_hash(&self) -> u64 {
+        crate::frame_hash::hash_framebuffer(&self.bus.ppu.framebuffer)
+    }
+
+    /// Imposta il sample rate del device audio dell'host
+    pub fn set_audio_output_rate(&mut self, hz: u32) {
+        self.bus.apu.set_output_rate(hz);
+    }
+
+    /// Sample rate dell'audio in uscita
+    pub fn audio_output_rate(&self) -> u32 {
+        self.bus.apu.output_rate()
+    }
+
+    /// Svuota i sample audio pronti (stereo interleaved i16)
+    pub fn drain_audio(&mut self, out: &mut Vec<i16>) {
+        self.bus.apu.drain_samples(out);
+    }
+
+    /// Installa la destinazione dell'audio
+    ///
+    /// Alla fine di ogni `run_frame` e `run_cycles` il sink riceve i sample
+    /// prodotti, al rate di `audio_output_rate`; `drain_audio` non ne
+    /// restituisce più.
+    pub fn set_audio_sink(&mut self, sink: Box<dyn AudioSink>) {
+        self.audio_sink = Some(sink);
+    }
+
+    /// Rimuove il sink audio e lo restituisce (si torna a `drain_audio`)
+    pub fn take_audio_sink(&mut self) -> Option<Box<dyn AudioSink>> {
+        self.audio_sink.take()
+    }
+
+    /// Passa al sink installato i sample generati
+    fn flush_audio_sink(&mut self) {
+        if let Some(sink) = &mut self.audio_sink {
+            self.bus.apu.drain_to_sink(sink.as_mut());
+        }
+    }
+
+    /// Inizia a registrare l'input, dal reset o dallo stato corrente
+    pub fn start_recording(&mut self, from_reset: bool) {
+        let start_state = if from_reset {
+            self.reset();
+            None
+        } else {
+            Some(movie::state_hash(&self.save_state()))
+        };
+        let rom_hash = movie::rom_hash(&self.bus.memory.rom);
+        self.recorder = Some(InputRecorder::new(rom_hash, start_state));
+    }
+
+    /// Termina la registrazione e ritorna il movie
+    pub fn stop_recording(&mut self) -> Option<Movie> {
+        self.recorder.take().map(InputRecorder::finish)
+    }
+
+    /// Riproduce un movie al posto dell'input live
+    ///
+    /// Un movie registrato dal reset riparte dal reset; uno registrato da
+    /// uno snapshot richiede che lo stesso snapshot sia già caricato.
+    pub fn start_playback(&mut self, movie: Movie) -> Result<(), MovieError> {
+        if movie.rom_hash != movie::rom_hash(&self.bus.memory.rom) {
+            return Err(MovieError::RomMismatch);
+        }
+        match movie.start_state {
+            None => self.reset(),
+            Some(hash) if hash != movie::state_hash(&self.save_state()) => {
+                return Err(MovieError::StateMismatch);
+            }
+            Some(_) => {}
+        }
+        self.player = Some(MoviePlayer::new(movie));
+        Ok(())
+    }
+
+    /// Interrompe la riproduzione e torna all'input live
+    pub fn stop_playback(&mut self) {
+        self.player = None;
+        self.bus.input.set_playback(None);
+    }
+
+    /// Riproduzione di un movie in corso
+    pub fn is_playing_movie(&self) -> bool {
+        self.player.is_some()
+    }
+
+    /// Inizio frame: applica l'input del movie e/o lo registra
+    fn update_movie(&mut self) {
+        if let Some(player) = &mut self.player {
+            let previous = self.bus.input.read_keyinput();
+            match player.next_frame() {
+                Some(keys) => self.bus.input.set_playback(Some(keys)),
+                None => self.stop_playback(),
+            }
+            if self.bus.input.read_keyinput() != previous {
+                self.bus.check_keypad_irq();
+            }
+        }
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record_frame(self.bus.input.read_keyinput());
+        }
+    }
+
+    /// Preme o rilascia un pulsante (KEYINPUT è active-low) e controlla
+    /// l'IRQ keypad impostato in KEYCNT
+    pub fn set_button(&mut self, button: Button, pressed: bool) {
+        self.bus.input.set_button(button, pressed);
+        self.bus.check_keypad_irq();
+    }
+
+    /// Pulsanti premuti come li vede il gioco (movie in riproduzione incluso)
+    pub fn buttons(&self) -> ButtonState {
+        ButtonState::from_keyinput(self.bus.input.read_keyinput())
+    }
+
     /// Ottieni riferimento mutabile all'input controller
     pub fn input_mut(&mut self) -> &mut crate::input::InputController {
         &mut self.bus.input
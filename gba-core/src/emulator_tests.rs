@@ -0,0 +1,837 @@
+use crate::interrupt::{InterruptFlags, PowerState};
+use crate::GbaEmulator;
+
+/// Emulatore resettato con `code` (ARM) caricato come ROM
+fn emulator_with_arm_code(code: &[u32]) -> GbaEmulator {
+    let rom = code.iter().flat_map(|w| w.to_le_bytes()).collect();
+    let mut emu = GbaEmulator::new();
+    emu.bus.load_rom(rom);
+    emu.reset();
+    emu
+}
+
+/// Programma ARM che scrive `haltcnt` in HALTCNT e prosegue con NOP
+fn emulator_writing_haltcnt(haltcnt: u8) -> GbaEmulator {
+    let code = [
+        0xE3A00301,                  // MOV r0, #0x04000000
+        0xE2800C03,                  // ADD r0, r0, #0x300
+        0xE3A01000 | haltcnt as u32, // MOV r1, #haltcnt
+        0xE5C01001,                  // STRB r1, [r0, #1]
+        0xE1A00000,                  // NOP
+        0xE1A00000,                  // NOP
+    ];
+    let mut emu = emulator_with_arm_code(&code);
+    for _ in 0..4 {
+        emu.step();
+    }
+    emu
+}
+
+#[test]
+fn test_haltcnt_halts_until_interrupt() {
+    let mut emu = emulator_writing_haltcnt(0x00);
+    assert_eq!(emu.bus.power, PowerState::Halt);
+
+    emu.step();
+    assert!(emu.cpu.halted);
+    let pc = emu.cpu.regs.pc();
+    for _ in 0..10 {
+        emu.step();
+    }
+    assert_eq!(emu.cpu.regs.pc(), pc);
+
+    // Un interrupt abilitato risveglia la CPU anche con IME = 0
+    emu.bus.interrupt.ie = InterruptFlags::TIMER0.bits();
+    emu.bus.interrupt.request(InterruptFlags::TIMER0);
+    emu.step();
+
+    assert!(!emu.cpu.halted);
+    assert_eq!(emu.bus.power, PowerState::Running);
+    assert_eq!(emu.cpu.regs.pc(), pc + 4);
+}
+
+#[test]
+fn test_haltcnt_stop_wakes_only_on_keypad() {
+    let mut emu = emulator_writing_haltcnt(0x80);
+    assert_eq!(emu.bus.power, PowerState::Stop);
+
+    emu.bus.interrupt.ie = (InterruptFlags::VBLANK | InterruptFlags::KEYPAD).bits();
+    emu.bus.interrupt.request(InterruptFlags::VBLANK);
+    emu.step();
+    assert!(emu.cpu.halted);
+
+    emu.bus.interrupt.request(InterruptFlags::KEYPAD);
+    emu.step();
+    assert!(!emu.cpu.halted);
+    assert_eq!(emu.bus.power, PowerState::Running);
+}
+
+#[test]
+fn test_postflg_byte_write_does_not_halt() {
+    use gba_arm7tdmi::cpu::MemoryBus;
+
+    let mut emu = GbaEmulator::new();
+    emu.bus.write_byte(0x0400_0300, 0x01);
+
+    assert_eq!(emu.bus.read_byte(0x0400_0300), 0x01);
+    assert_eq!(emu.bus.power, PowerState::Running);
+}
+
+#[test]
+fn test_intr_wait_sleeps_until_matching_interrupt() {
+    use crate::bios::BIOS_IF_MIRROR;
+
+    let code = [
+        0xE3A00001u32, // MOV r0, #1
+        0xE3A01001,    // MOV r1, #1 (VBlank)
+        0xEF040000,    // SWI 0x04 (IntrWait)
+        0xE1A00000,    // NOP
+    ];
+    let mut emu = emulator_with_arm_code(&code);
+    // VBlank vecchio: con r0 = 1 viene scartato
+    emu.bus.memory.write_halfword(BIOS_IF_MIRROR, 0x0001);
+    for _ in 0..3 {
+        emu.step();
+    }
+
+    assert!(emu.cpu.halted);
+    assert!(emu.bios.is_waiting());
+    assert_eq!(emu.cpu.regs.pc(), 0x0800000C);
+    assert_eq!(emu.bus.memory.read_halfword(BIOS_IF_MIRROR), 0);
+
+    // Un interrupt fuori dalla maschera r1 non risveglia la CPU
+    emu.bus.interrupt.request(InterruptFlags::TIMER0);
+    for _ in 0..10 {
+        emu.step();
+    }
+    assert!(emu.cpu.halted);
+    assert_eq!(emu.cpu.regs.pc(), 0x0800000C);
+
+    emu.bus.interrupt.request(InterruptFlags::VBLANK);
+    emu.step();
+    assert!(!emu.cpu.halted);
+    assert!(!emu.bios.is_waiting());
+    assert_eq!(emu.cpu.regs.pc(), 0x08000010);
+}
+
+#[test]
+fn test_run_cycles_advances_total_cycles() {
+    let mut emu = emulator_with_arm_code(&[0xE1A00000; 0x1000]); // NOP
+    assert_eq!(emu.total_cycles(), 0);
+
+    let first = emu.run_cycles(1000);
+    assert!(first >= 1000);
+    assert_eq!(emu.total_cycles(), first);
+
+    let second = emu.run_cycles(1000);
+    assert!(emu.total_cycles() > first);
+    assert_eq!(emu.total_cycles(), first + second);
+
+    // L'eccedenza della prima chiamata viene recuperata: il totale sfora
+    // 2000 cicli al massimo di un'istruzione
+    assert!(emu.total_cycles() >= 2000);
+    assert!(emu.total_cycles() - 2000 < 32);
+}
+
+#[test]
+fn test_multiboot_runs_from_ewram() {
+    use crate::emulator::MULTIBOOT_ENTRY;
+    use gba_arm7tdmi::Mode;
+
+    let code = [
+        0xE3A00042u32, // MOV r0, #0x42
+        0xE1A00000,    // NOP
+    ];
+    let image = code.iter().flat_map(|w| w.to_le_bytes()).collect();
+
+    let mut emu = GbaEmulator::new();
+    emu.load_multiboot(image).unwrap();
+
+    assert_eq!(emu.cpu.regs.pc(), MULTIBOOT_ENTRY);
+    assert_eq!(emu.cpu.regs.mode, Mode::System);
+    assert_eq!(emu.cpu.regs.sp(), 0x0300_7F00);
+    assert_eq!(emu.cpu.regs.r13_irq, 0x0300_7FA0);
+    assert_eq!(emu.cpu.regs.r13_svc, 0x0300_7FE0);
+
+    emu.step();
+    assert_eq!(emu.cpu.regs.r[0], 0x42);
+    assert_eq!(emu.cpu.regs.pc(), MULTIBOOT_ENTRY + 4);
+
+    // Il reset riparte dalla EWRAM
+    emu.reset();
+    assert_eq!(emu.cpu.regs.pc(), MULTIBOOT_ENTRY);
+}
+
+#[test]
+fn test_multiboot_entry_point_survives_save_state() {
+    use crate::emulator::MULTIBOOT_ENTRY;
+
+    let mut emu = GbaEmulator::new();
+    emu.load_multiboot(vec![0; 16]).unwrap();
+    let state = emu.save_state();
+
+    let mut other = GbaEmulator::new();
+    other.load_state(&state);
+    other.reset();
+    assert_eq!(other.cpu.regs.pc(), MULTIBOOT_ENTRY);
+}
+
+#[test]
+fn test_multiboot_rejects_oversize_image() {
+    use crate::cartridge::LoadError;
+    use crate::emulator::MULTIBOOT_MAX_SIZE;
+
+    let mut emu = GbaEmulator::new();
+    let result = emu.load_multiboot(vec![0; MULTIBOOT_MAX_SIZE + 1]);
+    assert!(matches!(
+        result,
+        Err(LoadError::MultibootTooLarge(size)) if size == MULTIBOOT_MAX_SIZE + 1
+    ));
+    assert!(emu.load_multiboot(vec![0; MULTIBOOT_MAX_SIZE]).is_ok());
+}
+
+#[test]
+fn test_set_button_updates_keyinput() {
+    use crate::bus::KEYCNT;
+    use crate::input::Button;
+    use gba_arm7tdmi::cpu::MemoryBus;
+
+    let mut emu = GbaEmulator::new();
+    assert_eq!(emu.bus.read_halfword(0x0400_0130), 0x03FF);
+
+    // Active-low: premere A azzera il bit 0
+    emu.set_button(Button::A, true);
+    assert_eq!(emu.bus.read_halfword(0x0400_0130), 0x03FE);
+    assert!(emu.buttons().is_pressed(Button::A));
+    assert!(!emu.buttons().is_pressed(Button::B));
+    assert_eq!(emu.buttons().bits(), 0x0001);
+    assert_eq!(emu.bus.interrupt.if_, 0);
+
+    emu.set_button(Button::A, false);
+    assert_eq!(emu.bus.read_halfword(0x0400_0130), 0x03FF);
+
+    // KEYCNT: IRQ quando sono premuti sia Start che Select (AND)
+    emu.bus.write_halfword(KEYCNT, 0xC00C);
+    emu.set_button(Button::Start, true);
+    assert_eq!(emu.bus.interrupt.if_, 0);
+    emu.set_button(Button::Select, true);
+    assert_eq!(emu.bus.interrupt.if_, InterruptFlags::KEYPAD.bits());
+
+    // Condizione già vera quando il gioco scrive KEYCNT
+    emu.bus.interrupt.if_ = 0;
+    emu.bus.write_halfword(KEYCNT, 0x0000);
+    assert_eq!(emu.bus.interrupt.if_, 0);
+    emu.bus.write_halfword(KEYCNT, 0x4008);
+    assert_eq!(emu.bus.interrupt.if_, InterruptFlags::KEYPAD.bits());
+}
+
+#[test]
+fn test_skip_bios_intro_reaches_cartridge_entry() {
+    use crate::cartridge::BIOS_SIZE;
+    use crate::emulator::CARTRIDGE_ENTRY;
+
+    /// BIOS di prova con `code` al vettore di reset
+    fn emulator_with_bios(code: &[u32]) -> GbaEmulator {
+        let mut bios = vec![0u8; BIOS_SIZE];
+        for (i, word) in code.iter().enumerate() {
+            bios[i * 4..i * 4 + 4].copy_from_slice(&word.to_le_bytes());
+        }
+
+        let mut emu = GbaEmulator::new();
+        emu.load_bios(bios).unwrap();
+        emu.bus.load_rom(vec![0; 0x200]);
+        emu.reset();
+        emu
+    }
+
+    let mut emu = emulator_with_bios(&[
+        0xE3A00302, // MOV r0, #0x08000000
+        0xE12FFF10, // BX r0
+    ]);
+    assert_eq!(emu.cpu.regs.pc(), 0);
+    assert!(emu.skip_bios_intro(1000));
+    assert_eq!(emu.cpu.regs.pc(), CARTRIDGE_ENTRY);
+
+    // Un BIOS che non arriva mai alla ROM si ferma al limite
+    let mut emu = emulator_with_bios(&[0xEAFFFFFE]); // B .
+    assert!(!emu.skip_bios_intro(1000));
+    assert!(emu.total_cycles() >= 1000);
+}
+
+#[test]
+fn test_hle_reset_sets_mode_stacks() {
+    use gba_arm7tdmi::Mode;
+
+    // Già da new(): senza BIOS è attiva l'HLE
+    let emu = GbaEmulator::new();
+    assert_eq!(emu.cpu.regs.sp(), 0x0300_7F00);
+    assert_eq!(emu.cpu.regs.r13_irq, 0x0300_7FA0);
+
+    let mut emu = emulator_with_arm_code(&[0; 0x80]);
+
+    let regs = &mut emu.cpu.regs;
+    regs.change_mode(Mode::IRQ);
+    assert_eq!(regs.sp(), 0x0300_7FA0);
+    regs.change_mode(Mode::User);
+    assert_eq!(regs.sp(), 0x0300_7F00);
+    regs.change_mode(Mode::Supervisor);
+    assert_eq!(regs.sp(), 0x0300_7FE0);
+}
+
+#[test]
+fn test_screenshot_rgba_expands_rgb555() {
+    let mut emu = GbaEmulator::new();
+    emu.bus.ppu.framebuffer[..4].copy_from_slice(&[0x001F, 0x03E0, 0x7C00, 0x4210]);
+
+    let rgba = emu.screenshot_rgba();
+    assert_eq!(rgba.len(), emu.framebuffer().len() * 4);
+    assert_eq!(rgba[0..4], [0xFF, 0x00, 0x00, 0xFF]);
+    assert_eq!(rgba[4..8], [0x00, 0xFF, 0x00, 0xFF]);
+    assert_eq!(rgba[8..12], [0x00, 0x00, 0xFF, 0xFF]);
+    // 16 su 5 bit -> 0x84 (0b10000 << 3 | 0b10000 >> 2)
+    assert_eq!(rgba[12..16], [0x84, 0x84, 0x84, 0xFF]);
+    assert_eq!(rgba[16..20], [0x00, 0x00, 0x00, 0xFF]);
+}
+
+#[test]
+fn test_frame_rgba_follows_completed_frame() {
+    use gba_arm7tdmi::cpu::MemoryBus;
+
+    let mut emu = GbaEmulator::new();
+    assert_eq!(emu.frame_rgba().len(), 240 * 160 * 4);
+    assert_eq!(emu.frame_rgba()[0..4], [0x00, 0x00, 0x00, 0xFF]);
+
+    // Mode 3 con BG2: la VRAM è il framebuffer RGB555
+    emu.bus.write_halfword(0x0400_0000, 0x0403);
+    emu.bus.write_halfword(0x0600_0000, 0x001F);
+    emu.bus.write_halfword(0x0600_0002, 0x4210);
+    let last_pixel = 0x0600_0000 + 2 * (240 * 160 - 1);
+    emu.bus.write_halfword(last_pixel, 0x7C00);
+    emu.run_frame();
+
+    let rgba = emu.frame_rgba();
+    assert_eq!(rgba.len(), 240 * 160 * 4);
+    assert_eq!(rgba[0..4], [0xFF, 0x00, 0x00, 0xFF]);
+    assert_eq!(rgba[4..8], [0x84, 0x84, 0x84, 0xFF]);
+    assert_eq!(rgba[rgba.len() - 4..], [0x00, 0x00, 0xFF, 0xFF]);
+    assert_eq!(rgba, emu.screenshot_rgba().as_slice());
+}
+
+#[test]
+fn test_color_correction_toggle() {
+    let mut emu = GbaEmulator::new();
+    emu.bus.ppu.framebuffer[0] = 0x7FFF;
+    assert!(!emu.color_correction());
+    assert_eq!(emu.screenshot_rgba()[0..4], [0xFF, 0xFF, 0xFF, 0xFF]);
+
+    // Il bianco dell'LCD è più scuro e leggermente tendente al blu
+    emu.set_color_correction(true);
+    assert!(emu.color_correction());
+    assert_eq!(emu.present_pixel(0x7FFF), [252, 238, 242]);
+    assert_eq!(emu.screenshot_rgba()[0..4], [252, 238, 242, 0xFF]);
+
+    emu.set_color_correction(false);
+    assert_eq!(emu.present_pixel(0x7FFF), [0xFF, 0xFF, 0xFF]);
+}
+
+#[test]
+fn test_video_capture_dma_runs_once_per_visible_scanline() {
+    use gba_arm7tdmi::cpu::MemoryBus;
+
+    let mut emu = emulator_with_arm_code(&[0xE1A00000; 0x40000]); // NOP
+
+    // DMA3: 1 word da 0x03000000 (fisso) a 0x03001000 (incremento),
+    // repeat, timing Special
+    emu.bus.write_word(0x0300_0000, 0xDEAD_BEEF);
+    emu.bus.write_word(0x0400_00D4, 0x0300_0000);
+    emu.bus.write_word(0x0400_00D8, 0x0300_1000);
+    emu.bus.write_halfword(0x0400_00DC, 1);
+    emu.bus.write_halfword(0x0400_00DE, 0xB700);
+
+    emu.run_cycles(280_896);
+
+    // Una word per ognuna delle 160 scanline, nessuna durante il VBlank
+    for line in 0..160 {
+        assert_eq!(emu.bus.read_word(0x0300_1000 + line * 4), 0xDEAD_BEEF);
+    }
+    assert_eq!(emu.bus.read_word(0x0300_1000 + 160 * 4), 0);
+
+    // Al VBlank il canale si disabilita
+    assert_eq!(emu.bus.read_halfword(0x0400_00DE) & 0x8000, 0);
+}
+
+#[test]
+fn test_hle_soft_reset_restarts_rom_with_fresh_stacks() {
+    use gba_arm7tdmi::cpu::MemoryBus;
+    use gba_arm7tdmi::Mode;
+
+    let code = [
+        0xE3A00042u32, // MOV r0, #0x42
+        0xEF000000,    // SWI 0x00 (SoftReset)
+    ];
+    let mut emu = emulator_with_arm_code(&code);
+
+    // Stack sporchi e flag a 0x03007FFA = 0 (riparte dalla ROM)
+    emu.cpu.regs.r13_svc = 0x1234;
+    emu.cpu.regs.r13_irq = 0x5678;
+    emu.bus.write_word(0x0300_7E00, 0xFFFF_FFFF);
+
+    emu.step();
+    emu.step();
+
+    assert_eq!(emu.cpu.regs.pc(), 0x0800_0000);
+    assert!(!emu.cpu.regs.is_thumb());
+    assert_eq!(emu.cpu.regs.mode, Mode::System);
+    assert_eq!(emu.cpu.regs.r[0], 0);
+    assert_eq!(emu.cpu.regs.sp(), 0x0300_7F00);
+    assert_eq!(emu.cpu.regs.r13_svc, 0x0300_7FE0);
+    assert_eq!(emu.cpu.regs.r13_irq, 0x0300_7FA0);
+    assert_eq!(emu.bus.read_word(0x0300_7E00), 0);
+}
+
+#[test]
+fn test_hle_diff_8bit_unfilter_writes_wram() {
+    use gba_arm7tdmi::cpu::MemoryBus;
+
+    let code = [
+        0xE3A00402u32, // MOV r0, #0x02000000
+        0xE2811C01,    // ADD r1, r1, #0x100
+        0xE0811000,    // ADD r1, r1, r0
+        0xEF160000,    // SWI 0x16 (Diff8bitUnFilterWram)
+    ];
+    let mut emu = emulator_with_arm_code(&code);
+
+    // Stream a 0x02000000: 4 differenze
+    emu.bus.write_word(0x0200_0000, 0x0000_0481);
+    emu.bus.write_word(0x0200_0004, 0x0302_0201);
+
+    for _ in 0..4 {
+        emu.step();
+    }
+
+    assert_eq!(emu.bus.read_word(0x0200_0100), 0x0805_0301);
+    assert_eq!(emu.cpu.regs.pc(), 0x0800_0010);
+}
+
+#[test]
+fn test_hle_midi_key2freq_returns_frequency() {
+    use gba_arm7tdmi::cpu::MemoryBus;
+
+    let code = [
+        0xE3A00402u32, // MOV r0, #0x02000000 (WaveData)
+        0xE3A01048,    // MOV r1, #72
+        0xE3A02000,    // MOV r2, #0
+        0xEF1F0000,    // SWI 0x1F (MidiKey2Freq)
+        0xE3A03001,    // MOV r3, #1
+    ];
+    let mut emu = emulator_with_arm_code(&code);
+
+    // Frequenza della WaveData (Hz * 1024) all'offset 4
+    emu.bus.write_word(0x0200_0004, 22050 << 10);
+
+    for _ in 0..5 {
+        emu.step();
+    }
+
+    // Tasto 72 = un'ottava sopra la frequenza base
+    assert_eq!(emu.cpu.regs.r[0], 44100);
+    assert_eq!(emu.cpu.regs.r[3], 1);
+    assert_eq!(emu.cpu.regs.pc(), 0x0800_0014);
+}
+
+#[test]
+fn test_hle_sound_driver_calls_return() {
+    use crate::bios::SOUND_AREA_POINTER;
+    use gba_arm7tdmi::cpu::MemoryBus;
+
+    let code = [
+        0xE3A00403u32, // MOV r0, #0x03000000 (SoundArea)
+        0xEF1A0000,    // SWI 0x1A (SoundDriverInit)
+        0xEF1C0000,    // SWI 0x1C (SoundDriverMain)
+        0xEF1D0000,    // SWI 0x1D (SoundDriverVSync)
+        0xE3A00000,    // MOV r0, #0
+        0xEF190000,    // SWI 0x19 (SoundBias)
+    ];
+    let mut emu = emulator_with_arm_code(&code);
+    let sp = emu.cpu.regs.sp();
+
+    for _ in 0..6 {
+        emu.step();
+    }
+
+    assert_eq!(emu.cpu.regs.pc(), 0x0800_0018);
+    assert_eq!(emu.cpu.regs.sp(), sp);
+    assert_eq!(emu.bus.read_word(SOUND_AREA_POINTER), 0x0300_0000);
+    assert_eq!(emu.bus.read_halfword(0x0400_0088) & 0x03FE, 0);
+}
+
+#[test]
+fn test_run_until_halt_reads_test_rom_result() {
+    let code = [
+        0xE3A00402u32, // MOV r0, #0x02000000
+        0xE3A01CC0,    // MOV r1, #0xC000
+        0xE28110DE,    // ADD r1, r1, #0xDE
+        0xE5801000,    // STR r1, [r0]
+        0xE3A00301,    // MOV r0, #0x04000000
+        0xE2800C03,    // ADD r0, r0, #0x300
+        0xE3A01000,    // MOV r1, #0
+        0xE5C01001,    // STRB r1, [r0, #1] (HALTCNT, IE = 0)
+        0xE1A00000,    // NOP
+    ];
+    let mut emu = emulator_with_arm_code(&code);
+
+    assert!(emu.run_until_halt_or(1));
+    assert_eq!(emu.result_word(0x0200_0000), 0xC0DE);
+}
+
+#[test]
+fn test_run_until_halt_detects_idle_loop() {
+    let mut emu = emulator_with_arm_code(&[
+        0xE3A00302, // MOV r0, #0x08000000
+        0xE280000D, // ADD r0, r0, #0x0D
+        0xE12FFF10, // BX r0 (THUMB a 0x0800000C)
+        0xE7FE2205, // THUMB: MOV r2, #5; B .
+    ]);
+
+    assert!(emu.run_until_halt_or(1));
+    assert_eq!(emu.cpu.regs.r[2], 5);
+    assert_eq!(emu.cpu.regs.pc(), 0x0800_000E);
+}
+
+#[test]
+fn test_run_until_halt_times_out_on_running_program() {
+    let code = [
+        0xE2800001u32, // ADD r0, r0, #1
+        0xEAFFFFFD,    // B 0x08000000
+    ];
+    let mut emu = emulator_with_arm_code(&code);
+
+    assert!(!emu.run_until_halt_or(1));
+    assert_ne!(emu.cpu.regs.r[0], 0);
+}
+
+#[test]
+fn test_hle_reset_boots_rom_with_valid_stacks() {
+    use gba_arm7tdmi::Mode;
+
+    assert!(GbaEmulator::new().bios_hle());
+    let mut emu = emulator_with_arm_code(&[0xE3A00042]); // MOV r0, #0x42
+
+    assert_eq!(emu.cpu.regs.pc(), 0x0800_0000);
+    assert_eq!(emu.cpu.regs.mode, Mode::System);
+    assert_eq!(emu.cpu.regs.sp(), 0x0300_7F00);
+    assert_eq!(emu.cpu.regs.r13_irq, 0x0300_7FA0);
+    assert_eq!(emu.cpu.regs.r13_svc, 0x0300_7FE0);
+
+    emu.step();
+    assert_eq!(emu.cpu.regs.r[0], 0x42);
+}
+
+#[test]
+fn test_real_bios_reset_starts_at_reset_vector() {
+    use gba_arm7tdmi::Mode;
+
+    let mut emu = GbaEmulator::new();
+    emu.load_bios(vec![0; 0x4000]).unwrap();
+    assert!(!emu.bios_hle());
+    emu.reset();
+    assert_eq!(emu.cpu.regs.pc(), 0x0000_0000);
+    assert_eq!(emu.cpu.regs.mode, Mode::Supervisor);
+
+    // Saltando l'intro si torna al boot HLE
+    emu.set_bios_hle(true);
+    emu.reset();
+    assert_eq!(emu.cpu.regs.pc(), 0x0800_0000);
+}
+
+#[test]
+fn test_hle_irq_dispatch_calls_game_handler() {
+    let code = [
+        0xE1A00000u32, // NOP
+        0xE1A00000,    // NOP
+    ];
+    let mut emu = emulator_with_arm_code(&code);
+    emu.bus.memory.write_word(0x0300_7FFC, 0x0300_1000);
+    emu.cpu.regs.r[0] = 0x1111;
+    emu.cpu.regs.r[12] = 0xCCCC;
+
+    emu.bus.interrupt.ime = true;
+    emu.bus.interrupt.ie = InterruptFlags::VBLANK.bits();
+    emu.bus.interrupt.request(InterruptFlags::VBLANK);
+    emu.step();
+
+    // Handler del gioco in ARM, r0 = base degli I/O, LR = ritorno nel BIOS
+    assert_eq!(emu.cpu.regs.pc(), 0x0300_1000);
+    assert!(!emu.cpu.regs.is_thumb());
+    assert_eq!(emu.cpu.regs.r[0], 0x0400_0000);
+    assert_eq!(emu.cpu.regs.lr(), 0x0000_0138);
+
+    // Frame salvato sullo stack IRQ: r0-r3, r12, LR di ritorno
+    let sp = emu.cpu.regs.sp();
+    assert_eq!(sp, 0x0300_7FA0 - 24);
+    assert_eq!(emu.bus.memory.read_word(sp), 0x1111);
+    assert_eq!(emu.bus.memory.read_word(sp + 16), 0xCCCC);
+    assert_eq!(emu.bus.memory.read_word(sp + 20), 0x0800_0008);
+
+    // IF copiato anche nel mirror delle IntrWait
+    assert_eq!(emu.bus.memory.read_halfword(0x0300_7FF8), 0x0001);
+}
+
+#[test]
+fn test_hle_irq_handler_returns_to_interrupted_code() {
+    use gba_arm7tdmi::Mode;
+
+    let code = [0xE1A00000u32; 4]; // NOP
+    let mut emu = emulator_with_arm_code(&code);
+
+    // Handler del gioco in IWRAM: MOV r0, #0x55; BX LR
+    emu.bus.memory.write_word(0x0300_1000, 0xE3A00055);
+    emu.bus.memory.write_word(0x0300_1004, 0xE12FFF1E);
+    emu.bus.memory.write_word(0x0300_7FFC, 0x0300_1000);
+    emu.cpu.regs.r[0] = 0x1234;
+
+    emu.bus.interrupt.ime = true;
+    emu.bus.interrupt.ie = InterruptFlags::VBLANK.bits();
+    emu.bus.interrupt.request(InterruptFlags::VBLANK);
+    emu.step();
+    assert_eq!(emu.cpu.regs.pc(), 0x0300_1000);
+
+    emu.step(); // MOV r0, #0x55
+    assert_eq!(emu.cpu.regs.r[0], 0x55);
+    emu.step(); // BX LR
+    assert_eq!(emu.cpu.regs.pc(), 0x0000_0138);
+
+    // L'handler avrebbe riconosciuto l'interrupt scrivendo IF
+    emu.bus.interrupt.if_ = 0;
+    emu.step();
+
+    // Di nuovo in System all'istruzione interrotta, registri ripristinati
+    assert_eq!(emu.cpu.regs.mode, Mode::System);
+    assert_eq!(emu.cpu.regs.pc(), 0x0800_0004);
+    assert_eq!(emu.cpu.regs.r[0], 0x1234);
+    assert_eq!(emu.cpu.regs.sp(), 0x0300_7F00);
+    assert_eq!(emu.cpu.regs.r13_irq, 0x0300_7FA0);
+    assert_eq!(emu.cpu.regs.cpsr & (1 << 7), 0);
+}
+
+#[test]
+fn test_hle_irq_acknowledged_by_handler_does_not_reenter() {
+    let code = [0xE1A00000u32; 4]; // NOP
+    let mut emu = emulator_with_arm_code(&code);
+
+    // Handler: REG_IE/IF = VBlank << 16 | VBlank, poi BX LR
+    // MOV r1, #0x10000; ORR r1, r1, #1; ADD r2, r0, #0x200; STR r1, [r2]
+    let handler = [
+        0xE3A01801u32,
+        0xE3811001,
+        0xE2802C02,
+        0xE5821000,
+        0xE12FFF1E,
+    ];
+    for (i, instr) in handler.iter().enumerate() {
+        emu.bus.memory.write_word(0x0300_1000 + 4 * i as u32, *instr);
+    }
+    emu.bus.memory.write_word(0x0300_7FFC, 0x0300_1000);
+
+    emu.bus.interrupt.ime = true;
+    emu.bus.interrupt.ie = InterruptFlags::VBLANK.bits();
+    emu.bus.interrupt.request(InterruptFlags::VBLANK);
+    emu.bus.interrupt.request(InterruptFlags::HBLANK);
+    emu.step();
+    assert_eq!(emu.cpu.regs.pc(), 0x0300_1000);
+
+    for _ in 0..handler.len() {
+        emu.step();
+    }
+    assert_eq!(emu.cpu.regs.pc(), 0x0000_0138);
+
+    // REG_IF = IRQ_VBLANK azzera solo VBlank
+    assert_eq!(emu.bus.interrupt.if_, InterruptFlags::HBLANK.bits());
+
+    // Ritorno nel codice interrotto senza rientrare nell'handler
+    emu.step();
+    assert_eq!(emu.cpu.regs.pc(), 0x0800_0004);
+    emu.step();
+    assert_eq!(emu.cpu.regs.pc(), 0x0800_0008);
+}
+
+#[test]
+fn test_timer_overflow_requests_irq_and_feeds_sound_fifo() {
+    use gba_arm7tdmi::cpu::MemoryBus;
+
+    let mut emu = emulator_with_arm_code(&[0xE1A00000; 0x40000]); // NOP
+
+    // Sample Direct Sound in EWRAM
+    for offset in (0..0x400).step_by(4) {
+        emu.bus.write_word(0x0200_0000 + offset, 0x4040_4040);
+    }
+
+    // Audio on, FIFO A al 100% su entrambi i lati, clock dal timer 0
+    emu.bus.write_halfword(0x0400_0084, 0x0080);
+    emu.bus.write_halfword(0x0400_0082, 0x0304);
+
+    // DMA1: 4 word verso FIFO A (destinazione fissa), repeat, timing Special
+    emu.bus.write_word(0x0400_00BC, 0x0200_0000);
+    emu.bus.write_word(0x0400_00C0, 0x0400_00A0);
+    emu.bus.write_halfword(0x0400_00C4, 4);
+    emu.bus.write_halfword(0x0400_00C6, 0xB640);
+
+    // Timer 0: overflow ogni 256 cicli con IRQ
+    emu.bus.write_halfword(0x0400_0100, 0xFF00);
+    emu.bus.write_halfword(0x0400_0102, 0x00C0);
+
+    emu.run_cycles(20_000);
+
+    let flags = InterruptFlags::from_bits_truncate(emu.bus.read_halfword(0x0400_0202));
+    assert!(flags.contains(InterruptFlags::TIMER0));
+
+    let mut samples = Vec::new();
+    emu.bus.apu.drain_samples(&mut samples);
+    assert!(
+        samples.iter().any(|&s| s > 0),
+        "il FIFO A non ha mai suonato"
+    );
+}
+
+#[test]
+fn test_dma_stall_clocks_sound_fifo_for_every_timer_overflow() {
+    use gba_arm7tdmi::cpu::MemoryBus;
+
+    let mut emu = emulator_with_arm_code(&[0xE1A00000; 0x100]); // NOP
+
+    // Audio on, FIFO A al 100% su entrambi i lati, clock dal timer 0
+    emu.bus.write_halfword(0x0400_0084, 0x0080);
+    emu.bus.write_halfword(0x0400_0082, 0x0304);
+    for sample in 1..=31 {
+        emu.bus.apu.write_fifo_a(sample);
+    }
+    let silence = emu.bus.apu.generate_sample();
+
+    // Timer 0: overflow ogni 1000 cicli
+    emu.bus.write_halfword(0x0400_0100, 0xFC18);
+    emu.bus.write_halfword(0x0400_0102, 0x0080);
+
+    // DMA3 immediato di 2000 word: un solo step lungo decine di periodi
+    emu.bus.write_word(0x0400_00D4, 0x0200_0000);
+    emu.bus.write_word(0x0400_00D8, 0x0201_0000);
+    emu.bus.write_halfword(0x0400_00DC, 2000);
+    emu.bus.write_halfword(0x0400_00DE, 0x8400);
+
+    let overflows = emu.step() / 1000;
+    assert!(overflows > 1 && overflows <= 31, "{overflows} overflow");
+
+    // Ogni overflow ha fatto avanzare il FIFO: in uscita c'è il sample n
+    let (left, _) = emu.bus.apu.generate_sample();
+    assert_eq!(left, silence.0 + overflows as i16 * 4 * 64);
+}
+
+#[test]
+fn test_hblank_dma_runs_once_per_visible_scanline() {
+    use gba_arm7tdmi::cpu::MemoryBus;
+
+    let mut emu = emulator_with_arm_code(&[0xE1A00000; 0x40000]); // NOP
+
+    // DMA0: 1 halfword da 0x03000000 (fisso) a 0x03001000 (incremento),
+    // repeat, timing HBlank
+    emu.bus.write_halfword(0x0300_0000, 0xBEEF);
+    emu.bus.write_word(0x0400_00B0, 0x0300_0000);
+    emu.bus.write_word(0x0400_00B4, 0x0300_1000);
+    emu.bus.write_halfword(0x0400_00B8, 1);
+    emu.bus.write_halfword(0x0400_00BA, 0xA300);
+
+    // Prima della fine della prima linea visibile nessun trasferimento
+    emu.run_cycles(900);
+    assert_eq!(emu.bus.read_halfword(0x0300_1000), 0);
+
+    emu.run_cycles(280_896);
+
+    // Una halfword per ognuna delle 160 scanline, nessuna durante il VBlank
+    for line in 0..160 {
+        assert_eq!(emu.bus.read_halfword(0x0300_1000 + line * 2), 0xBEEF);
+    }
+    assert_eq!(emu.bus.read_halfword(0x0300_1000 + 160 * 2), 0);
+}
+
+#[test]
+fn test_clock_scale_runs_more_instructions_per_frame() {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    let instructions_per_frame = |scale: f32| {
+        let mut emu = emulator_with_arm_code(&[0xE1A00000; 0x40000]); // NOP
+        emu.set_clock_scale(scale);
+
+        let count = Rc::new(Cell::new(0u32));
+        let counter = Rc::clone(&count);
+        emu.cpu
+            .set_trace(Some(Box::new(move |_| counter.set(counter.get() + 1))));
+        emu.run_frame();
+        count.get()
+    };
+
+    let normal = instructions_per_frame(1.0);
+    let doubled = instructions_per_frame(2.0);
+    let ratio = doubled as f32 / normal as f32;
+    assert!((1.95..=2.05).contains(&ratio), "rapporto {}", ratio);
+
+    // Valori fuori range vengono limitati
+    let mut emu = GbaEmulator::new();
+    emu.set_clock_scale(100.0);
+    assert_eq!(emu.clock_scale(), crate::emulator::MAX_CLOCK_SCALE);
+    emu.set_clock_scale(f32::NAN);
+    assert_eq!(emu.clock_scale(), 1.0);
+}
+
+#[test]
+fn test_last_frame_stats() {
+    let mut emu = GbaEmulator::new();
+    assert_eq!(emu.last_frame_stats(), crate::FrameStats::default());
+
+    emu.run_frame();
+    let stats = emu.last_frame_stats();
+
+    // Un frame dura 280896 cicli, l'ultima istruzione può sforare di poco
+    assert!(
+        (280_896..280_896 + 64).contains(&stats.cycles),
+        "{:?}",
+        stats
+    );
+    assert!(stats.instructions > 0 && stats.instructions <= stats.cycles);
+    // Il tempo reale si misura solo con `std`
+    assert_eq!(stats.wall_time_ns > 0, cfg!(feature = "std"));
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_wav_recorder_sink_collects_frame_audio() {
+    use crate::apu::{WavRecorder, NATIVE_SAMPLE_RATE};
+
+    let path = std::env::temp_dir().join("test_audio_sink.wav");
+    let mut emu = GbaEmulator::new();
+    assert_eq!(emu.audio_output_rate(), NATIVE_SAMPLE_RATE);
+    let recorder = WavRecorder::create(&path, emu.audio_output_rate()).unwrap();
+    emu.set_audio_sink(Box::new(recorder));
+
+    // 280896 cicli / 512 per sample: 548 sample nel primo frame
+    emu.run_frame();
+    let mut polled = Vec::new();
+    emu.drain_audio(&mut polled);
+    assert!(polled.is_empty());
+
+    // Il drop del sink completa l'header
+    drop(emu.take_audio_sink());
+    let wav = std::fs::read(&path).unwrap();
+    assert_eq!(&wav[0..4], b"RIFF");
+    assert_eq!(&wav[8..16], b"WAVEfmt ");
+    assert_eq!(wav[24..28], NATIVE_SAMPLE_RATE.to_le_bytes());
+    assert_eq!(&wav[36..40], b"data");
+    assert_eq!(wav[40..44], (548u32 * 4).to_le_bytes());
+    assert_eq!(wav.len(), 44 + 548 * 4);
+
+    // Senza sink si torna al polling
+    emu.run_frame();
+    emu.drain_audio(&mut polled);
+    assert!(!polled.is_empty());
+
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,37 @@
+// Frame Hash - Hash del framebuffer per i test di regressione
+//
+// L'hash (FNV-1a a 64 bit sui pixel RGB555 little-endian) è stabile tra
+// esecuzioni e piattaforme: una ROM deterministica eseguita per N frame
+// produce sempre la stessa sequenza, confrontabile con hash di riferimento.
+
+use crate::emulator::GbaEmulator;
+use alloc::vec::Vec;
+
+const FNV_OFFSET_BASIS: u64 = 0xCBF2_9CE4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01B3;
+
+/// Hash FNV-1a di una sequenza di byte
+pub fn hash_bytes(bytes: impl IntoIterator<Item = u8>) -> u64 {
+    bytes.into_iter().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// Hash FNV-1a di un framebuffer RGB555
+pub fn hash_framebuffer(framebuffer: &[u16]) -> u64 {
+    hash_bytes(framebuffer.iter().flat_map(|pixel| pixel.to_le_bytes()))
+}
+
+/// Esegue `frames` frame della ROM (senza BIOS) e ritorna l'hash di ciascuno
+pub fn run_rom_frame_hashes(rom: &[u8], frames: usize) -> Vec<u64> {
+    let mut emu = GbaEmulator::new();
+    emu.bus.load_rom(rom.to_vec());
+    emu.reset();
+
+    (0..frames)
+        .map(|_| {
+            emu.run_frame();
+            emu.frame_hash()
+        })
+        .collect()
+}
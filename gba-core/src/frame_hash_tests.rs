@@ -0,0 +1,41 @@
+use crate::frame_hash::{hash_framebuffer, run_rom_frame_hashes};
+
+/// Mode 3: riempie la VRAM con colori che cambiano ad ogni coppia di pixel
+fn gradient_rom() -> Vec<u8> {
+    let code: [u32; 10] = [
+        0xE3A00301, // MOV r0, #0x04000000
+        0xE3A01B01, // MOV r1, #0x400 (BG2)
+        0xE3811003, // ORR r1, r1, #3 (Mode 3)
+        0xE5801000, // STR r1, [r0]
+        0xE3A02406, // MOV r2, #0x06000000
+        0xE3A03000, // MOV r3, #0
+        0xE1A00000, // NOP
+        0xE4823004, // loop: STR r3, [r2], #4
+        0xE2833821, // ADD r3, r3, #0x210000
+        0xEAFFFFFC, // B loop
+    ];
+    code.iter().flat_map(|w| w.to_le_bytes()).collect()
+}
+
+#[test]
+fn test_hash_framebuffer() {
+    // FNV-1a a 64 bit di un input vuoto è l'offset basis
+    assert_eq!(hash_framebuffer(&[]), 0xCBF2_9CE4_8422_2325);
+    assert_ne!(hash_framebuffer(&[0x0000]), hash_framebuffer(&[0x0001]));
+    assert_ne!(
+        hash_framebuffer(&[0x0001, 0]),
+        hash_framebuffer(&[0, 0x0001])
+    );
+}
+
+#[test]
+fn test_frame_hashes_are_stable() {
+    let rom = gradient_rom();
+    let first = run_rom_frame_hashes(&rom, 10);
+    let second = run_rom_frame_hashes(&rom, 10);
+
+    assert_eq!(first.len(), 10);
+    assert_eq!(first, second);
+    // La ROM disegna davvero qualcosa
+    assert_ne!(first[9], hash_framebuffer(&[0; 240 * 160]));
+}
@@ -0,0 +1,250 @@
+// GPIO della cartuccia - porta a 4 pin mappata nell'area ROM
+//
+// Alcune cartucce (rumble, RTC, sensori) espongono 4 linee di I/O nello
+// spazio della ROM. Qui sono decodificati:
+// - il motore del rumble sul pin 3: il frontend riceve un callback a ogni
+//   cambio di stato
+// - il sensore solare di Boktai: pin 0 clock, 1 reset, 2 chip select (attivo
+//   basso), 3 flag che si alza quando il contatore raggiunge il livello di luce
+// - il giroscopio di WarioWare Twisted: pin 0 avvia la conversione, pin 1
+//   clock, pin 2 dato seriale (16 bit, MSB per primo)
+//
+// Registri (halfword, bit 0-3 = pin 0-3):
+// - DATA (0x080000C4): valore dei pin
+// - DIRECTION (0x080000C6): 1 = pin in uscita (pilotato dal gioco)
+// - CONTROL (0x080000C8): bit 0 = registri leggibili (altrimenti si legge la ROM)
+
+use alloc::boxed::Box;
+
+pub const GPIO_DATA: u32 = 0x080000C4;
+pub const GPIO_DIRECTION: u32 = 0x080000C6;
+pub const GPIO_CONTROL: u32 = 0x080000C8;
+
+/// Pin del motore rumble
+const RUMBLE_PIN: u8 = 1 << 3;
+
+/// Pin del sensore solare
+const SOLAR_CLOCK: u8 = 1 << 0;
+const SOLAR_RESET: u8 = 1 << 1;
+const SOLAR_CS: u8 = 1 << 2;
+const SOLAR_FLAG: u8 = 1 << 3;
+
+/// Pin del giroscopio
+const GYRO_START: u8 = 1 << 0;
+const GYRO_CLOCK: u8 = 1 << 1;
+const GYRO_DATA: u8 = 1 << 2;
+
+/// Valore dell'ADC del giroscopio a riposo
+const GYRO_CENTER: i32 = 0x6C0;
+
+/// Sensore analogico collegato ai pin GPIO
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Sensor {
+    None,
+    Solar(SolarSensor),
+    Gyro(GyroSensor),
+}
+
+/// Sensore di luce: il gioco conta i clock finché il flag non si alza,
+/// più luce = meno clock
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SolarSensor {
+    level: u8,
+    counter: u8,
+    clock: bool,
+}
+
+impl SolarSensor {
+    fn clock_pins(&mut self, pins: u8) -> u8 {
+        if pins & SOLAR_CS != 0 {
+            return 0; // Chip non selezionato
+        }
+        if pins & SOLAR_RESET != 0 {
+            self.counter = 0;
+        }
+
+        // Il contatore avanza sul fronte di salita del clock
+        let clock = pins & SOLAR_CLOCK != 0;
+        if clock && !self.clock {
+            self.counter = self.counter.saturating_add(1);
+        }
+        self.clock = clock;
+
+        if self.counter >= 0xFF - self.level {
+            SOLAR_FLAG
+        } else {
+            0
+        }
+    }
+}
+
+/// Giroscopio: la rotazione campionata esce bit per bit sul pin 2
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct GyroSensor {
+    rotation: i16,
+    sample: u16,
+    clock: bool,
+    bit: u8,
+}
+
+impl GyroSensor {
+    fn clock_pins(&mut self, pins: u8) -> u8 {
+        if pins & GYRO_START != 0 {
+            self.sample = ((self.rotation as i32 >> 5) + GYRO_CENTER) as u16;
+        }
+
+        // Un bit sul fronte di discesa del clock
+        let clock = pins & GYRO_CLOCK != 0;
+        if self.clock && !clock {
+            self.bit = if self.sample & 0x8000 != 0 {
+                GYRO_DATA
+            } else {
+                0
+            };
+            self.sample <<= 1;
+        }
+        self.clock = clock;
+
+        self.bit
+    }
+}
+
+/// Porta GPIO della cartuccia
+pub struct Gpio {
+    data: u8,
+    direction: u8,
+    control: u8,
+    rumble: bool,
+    rumble_handler: Option<Box<dyn FnMut(bool)>>,
+    sensor: Sensor,
+}
+
+impl Gpio {
+    pub fn new() -> Self {
+        Self {
+            data: 0,
+            direction: 0,
+            control: 0,
+            rumble: false,
+            rumble_handler: None,
+            sensor: Sensor::None,
+        }
+    }
+
+    /// Registra la callback chiamata quando il rumble si accende o si spegne
+    pub fn set_rumble_handler(&mut self, handler: Box<dyn FnMut(bool)>) {
+        self.rumble_handler = Some(handler);
+    }
+
+    /// Collega il sensore solare e imposta il livello di luce (0 = buio)
+    pub fn set_solar_level(&mut self, level: u8) {
+        match &mut self.sensor {
+            Sensor::Solar(solar) => solar.level = level,
+            sensor => {
+                *sensor = Sensor::Solar(SolarSensor {
+                    level,
+                    counter: 0,
+                    clock: false,
+                })
+            }
+        }
+    }
+
+    /// Collega il giroscopio e imposta la velocità di rotazione (0 = fermo)
+    pub fn set_gyro(&mut self, rotation: i16) {
+        match &mut self.sensor {
+            Sensor::Gyro(gyro) => gyro.rotation = rotation,
+            sensor => {
+                *sensor = Sensor::Gyro(GyroSensor {
+                    rotation,
+                    sample: 0,
+                    clock: false,
+                    bit: 0,
+                })
+            }
+        }
+    }
+
+    /// Stato attuale del motore rumble
+    pub fn rumble(&self) -> bool {
+        self.rumble
+    }
+
+    /// Verifica se l'indirizzo è uno dei registri GPIO
+    pub fn covers(addr: u32) -> bool {
+        (GPIO_DATA..GPIO_CONTROL + 2).contains(&addr)
+    }
+
+    /// Registri leggibili dal gioco (CONTROL bit 0)
+    pub fn readable(&self) -> bool {
+        self.control & 1 != 0
+    }
+
+    /// Legge un registro GPIO
+    pub fn read_halfword(&self, addr: u32) -> u16 {
+        match addr & !1 {
+            GPIO_DATA => self.data as u16,
+            GPIO_DIRECTION => self.direction as u16,
+            GPIO_CONTROL => self.control as u16,
+            _ => 0,
+        }
+    }
+
+    /// Scrive un registro GPIO (solo i 4 bit bassi sono collegati)
+    pub fn write_halfword(&mut self, addr: u32, value: u16) {
+        let value = (value & 0x0F) as u8;
+        match addr & !1 {
+            GPIO_DATA => {
+                // Il gioco pilota solo i pin in uscita
+                self.data = (self.data & !self.direction) | (value & self.direction);
+                self.update_sensor();
+                self.update_rumble();
+            }
+            GPIO_DIRECTION => {
+                self.direction = value;
+                self.update_sensor();
+                self.update_rumble();
+            }
+            GPIO_CONTROL => self.control = value & 1,
+            _ => {}
+        }
+    }
+
+    /// Scrittura a 8 bit: conta solo il byte basso di ogni registro
+    pub fn write_byte(&mut self, addr: u32, value: u8) {
+        if addr & 1 == 0 {
+            self.write_halfword(addr, value as u16);
+        }
+    }
+
+    /// Fa avanzare il protocollo del sensore con i pin pilotati dal gioco
+    fn update_sensor(&mut self) {
+        let pins = self.data & self.direction;
+        let output = match &mut self.sensor {
+            Sensor::None => return,
+            Sensor::Solar(solar) => solar.clock_pins(pins),
+            Sensor::Gyro(gyro) => gyro.clock_pins(pins),
+        };
+
+        // Il sensore pilota solo i pin in ingresso
+        self.data = pins | (output & !self.direction);
+    }
+
+    /// Notifica il frontend se il pin del rumble ha cambiato stato
+    fn update_rumble(&mut self) {
+        let rumble = self.direction & self.data & RUMBLE_PIN != 0;
+        if rumble == self.rumble {
+            return;
+        }
+        self.rumble = rumble;
+        if let Some(handler) = &mut self.rumble_handler {
+            handler(rumble);
+        }
+    }
+}
+
+impl Default for Gpio {
+    fn default() -> Self {
+        Self::new()
+    }
+}
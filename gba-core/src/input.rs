@@ -1,3 +1,81 @@
+/// Pulsanti del GBA (il valore è il bit in KEYINPUT)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Button {
+    A = 0,
+    B = 1,
+    Select = 2,
+    Start = 3,
+    Right = 4,
+    Left = 5,
+    Up = 6,
+    Down = 7,
+    R = 8,
+    L = 9,
+}
+
+impl Button {
+    /// Tutti i pulsanti, nell'ordine usato per il remap
+    pub const ALL: [Button; 10] = [
+        Button::Up,
+        Button::Down,
+        Button::Left,
+        Button::Right,
+        Button::A,
+        Button::B,
+        Button::L,
+        Button::R,
+        Button::Start,
+        Button::Select,
+    ];
+
+    /// Bit del pulsante in KEYINPUT/KEYCNT
+    pub fn mask(self) -> u16 {
+        1 << self as u16
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Button::A => "A",
+            Button::B => "B",
+            Button::L => "L",
+            Button::R => "R",
+            Button::Start => "Start",
+            Button::Select => "Select",
+            Button::Up => "Up",
+            Button::Down => "Down",
+            Button::Left => "Left",
+            Button::Right => "Right",
+        }
+    }
+
+    /// Pulsante dal nome (senza distinzione maiuscole/minuscole)
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|b| b.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// Pulsanti premuti (bit a 1 = premuto, al contrario di KEYINPUT)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ButtonState(u16);
+
+impl ButtonState {
+    /// Stato letto da un valore di KEYINPUT (active-low)
+    pub fn from_keyinput(keyinput: u16) -> Self {
+        Self(!keyinput & 0x03FF)
+    }
+
+    pub fn is_pressed(&self, button: Button) -> bool {
+        self.0 & button.mask() != 0
+    }
+
+    /// Maschera dei pulsanti premuti
+    pub fn bits(&self) -> u16 {
+        self.0
+    }
+}
+
 /// Controller input (KEYINPUT register 0x04000130)
 /// 
 /// Bit 0: A button
@@ -15,108 +93,85 @@
 pub struct InputController {
     /// Stato corrente dei pulsanti (bit invertiti)
     keyinput: u16,
+    /// Stato imposto dalla riproduzione di un movie, al posto dell'input live
+    playback: Option<u16>,
 }
 
 impl InputController {
     pub fn new() -> Self {
         Self {
             keyinput: 0x03FF, // Tutti i pulsanti rilasciati (bit a 1)
+            playback: None,
         }
     }
     
     /// Leggi registro KEYINPUT
     pub fn read_keyinput(&self) -> u16 {
-        self.keyinput
+        self.playback.unwrap_or(self.keyinput)
     }
     
-    /// Imposta stato pulsante A
-    pub fn set_button_a(&mut self, pressed: bool) {
+    /// Imposta (o rimuove con `None`) lo stato riprodotto da un movie
+    pub fn set_playback(&mut self, keyinput: Option<u16>) {
+        self.playback = keyinput.map(|keys| keys & 0x03FF);
+    }
+    
+    /// Imposta lo stato di un pulsante
+    pub fn set_button(&mut self, button: Button, pressed: bool) {
         if pressed {
-            self.keyinput &= !(1 << 0);
+            self.keyinput &= !button.mask();
         } else {
-            self.keyinput |= 1 << 0;
+            self.keyinput |= button.mask();
         }
     }
     
+    /// Imposta stato pulsante A
+    pub fn set_button_a(&mut self, pressed: bool) {
+        self.set_button(Button::A, pressed);
+    }
+    
     /// Imposta stato pulsante B
     pub fn set_button_b(&mut self, pressed: bool) {
-        if pressed {
-            self.keyinput &= !(1 << 1);
-        } else {
-            self.keyinput |= 1 << 1;
-        }
+        self.set_button(Button::B, pressed);
     }
     
     /// Imposta stato pulsante Select
     pub fn set_button_select(&mut self, pressed: bool) {
-        if pressed {
-            self.keyinput &= !(1 << 2);
-        } else {
-            self.keyinput |= 1 << 2;
-        }
+        self.set_button(Button::Select, pressed);
     }
     
     /// Imposta stato pulsante Start
     pub fn set_button_start(&mut self, pressed: bool) {
-        if pressed {
-            self.keyinput &= !(1 << 3);
-        } else {
-            self.keyinput |= 1 << 3;
-        }
+        self.set_button(Button::Start, pressed);
     }
     
     /// Imposta stato D-Pad Right
     pub fn set_dpad_right(&mut self, pressed: bool) {
-        if pressed {
-            self.keyinput &= !(1 << 4);
-        } else {
-            self.keyinput |= 1 << 4;
-        }
+        self.set_button(Button::Right, pressed);
     }
     
     /// Imposta stato D-Pad Left
     pub fn set_dpad_left(&mut self, pressed: bool) {
-        if pressed {
-            self.keyinput &= !(1 << 5);
-        } else {
-            self.keyinput |= 1 << 5;
-        }
+        self.set_button(Button::Left, pressed);
     }
     
     /// Imposta stato D-Pad Up
     pub fn set_dpad_up(&mut self, pressed: bool) {
-        if pressed {
-            self.keyinput &= !(1 << 6);
-        } else {
-            self.keyinput |= 1 << 6;
-        }
+        self.set_button(Button::Up, pressed);
     }
     
     /// Imposta stato D-Pad Down
     pub fn set_dpad_down(&mut self, pressed: bool) {
-        if pressed {
-            self.keyinput &= !(1 << 7);
-        } else {
-            self.keyinput |= 1 << 7;
-        }
+        self.set_button(Button::Down, pressed);
     }
     
     /// Imposta stato pulsante R
     pub fn set_button_r(&mut self, pressed: bool) {
-        if pressed {
-            self.keyinput &= !(1 << 8);
-        } else {
-            self.keyinput |= 1 << 8;
-        }
+        self.set_button(Button::R, pressed);
     }
     
     /// Imposta stato pulsante L
     pub fn set_button_l(&mut self, pressed: bool) {
-        if pressed {
-            self.keyinput &= !(1 << 9);
-        } else {
-            self.keyinput |= 1 << 9;
-        }
+        self.set_button(Button::L, pressed);
     }
 }
 
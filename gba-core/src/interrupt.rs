@@ -20,6 +20,17 @@ bitflags! {
     }
 }
 
+/// Stato di consumo della CPU (HALTCNT, 0x04000301)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerState {
+    Running,
+    /// CPU ferma fino a un qualsiasi interrupt abilitato (IE & IF)
+    Halt,
+    /// CPU e video fermi fino a un interrupt di keypad, seriale o cartridge
+    Stop,
+}
+
+#[derive(Clone)]
 pub struct InterruptController {
     /// Interrupt Enable
     pub ie: u16,
@@ -50,6 +61,22 @@ impl InterruptController {
         self.ime && (self.ie & self.if_) != 0
     }
     
+    /// Verifica se un interrupt risveglia la CPU dallo stato `state`
+    ///
+    /// Il risveglio dipende solo da IE & IF: IME e il bit I del CPSR
+    /// decidono soltanto se l'interrupt viene poi servito.
+    pub fn wakes(&self, state: PowerState) -> bool {
+        let requested = self.ie & self.if_;
+        match state {
+            PowerState::Running => true,
+            PowerState::Halt => requested != 0,
+            PowerState::Stop => {
+                let stop_sources = InterruptFlags::KEYPAD | InterruptFlags::SERIAL | InterruptFlags::GAMEPAK;
+                requested & stop_sources.bits() != 0
+            }
+        }
+    }
+    
     /// Acknowledgeun interrupt
     pub fn acknowledge(&mut self, flag: InterruptFlags) {
         self.if_ &= !flag.bits();
@@ -1,30 +1,77 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
 pub mod apu;
+pub mod archive;
+#[cfg(test)]
+mod archive_tests;
 pub mod bios;
 mod bios_impl;
 #[cfg(test)]
 mod bios_tests;
 pub mod bus;
+#[cfg(test)]
+mod bus_tests;
 pub mod cartridge;
+#[cfg(test)]
+mod cartridge_tests;
+pub mod cheats;
+#[cfg(test)]
+mod cheats_tests;
+pub mod checksum;
+#[cfg(test)]
+mod checksum_tests;
+pub mod debugger;
+#[cfg(test)]
+mod debugger_tests;
 pub mod dma;
 mod dma_impl;
 #[cfg(test)]
 mod dma_tests;
 pub mod emulator;
+#[cfg(test)]
+mod emulator_tests;
+pub mod frame_hash;
+#[cfg(test)]
+mod frame_hash_tests;
+pub mod gpio;
+#[cfg(test)]
+mod gpio_tests;
 pub mod input;
 pub mod interrupt;
 pub mod memory;
+pub mod memory_search;
+#[cfg(test)]
+mod memory_search_tests;
+pub mod movie;
+#[cfg(test)]
+mod movie_tests;
 pub mod ppu;
 mod ppu_impl;
+pub mod rewind;
+#[cfg(test)]
+mod rewind_tests;
 pub mod save;
 mod save_impl;
 #[cfg(test)]
 mod save_tests;
+pub mod savestate;
+#[cfg(test)]
+mod savestate_tests;
+pub mod serial;
+#[cfg(test)]
+mod serial_tests;
 pub mod timer;
 mod timer_impl;
 #[cfg(test)]
 mod timer_tests;
+pub mod timing;
+#[cfg(test)]
+mod timing_tests;
 
 pub use bus::Bus;
-pub use cartridge::Cartridge;
-pub use emulator::GbaEmulator;
-pub use input::InputController;
+pub use cartridge::{Cartridge, LoadError};
+pub use debugger::Debugger;
+pub use emulator::{FrameStats, GbaEmulator};
+pub use input::{Button, ButtonState, InputController};
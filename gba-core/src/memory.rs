@@ -10,7 +10,7 @@
 // 0x03000000 - 0x03007FFF : IWRAM (32 KB) - RAM interna, VELOCE
 // 0x04000000 - 0x040003FE : I/O Registers - Controllo hardware
 // 0x05000000 - 0x050003FF : Palette RAM (1 KB) - Colori
-// 0x06000000 - 0x06017FFF : VRAM (96 KB) - Grafica
+// 0x06000000 - 0x06017FFF : VRAM (96 KB) - Grafica (mirror ogni 128 KB)
 // 0x07000000 - 0x070003FF : OAM (1 KB) - Sprite attributes
 // 0x08000000 - 0x09FFFFFF : ROM (32 MB) - Gioco, READ-ONLY
 // 0x0E000000 - 0x0E00FFFF : SRAM (64 KB) - Salvataggi
@@ -24,8 +24,26 @@
 // MIRRORS:
 // Alcune regioni sono "mirrorate" (replicate) in più indirizzi.
 // Es: ROM a 0x08000000 è visibile anche a 0x0A000000, 0x0C000000
+// EWRAM si ripete ogni 256 KB fino a 0x02FFFFFF, IWRAM ogni 32 KB fino a
+// 0x03FFFFFF (0x03FFFFFC è quindi 0x03007FFC, il vettore IRQ del BIOS),
+// Palette RAM e OAM ogni 1 KB nelle rispettive regioni da 16 MB.
+// La VRAM (96 KB) si ripete ogni 128 KB: gli ultimi 32 KB di ogni blocco
+// (0x06018000-0x0601FFFF) sono un mirror dell'area OBJ 0x06010000-0x06017FFF.
 //==============================================================================
 
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Offset nella VRAM di un indirizzo nella regione 0x06xxxxxx
+pub fn vram_offset(addr: u32) -> usize {
+    let offset = (addr & 0x1_FFFF) as usize;
+    if offset >= 0x1_8000 {
+        offset - 0x8000
+    } else {
+        offset
+    }
+}
+
 /// Mappa della memoria del GBA con timing e caratteristiche
 pub struct Memory {
     // BIOS - Sistema BIOS (16 KB)
@@ -84,15 +102,15 @@ impl Memory {
             // BIOS
             0x0000_0000..=0x0000_3FFF => self.bios.get(addr as usize).copied().unwrap_or(0),
 
-            // External WRAM
-            0x0200_0000..=0x0203_FFFF => {
-                let offset = (addr - 0x0200_0000) as usize;
+            // External WRAM (mirror ogni 256 KB)
+            0x0200_0000..=0x02FF_FFFF => {
+                let offset = (addr & 0x3_FFFF) as usize;
                 self.ewram.get(offset).copied().unwrap_or(0)
             }
 
-            // Internal WRAM
-            0x0300_0000..=0x0300_7FFF => {
-                let offset = (addr - 0x0300_0000) as usize;
+            // Internal WRAM (mirror ogni 32 KB)
+            0x0300_0000..=0x03FF_FFFF => {
+                let offset = (addr & 0x7FFF) as usize;
                 self.iwram.get(offset).copied().unwrap_or(0)
             }
 
@@ -102,21 +120,21 @@ impl Memory {
                 self.io_registers.get(offset).copied().unwrap_or(0)
             }
 
-            // Palette RAM
-            0x0500_0000..=0x0500_03FF => {
-                let offset = (addr - 0x0500_0000) as usize;
+            // Palette RAM (mirror ogni 1 KB)
+            0x0500_0000..=0x05FF_FFFF => {
+                let offset = (addr & 0x3FF) as usize;
                 self.palette_ram.get(offset).copied().unwrap_or(0)
             }
 
-            // VRAM
-            0x0600_0000..=0x0601_7FFF => {
-                let offset = (addr - 0x0600_0000) as usize;
+            // VRAM (con mirror)
+            0x0600_0000..=0x06FF_FFFF => {
+                let offset = vram_offset(addr);
                 self.vram.get(offset).copied().unwrap_or(0)
             }
 
-            // OAM
-            0x0700_0000..=0x0700_03FF => {
-                let offset = (addr - 0x0700_0000) as usize;
+            // OAM (mirror ogni 1 KB)
+            0x0700_0000..=0x07FF_FFFF => {
+                let offset = (addr & 0x3FF) as usize;
                 self.oam.get(offset).copied().unwrap_or(0)
             }
 
@@ -155,17 +173,17 @@ impl Memory {
             // BIOS - read only
             0x0000_0000..=0x0000_3FFF => {}
 
-            // External WRAM
-            0x0200_0000..=0x0203_FFFF => {
-                let offset = (addr - 0x0200_0000) as usize;
+            // External WRAM (mirror ogni 256 KB)
+            0x0200_0000..=0x02FF_FFFF => {
+                let offset = (addr & 0x3_FFFF) as usize;
                 if let Some(byte) = self.ewram.get_mut(offset) {
                     *byte = value;
                 }
             }
 
-            // Internal WRAM
-            0x0300_0000..=0x0300_7FFF => {
-                let offset = (addr - 0x0300_0000) as usize;
+            // Internal WRAM (mirror ogni 32 KB)
+            0x0300_0000..=0x03FF_FFFF => {
+                let offset = (addr & 0x7FFF) as usize;
                 if let Some(byte) = self.iwram.get_mut(offset) {
                     *byte = value;
                 }
@@ -179,25 +197,25 @@ impl Memory {
                 }
             }
 
-            // Palette RAM
-            0x0500_0000..=0x0500_03FF => {
-                let offset = (addr - 0x0500_0000) as usize;
+            // Palette RAM (mirror ogni 1 KB)
+            0x0500_0000..=0x05FF_FFFF => {
+                let offset = (addr & 0x3FF) as usize;
                 if let Some(byte) = self.palette_ram.get_mut(offset) {
                     *byte = value;
                 }
             }
 
-            // VRAM
-            0x0600_0000..=0x0601_7FFF => {
-                let offset = (addr - 0x0600_0000) as usize;
+            // VRAM (con mirror)
+            0x0600_0000..=0x06FF_FFFF => {
+                let offset = vram_offset(addr);
                 if let Some(byte) = self.vram.get_mut(offset) {
                     *byte = value;
                 }
             }
 
-            // OAM
-            0x0700_0000..=0x0700_03FF => {
-                let offset = (addr - 0x0700_0000) as usize;
+            // OAM (mirror ogni 1 KB)
+            0x0700_0000..=0x07FF_FFFF => {
+                let offset = (addr & 0x3FF) as usize;
                 if let Some(byte) = self.oam.get_mut(offset) {
                     *byte = value;
                 }
@@ -0,0 +1,122 @@
+// Memory Search - Ricerca di valori in RAM per creare cheat
+//
+// Si parte da uno snapshot di EWRAM e IWRAM in cui ogni indirizzo allineato
+// è un candidato; ogni filtro confronta i valori attuali con quelli dello
+// snapshot precedente (o con un valore fisso) e scarta chi non corrisponde.
+// Dopo qualche giro restano pochi indirizzi: quelli della variabile cercata.
+//
+// Le letture passano da `Bus::peek_*`: nessun wait state, watchpoint o
+// effetto collaterale sull'emulazione.
+
+use crate::bus::Bus;
+use alloc::vec::Vec;
+
+/// Regioni di RAM esaminate: (inizio, dimensione)
+pub const SEARCH_REGIONS: [(u32, u32); 2] = [
+    (0x0200_0000, 0x4_0000), // EWRAM
+    (0x0300_0000, 0x8000),   // IWRAM
+];
+
+/// Dimensione dei valori cercati
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchSize {
+    Byte,
+    Halfword,
+    Word,
+}
+
+impl SearchSize {
+    /// Dimensione in byte (e allineamento degli indirizzi)
+    pub fn bytes(self) -> u32 {
+        match self {
+            SearchSize::Byte => 1,
+            SearchSize::Halfword => 2,
+            SearchSize::Word => 4,
+        }
+    }
+}
+
+/// Filtro applicato ai candidati
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchFilter {
+    /// Valore attuale uguale a N
+    Equal(u32),
+    /// Diverso dallo snapshot precedente
+    Changed,
+    /// Uguale allo snapshot precedente
+    Unchanged,
+    /// Maggiore dello snapshot precedente
+    Increased,
+    /// Minore dello snapshot precedente
+    Decreased,
+}
+
+impl SearchFilter {
+    fn matches(self, previous: u32, current: u32) -> bool {
+        match self {
+            SearchFilter::Equal(value) => current == value,
+            SearchFilter::Changed => current != previous,
+            SearchFilter::Unchanged => current == previous,
+            SearchFilter::Increased => current > previous,
+            SearchFilter::Decreased => current < previous,
+        }
+    }
+}
+
+/// Ricerca in corso: indirizzi candidati e valore all'ultimo snapshot
+pub struct MemorySearch {
+    size: SearchSize,
+    candidates: Vec<(u32, u32)>,
+}
+
+impl MemorySearch {
+    /// Nuova ricerca: ogni indirizzo allineato di EWRAM e IWRAM è candidato
+    pub fn new(bus: &Bus, size: SearchSize) -> Self {
+        let step = size.bytes();
+        let candidates = SEARCH_REGIONS
+            .iter()
+            .flat_map(|&(start, len)| (start..start + len).step_by(step as usize))
+            .map(|addr| (addr, Self::peek(bus, size, addr)))
+            .collect();
+        Self { size, candidates }
+    }
+
+    /// Tiene i candidati che soddisfano `filter` e aggiorna lo snapshot
+    pub fn filter(&mut self, bus: &Bus, filter: SearchFilter) -> Vec<u32> {
+        let size = self.size;
+        self.candidates.retain_mut(|(addr, previous)| {
+            let current = Self::peek(bus, size, *addr);
+            let keep = filter.matches(*previous, current);
+            *previous = current;
+            keep
+        });
+        self.addresses()
+    }
+
+    /// Indirizzi ancora candidati
+    pub fn addresses(&self) -> Vec<u32> {
+        self.candidates.iter().map(|&(addr, _)| addr).collect()
+    }
+
+    /// Numero di candidati rimasti
+    pub fn len(&self) -> usize {
+        self.candidates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.candidates.is_empty()
+    }
+
+    /// Dimensione dei valori cercati
+    pub fn size(&self) -> SearchSize {
+        self.size
+    }
+
+    fn peek(bus: &Bus, size: SearchSize, addr: u32) -> u32 {
+        match size {
+            SearchSize::Byte => bus.peek_byte(addr) as u32,
+            SearchSize::Halfword => bus.peek_halfword(addr) as u32,
+            SearchSize::Word => bus.peek_word(addr),
+        }
+    }
+}
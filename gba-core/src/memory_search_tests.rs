@@ -0,0 +1,56 @@
+use crate::bus::Bus;
+use crate::memory_search::*;
+use gba_arm7tdmi::cpu::MemoryBus;
+
+#[test]
+fn test_changed_filter_finds_modified_address() {
+    let mut bus = Bus::new();
+    for i in 0..0x100 {
+        bus.write_byte(0x0200_0000 + i, i as u8);
+    }
+
+    let mut search = MemorySearch::new(&bus, SearchSize::Byte);
+    assert_eq!(search.len(), 0x4_0000 + 0x8000);
+
+    bus.write_byte(0x0200_0042, 0x99);
+    assert_eq!(
+        search.filter(&bus, SearchFilter::Changed),
+        vec![0x0200_0042]
+    );
+}
+
+#[test]
+fn test_successive_filters_narrow_candidates() {
+    let mut bus = Bus::new();
+    bus.write_halfword(0x0300_0010, 100);
+    bus.write_halfword(0x0200_0020, 100);
+
+    let mut search = MemorySearch::new(&bus, SearchSize::Halfword);
+    let found = search.filter(&bus, SearchFilter::Equal(100));
+    assert_eq!(found, vec![0x0200_0020, 0x0300_0010]);
+
+    // Solo una delle due "vite" scende
+    bus.write_halfword(0x0300_0010, 99);
+    assert_eq!(
+        search.filter(&bus, SearchFilter::Decreased),
+        vec![0x0300_0010]
+    );
+    assert!(search.filter(&bus, SearchFilter::Increased).is_empty());
+    assert!(search.is_empty());
+}
+
+#[test]
+fn test_unchanged_filter_uses_latest_snapshot() {
+    let mut bus = Bus::new();
+    let mut search = MemorySearch::new(&bus, SearchSize::Word);
+
+    bus.write_word(0x0200_0000, 5);
+    search.filter(&bus, SearchFilter::Increased);
+    assert_eq!(search.addresses(), vec![0x0200_0000]);
+
+    // Lo snapshot ora contiene 5: il valore non è più cambiato
+    assert_eq!(
+        search.filter(&bus, SearchFilter::Unchanged),
+        vec![0x0200_0000]
+    );
+}
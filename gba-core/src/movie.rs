@@ -0,0 +1,182 @@
+// Movie - Registrazione e riproduzione dell'input per replay deterministici
+//
+// Un movie è la sequenza dei valori di KEYINPUT campionati all'inizio di
+// ogni frame, legata alla ROM (hash FNV-1a) e allo stato di partenza: dal
+// reset oppure da uno snapshot, di cui il file conserva solo l'hash. Con
+// l'esecuzione deterministica a cicli la riproduzione è identica bit per bit.
+//
+// Formato del file (little-endian):
+//   "GBAMOVIE"  magic
+//   u8          versione (1)
+//   u8          1 = parte da uno snapshot, 0 = dal reset
+//   u64         hash della ROM
+//   u64         hash dello snapshot iniziale (0 se dal reset)
+//   u32         numero di frame
+//   u16 * N     KEYINPUT di ogni frame
+
+use crate::frame_hash::hash_bytes;
+use crate::savestate::SaveState;
+use alloc::vec::Vec;
+use thiserror::Error;
+
+const MOVIE_MAGIC: &[u8; 8] = b"GBAMOVIE";
+const MOVIE_VERSION: u8 = 1;
+const HEADER_SIZE: usize = 8 + 1 + 1 + 8 + 8 + 4;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum MovieError {
+    #[error("Not a movie file")]
+    InvalidMagic,
+
+    #[error("Unsupported movie version: {0}")]
+    UnsupportedVersion(u8),
+
+    #[error("Movie file is truncated")]
+    Truncated,
+
+    #[error("Movie was recorded with a different ROM")]
+    RomMismatch,
+
+    #[error("Movie starts from a different save state")]
+    StateMismatch,
+}
+
+/// Hash della ROM a cui è legato un movie
+pub fn rom_hash(rom: &[u8]) -> u64 {
+    hash_bytes(rom.iter().copied())
+}
+
+/// Hash di uno snapshot (registri CPU esclusi: basta la RAM con l'I/O)
+pub fn state_hash(state: &SaveState) -> u64 {
+    hash_bytes(state.ram().iter().copied())
+}
+
+/// Input registrato, frame per frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Movie {
+    pub rom_hash: u64,
+    /// Hash dello snapshot iniziale, `None` se il movie parte dal reset
+    pub start_state: Option<u64>,
+    /// KEYINPUT di ogni frame (bit invertiti, 0 = premuto)
+    pub frames: Vec<u16>,
+}
+
+impl Movie {
+    /// Serializza il movie nel formato file
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(HEADER_SIZE + self.frames.len() * 2);
+        out.extend_from_slice(MOVIE_MAGIC);
+        out.push(MOVIE_VERSION);
+        out.push(self.start_state.is_some() as u8);
+        out.extend_from_slice(&self.rom_hash.to_le_bytes());
+        out.extend_from_slice(&self.start_state.unwrap_or(0).to_le_bytes());
+        out.extend_from_slice(&(self.frames.len() as u32).to_le_bytes());
+        for keys in &self.frames {
+            out.extend_from_slice(&keys.to_le_bytes());
+        }
+        out
+    }
+
+    /// Legge un movie dal formato file
+    pub fn from_bytes(data: &[u8]) -> Result<Self, MovieError> {
+        if data.len() < MOVIE_MAGIC.len() || &data[..8] != MOVIE_MAGIC {
+            return Err(MovieError::InvalidMagic);
+        }
+        if data.len() < HEADER_SIZE {
+            return Err(MovieError::Truncated);
+        }
+        if data[8] != MOVIE_VERSION {
+            return Err(MovieError::UnsupportedVersion(data[8]));
+        }
+
+        let u64_at =
+            |offset: usize| u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+        let rom_hash = u64_at(10);
+        let start_state = (data[9] != 0).then(|| u64_at(18));
+        let count = u32::from_le_bytes(data[26..30].try_into().unwrap()) as usize;
+
+        let body = &data[HEADER_SIZE..];
+        if body.len() < count * 2 {
+            return Err(MovieError::Truncated);
+        }
+        let frames = body
+            .chunks_exact(2)
+            .take(count)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+
+        Ok(Self {
+            rom_hash,
+            start_state,
+            frames,
+        })
+    }
+}
+
+/// Registra KEYINPUT a ogni frame
+#[derive(Debug, Clone)]
+pub struct InputRecorder {
+    movie: Movie,
+}
+
+impl InputRecorder {
+    /// Nuova registrazione per la ROM, dal reset o da uno snapshot
+    pub fn new(rom_hash: u64, start_state: Option<u64>) -> Self {
+        Self {
+            movie: Movie {
+                rom_hash,
+                start_state,
+                frames: Vec::new(),
+            },
+        }
+    }
+
+    /// Aggiunge lo stato dei tasti di un frame
+    pub fn record_frame(&mut self, keyinput: u16) {
+        self.movie.frames.push(keyinput);
+    }
+
+    /// Frame registrati finora
+    pub fn len(&self) -> usize {
+        self.movie.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.movie.frames.is_empty()
+    }
+
+    /// Termina la registrazione
+    pub fn finish(self) -> Movie {
+        self.movie
+    }
+}
+
+/// Riproduce un movie un frame alla volta
+#[derive(Debug, Clone)]
+pub struct MoviePlayer {
+    movie: Movie,
+    position: usize,
+}
+
+impl MoviePlayer {
+    pub fn new(movie: Movie) -> Self {
+        Self { movie, position: 0 }
+    }
+
+    /// KEYINPUT del prossimo frame, `None` a fine movie
+    pub fn next_frame(&mut self) -> Option<u16> {
+        let keys = self.movie.frames.get(self.position).copied()?;
+        self.position += 1;
+        Some(keys)
+    }
+
+    /// Frame già riprodotti
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Movie terminato
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.movie.frames.len()
+    }
+}
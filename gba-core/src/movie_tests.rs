@@ -0,0 +1,145 @@
+use crate::movie::{InputRecorder, Movie, MovieError, MoviePlayer};
+use crate::GbaEmulator;
+use gba_arm7tdmi::cpu::MemoryBus;
+
+/// KEYINPUT sintetico: ogni frame preme una combinazione diversa
+fn synthetic_keys(frame: u16) -> u16 {
+    !(frame.wrapping_mul(37) ^ (frame << 3)) & 0x03FF
+}
+
+fn test_rom() -> Vec<u8> {
+    [0xE1A00000u32; 0x100] // NOP
+        .iter()
+        .flat_map(|w| w.to_le_bytes())
+        .collect()
+}
+
+#[test]
+fn test_recorder_and_player() {
+    let mut recorder = InputRecorder::new(0x1234, None);
+    assert!(recorder.is_empty());
+    for frame in 0..3 {
+        recorder.record_frame(synthetic_keys(frame));
+    }
+    assert_eq!(recorder.len(), 3);
+
+    let mut player = MoviePlayer::new(recorder.finish());
+    for frame in 0..3 {
+        assert_eq!(player.next_frame(), Some(synthetic_keys(frame)));
+    }
+    assert!(player.is_finished());
+    assert_eq!(player.next_frame(), None);
+    assert_eq!(player.position(), 3);
+}
+
+#[test]
+fn test_movie_file_roundtrip() {
+    let movie = Movie {
+        rom_hash: 0xDEAD_BEEF_0123_4567,
+        start_state: Some(42),
+        frames: (0..60).map(synthetic_keys).collect(),
+    };
+    let bytes = movie.to_bytes();
+    assert_eq!(Movie::from_bytes(&bytes), Ok(movie));
+
+    assert_eq!(
+        Movie::from_bytes(b"NOTAMOVIE"),
+        Err(MovieError::InvalidMagic)
+    );
+    assert_eq!(
+        Movie::from_bytes(&bytes[..bytes.len() - 1]),
+        Err(MovieError::Truncated)
+    );
+    let mut future = bytes.clone();
+    future[8] = 2;
+    assert_eq!(
+        Movie::from_bytes(&future),
+        Err(MovieError::UnsupportedVersion(2))
+    );
+}
+
+#[test]
+fn test_playback_reproduces_recorded_input() {
+    let mut emu = GbaEmulator::new();
+    emu.bus.load_rom(test_rom());
+    emu.start_recording(true);
+
+    // 60 frame con input sintetico
+    for frame in 0..60u16 {
+        let keys = synthetic_keys(frame);
+        let input = emu.input_mut();
+        input.set_button_a(keys & 0x001 == 0);
+        input.set_button_b(keys & 0x002 == 0);
+        input.set_button_select(keys & 0x004 == 0);
+        input.set_button_start(keys & 0x008 == 0);
+        input.set_dpad_right(keys & 0x010 == 0);
+        input.set_dpad_left(keys & 0x020 == 0);
+        input.set_dpad_up(keys & 0x040 == 0);
+        input.set_dpad_down(keys & 0x080 == 0);
+        input.set_button_r(keys & 0x100 == 0);
+        input.set_button_l(keys & 0x200 == 0);
+        emu.run_frame();
+    }
+    let movie = emu.stop_recording().unwrap();
+    assert_eq!(movie.frames.len(), 60);
+
+    // Riproduzione su un emulatore nuovo: l'input live viene ignorato
+    let mut replay = GbaEmulator::new();
+    replay.bus.load_rom(test_rom());
+    replay.input_mut().set_button_a(true);
+    replay.start_playback(movie).unwrap();
+
+    for frame in 0..60u16 {
+        replay.run_frame();
+        assert_eq!(replay.bus.read_halfword(0x0400_0130), synthetic_keys(frame));
+    }
+
+    // A fine movie torna l'input live
+    assert!(replay.is_playing_movie());
+    replay.run_frame();
+    assert!(!replay.is_playing_movie());
+    assert_eq!(replay.bus.read_halfword(0x0400_0130), 0x03FE);
+}
+
+#[test]
+fn test_playback_input_raises_keypad_irq() {
+    use crate::bus::KEYCNT;
+    use crate::interrupt::InterruptFlags;
+
+    let mut emu = GbaEmulator::new();
+    emu.bus.load_rom(test_rom());
+    let movie = Movie {
+        rom_hash: crate::movie::rom_hash(&emu.bus.memory.rom),
+        start_state: None,
+        frames: vec![0x03FF, 0x03FE],
+    };
+    emu.start_playback(movie).unwrap();
+
+    // KEYCNT: IRQ alla pressione di A
+    emu.bus.write_halfword(KEYCNT, 0x4001);
+    emu.run_frame();
+    assert_eq!(emu.bus.interrupt.if_ & InterruptFlags::KEYPAD.bits(), 0);
+    emu.run_frame();
+    assert_ne!(emu.bus.interrupt.if_ & InterruptFlags::KEYPAD.bits(), 0);
+}
+
+#[test]
+fn test_playback_rejects_other_rom_or_state() {
+    let mut emu = GbaEmulator::new();
+    emu.bus.load_rom(test_rom());
+    emu.start_recording(false);
+    emu.run_frame();
+    let movie = emu.stop_recording().unwrap();
+    assert!(movie.start_state.is_some());
+
+    let mut other = GbaEmulator::new();
+    other.bus.load_rom(vec![0; 0x400]);
+    assert_eq!(
+        other.start_playback(movie.clone()),
+        Err(MovieError::RomMismatch)
+    );
+
+    // Stessa ROM ma stato diverso
+    emu.bus.write_word(0x0200_0000, 0x1234_5678);
+    assert_eq!(emu.start_playback(movie), Err(MovieError::StateMismatch));
+}
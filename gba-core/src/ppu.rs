@@ -2,7 +2,9 @@
 /// Modular implementation in ppu_impl/
 pub use crate::ppu_impl::{
     BgControl,
+    ColorCorrection,
     DisplayMode,
+    Layer,
     SpriteAttribute,
     // Constants
     BG0CNT,
@@ -19,10 +21,16 @@ pub use crate::ppu_impl::{
     BG3VOFS,
     DISPCNT,
     DISPSTAT,
+    GREENSWAP,
     PPU,
+    PpuStepResult,
     SCREEN_HEIGHT,
     SCREEN_WIDTH,
+    TILE_VIEWER_WIDTH,
     VCOUNT,
+    // Helpers
+    present_rgb888,
+    rgb555_to_rgb888,
 };
 
 #[cfg(test)]
@@ -79,8 +87,27 @@ mod tests {
         assert_eq!(ppu.read_palette_halfword(10), 0x001F);
 
         ppu.write_palette_byte(20, 0xAB);
+        assert_eq!(ppu.read_palette_halfword(20), 0xABAB);
+    }
+
+    #[test]
+    fn test_palette_byte_write_duplicates() {
+        let mut ppu = PPU::new();
+
+        // Byte write to the odd half still fills the whole halfword
         ppu.write_palette_byte(21, 0xCD);
-        assert_eq!(ppu.read_palette_halfword(20), 0xCDAB);
+        assert_eq!(ppu.read_palette_halfword(20), 0xCDCD);
+    }
+
+    #[test]
+    fn test_oam_byte_write_ignored() {
+        let mut ppu = PPU::new();
+
+        ppu.write_oam_halfword(0, 0x1234);
+        ppu.write_oam_byte(0, 0xFF);
+        ppu.write_oam_byte(1, 0xFF);
+
+        assert_eq!(ppu.read_oam_halfword(0), 0x1234);
     }
 
     #[test]
@@ -167,6 +194,41 @@ mod tests {
         ppu.step(1232, &vram);
     }
 
+    #[test]
+    fn test_layer_toggle_hides_bg0_only() {
+        let mut ppu = PPU::new();
+        ppu.write_register(DISPCNT, 0x0300);
+        ppu.write_register(BG0CNT, 0x0800); // Map at 0x4000
+        ppu.write_register(BG1CNT, 0x0900); // Map at 0x4800
+
+        let mut vram = vec![0u8; 96 * 1024];
+        ppu.write_palette_halfword(2, 0x001F);
+        ppu.write_palette_halfword(4, 0x03E0);
+
+        // Tile 0 transparent, tile 1 red, tile 2 green
+        vram[32..64].fill(0x11);
+        vram[64..96].fill(0x22);
+        vram[0x4000] = 1; // BG0: tile 1 at x 0-7
+        vram[0x4802] = 2; // BG1: tile 2 at x 8-15
+
+        ppu.set_layer_enabled(Layer::Bg0, false);
+        assert!(!ppu.layer_enabled(Layer::Bg0));
+        assert!(ppu.layer_enabled(Layer::Bg1));
+        ppu.scanline = 0;
+        ppu.step(1232, &vram);
+
+        assert_eq!(ppu.framebuffer[0], 0x0000);
+        assert_eq!(ppu.framebuffer[8], 0x03E0);
+        // The game still sees BG0 enabled
+        assert_eq!(ppu.read_register(DISPCNT), 0x0300);
+
+        ppu.set_layer_enabled(Layer::Bg0, true);
+        ppu.scanline = 0;
+        ppu.step(1232, &vram);
+        assert_eq!(ppu.framebuffer[0], 0x001F);
+        assert_eq!(ppu.framebuffer[8], 0x03E0);
+    }
+
     #[test]
     fn test_mode0_transparency() {
         let mut ppu = PPU::new();
@@ -316,6 +378,62 @@ mod tests {
         assert_eq!(ppu.framebuffer[3], 0x7FFF);
     }
 
+    #[test]
+    fn test_green_swap_exchanges_green_of_pixel_pairs() {
+        let mut ppu = PPU::new();
+        ppu.write_register(DISPCNT, 0x0403); // Mode 3 + BG2
+        ppu.write_register(GREENSWAP, 1);
+        assert_eq!(ppu.read_register(GREENSWAP), 1);
+
+        let mut vram = vec![0u8; 96 * 1024];
+        vram[0..2].copy_from_slice(&0x7FFFu16.to_le_bytes());
+        vram[2..4].copy_from_slice(&0x001Fu16.to_le_bytes());
+
+        ppu.scanline = 0;
+        ppu.step(1232, &vram);
+        assert_eq!(ppu.framebuffer[0], 0x7C1F);
+        assert_eq!(ppu.framebuffer[1], 0x03FF);
+
+        // Disabled: pixels untouched
+        ppu.write_register(GREENSWAP, 0);
+        ppu.scanline = 0;
+        ppu.bg2_affine.latch(); // As at VBlank: back to the top of the bitmap
+        ppu.step(1232, &vram);
+        assert_eq!(ppu.framebuffer[0], 0x7FFF);
+        assert_eq!(ppu.framebuffer[1], 0x001F);
+    }
+
+    #[test]
+    fn test_mode3_scaled_frame_shows_backdrop_outside_bitmap() {
+        let mut ppu = PPU::new();
+        ppu.write_register(DISPCNT, 0x0403); // Mode 3 + BG2
+        ppu.write_palette_halfword(0, 0x03E0); // Green backdrop
+
+        // 0.5x zoom: the 240x160 bitmap covers only the top-left quarter
+        ppu.write_register(0x04000020, 0x0200); // BG2PA = 2.0
+        ppu.write_register(0x04000026, 0x0200); // BG2PD = 2.0
+
+        let mut vram = vec![0u8; 96 * 1024];
+        for pixel in vram[..SCREEN_WIDTH * 160 * 2].chunks_exact_mut(2) {
+            pixel.copy_from_slice(&0x001Fu16.to_le_bytes()); // Red bitmap
+        }
+
+        ppu.step(1232 * 160, &vram);
+        assert_eq!(ppu.framebuffer[0], 0x001F);
+        assert_eq!(ppu.framebuffer[79 * SCREEN_WIDTH + 119], 0x001F);
+        // Outside the bitmap the backdrop shows through
+        assert_eq!(ppu.framebuffer[120], 0x03E0);
+        assert_eq!(ppu.framebuffer[80 * SCREEN_WIDTH], 0x03E0);
+        assert_eq!(ppu.framebuffer[159 * SCREEN_WIDTH + 239], 0x03E0);
+    }
+
+    #[test]
+    fn test_dispcnt_cgb_bit_is_read_only() {
+        let mut ppu = PPU::new();
+        ppu.write_register(DISPCNT, 0xFFFF);
+        assert_eq!(ppu.read_register(DISPCNT), 0xFFF7);
+    }
+
     #[test]
     fn test_mode1_affine_bg2() {
         let mut ppu = PPU::new();
@@ -532,4 +650,147 @@ mod tests {
 
         // Scaling should work without issues
     }
+
+    #[test]
+    fn test_bg2x_halfword_writes_latch() {
+        let mut ppu = PPU::new();
+        let vram = vec![0u8; 96 * 1024];
+
+        // BG2X = -2.5 (0xFFFFD80 as a 28-bit value), written as two halfwords
+        ppu.write_register(0x04000028, 0xFD80);
+        ppu.write_register(0x0400002A, 0x0FFF);
+        assert_eq!(ppu.bg2_affine.ref_x, -0x280);
+        assert_eq!(ppu.bg2_affine.current_x, -0x280);
+
+        // Each drawn line adds PB to the internal copy only
+        ppu.write_register(0x04000022, 0x0100); // BG2PB = 1.0
+        ppu.step(1232 * 4, &vram);
+        assert_eq!(ppu.bg2_affine.current_x, -0x280 + 4 * 0x100);
+        assert_eq!(ppu.bg2_affine.ref_x, -0x280);
+
+        // A mid-frame write restarts from the new value
+        ppu.write_register(0x04000028, 0x0000);
+        ppu.write_register(0x0400002A, 0x0000);
+        ppu.step(1232, &vram);
+        assert_eq!(ppu.bg2_affine.current_x, 0x100);
+
+        // Entering VBlank reloads BG2X
+        ppu.step(1232 * (160 - 5), &vram);
+        assert_eq!(ppu.bg2_affine.current_x, 0);
+    }
+
+    #[test]
+    fn test_hblank_starts_at_cycle_960() {
+        let mut ppu = PPU::new();
+        let vram = vec![0u8; 0x18000];
+        ppu.write_register(DISPSTAT, 0x0018); // IRQ VBlank + HBlank
+
+        // Still drawing one cycle before HBlank
+        let result = ppu.step(959, &vram);
+        assert_eq!(result.drawn_lines, 0);
+        assert_eq!(ppu.read_register(DISPSTAT) & 0x0002, 0);
+
+        let result = ppu.step(1, &vram);
+        assert_eq!(result.drawn_lines, 1);
+        assert_eq!(result.irq_flags, ppu_impl::IRQ_HBLANK);
+        assert_eq!(ppu.read_register(DISPSTAT) & 0x0002, 0x0002);
+        assert_eq!(ppu.scanline, 0);
+
+        // HBlank lasts until the end of the line
+        let result = ppu.step(271, &vram);
+        assert_eq!(result, PpuStepResult::default());
+        let result = ppu.step(1, &vram);
+        assert_eq!(result.drawn_lines, 0);
+        assert_eq!(ppu.scanline, 1);
+        assert_eq!(ppu.read_register(DISPSTAT) & 0x0002, 0);
+
+        // A whole frame: one HBlank per visible line, one VBlank start
+        let result = ppu.step(1232 * 228, &vram);
+        assert_eq!(result.drawn_lines, 160);
+        assert!(result.vblank_started);
+        assert_eq!(
+            result.irq_flags,
+            ppu_impl::IRQ_HBLANK | ppu_impl::IRQ_VBLANK
+        );
+    }
+
+    #[test]
+    fn test_scroll_latched_at_line_start() {
+        let mut ppu = PPU::new();
+        ppu.write_register(DISPCNT, 0x0100);
+        ppu.write_register(BG0CNT, 0x0800); // Map at 0x4000, tiles at 0
+        ppu.write_palette_halfword(2, 0x001F);
+
+        // Tile 0 solid color 1, tile 1 transparent; map entry 1 uses tile 1
+        let mut vram = vec![0u8; 0x18000];
+        vram.iter_mut().take(32).for_each(|v| *v = 0x11);
+        vram[0x4002] = 0x01;
+
+        // Line 0 without scroll
+        ppu.step(ppu_impl::HDRAW_CYCLES, &vram);
+        assert_eq!(ppu.framebuffer[0], 0x001F);
+
+        // A write during HBlank applies to line 1
+        ppu.write_register(BG0HOFS, 8);
+        ppu.step(ppu_impl::CYCLES_PER_SCANLINE - ppu_impl::HDRAW_CYCLES, &vram);
+
+        // A write while line 1 is drawing waits for line 2
+        ppu.step(100, &vram);
+        ppu.write_register(BG0HOFS, 0);
+        ppu.step(ppu_impl::HDRAW_CYCLES - 100, &vram);
+        assert_eq!(ppu.framebuffer[SCREEN_WIDTH], 0x0000);
+        assert_eq!(ppu.framebuffer[SCREEN_WIDTH + 8], 0x001F);
+
+        ppu.step(ppu_impl::CYCLES_PER_SCANLINE, &vram);
+        assert_eq!(ppu.framebuffer[2 * SCREEN_WIDTH], 0x001F);
+    }
+
+    #[test]
+    fn test_dispstat_and_vcount_write_masks() {
+        let mut ppu = PPU::new();
+        let vram = vec![0u8; 0x18000];
+
+        // VCOUNT is read-only
+        ppu.write_register(VCOUNT, 0x0055);
+        assert_eq!(ppu.read_register(VCOUNT), 0);
+
+        // Status bits 0-2 and unused bits 6-7 ignore writes
+        ppu.write_register(DISPSTAT, 0xFFFF);
+        assert_eq!(ppu.read_register(DISPSTAT), 0xFF38);
+
+        // Live status ORed with the stored settings: VCOUNT match on line 0
+        ppu.write_register(DISPSTAT, 0x0038);
+        assert_eq!(ppu.read_register(DISPSTAT), 0x003C);
+
+        // HBlank flag and V-counter IRQ on line 1
+        ppu.write_register(DISPSTAT, 0x0120);
+        ppu.step(ppu_impl::HDRAW_CYCLES, &vram);
+        assert_eq!(ppu.read_register(DISPSTAT), 0x0122);
+        let result = ppu.step(ppu_impl::CYCLES_PER_SCANLINE - ppu_impl::HDRAW_CYCLES, &vram);
+        assert_eq!(result.irq_flags, ppu_impl::IRQ_VCOUNT);
+        assert_eq!(ppu.read_register(DISPSTAT), 0x0124);
+        assert_eq!(ppu.read_register(VCOUNT), 1);
+    }
+
+    #[test]
+    fn test_vblank_flag_clear_on_last_line() {
+        let mut ppu = PPU::new();
+        let vram = vec![0u8; 0x18000];
+
+        ppu.step(ppu_impl::CYCLES_PER_SCANLINE * 160, &vram);
+        assert_eq!(ppu.read_register(VCOUNT), 160);
+        assert!(ppu.in_vblank());
+        assert_eq!(ppu.read_register(DISPSTAT) & 1, 1);
+
+        ppu.step(ppu_impl::CYCLES_PER_SCANLINE * 66, &vram);
+        assert_eq!(ppu.read_register(VCOUNT), 226);
+        assert!(ppu.in_vblank());
+        assert_eq!(ppu.read_register(DISPSTAT) & 1, 1);
+
+        // Line 227 is still part of the vertical blank period, but not flagged
+        ppu.step(ppu_impl::CYCLES_PER_SCANLINE, &vram);
+        assert_eq!(ppu.read_register(VCOUNT), 227);
+        assert!(!ppu.in_vblank());
+        assert_eq!(ppu.read_register(DISPSTAT) & 1, 0);
+    }
 }
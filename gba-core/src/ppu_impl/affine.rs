@@ -1,23 +1,27 @@
-/// PPU Affine Backgrounds - Rotation/Scaling (Mode 1-2)
-///
-/// Affine backgrounds allow rotation, scaling, and shearing transformations.
-/// Available in:
-/// - Mode 1: BG2 is affine (BG0, BG1 are regular tile backgrounds)
-/// - Mode 2: BG2 and BG3 are both affine
-///
-/// Transformation matrix (2x2):
-/// | PA  PB |   | dx/dx  dy/dx |
-/// | PC  PD | = | dx/dy  dy/dy |
-///
-/// Reference point (X, Y) in background space
-/// Transformed pixel calculation:
-/// screen_x' = PA * (screen_x - ref_x) + PB * (screen_y - ref_y) + bg_x
-/// screen_y' = PC * (screen_x - ref_x) + PD * (screen_y - ref_y) + bg_y
-///
-/// Registers per affine BG:
-/// - BGxPA, BGxPB, BGxPC, BGxPD: Transformation matrix (fixed-point 8.8)
-/// - BGxX, BGxY: Reference point (fixed-point 20.8)
-use super::constants::SCREEN_WIDTH;
+//! PPU Affine Backgrounds - Rotation/Scaling (Mode 1-2)
+//!
+//! Affine backgrounds allow rotation, scaling, and shearing transformations.
+//! Available in:
+//! - Mode 1: BG2 is affine (BG0, BG1 are regular tile backgrounds)
+//! - Mode 2: BG2 and BG3 are both affine
+//!
+//! Transformation matrix (2x2):
+//! | PA  PB |   | dx/dx  dy/dx |
+//! | PC  PD | = | dx/dy  dy/dy |
+//!
+//! Reference point (X, Y) is the background position of the top-left
+//! screen pixel. Transformed pixel calculation:
+//! bg_x = X + PA * screen_x + PB * screen_y
+//! bg_y = Y + PC * screen_x + PD * screen_y
+//!
+//! The hardware doesn't multiply by screen_y: it keeps an internal copy of
+//! X/Y that is reloaded at VBlank (and on every write to BGxX/BGxY) and
+//! advanced by PB/PD after each line. Games rely on this for per-line
+//! effects, so the internal copy is emulated as-is.
+//!
+//! Registers per affine BG:
+//! - BGxPA, BGxPB, BGxPC, BGxPD: Transformation matrix (fixed-point 8.8)
+//! - BGxX, BGxY: Reference point (signed 28-bit, fixed-point 20.8)
 
 /// Affine transformation matrix
 #[derive(Debug, Clone, Copy)]
@@ -42,8 +46,8 @@ impl AffineMatrix {
     /// angle in degrees
     pub fn rotation(angle: f32) -> Self {
         let rad = angle.to_radians();
-        let cos = rad.cos();
-        let sin = rad.sin();
+        let cos = libm::cosf(rad);
+        let sin = libm::sinf(rad);
 
         Self {
             pa: (cos * 256.0) as i16,
@@ -68,8 +72,10 @@ impl AffineMatrix {
 #[derive(Debug, Clone, Copy)]
 pub struct AffineParams {
     pub matrix: AffineMatrix,
-    pub ref_x: i32, // 20.8 fixed-point
-    pub ref_y: i32, // 20.8 fixed-point
+    pub ref_x: i32,     // 20.8 fixed-point (BGxX)
+    pub ref_y: i32,     // 20.8 fixed-point (BGxY)
+    pub current_x: i32, // Internal X of the current line
+    pub current_y: i32, // Internal Y of the current line
 }
 
 impl AffineParams {
@@ -78,32 +84,89 @@ impl AffineParams {
             matrix: AffineMatrix::identity(),
             ref_x: 0,
             ref_y: 0,
+            current_x: 0,
+            current_y: 0,
         }
     }
+
+    /// Write one halfword of BGxX (`upper` = bits 16-27)
+    pub fn write_ref_x(&mut self, value: u16, upper: bool) {
+        self.ref_x = merge_reference(self.ref_x, value, upper);
+        self.current_x = self.ref_x;
+    }
+
+    /// Write one halfword of BGxY (`upper` = bits 16-27)
+    pub fn write_ref_y(&mut self, value: u16, upper: bool) {
+        self.ref_y = merge_reference(self.ref_y, value, upper);
+        self.current_y = self.ref_y;
+    }
+
+    /// Reload the internal reference point (start of frame)
+    pub fn latch(&mut self) {
+        self.current_x = self.ref_x;
+        self.current_y = self.ref_y;
+    }
+
+    /// Advance the internal reference point to the next line
+    pub fn next_line(&mut self) {
+        self.current_x = self.current_x.wrapping_add(self.matrix.pb as i32);
+        self.current_y = self.current_y.wrapping_add(self.matrix.pd as i32);
+    }
 }
 
-/// Transform screen coordinates to background coordinates
-/// Returns (bg_x, bg_y) in 8.8 fixed-point
-#[allow(dead_code)]
-pub fn transform_point(screen_x: i32, screen_y: i32, params: &AffineParams) -> (i32, i32) {
-    // Screen coordinates relative to reference point (center of screen)
-    let dx = (screen_x << 8) - params.ref_x;
-    let dy = (screen_y << 8) - params.ref_y;
+/// Combine a halfword write into a 28-bit reference point (sign-extended)
+fn merge_reference(reference: i32, value: u16, upper: bool) -> i32 {
+    let raw = if upper {
+        (reference as u32 & 0xFFFF) | ((value as u32 & 0x0FFF) << 16)
+    } else {
+        (reference as u32 & !0xFFFF) | value as u32
+    };
+    ((raw << 4) as i32) >> 4
+}
 
-    // Apply transformation matrix
-    let bg_x = (params.matrix.pa as i32 * dx + params.matrix.pb as i32 * dy) >> 8;
-    let bg_y = (params.matrix.pc as i32 * dx + params.matrix.pd as i32 * dy) >> 8;
+/// Transform a pixel of the current line to background coordinates
+/// Returns (bg_x, bg_y) in 8.8 fixed-point
+pub fn transform_point(screen_x: i32, params: &AffineParams) -> (i32, i32) {
+    let bg_x = params.current_x + params.matrix.pa as i32 * screen_x;
+    let bg_y = params.current_y + params.matrix.pc as i32 * screen_x;
 
     (bg_x, bg_y)
 }
 
-/// Render affine background scanline
-#[allow(dead_code)]
+/// Map a pixel of the current line to bitmap coordinates (BG2 in Mode 3/4/5)
+///
+/// Returns None when the transformed point falls outside the
+/// `width` x `height` bitmap and wraparound is disabled.
+pub fn bitmap_point(
+    screen_x: i32,
+    width: usize,
+    height: usize,
+    wraparound: bool,
+    params: &AffineParams,
+) -> Option<(usize, usize)> {
+    let (bg_x_fp, bg_y_fp) = transform_point(screen_x, params);
+    let bg_x = bg_x_fp >> 8;
+    let bg_y = bg_y_fp >> 8;
+
+    if wraparound {
+        return Some((
+            bg_x.rem_euclid(width as i32) as usize,
+            bg_y.rem_euclid(height as i32) as usize,
+        ));
+    }
+    if bg_x < 0 || bg_y < 0 || bg_x >= width as i32 || bg_y >= height as i32 {
+        return None;
+    }
+    Some((bg_x as usize, bg_y as usize))
+}
+
+/// Render one scanline of an affine background into `line`
+///
+/// Transparent pixels (palette index 0, or outside the BG without
+/// wraparound) are left as `None` for the compositor.
 #[allow(clippy::too_many_arguments)]
 pub fn render_affine_scanline(
-    framebuffer: &mut [u16],
-    scanline: usize,
-    width: usize,
+    line: &mut [Option<u16>],
     bg_size: usize, // Background size in pixels (128, 256, 512, 1024)
     wraparound: bool,
     vram: &[u8],
@@ -112,11 +175,9 @@ pub fn render_affine_scanline(
     screen_base: usize,
     params: &AffineParams,
 ) {
-    let line_offset = scanline * SCREEN_WIDTH;
-
-    for x in 0..width {
+    for (x, pixel) in line.iter_mut().enumerate() {
         // Transform screen coordinates to background space
-        let (bg_x_fp, bg_y_fp) = transform_point(x as i32, scanline as i32, params);
+        let (bg_x_fp, bg_y_fp) = transform_point(x as i32, params);
 
         // Convert from fixed-point to integer (8.8 -> integer)
         let bg_x = bg_x_fp >> 8;
@@ -131,7 +192,7 @@ pub fn render_affine_scanline(
         } else {
             // Clipping: out-of-bounds = transparent
             if bg_x < 0 || bg_y < 0 || bg_x >= bg_size as i32 || bg_y >= bg_size as i32 {
-                framebuffer[line_offset + x] = 0; // Transparent
+                *pixel = None;
                 continue;
             }
             (bg_x as usize, bg_y as usize)
@@ -155,16 +216,14 @@ pub fn render_affine_scanline(
         let palette_index = vram.get(tile_addr).copied().unwrap_or(0) as usize;
 
         // Lookup color in palette (256-color mode, BG palette)
-        if palette_index == 0 {
-            framebuffer[line_offset + x] = 0; // Transparent
+        let color_addr = palette_index * 2;
+        *pixel = if palette_index != 0 && color_addr + 1 < 512 {
+            let color_low = palette_ram[color_addr] as u16;
+            let color_high = palette_ram[color_addr + 1] as u16;
+            Some(color_low | (color_high << 8))
         } else {
-            let color_addr = palette_index * 2;
-            if color_addr + 1 < 512 {
-                let color_low = palette_ram[color_addr] as u16;
-                let color_high = palette_ram[color_addr + 1] as u16;
-                framebuffer[line_offset + x] = color_low | (color_high << 8);
-            }
-        }
+            None // Transparent
+        };
     }
 }
 
@@ -172,6 +231,18 @@ pub fn render_affine_scanline(
 mod tests {
     use super::*;
 
+    /// Parameters as the PPU has them when drawing `line` (reference point 0)
+    fn params_at_line(matrix: AffineMatrix, line: i32) -> AffineParams {
+        let mut params = AffineParams {
+            matrix,
+            ..AffineParams::new()
+        };
+        for _ in 0..line {
+            params.next_line();
+        }
+        params
+    }
+
     #[test]
     fn test_identity_matrix() {
         let matrix = AffineMatrix::identity();
@@ -183,13 +254,9 @@ mod tests {
 
     #[test]
     fn test_transform_identity() {
-        let params = AffineParams {
-            matrix: AffineMatrix::identity(),
-            ref_x: 0,
-            ref_y: 0,
-        };
+        let params = params_at_line(AffineMatrix::identity(), 20);
 
-        let (bg_x, bg_y) = transform_point(10, 20, &params);
+        let (bg_x, bg_y) = transform_point(10, &params);
         // Identity: output = input (in 8.8 fixed-point)
         assert_eq!(bg_x, 10 << 8);
         assert_eq!(bg_y, 20 << 8);
@@ -197,13 +264,9 @@ mod tests {
 
     #[test]
     fn test_transform_scale_2x() {
-        let params = AffineParams {
-            matrix: AffineMatrix::scale(2.0, 2.0),
-            ref_x: 0,
-            ref_y: 0,
-        };
+        let params = params_at_line(AffineMatrix::scale(2.0, 2.0), 20);
 
-        let (bg_x, bg_y) = transform_point(10, 20, &params);
+        let (bg_x, bg_y) = transform_point(10, &params);
         // 2x scale: output = input * 2
         assert_eq!(bg_x >> 8, 20);
         assert_eq!(bg_y >> 8, 40);
@@ -211,13 +274,9 @@ mod tests {
 
     #[test]
     fn test_transform_scale_half() {
-        let params = AffineParams {
-            matrix: AffineMatrix::scale(0.5, 0.5),
-            ref_x: 0,
-            ref_y: 0,
-        };
+        let params = params_at_line(AffineMatrix::scale(0.5, 0.5), 200);
 
-        let (bg_x, bg_y) = transform_point(100, 200, &params);
+        let (bg_x, bg_y) = transform_point(100, &params);
         // 0.5x scale: output = input / 2
         assert_eq!(bg_x >> 8, 50);
         assert_eq!(bg_y >> 8, 100);
@@ -225,13 +284,9 @@ mod tests {
 
     #[test]
     fn test_transform_rotation_90() {
-        let params = AffineParams {
-            matrix: AffineMatrix::rotation(90.0),
-            ref_x: 0,
-            ref_y: 0,
-        };
+        let params = params_at_line(AffineMatrix::rotation(90.0), 0);
 
-        let (bg_x, bg_y) = transform_point(10, 0, &params);
+        let (bg_x, bg_y) = transform_point(10, &params);
         // 90° rotation: (10, 0) -> (0, 10) approximately
         assert!((bg_x >> 8).abs() < 2); // Close to 0
         assert!(((bg_y >> 8) - 10).abs() < 2); // Close to 10
@@ -239,7 +294,7 @@ mod tests {
 
     #[test]
     fn test_wraparound() {
-        let mut framebuffer = vec![0u16; 240 * 160];
+        let mut line = vec![None; 240];
         let vram = vec![0u8; 0x18000];
         let palette_ram = vec![0u8; 512];
 
@@ -247,9 +302,7 @@ mod tests {
 
         // Render with wraparound enabled
         render_affine_scanline(
-            &mut framebuffer,
-            0,
-            240,
+            &mut line,
             256,  // BG size
             true, // wraparound
             &vram,
@@ -264,21 +317,18 @@ mod tests {
 
     #[test]
     fn test_clipping() {
-        let mut framebuffer = vec![0u16; 240 * 160];
+        let mut line = vec![Some(0x7FFF); 240];
         let vram = vec![0u8; 0x18000];
         let palette_ram = vec![0u8; 512];
 
         let params = AffineParams {
             matrix: AffineMatrix::scale(10.0, 10.0), // Large scale = out of bounds
-            ref_x: 0,
-            ref_y: 0,
+            ..AffineParams::new()
         };
 
         // Render without wraparound (clipping mode)
         render_affine_scanline(
-            &mut framebuffer,
-            0,
-            240,
+            &mut line,
             256,
             false, // no wraparound
             &vram,
@@ -288,8 +338,8 @@ mod tests {
             &params,
         );
 
-        // Out-of-bounds pixels should be transparent (0)
-        // Most pixels will be out of bounds due to 10x scale
+        // Out-of-bounds pixels are transparent
+        assert!(line.iter().all(Option::is_none));
     }
 
     #[test]
@@ -304,22 +354,64 @@ mod tests {
 
     #[test]
     fn test_reference_point() {
-        // Reference point shifts the transformation center
-        let params1 = AffineParams {
-            matrix: AffineMatrix::identity(),
-            ref_x: 100 << 8,
-            ref_y: 50 << 8,
-        };
+        // The reference point is the background position of screen (0, 0)
+        let mut params = AffineParams::new();
+        params.ref_x = 100 << 8;
+        params.ref_y = 50 << 8;
+        params.latch();
+
+        let (bg_x, bg_y) = transform_point(0, &params);
+        assert_eq!(bg_x >> 8, 100);
+        assert_eq!(bg_y >> 8, 50);
+
+        // Ten lines further down, ten pixels to the right
+        for _ in 0..10 {
+            params.next_line();
+        }
+        let (bg_x2, bg_y2) = transform_point(10, &params);
+        assert_eq!(bg_x2 >> 8, 110);
+        assert_eq!(bg_y2 >> 8, 60);
+    }
 
-        let (bg_x, bg_y) = transform_point(100, 50, &params1);
-        // At reference point, output should be 0
-        assert_eq!(bg_x, 0);
-        assert_eq!(bg_y, 0);
+    #[test]
+    fn test_reference_point_halfword_writes() {
+        let mut params = AffineParams::new();
+
+        // 0x0123456 = 0x1234.56 in 20.8 fixed-point
+        params.write_ref_x(0x3456, false);
+        params.write_ref_x(0x0012, true);
+        assert_eq!(params.ref_x, 0x0012_3456);
+        assert_eq!(params.current_x, 0x0012_3456);
+
+        // Bit 27 is the sign: 0xFFFFF00 = -1.0, upper bits 28-31 ignored
+        params.write_ref_y(0xFF00, false);
+        params.write_ref_y(0xFFFF, true);
+        assert_eq!(params.ref_y, -0x100);
+        assert_eq!(params.current_y, -0x100);
+    }
 
-        let (bg_x2, bg_y2) = transform_point(110, 60, &params1);
-        // Offset from reference
-        assert_eq!(bg_x2 >> 8, 10);
-        assert_eq!(bg_y2 >> 8, 10);
+    #[test]
+    fn test_internal_reference_advances_and_relatches() {
+        let mut params = AffineParams::new();
+        params.matrix.pb = 0x20;
+        params.matrix.pd = 0x180;
+        params.write_ref_x(0x1000, false);
+
+        params.next_line();
+        params.next_line();
+        assert_eq!(params.current_x, 0x1040);
+        assert_eq!(params.current_y, 0x300);
+        // BGxX keeps the written value
+        assert_eq!(params.ref_x, 0x1000);
+
+        // A mid-frame write only reloads the written coordinate
+        params.write_ref_x(0x0800, false);
+        assert_eq!(params.current_x, 0x0800);
+        assert_eq!(params.current_y, 0x300);
+
+        // VBlank reloads both
+        params.latch();
+        assert_eq!(params.current_y, 0);
     }
 
     #[test]
@@ -176,6 +176,18 @@ pub fn brightness_decrease(color: u16, evy: u8) -> u16 {
     (r as u16) | ((g as u16) << 5) | ((b as u16) << 10)
 }
 
+/// Expand an RGB555 color to 8 bits per channel (R, G, B)
+///
+/// Each 5-bit channel is replicated into the low bits so that 0x1F maps
+/// to 0xFF and 0 to 0.
+pub fn rgb555_to_rgb888(color: u16) -> [u8; 3] {
+    let expand = |c: u16| {
+        let c = (c & 0x1F) as u8;
+        (c << 3) | (c >> 2)
+    };
+    [expand(color), expand(color >> 5), expand(color >> 10)]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,103 @@
+/// Color correction - GBA LCD gamma and color mixing
+///
+/// The GBA screen is darker than a PC monitor (gamma ~4.0) and its
+/// subpixels bleed into each other, so games were drawn with oversaturated
+/// colors. Raw RGB555 expansion therefore looks washed out; this filter
+/// maps each color through the commonly used GBA LCD model (byuu/Talarubi)
+/// to approximate what the hardware displays.
+use super::blending::rgb555_to_rgb888;
+use alloc::vec::Vec;
+
+/// Gamma of the GBA LCD
+const LCD_GAMMA: f64 = 4.0;
+
+/// Gamma of the host display
+const OUTPUT_GAMMA: f64 = 2.2;
+
+/// Contribution of (R, G, B) LCD light to each output channel, out of 255
+const COLOR_MIX: [[f64; 3]; 3] = [
+    [255.0, 50.0, 0.0],  // Red
+    [10.0, 230.0, 30.0], // Green
+    [50.0, 10.0, 220.0], // Blue
+];
+
+/// Output scale: the brightest mixed channel is ~280/255
+const OUTPUT_SCALE: f64 = 255.0 * 255.0 / 280.0;
+
+/// Number of RGB555 colors
+const COLOR_COUNT: usize = 0x8000;
+
+/// Lookup table from RGB555 to corrected RGB888
+#[derive(Clone)]
+pub struct ColorCorrection {
+    lut: Vec<[u8; 3]>,
+}
+
+impl ColorCorrection {
+    pub fn new() -> Self {
+        Self {
+            lut: (0..COLOR_COUNT as u16).map(correct_color).collect(),
+        }
+    }
+
+    /// Corrected 8-bit (R, G, B) for an RGB555 color
+    pub fn apply(&self, color: u16) -> [u8; 3] {
+        self.lut[(color & 0x7FFF) as usize]
+    }
+}
+
+impl Default for ColorCorrection {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// RGB888 output, with or without the LCD color correction
+pub fn present_rgb888(color: u16, correction: Option<&ColorCorrection>) -> [u8; 3] {
+    match correction {
+        Some(correction) => correction.apply(color),
+        None => rgb555_to_rgb888(color),
+    }
+}
+
+/// Run one color through the LCD model
+fn correct_color(color: u16) -> [u8; 3] {
+    // Light emitted by each LCD channel (linear)
+    let light = [0, 5, 10].map(|shift| {
+        let c = ((color >> shift) & 0x1F) as f64 / 31.0;
+        libm::pow(c, LCD_GAMMA)
+    });
+
+    COLOR_MIX.map(|mix| {
+        let linear = (mix[0] * light[0] + mix[1] * light[1] + mix[2] * light[2]) / 255.0;
+        libm::round(libm::pow(linear, 1.0 / OUTPUT_GAMMA) * OUTPUT_SCALE).min(255.0) as u8
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_black_stays_black() {
+        let correction = ColorCorrection::new();
+        assert_eq!(correction.apply(0x0000), [0, 0, 0]);
+    }
+
+    #[test]
+    fn test_white_and_red_are_desaturated() {
+        let correction = ColorCorrection::new();
+
+        // White gets a slight blue-ish tint and loses brightness
+        assert_eq!(correction.apply(0x7FFF), [252, 238, 242]);
+
+        // Pure red bleeds into green and blue
+        assert_eq!(correction.apply(0x001F), [232, 53, 111]);
+    }
+
+    #[test]
+    fn test_present_without_correction_is_plain_expansion() {
+        assert_eq!(present_rgb888(0x7FFF, None), [255, 255, 255]);
+        assert_eq!(present_rgb888(0x03E0, None), [0, 255, 0]);
+    }
+}
@@ -5,9 +5,43 @@ pub const SCREEN_HEIGHT: usize = 160;
 
 /// LCD I/O Registers
 pub const DISPCNT: u32 = 0x04000000; // Display Control
+pub const GREENSWAP: u32 = 0x04000002; // Green Swap (undocumented)
 pub const DISPSTAT: u32 = 0x04000004; // Display Status
 pub const VCOUNT: u32 = 0x04000006; // Vertical Counter
 
+/// DISPSTAT status flags and IRQ enables
+pub const DISPSTAT_VBLANK: u16 = 1 << 0;
+pub const DISPSTAT_HBLANK: u16 = 1 << 1;
+pub const DISPSTAT_VCOUNT: u16 = 1 << 2;
+pub const DISPSTAT_VBLANK_IRQ: u16 = 1 << 3;
+pub const DISPSTAT_HBLANK_IRQ: u16 = 1 << 4;
+pub const DISPSTAT_VCOUNT_IRQ: u16 = 1 << 5;
+
+/// DISPSTAT fields writable by the game: IRQ enables and the VCOUNT
+/// setting (bits 8-15); bits 0-2 are live status, bits 6-7 unused
+pub const DISPSTAT_WRITABLE: u16 = 0xFF38;
+
+/// LCD interrupt flags as laid out in IE/IF
+pub const IRQ_VBLANK: u16 = 1 << 0;
+pub const IRQ_HBLANK: u16 = 1 << 1;
+pub const IRQ_VCOUNT: u16 = 1 << 2;
+
+/// DISPCNT bit 3: CGB mode, only settable by BIOS opcodes
+pub const DISPCNT_CGB_MODE: u16 = 1 << 3;
+
+/// DISPCNT bit 7: forced blank (screen white, VRAM free for the CPU)
+pub const DISPCNT_FORCED_BLANK: u16 = 1 << 7;
+
+/// DISPCNT bit 5: H-Blank interval free (OBJ processing stops before HBlank)
+pub const DISPCNT_HBLANK_FREE: u16 = 1 << 5;
+
+/// OBJ rendering cycles per scanline, normal and with H-Blank interval free
+pub const OBJ_CYCLES_PER_LINE: u32 = 1210;
+pub const OBJ_CYCLES_HBLANK_FREE: u32 = 954;
+
+/// DISPCNT bits 8-15: BG0-3, OBJ and window enables
+pub const DISPCNT_LAYERS: u16 = 0xFF00;
+
 /// Background Control Registers (BGxCNT)
 pub const BG0CNT: u32 = 0x04000008;
 pub const BG1CNT: u32 = 0x0400000A;
@@ -64,7 +98,14 @@ pub const OAM_SPRITE_COUNT: usize = 128;
 /// OBJ tiles in VRAM: 0x06010000-0x06017FFF (32KB in Mode 0-2)
 pub const OBJ_TILE_BASE: usize = 0x10000;
 
+/// OBJ tiles in VRAM in bitmap modes: 0x06014000-0x06017FFF (16KB in Mode 3-5)
+pub const OBJ_TILE_BASE_BITMAP: usize = 0x14000;
+
+/// Size of the OBJ tile area; tile addresses wrap inside it
+pub const OBJ_TILE_AREA_SIZE: usize = 0x8000;
+
 /// Timing constants
 pub const CYCLES_PER_SCANLINE: u32 = 1232;
+pub const HDRAW_CYCLES: u32 = 960; // Visible part of a scanline, then HBlank
 pub const SCANLINES_TOTAL: u16 = 228;
 pub const VISIBLE_SCANLINES: u16 = 160;
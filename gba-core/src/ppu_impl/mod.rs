@@ -2,6 +2,7 @@
 /// Modular implementation
 mod affine;
 mod blending;
+mod color_correction;
 mod constants;
 mod mode0;
 mod mode3;
@@ -9,12 +10,32 @@ mod mode4;
 mod mode5;
 mod sprites;
 mod types;
+mod viewer;
 mod windows;
 
+use alloc::vec;
+use alloc::vec::Vec;
+
+pub use blending::rgb555_to_rgb888;
+pub use color_correction::{present_rgb888, ColorCorrection};
 pub use constants::*;
 pub use sprites::SpriteAttribute;
-pub use types::{BgControl, DisplayMode};
+pub use types::{BgControl, DisplayMode, Layer};
+pub use viewer::TILE_VIEWER_WIDTH;
+
+/// Events produced while stepping the PPU
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PpuStepResult {
+    /// Visible scanlines that were drawn and entered HBlank
+    /// (HBlank DMA and video capture run once per line)
+    pub drawn_lines: u32,
+    /// VBlank started (line 160 reached)
+    pub vblank_started: bool,
+    /// LCD interrupts enabled in DISPSTAT, in IE/IF layout
+    pub irq_flags: u16,
+}
 
+#[derive(Clone)]
 pub struct PPU {
     /// Frame buffer (RGB555 format: xBBBBBGGGGGRRRRR)
     pub framebuffer: Vec<u16>,
@@ -22,6 +43,9 @@ pub struct PPU {
     /// Display Control Register (DISPCNT)
     pub dispcnt: u16,
 
+    /// Green Swap (0x04000002, bit 0)
+    pub green_swap: u16,
+
     /// Display Status Register (DISPSTAT)
     pub dispstat: u16,
 
@@ -40,6 +64,11 @@ pub struct PPU {
     /// Background Scroll Y (BG0-BG3)
     pub bg_vofs: [u16; 4],
 
+    /// Scroll values latched when the current line started drawing, so
+    /// writes during the line (HBlank DMA/IRQ) apply from the next one
+    line_hofs: [u16; 4],
+    line_vofs: [u16; 4],
+
     /// Palette RAM (1KB: 512 bytes BG + 512 bytes OBJ)
     pub palette_ram: Vec<u8>,
 
@@ -63,6 +92,10 @@ pub struct PPU {
 
     /// Affine parameters for BG3
     pub bg3_affine: affine::AffineParams,
+
+    /// Debug layer toggles (DISPCNT bits 8-15), ANDed with DISPCNT when
+    /// rendering without changing the register the game sees
+    pub(crate) layer_mask: u16,
 }
 
 impl PPU {
@@ -70,12 +103,15 @@ impl PPU {
         Self {
             framebuffer: vec![0; SCREEN_WIDTH * SCREEN_HEIGHT],
             dispcnt: 0,
+            green_swap: 0,
             dispstat: 0,
             scanline: 0,
             cycles: 0,
             bg_control: [BgControl::default(); 4],
             bg_hofs: [0; 4],
             bg_vofs: [0; 4],
+            line_hofs: [0; 4],
+            line_vofs: [0; 4],
             palette_ram: vec![0; PALETTE_RAM_SIZE],
             oam: vec![0; OAM_SIZE],
             windows: windows::Windows::new(),
@@ -84,14 +120,35 @@ impl PPU {
             brightness_coeff: 0,
             bg2_affine: affine::AffineParams::new(),
             bg3_affine: affine::AffineParams::new(),
+            layer_mask: DISPCNT_LAYERS,
+        }
+    }
+
+    /// Show or hide a layer for debugging, regardless of DISPCNT
+    pub fn set_layer_enabled(&mut self, layer: Layer, enabled: bool) {
+        if enabled {
+            self.layer_mask |= layer.dispcnt_bit();
+        } else {
+            self.layer_mask &= !layer.dispcnt_bit();
         }
     }
 
+    /// Layer not hidden by the debug toggles
+    pub fn layer_enabled(&self, layer: Layer) -> bool {
+        self.layer_mask & layer.dispcnt_bit() != 0
+    }
+
+    /// DISPCNT as used by the renderer, with debug-hidden layers disabled
+    fn render_dispcnt(&self) -> u16 {
+        self.dispcnt & (self.layer_mask | !DISPCNT_LAYERS)
+    }
+
     /// Read I/O register
     pub fn read_register(&self, addr: u32) -> u16 {
         match addr {
             DISPCNT => self.dispcnt,
-            DISPSTAT => self.dispstat | (self.in_vblank() as u16),
+            GREENSWAP => self.green_swap,
+            DISPSTAT => self.dispstat | (self.in_vblank() as u16) | self.vcount_flag(),
             VCOUNT => self.scanline,
             BG0CNT => self.bg_control[0].to_u16(),
             BG1CNT => self.bg_control[1].to_u16(),
@@ -127,11 +184,16 @@ impl PPU {
     pub fn write_register(&mut self, addr: u32, value: u16) {
         match addr {
             DISPCNT => {
-                self.dispcnt = value;
+                // The CGB mode bit can't be changed from game code
+                self.dispcnt = (value & !DISPCNT_CGB_MODE) | (self.dispcnt & DISPCNT_CGB_MODE);
+                self.windows.set_dispcnt(value);
             }
+            GREENSWAP => self.green_swap = value & 1,
             DISPSTAT => {
-                self.dispstat = (self.dispstat & 0x0007) | (value & 0xFFF8);
+                let status = DISPSTAT_VBLANK | DISPSTAT_HBLANK;
+                self.dispstat = (self.dispstat & status) | (value & DISPSTAT_WRITABLE);
             }
+            VCOUNT => {} // Read-only
             BG0CNT => self.bg_control[0] = BgControl::from_u16(value),
             BG1CNT => self.bg_control[1] = BgControl::from_u16(value),
             BG2CNT => self.bg_control[2] = BgControl::from_u16(value),
@@ -148,46 +210,18 @@ impl PPU {
             BG2PB => self.bg2_affine.matrix.pb = value as i16,
             BG2PC => self.bg2_affine.matrix.pc = value as i16,
             BG2PD => self.bg2_affine.matrix.pd = value as i16,
-            0x04000028 => {
-                // BG2X lower 16 bits
-                self.bg2_affine.ref_x = (self.bg2_affine.ref_x & !0xFFFF) | (value as i32 & 0xFFFF);
-            }
-            0x0400002A => {
-                // BG2X upper 12 bits (sign-extended)
-                let upper = ((value as i32) << 20) >> 4;
-                self.bg2_affine.ref_x = (self.bg2_affine.ref_x & 0xFFFF) | upper;
-            }
-            0x0400002C => {
-                // BG2Y lower 16 bits
-                self.bg2_affine.ref_y = (self.bg2_affine.ref_y & !0xFFFF) | (value as i32 & 0xFFFF);
-            }
-            0x0400002E => {
-                // BG2Y upper 12 bits (sign-extended)
-                let upper = ((value as i32) << 20) >> 4;
-                self.bg2_affine.ref_y = (self.bg2_affine.ref_y & 0xFFFF) | upper;
-            }
+            0x04000028 => self.bg2_affine.write_ref_x(value, false),
+            0x0400002A => self.bg2_affine.write_ref_x(value, true),
+            0x0400002C => self.bg2_affine.write_ref_y(value, false),
+            0x0400002E => self.bg2_affine.write_ref_y(value, true),
             BG3PA => self.bg3_affine.matrix.pa = value as i16,
             BG3PB => self.bg3_affine.matrix.pb = value as i16,
             BG3PC => self.bg3_affine.matrix.pc = value as i16,
             BG3PD => self.bg3_affine.matrix.pd = value as i16,
-            0x04000038 => {
-                // BG3X lower 16 bits
-                self.bg3_affine.ref_x = (self.bg3_affine.ref_x & !0xFFFF) | (value as i32 & 0xFFFF);
-            }
-            0x0400003A => {
-                // BG3X upper 12 bits
-                let upper = ((value as i32) << 20) >> 4;
-                self.bg3_affine.ref_x = (self.bg3_affine.ref_x & 0xFFFF) | upper;
-            }
-            0x0400003C => {
-                // BG3Y lower 16 bits
-                self.bg3_affine.ref_y = (self.bg3_affine.ref_y & !0xFFFF) | (value as i32 & 0xFFFF);
-            }
-            0x0400003E => {
-                // BG3Y upper 12 bits
-                let upper = ((value as i32) << 20) >> 4;
-                self.bg3_affine.ref_y = (self.bg3_affine.ref_y & 0xFFFF) | upper;
-            }
+            0x04000038 => self.bg3_affine.write_ref_x(value, false),
+            0x0400003A => self.bg3_affine.write_ref_x(value, true),
+            0x0400003C => self.bg3_affine.write_ref_y(value, false),
+            0x0400003E => self.bg3_affine.write_ref_y(value, true),
             WIN0H => {
                 let (left, right) = windows::WindowBounds::from_horizontal(value);
                 self.windows.win0.left = left;
@@ -238,60 +272,167 @@ impl PPU {
         }
     }
 
-    /// Execute PPU cycles
-    pub fn step(&mut self, cycles: u32, vram: &[u8]) {
-        self.cycles += cycles;
+    /// Start of the OBJ tile area in VRAM for the current display mode
+    pub fn obj_vram_start(&self) -> usize {
+        if self.display_mode().is_bitmap() {
+            OBJ_TILE_BASE_BITMAP
+        } else {
+            OBJ_TILE_BASE
+        }
+    }
+
+    /// OBJ rendering cycles available on each scanline (DISPCNT bit 5)
+    pub fn obj_cycles(&self) -> u32 {
+        if self.dispcnt & DISPCNT_HBLANK_FREE != 0 {
+            OBJ_CYCLES_HBLANK_FREE
+        } else {
+            OBJ_CYCLES_PER_LINE
+        }
+    }
 
-        while self.cycles >= CYCLES_PER_SCANLINE {
-            self.cycles -= CYCLES_PER_SCANLINE;
+    /// Execute PPU cycles, reporting the HBlank/VBlank events crossed
+    pub fn step(&mut self, cycles: u32, vram: &[u8]) -> PpuStepResult {
+        let mut result = PpuStepResult::default();
+        let mut remaining = cycles;
+
+        // Advance phase by phase: draw (0-959), then HBlank (960-1231)
+        while remaining > 0 {
+            // First cycle of the line: the renderer fetches the scroll now
+            if self.cycles == 0 {
+                self.line_hofs = self.bg_hofs;
+                self.line_vofs = self.bg_vofs;
+            }
 
-            // Render scanline if visible
-            if self.scanline < VISIBLE_SCANLINES {
-                self.render_scanline(vram);
+            let phase_end = if self.cycles < HDRAW_CYCLES {
+                HDRAW_CYCLES
+            } else {
+                CYCLES_PER_SCANLINE
+            };
+            let advance = remaining.min(phase_end - self.cycles);
+            self.cycles += advance;
+            remaining -= advance;
+
+            if self.cycles == HDRAW_CYCLES {
+                self.enter_hblank(vram, &mut result);
+            } else if self.cycles >= CYCLES_PER_SCANLINE {
+                self.cycles = 0;
+                self.next_scanline(&mut result);
             }
+        }
+
+        result
+    }
+
+    /// Start of HBlank: the line is drawn and HBlank DMA/IRQ can fire
+    fn enter_hblank(&mut self, vram: &[u8], result: &mut PpuStepResult) {
+        self.dispstat |= DISPSTAT_HBLANK;
+        if self.dispstat & DISPSTAT_HBLANK_IRQ != 0 {
+            result.irq_flags |= IRQ_HBLANK;
+        }
 
-            self.scanline += 1;
+        // Render scanline if visible
+        if self.scanline < VISIBLE_SCANLINES {
+            self.render_scanline(vram);
+            result.drawn_lines += 1;
 
-            if self.scanline >= SCANLINES_TOTAL {
-                self.scanline = 0;
+            // Affine BGs move their internal reference point by PB/PD
+            self.bg2_affine.next_line();
+            self.bg3_affine.next_line();
+        }
+    }
+
+    /// End of HBlank: move to the next line, entering VBlank after line 159
+    fn next_scanline(&mut self, result: &mut PpuStepResult) {
+        self.dispstat &= !DISPSTAT_HBLANK;
+        self.scanline += 1;
+
+        if self.scanline == VISIBLE_SCANLINES {
+            // The reference points are reloaded from BGxX/BGxY at VBlank
+            self.bg2_affine.latch();
+            self.bg3_affine.latch();
+
+            result.vblank_started = true;
+            if self.dispstat & DISPSTAT_VBLANK_IRQ != 0 {
+                result.irq_flags |= IRQ_VBLANK;
             }
+        }
 
-            self.update_dispstat();
+        if self.scanline >= SCANLINES_TOTAL {
+            self.scanline = 0;
+        }
+
+        if self.vcount_flag() != 0 && self.dispstat & DISPSTAT_VCOUNT_IRQ != 0 {
+            result.irq_flags |= IRQ_VCOUNT;
+        }
+
+        self.update_dispstat();
+    }
+
+    /// V-counter match flag: VCOUNT equals the DISPSTAT setting (bits 8-15)
+    fn vcount_flag(&self) -> u16 {
+        if self.scanline == self.dispstat >> 8 {
+            DISPSTAT_VCOUNT
+        } else {
+            0
         }
     }
 
     /// Update DISPSTAT flags
     fn update_dispstat(&mut self) {
         if self.in_vblank() {
-            self.dispstat |= 0x0001;
+            self.dispstat |= DISPSTAT_VBLANK;
         } else {
-            self.dispstat &= !0x0001;
+            self.dispstat &= !DISPSTAT_VBLANK;
         }
     }
 
     /// Check if in VBlank
+    ///
+    /// The flag covers lines 160-226: it is already clear on the last
+    /// line (227), although VCOUNT only wraps to 0 after it.
     pub fn in_vblank(&self) -> bool {
-        self.scanline >= VISIBLE_SCANLINES
+        (VISIBLE_SCANLINES..SCANLINES_TOTAL - 1).contains(&self.scanline)
+    }
+
+    /// The PPU is fetching from VRAM, palette and OAM: a visible line
+    /// before HBlank, with forced blank off
+    pub fn is_drawing(&self) -> bool {
+        self.scanline < VISIBLE_SCANLINES
+            && self.cycles < HDRAW_CYCLES
+            && self.dispcnt & DISPCNT_FORCED_BLANK == 0
     }
 
     /// Render a single scanline
     fn render_scanline(&mut self, vram: &[u8]) {
-        match self.display_mode() {
+        let windows = self.scanline_window_controls(vram);
+        let mode = self.display_mode();
+        let dispcnt = self.render_dispcnt();
+        let backdrop = self.backdrop_color();
+
+        match mode {
             DisplayMode::Mode0 => {
                 mode0::render_mode0_scanline(
                     self.scanline as usize,
                     SCREEN_WIDTH,
-                    self.dispcnt,
+                    dispcnt,
                     &self.bg_control,
-                    &self.bg_hofs,
-                    &self.bg_vofs,
+                    &self.line_hofs,
+                    &self.line_vofs,
                     vram,
                     &self.palette_ram,
                     &mut self.framebuffer,
+                    &windows,
                 );
             }
             DisplayMode::Mode3 => {
-                mode3::render_mode3_scanline(self.scanline, vram, &mut self.framebuffer);
+                mode3::render_mode3_scanline(
+                    self.scanline,
+                    vram,
+                    &mut self.framebuffer,
+                    &self.bg2_affine,
+                    self.bg_control[2].wrap,
+                    backdrop,
+                );
             }
             DisplayMode::Mode4 => {
                 // Bit 4 of DISPCNT = frame select (0 or 1)
@@ -302,6 +443,9 @@ impl PPU {
                     &self.palette_ram,
                     self.scanline as usize,
                     frame_select,
+                    &self.bg2_affine,
+                    self.bg_control[2].wrap,
+                    backdrop,
                 );
             }
             DisplayMode::Mode5 => {
@@ -312,106 +456,157 @@ impl PPU {
                     vram,
                     self.scanline as usize,
                     frame_select,
+                    &self.bg2_affine,
+                    self.bg_control[2].wrap,
+                    backdrop,
                 );
             }
             DisplayMode::Mode1 => {
                 // Mode 1: BG0, BG1 = regular tile, BG2 = affine
                 // For simplicity, render affine BG2 only (most games use this)
                 // TODO: Full Mode0-style layer compositing with BG0/BG1
-                
-                // Clear scanline first
-                let line_start = self.scanline as usize * constants::SCREEN_WIDTH;
-                for i in 0..constants::SCREEN_WIDTH {
-                    self.framebuffer[line_start + i] = 0;
-                }
-
-                // Render BG2 (affine) if enabled (bit 10 of DISPCNT)
-                if (self.dispcnt & (1 << 10)) != 0 {
-                    let bg_size = self.bg_control[2].get_affine_size();
-                    let char_base = (self.bg_control[2].char_base as usize) * 0x4000;
-                    let screen_base = (self.bg_control[2].screen_base as usize) * 0x800;
-                    let wraparound = self.bg_control[2].wrap;
-
-                    affine::render_affine_scanline(
-                        &mut self.framebuffer,
-                        self.scanline as usize,
-                        constants::SCREEN_WIDTH,
-                        bg_size,
-                        wraparound,
-                        vram,
-                        &self.palette_ram,
-                        char_base,
-                        screen_base,
-                        &self.bg2_affine,
-                    );
-                }
+                self.render_affine_bgs(vram, dispcnt, &[2], &windows);
             }
             DisplayMode::Mode2 => {
                 // Mode 2: BG2, BG3 = both affine
-                
-                // Clear scanline first
-                let line_start = self.scanline as usize * constants::SCREEN_WIDTH;
-                for i in 0..constants::SCREEN_WIDTH {
-                    self.framebuffer[line_start + i] = 0;
-                }
-
-                // Render BG3 first if enabled (bit 11, usually lower priority)
-                if (self.dispcnt & (1 << 11)) != 0 {
-                    let bg_size = self.bg_control[3].get_affine_size();
-                    let char_base = (self.bg_control[3].char_base as usize) * 0x4000;
-                    let screen_base = (self.bg_control[3].screen_base as usize) * 0x800;
-                    let wraparound = self.bg_control[3].wrap;
-
-                    affine::render_affine_scanline(
-                        &mut self.framebuffer,
-                        self.scanline as usize,
-                        constants::SCREEN_WIDTH,
-                        bg_size,
-                        wraparound,
-                        vram,
-                        &self.palette_ram,
-                        char_base,
-                        screen_base,
-                        &self.bg3_affine,
-                    );
-                }
-
-                // Render BG2 on top if enabled (bit 10, usually higher priority)
-                if (self.dispcnt & (1 << 10)) != 0 {
-                    let bg_size = self.bg_control[2].get_affine_size();
-                    let char_base = (self.bg_control[2].char_base as usize) * 0x4000;
-                    let screen_base = (self.bg_control[2].screen_base as usize) * 0x800;
-                    let wraparound = self.bg_control[2].wrap;
-
-                    affine::render_affine_scanline(
-                        &mut self.framebuffer,
-                        self.scanline as usize,
-                        constants::SCREEN_WIDTH,
-                        bg_size,
-                        wraparound,
-                        vram,
-                        &self.palette_ram,
-                        char_base,
-                        screen_base,
-                        &self.bg2_affine,
-                    );
-                }
+                self.render_affine_bgs(vram, dispcnt, &[2, 3], &windows);
             }
         }
 
+        // Tiled modes apply the windows per layer while compositing
+        if mode.is_bitmap() {
+            self.apply_bg2_window(&windows);
+        }
+
+        // Bitmap modes always draw BG2: only the debug toggle hides it
+        if mode.is_bitmap() && !self.layer_enabled(Layer::Bg2) {
+            let line_start = self.scanline as usize * SCREEN_WIDTH;
+            self.framebuffer[line_start..line_start + SCREEN_WIDTH].fill(0);
+        }
+
         // Render sprites if enabled (bit 12 of DISPCNT)
-        if (self.dispcnt & (1 << 12)) != 0 {
+        if (dispcnt & (1 << 12)) != 0 {
             sprites::render_sprites_scanline(
                 self.scanline as usize,
                 SCREEN_WIDTH,
                 &self.oam,
                 vram,
+                self.obj_vram_start(),
+                self.obj_cycles(),
                 &self.palette_ram,
                 &mut self.framebuffer,
+                &windows,
+            );
+        }
+
+        if self.green_swap & 1 != 0 {
+            self.apply_green_swap();
+        }
+    }
+
+    /// Green swap: exchange the green component of each pair of pixels
+    /// (0 and 1, 2 and 3, ...) on the current scanline
+    fn apply_green_swap(&mut self) {
+        const GREEN: u16 = 0x1F << 5;
+        let line_start = self.scanline as usize * SCREEN_WIDTH;
+        let line = &mut self.framebuffer[line_start..line_start + SCREEN_WIDTH];
+        for pair in line.chunks_exact_mut(2) {
+            let (left, right) = (pair[0], pair[1]);
+            pair[0] = (left & !GREEN) | (right & GREEN);
+            pair[1] = (right & !GREEN) | (left & GREEN);
+        }
+    }
+
+    /// Window control of each pixel of the current scanline
+    ///
+    /// OBJ window sprites are evaluated first so that `winobj_control`
+    /// applies to their silhouette.
+    fn scanline_window_controls(&self, vram: &[u8]) -> Vec<windows::WindowControl> {
+        let dispcnt = self.render_dispcnt();
+        let mut windows = self.windows.clone();
+        windows.set_dispcnt(dispcnt);
+
+        let obj_window = if windows.winobj_enabled && (dispcnt & (1 << 12)) != 0 {
+            sprites::render_obj_window_scanline(
+                self.scanline as usize,
+                SCREEN_WIDTH,
+                &self.oam,
+                vram,
+                self.obj_vram_start(),
+                self.obj_cycles(),
+            )
+        } else {
+            Vec::new()
+        };
+
+        windows.scanline_controls(self.scanline as u8, SCREEN_WIDTH, &obj_window)
+    }
+
+    /// Composite the affine BGs of modes 1 and 2 over the backdrop
+    ///
+    /// Each pixel shows the first opaque BG allowed by the window, by
+    /// priority and then BG number.
+    fn render_affine_bgs(
+        &mut self,
+        vram: &[u8],
+        dispcnt: u16,
+        bgs: &[usize],
+        windows: &[windows::WindowControl],
+    ) {
+        let mut layers = Vec::with_capacity(bgs.len());
+        for &bg in bgs {
+            if dispcnt & (1 << (8 + bg)) == 0 {
+                continue;
+            }
+            let control = &self.bg_control[bg];
+            let params = if bg == 2 {
+                &self.bg2_affine
+            } else {
+                &self.bg3_affine
+            };
+            let mut line = vec![None; SCREEN_WIDTH];
+            affine::render_affine_scanline(
+                &mut line,
+                control.get_affine_size(),
+                control.wrap,
+                vram,
+                &self.palette_ram,
+                (control.char_base as usize) * 0x4000,
+                (control.screen_base as usize) * 0x800,
+                params,
             );
+            layers.push((bg, control.priority, line));
+        }
+        // Stable sort: on equal priority the lower BG stays in front
+        layers.sort_by_key(|&(_, priority, _)| priority);
+
+        let backdrop = self.backdrop_color();
+        let line_start = self.scanline as usize * SCREEN_WIDTH;
+        for x in 0..SCREEN_WIDTH {
+            let color = layers
+                .iter()
+                .filter(|(bg, _, _)| windows.get(x).is_none_or(|w| w.bg_enabled(*bg)))
+                .find_map(|(_, _, line)| line[x]);
+            self.framebuffer[line_start + x] = color.unwrap_or(backdrop);
+        }
+    }
+
+    /// Hide BG2 where the window disables it (bitmap modes 3, 4, 5)
+    fn apply_bg2_window(&mut self, windows: &[windows::WindowControl]) {
+        let backdrop = self.backdrop_color();
+        let line_start = self.scanline as usize * SCREEN_WIDTH;
+        for (x, control) in windows.iter().enumerate() {
+            if !control.bg2_enable {
+                self.framebuffer[line_start + x] = backdrop;
+            }
         }
     }
 
+    /// Backdrop colour: BG palette entry 0, shown where no layer is drawn
+    fn backdrop_color(&self) -> u16 {
+        self.read_palette_halfword(0)
+    }
+
     /// Read byte from palette RAM
     pub fn read_palette_byte(&self, offset: usize) -> u8 {
         if offset < PALETTE_RAM_SIZE {
@@ -422,9 +617,14 @@ impl PPU {
     }
 
     /// Write byte to palette RAM
+    ///
+    /// Palette RAM sits on a 16-bit bus: an 8-bit write stores the byte
+    /// in both halves of the addressed halfword.
     pub fn write_palette_byte(&mut self, offset: usize, value: u8) {
-        if offset < PALETTE_RAM_SIZE {
-            self.palette_ram[offset] = value;
+        let aligned = offset & !1;
+        if aligned + 1 < PALETTE_RAM_SIZE {
+            self.palette_ram[aligned] = value;
+            self.palette_ram[aligned + 1] = value;
         }
     }
 
@@ -455,11 +655,9 @@ impl PPU {
     }
 
     /// Write byte to OAM
-    pub fn write_oam_byte(&mut self, offset: usize, value: u8) {
-        if offset < OAM_SIZE {
-            self.oam[offset] = value;
-        }
-    }
+    ///
+    /// OAM only accepts 16/32-bit writes: 8-bit writes are ignored.
+    pub fn write_oam_byte(&mut self, _offset: usize, _value: u8) {}
 
     /// Read halfword from OAM
     pub fn read_oam_halfword(&self, offset: usize) -> u16 {
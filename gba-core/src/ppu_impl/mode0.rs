@@ -1,5 +1,8 @@
 use super::constants::*;
 use super::types::BgControl;
+use super::windows::WindowControl;
+use alloc::vec;
+use alloc::vec::Vec;
 
 /// Render scanline in Mode 0 (4 tiled backgrounds)
 #[allow(clippy::too_many_arguments)]
@@ -13,6 +16,7 @@ pub fn render_mode0_scanline(
     vram: &[u8],
     palette_ram: &[u8],
     framebuffer: &mut [u16],
+    windows: &[WindowControl],
 ) {
     // Temporary buffer for pixels of each layer with priority
     // (color_rgb555, priority, has_pixel)
@@ -45,15 +49,20 @@ pub fn render_mode0_scanline(
 
     // Compositing: lower priority = in front
     // For each pixel X, find the layer with lowest priority that has a pixel
+    let backdrop = read_bg_palette(palette_ram, 0);
     for x in 0..screen_width {
-        let mut final_color = 0u16; // Backdrop (black)
+        let mut final_color = backdrop;
         let mut found = false;
 
         // Scan all priorities from 0 to 3
         for priority in 0..=3 {
             // Check each layer for this priority
-            for layer in &layers {
+            for (bg_num, layer) in layers.iter().enumerate() {
                 let (color, layer_priority, has_pixel) = layer[x];
+                // BG hidden by the window at this pixel
+                if windows.get(x).is_some_and(|w| !w.bg_enabled(bg_num)) {
+                    continue;
+                }
                 if has_pixel && layer_priority == priority {
                     final_color = color;
                     found = true;
@@ -141,30 +150,14 @@ fn render_bg_scanline(
 
         // Read pixel from tile data
         let char_base_addr = (bg_control.char_base as usize) * 16384;
-        let palette_index = if bg_control.palette_256 {
-            // 256 colors: 1 byte per pixel, 64 bytes per tile
-            let tile_addr = char_base_addr + tile_num * 64;
-            let pixel_addr = tile_addr + tile_pixel_y * 8 + tile_pixel_x;
-            if pixel_addr >= vram.len() {
-                0
-            } else {
-                vram[pixel_addr] as usize
-            }
-        } else {
-            // 16 colors: 4 bits per pixel, 32 bytes per tile
-            let tile_addr = char_base_addr + tile_num * 32;
-            let pixel_addr = tile_addr + tile_pixel_y * 4 + tile_pixel_x / 2;
-            if pixel_addr >= vram.len() {
-                0
-            } else {
-                let byte = vram[pixel_addr];
-                if tile_pixel_x & 1 == 0 {
-                    (byte & 0xF) as usize
-                } else {
-                    ((byte >> 4) & 0xF) as usize
-                }
-            }
-        };
+        let palette_index = tile_palette_index(
+            vram,
+            char_base_addr,
+            tile_num,
+            tile_pixel_x,
+            tile_pixel_y,
+            bg_control.palette_256,
+        );
 
         // Color 0 = transparent
         if palette_index == 0 {
@@ -185,6 +178,33 @@ fn render_bg_scanline(
     }
 }
 
+/// Read the palette index of a pixel inside a tile
+///
+/// 256 colors: 1 byte per pixel, 64 bytes per tile.
+/// 16 colors: 4 bits per pixel, 32 bytes per tile.
+/// Out-of-range addresses read as 0 (transparent).
+pub(super) fn tile_palette_index(
+    vram: &[u8],
+    char_base_addr: usize,
+    tile_num: usize,
+    x: usize,
+    y: usize,
+    palette_256: bool,
+) -> usize {
+    if palette_256 {
+        let pixel_addr = char_base_addr + tile_num * 64 + y * 8 + x;
+        vram.get(pixel_addr).copied().unwrap_or(0) as usize
+    } else {
+        let pixel_addr = char_base_addr + tile_num * 32 + y * 4 + x / 2;
+        let byte = vram.get(pixel_addr).copied().unwrap_or(0);
+        if x & 1 == 0 {
+            (byte & 0xF) as usize
+        } else {
+            ((byte >> 4) & 0xF) as usize
+        }
+    }
+}
+
 /// Read RGB555 color from BG palette RAM
 fn read_bg_palette(palette_ram: &[u8], index: usize) -> u16 {
     let addr = index * 2;
@@ -1,15 +1,30 @@
-use super::constants::SCREEN_WIDTH;
+use super::affine::{bitmap_point, AffineParams};
+use super::constants::{SCREEN_HEIGHT, SCREEN_WIDTH};
 
 /// Render scanline in Mode 3 (16-bit bitmap)
-pub fn render_mode3_scanline(scanline: u16, vram: &[u8], framebuffer: &mut [u16]) {
+///
+/// BG2 is affine: each screen pixel is mapped into the 240x160 bitmap
+/// through the BG2 matrix and reference point; pixels that fall outside
+/// it are transparent and show the `backdrop` colour.
+pub fn render_mode3_scanline(
+    scanline: u16,
+    vram: &[u8],
+    framebuffer: &mut [u16],
+    params: &AffineParams,
+    wraparound: bool,
+    backdrop: u16,
+) {
     // Mode 3: VRAM is array of u16 (RGB555)
-    // Offset = scanline * width * 2 bytes
     let line = scanline as usize;
-    let offset = line * SCREEN_WIDTH * 2;
 
-    // Copy scanline from VRAM to framebuffer
     for x in 0..SCREEN_WIDTH {
-        let vram_idx = offset + x * 2;
+        let point = bitmap_point(x as i32, SCREEN_WIDTH, SCREEN_HEIGHT, wraparound, params);
+        let Some((bg_x, bg_y)) = point else {
+            // Outside the bitmap: transparent
+            framebuffer[line * SCREEN_WIDTH + x] = backdrop;
+            continue;
+        };
+        let vram_idx = (bg_y * SCREEN_WIDTH + bg_x) * 2;
 
         // Read RGB555 pixel (little endian)
         if vram_idx + 1 < vram.len() {
@@ -21,3 +36,64 @@ pub fn render_mode3_scanline(scanline: u16, vram: &[u8], framebuffer: &mut [u16]
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::super::affine::AffineMatrix;
+    use super::*;
+
+    fn write_pixel(vram: &mut [u8], x: usize, y: usize, color: u16) {
+        let addr = (y * SCREEN_WIDTH + x) * 2;
+        vram[addr..addr + 2].copy_from_slice(&color.to_le_bytes());
+    }
+
+    #[test]
+    fn test_mode3_identity() {
+        let mut framebuffer = vec![0u16; SCREEN_WIDTH * SCREEN_HEIGHT];
+        let mut vram = vec![0u8; 0x18000];
+        write_pixel(&mut vram, 7, 3, 0x7C00);
+
+        // Internal reference point as the PPU leaves it after three lines
+        let params = AffineParams {
+            current_y: 3 << 8,
+            ..AffineParams::new()
+        };
+        render_mode3_scanline(3, &vram, &mut framebuffer, &params, false, 0);
+        assert_eq!(framebuffer[3 * SCREEN_WIDTH + 7], 0x7C00);
+    }
+
+    #[test]
+    fn test_mode3_rotated_90() {
+        let mut framebuffer = vec![0u16; SCREEN_WIDTH * SCREEN_HEIGHT];
+        let mut vram = vec![0u8; 0x18000];
+        write_pixel(&mut vram, 20, 10, 0x03E0);
+        write_pixel(&mut vram, 20, 11, 0x001F);
+
+        // 90 degrees: bitmap (bx, by) = (120 - y, x)
+        let mut params = AffineParams {
+            matrix: AffineMatrix {
+                pa: 0,
+                pb: -0x100,
+                pc: 0x100,
+                pd: 0,
+            },
+            ..AffineParams::new()
+        };
+        params.write_ref_x(120 << 8, false);
+        for _ in 0..100 {
+            params.next_line();
+        }
+
+        // Screen (10, 100) -> bitmap (20, 10), screen (11, 100) -> (20, 11)
+        render_mode3_scanline(100, &vram, &mut framebuffer, &params, false, 0);
+        assert_eq!(framebuffer[100 * SCREEN_WIDTH + 10], 0x03E0);
+        assert_eq!(framebuffer[100 * SCREEN_WIDTH + 11], 0x001F);
+        // Screen (200, 100) -> bitmap (20, 200): clipped
+        assert_eq!(framebuffer[100 * SCREEN_WIDTH + 200], 0);
+
+        // With wraparound bitmap (20, 200) wraps to (20, 40)
+        write_pixel(&mut vram, 20, 40, 0x7FFF);
+        render_mode3_scanline(100, &vram, &mut framebuffer, &params, true, 0);
+        assert_eq!(framebuffer[100 * SCREEN_WIDTH + 200], 0x7FFF);
+    }
+}
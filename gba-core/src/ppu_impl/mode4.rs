@@ -4,15 +4,22 @@
 /// Two frame buffers for page flipping (double buffering).
 /// Frame 0: 0x06000000-0x06009600 (38400 bytes)
 /// Frame 1: 0x0600A000-0x06013600 (38400 bytes)
+use super::affine::{bitmap_point, AffineParams};
 use super::constants::*;
 
 /// Render Mode 4 scanline - 8-bit paletted bitmap
+///
+/// Pixels the BG2 transform maps outside the bitmap show `backdrop`.
+#[allow(clippy::too_many_arguments)]
 pub fn render_mode4_scanline(
     framebuffer: &mut [u16],
     vram: &[u8],
     palette_ram: &[u8],
     scanline: usize,
     frame_select: bool,
+    params: &AffineParams,
+    wraparound: bool,
+    backdrop: u16,
 ) {
     // Page flip: frame 0 or frame 1
     let frame_offset = if frame_select { 0xA000 } else { 0x0000 };
@@ -20,7 +27,14 @@ pub fn render_mode4_scanline(
     let line_offset = scanline * SCREEN_WIDTH;
 
     for x in 0..SCREEN_WIDTH {
-        let vram_addr = frame_offset + (scanline * SCREEN_WIDTH + x);
+        // BG2 affine transform into the 240x160 bitmap
+        let point = bitmap_point(x as i32, SCREEN_WIDTH, SCREEN_HEIGHT, wraparound, params);
+        let Some((bg_x, bg_y)) = point else {
+            // Outside the bitmap: transparent
+            framebuffer[line_offset + x] = backdrop;
+            continue;
+        };
+        let vram_addr = frame_offset + (bg_y * SCREEN_WIDTH + bg_x);
 
         // Read 8-bit palette index from VRAM
         let palette_index = vram.get(vram_addr).copied().unwrap_or(0) as usize;
@@ -59,7 +73,16 @@ mod tests {
         vram[1] = 2; // Pixel 1 = green
         vram[100] = 1; // Pixel 100 = red
 
-        render_mode4_scanline(&mut framebuffer, &vram, &palette_ram, 0, false);
+        render_mode4_scanline(
+            &mut framebuffer,
+            &vram,
+            &palette_ram,
+            0,
+            false,
+            &AffineParams::new(),
+            false,
+            0,
+        );
 
         assert_eq!(framebuffer[0], 0x001F); // Red
         assert_eq!(framebuffer[1], 0x03E0); // Green
@@ -84,12 +107,30 @@ mod tests {
         vram[0xA000] = 1;
 
         // Render frame 0
-        render_mode4_scanline(&mut framebuffer, &vram, &palette_ram, 0, false);
+        render_mode4_scanline(
+            &mut framebuffer,
+            &vram,
+            &palette_ram,
+            0,
+            false,
+            &AffineParams::new(),
+            false,
+            0,
+        );
         assert_eq!(framebuffer[0], 0x7C00); // Blue from frame 0
 
         // Clear and render frame 1
         framebuffer.fill(0);
-        render_mode4_scanline(&mut framebuffer, &vram, &palette_ram, 0, true);
+        render_mode4_scanline(
+            &mut framebuffer,
+            &vram,
+            &palette_ram,
+            0,
+            true,
+            &AffineParams::new(),
+            false,
+            0,
+        );
         assert_eq!(framebuffer[0], 0x7C00); // Blue from frame 1
     }
 
@@ -111,7 +152,16 @@ mod tests {
             vram[x] = (x % 256) as u8;
         }
 
-        render_mode4_scanline(&mut framebuffer, &vram, &palette_ram, 0, false);
+        render_mode4_scanline(
+            &mut framebuffer,
+            &vram,
+            &palette_ram,
+            0,
+            false,
+            &AffineParams::new(),
+            false,
+            0,
+        );
 
         // Verify gradient
         for x in 0..SCREEN_WIDTH {
@@ -134,7 +184,19 @@ mod tests {
         // Pixel at scanline 50, x=100
         vram[50 * SCREEN_WIDTH + 100] = 255;
 
-        render_mode4_scanline(&mut framebuffer, &vram, &palette_ram, 50, false);
+        render_mode4_scanline(
+            &mut framebuffer,
+            &vram,
+            &palette_ram,
+            50,
+            false,
+            &AffineParams {
+                current_y: 50 << 8,
+                ..AffineParams::new()
+            },
+            false,
+            0,
+        );
 
         assert_eq!(framebuffer[50 * SCREEN_WIDTH + 100], 0x7FFF); // White
     }
@@ -5,6 +5,7 @@
 /// Two frame buffers for page flipping (double buffering).
 /// Frame 0: 0x06000000-0x06005000 (40960 bytes = 160*128*2)
 /// Frame 1: 0x0600A000-0x0600F000 (40960 bytes)
+use super::affine::{bitmap_point, AffineParams};
 use super::constants::*;
 
 /// Mode 5 screen dimensions
@@ -12,22 +13,18 @@ pub const MODE5_WIDTH: usize = 160;
 pub const MODE5_HEIGHT: usize = 128;
 
 /// Render Mode 5 scanline - 16-bit RGB bitmap
+///
+/// The border around the 160x128 image and pixels the BG2 transform maps
+/// outside it show `backdrop`.
 pub fn render_mode5_scanline(
     framebuffer: &mut [u16],
     vram: &[u8],
     scanline: usize,
     frame_select: bool,
+    params: &AffineParams,
+    wraparound: bool,
+    backdrop: u16,
 ) {
-    // Only render if within Mode 5 bounds
-    if scanline >= MODE5_HEIGHT {
-        // Fill rest of screen with black
-        let line_offset = scanline * SCREEN_WIDTH;
-        for x in 0..SCREEN_WIDTH {
-            framebuffer[line_offset + x] = 0;
-        }
-        return;
-    }
-
     // Page flip: frame 0 or frame 1
     let frame_offset = if frame_select { 0xA000 } else { 0x0000 };
 
@@ -36,30 +33,32 @@ pub fn render_mode5_scanline(
     // Center 160x128 image on 240x160 screen
     let x_offset = (SCREEN_WIDTH - MODE5_WIDTH) / 2; // 40 pixels border left/right
 
-    // Black borders on left
-    for x in 0..x_offset {
-        framebuffer[line_offset + x] = 0;
-    }
-
-    // Render Mode 5 pixels (160 wide)
-    for x in 0..MODE5_WIDTH {
-        let vram_addr = frame_offset + (scanline * MODE5_WIDTH + x) * 2;
+    // Render the 160x128 bitmap through the BG2 affine transform
+    // (screen coordinates relative to the centered image)
+    for x in 0..SCREEN_WIDTH {
+        let point = bitmap_point(
+            x as i32 - x_offset as i32,
+            MODE5_WIDTH,
+            MODE5_HEIGHT,
+            wraparound,
+            params,
+        );
+        let Some((bg_x, bg_y)) = point else {
+            // Outside the bitmap: transparent
+            framebuffer[line_offset + x] = backdrop;
+            continue;
+        };
+        let vram_addr = frame_offset + (bg_y * MODE5_WIDTH + bg_x) * 2;
 
         // Read 16-bit RGB555 color directly from VRAM
         if vram_addr + 1 < vram.len() {
             let color_low = vram[vram_addr] as u16;
             let color_high = vram[vram_addr + 1] as u16;
-            let rgb555 = color_low | (color_high << 8);
-            framebuffer[line_offset + x_offset + x] = rgb555;
+            framebuffer[line_offset + x] = color_low | (color_high << 8);
         } else {
-            framebuffer[line_offset + x_offset + x] = 0;
+            framebuffer[line_offset + x] = 0;
         }
     }
-
-    // Black borders on right
-    for x in (x_offset + MODE5_WIDTH)..SCREEN_WIDTH {
-        framebuffer[line_offset + x] = 0;
-    }
 }
 
 #[cfg(test)]
@@ -79,7 +78,15 @@ mod tests {
         vram[2] = 0xE0; // Green low byte
         vram[3] = 0x03; // Green high byte
 
-        render_mode5_scanline(&mut framebuffer, &vram, 0, false);
+        render_mode5_scanline(
+            &mut framebuffer,
+            &vram,
+            0,
+            false,
+            &AffineParams::new(),
+            false,
+            0,
+        );
 
         let x_offset = (SCREEN_WIDTH - MODE5_WIDTH) / 2;
         assert_eq!(framebuffer[x_offset], 0x001F); // Red
@@ -104,12 +111,28 @@ mod tests {
         let x_offset = (SCREEN_WIDTH - MODE5_WIDTH) / 2;
 
         // Render frame 0
-        render_mode5_scanline(&mut framebuffer, &vram, 0, false);
+        render_mode5_scanline(
+            &mut framebuffer,
+            &vram,
+            0,
+            false,
+            &AffineParams::new(),
+            false,
+            0,
+        );
         assert_eq!(framebuffer[x_offset], 0x7C00); // Blue
 
         // Clear and render frame 1
         framebuffer.fill(0);
-        render_mode5_scanline(&mut framebuffer, &vram, 0, true);
+        render_mode5_scanline(
+            &mut framebuffer,
+            &vram,
+            0,
+            true,
+            &AffineParams::new(),
+            false,
+            0,
+        );
         assert_eq!(framebuffer[x_offset], 0x7FFF); // White
     }
 
@@ -128,7 +151,15 @@ mod tests {
         }
 
         // Render first scanline
-        render_mode5_scanline(&mut framebuffer, &vram, 0, false);
+        render_mode5_scanline(
+            &mut framebuffer,
+            &vram,
+            0,
+            false,
+            &AffineParams::new(),
+            false,
+            0,
+        );
 
         let x_offset = (SCREEN_WIDTH - MODE5_WIDTH) / 2;
 
@@ -150,7 +181,18 @@ mod tests {
         let vram = vec![0u8; 0x18000];
 
         // Render scanline beyond Mode 5 height (128)
-        render_mode5_scanline(&mut framebuffer, &vram, 150, false);
+        render_mode5_scanline(
+            &mut framebuffer,
+            &vram,
+            150,
+            false,
+            &AffineParams {
+                current_y: 150 << 8,
+                ..AffineParams::new()
+            },
+            false,
+            0,
+        );
 
         // Entire line should be black
         for x in 0..SCREEN_WIDTH {
@@ -185,7 +227,15 @@ mod tests {
             vram[addr + 1] = ((color >> 8) & 0xFF) as u8;
         }
 
-        render_mode5_scanline(&mut framebuffer, &vram, 0, false);
+        render_mode5_scanline(
+            &mut framebuffer,
+            &vram,
+            0,
+            false,
+            &AffineParams::new(),
+            false,
+            0,
+        );
 
         let x_offset = (SCREEN_WIDTH - MODE5_WIDTH) / 2;
 
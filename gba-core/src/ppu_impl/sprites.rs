@@ -1,4 +1,7 @@
 use super::constants::*;
+use super::windows::WindowControl;
+use alloc::vec;
+use alloc::vec::Vec;
 
 /// Sprite Attribute (OAM entry)
 #[derive(Debug, Clone, Copy)]
@@ -78,6 +81,48 @@ impl SpriteAttribute {
         }
     }
 
+    /// Get the on-screen bounding box (width, height)
+    ///
+    /// Double-size affine sprites (obj_mode 3) take twice the space of
+    /// their source graphics.
+    pub fn bounding_size(&self) -> (usize, usize) {
+        let (width, height) = self.get_size();
+        if self.obj_mode == 3 {
+            (width * 2, height * 2)
+        } else {
+            (width, height)
+        }
+    }
+
+    /// Affine sprite (obj_mode 1 or 3): attr1 bits 9-13 select the affine
+    /// parameters instead of the flip flags
+    pub fn is_affine(&self) -> bool {
+        self.obj_mode & 1 != 0
+    }
+
+    /// OBJ unit cycles spent on a scanline covered by this sprite: one per
+    /// pixel, affine sprites two per bounding box pixel plus setup
+    pub fn render_cycles(&self) -> u32 {
+        let (box_width, _) = self.bounding_size();
+        if self.is_affine() {
+            10 + 2 * box_width as u32
+        } else {
+            box_width as u32
+        }
+    }
+
+    /// Row of the bounding box drawn on `scanline`, if the sprite covers it
+    ///
+    /// Y is an 8-bit coordinate, so the box wraps at 256: a box starting
+    /// below the screen (e.g. Y = 250, 32 lines high) straddles the top
+    /// and its last rows are drawn on lines 0-25.
+    pub fn box_row(&self, scanline: usize) -> Option<usize> {
+        let (_, box_height) = self.bounding_size();
+        let row = scanline.wrapping_sub(self.y as usize) & 0xFF;
+
+        (row < box_height).then_some(row)
+    }
+
     /// Check if sprite is visible
     pub fn is_visible(&self) -> bool {
         // obj_mode == 2 means disabled
@@ -105,62 +150,103 @@ impl Default for SpriteAttribute {
     }
 }
 
-/// Render sprites for current scanline
-pub fn render_sprites_scanline(
+/// Sprite GFX mode that defines the OBJ window instead of drawing
+pub const GFX_MODE_OBJ_WINDOW: u8 = 2;
+
+/// Number of OAM entries, counting from index 0, that fit in the
+/// `obj_cycles` budget of `scanline`
+///
+/// The OBJ unit walks OAM in order; once the budget runs out the sprite
+/// being processed and every later one are dropped from the line.
+fn sprites_within_budget(scanline: usize, oam: &[u8], obj_cycles: u32) -> usize {
+    let mut used = 0;
+    for sprite_idx in 0..OAM_SPRITE_COUNT {
+        let offset = sprite_idx * 8;
+        if offset + 6 > oam.len() {
+            break;
+        }
+        let sprite = SpriteAttribute::from_oam_bytes(&oam[offset..offset + 6]);
+        if !sprite.is_visible() || sprite.box_row(scanline).is_none() {
+            continue;
+        }
+
+        used += sprite.render_cycles();
+        if used > obj_cycles {
+            return sprite_idx;
+        }
+    }
+    OAM_SPRITE_COUNT
+}
+
+/// Call `f(sprite_idx, sprite, screen_x, palette_index)` for every opaque
+/// pixel of the sprites on `scanline`, from the highest OAM index to the
+/// lowest
+///
+/// `obj_window` selects the OBJ window sprites (gfx_mode 2) instead of
+/// the normal ones. Tiles below `obj_tile_start` (the first 512 tiles in
+/// bitmap modes, which overlap the frame buffers) are not displayed.
+/// Sprites past the `obj_cycles` budget of the line are skipped.
+#[allow(clippy::too_many_arguments)]
+fn for_each_sprite_pixel(
     scanline: usize,
     screen_width: usize,
     oam: &[u8],
     vram: &[u8],
-    palette_ram: &[u8],
-    framebuffer: &mut [u16],
+    obj_tile_start: usize,
+    obj_cycles: u32,
+    obj_window: bool,
+    mut f: impl FnMut(usize, &SpriteAttribute, usize, usize),
 ) {
-    // Sprite priority buffer (color, priority, has_sprite)
-    let mut sprite_buffer: Vec<(u16, u8, bool)> = vec![(0, 4, false); screen_width];
+    let drawn = sprites_within_budget(scanline, oam, obj_cycles);
 
     // Render sprites in reverse order (higher index = behind)
-    for sprite_idx in (0..OAM_SPRITE_COUNT).rev() {
+    for sprite_idx in (0..drawn).rev() {
         let offset = sprite_idx * 8;
         if offset + 6 > oam.len() {
             continue;
         }
         let sprite = SpriteAttribute::from_oam_bytes(&oam[offset..offset + 6]);
 
-        if !sprite.is_visible() {
+        if !sprite.is_visible() || (sprite.gfx_mode == GFX_MODE_OBJ_WINDOW) != obj_window {
             continue;
         }
 
         let (sprite_width, sprite_height) = sprite.get_size();
-        let sprite_y = sprite.y as usize;
+        let (box_width, box_height) = sprite.bounding_size();
 
         // Check if sprite intersects this scanline
-        let y_in_sprite = if scanline >= sprite_y {
-            scanline.wrapping_sub(sprite_y)
-        } else {
-            // Wrap-around for Y > 160
-            scanline.wrapping_add(256).wrapping_sub(sprite_y)
+        let Some(box_y) = sprite.box_row(scanline) else {
+            continue;
         };
 
+        // Source graphics are centered in the bounding box
+        let y_in_sprite = box_y.wrapping_sub((box_height - sprite_height) / 2);
         if y_in_sprite >= sprite_height {
             continue;
         }
 
         // Apply V-flip
-        let actual_y = if sprite.v_flip {
+        let actual_y = if sprite.v_flip && !sprite.is_affine() {
             sprite_height - 1 - y_in_sprite
         } else {
             y_in_sprite
         };
 
-        // Render each sprite pixel
-        for sprite_x in 0..sprite_width {
-            let screen_x = (sprite.x as usize).wrapping_add(sprite_x) & 0x1FF;
+        // Render each pixel of the bounding box
+        for box_x in 0..box_width {
+            let screen_x = (sprite.x as usize).wrapping_add(box_x) & 0x1FF;
 
             if screen_x >= screen_width {
                 continue;
             }
 
+            let sprite_x = box_x.wrapping_sub((box_width - sprite_width) / 2);
+            if sprite_x >= sprite_width {
+                continue;
+            }
+
             // Apply H-flip
-            let actual_x = if sprite.h_flip {
+            let actual_x = if sprite.h_flip && !sprite.is_affine() {
                 sprite_width - 1 - sprite_x
             } else {
                 sprite_x
@@ -184,30 +270,27 @@ pub fn render_sprites_scanline(
 
             let tile_num = sprite.tile_index as usize + tile_offset;
 
-            // Read pixel from tile in VRAM OBJ
-            let palette_index = if sprite.palette_256 {
+            // Offset of the pixel byte inside the OBJ tile area
+            let tile_byte = if sprite.palette_256 {
                 // 256 colors: 64 bytes per tile
-                let tile_addr = OBJ_TILE_BASE + tile_num * 64;
-                let pixel_addr = tile_addr + pixel_y * 8 + pixel_x;
-                if pixel_addr < vram.len() {
-                    vram[pixel_addr] as usize
-                } else {
-                    0
-                }
+                tile_num * 64 + pixel_y * 8 + pixel_x
             } else {
                 // 16 colors: 32 bytes per tile
-                let tile_addr = OBJ_TILE_BASE + tile_num * 32;
-                let pixel_addr = tile_addr + pixel_y * 4 + pixel_x / 2;
-                if pixel_addr < vram.len() {
-                    let byte = vram[pixel_addr];
-                    if pixel_x & 1 == 0 {
-                        (byte & 0xF) as usize
-                    } else {
-                        ((byte >> 4) & 0xF) as usize
-                    }
-                } else {
-                    0
-                }
+                tile_num * 32 + pixel_y * 4 + pixel_x / 2
+            };
+            let pixel_addr = OBJ_TILE_BASE + tile_byte % OBJ_TILE_AREA_SIZE;
+            if pixel_addr < obj_tile_start || pixel_addr >= vram.len() {
+                continue;
+            }
+
+            // Read pixel from tile in VRAM OBJ
+            let byte = vram[pixel_addr];
+            let palette_index = if sprite.palette_256 {
+                byte as usize
+            } else if pixel_x & 1 == 0 {
+                (byte & 0xF) as usize
+            } else {
+                ((byte >> 4) & 0xF) as usize
             };
 
             // Color 0 = transparent
@@ -215,6 +298,46 @@ pub fn render_sprites_scanline(
                 continue;
             }
 
+            f(sprite_idx, &sprite, screen_x, palette_index);
+        }
+    }
+}
+
+/// Render sprites for current scanline
+///
+/// `windows` holds the window control of each pixel: sprites are hidden
+/// where OBJ display is disabled. `obj_tile_start` is the first VRAM
+/// offset usable for OBJ tiles in the current display mode, `obj_cycles`
+/// the OBJ rendering budget of the line.
+#[allow(clippy::too_many_arguments)]
+pub fn render_sprites_scanline(
+    scanline: usize,
+    screen_width: usize,
+    oam: &[u8],
+    vram: &[u8],
+    obj_tile_start: usize,
+    obj_cycles: u32,
+    palette_ram: &[u8],
+    framebuffer: &mut [u16],
+    windows: &[WindowControl],
+) {
+    // Front sprite of each pixel: (color, (priority, OAM index))
+    let mut sprite_buffer: Vec<Option<(u16, (u8, usize))>> = vec![None; screen_width];
+
+    for_each_sprite_pixel(
+        scanline,
+        screen_width,
+        oam,
+        vram,
+        obj_tile_start,
+        obj_cycles,
+        false,
+        |sprite_idx, sprite, screen_x, palette_index| {
+            // OBJ hidden by the window at this pixel
+            if windows.get(screen_x).is_some_and(|w| !w.obj_enable) {
+                return;
+            }
+
             // Lookup in OBJ palette
             let color = if sprite.palette_256 {
                 // 256 colors
@@ -225,17 +348,17 @@ pub fn render_sprites_scanline(
                 read_obj_palette(palette_ram, palette_offset)
             };
 
-            // Check priority: sprite with lower priority (number) = in front
-            let (_, current_priority, has_sprite) = sprite_buffer[screen_x];
-            if !has_sprite || sprite.priority <= current_priority {
-                sprite_buffer[screen_x] = (color, sprite.priority, true);
+            // Lower priority field wins, then lower OAM index
+            let key = (sprite.priority, sprite_idx);
+            if sprite_buffer[screen_x].is_none_or(|(_, front)| key < front) {
+                sprite_buffer[screen_x] = Some((color, key));
             }
-        }
-    }
+        },
+    );
 
     // Composite sprites onto framebuffer
-    for (x, &(sprite_color, _sprite_priority, has_sprite)) in sprite_buffer.iter().enumerate() {
-        if has_sprite {
+    for (x, front) in sprite_buffer.iter().enumerate() {
+        if let Some((sprite_color, _)) = *front {
             // TODO: Consider BG vs OBJ priority
             // For now sprites always on top of background
             framebuffer[scanline * screen_width + x] = sprite_color;
@@ -243,6 +366,34 @@ pub fn render_sprites_scanline(
     }
 }
 
+/// OBJ window coverage for the current scanline
+///
+/// Opaque pixels of OBJ window sprites (gfx_mode 2) are not drawn: they
+/// mark where `winobj_control` applies.
+pub fn render_obj_window_scanline(
+    scanline: usize,
+    screen_width: usize,
+    oam: &[u8],
+    vram: &[u8],
+    obj_tile_start: usize,
+    obj_cycles: u32,
+) -> Vec<bool> {
+    let mut coverage = vec![false; screen_width];
+    for_each_sprite_pixel(
+        scanline,
+        screen_width,
+        oam,
+        vram,
+        obj_tile_start,
+        obj_cycles,
+        true,
+        |_, _, screen_x, _| {
+            coverage[screen_x] = true;
+        },
+    );
+    coverage
+}
+
 /// Read RGB555 color from OBJ palette
 fn read_obj_palette(palette_ram: &[u8], index: usize) -> u16 {
     let addr = OBJ_PALETTE_OFFSET + index * 2;
@@ -252,3 +403,214 @@ fn read_obj_palette(palette_ram: &[u8], index: usize) -> u16 {
         0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 8x8 16-color sprite at (0, 0) using `tile`
+    fn oam_with_sprite(tile: u16) -> Vec<u8> {
+        let mut oam = vec![0; OAM_SIZE];
+        // Hide every other sprite (disable flag)
+        for sprite in oam.chunks_mut(8).skip(1) {
+            sprite[1] = 0x02;
+        }
+        oam[4..6].copy_from_slice(&tile.to_le_bytes());
+        oam
+    }
+
+    fn render(oam: &[u8], vram: &[u8], obj_tile_start: usize) -> Vec<u16> {
+        render_with_budget(oam, vram, obj_tile_start, OBJ_CYCLES_PER_LINE)
+    }
+
+    fn render_with_budget(
+        oam: &[u8],
+        vram: &[u8],
+        obj_tile_start: usize,
+        obj_cycles: u32,
+    ) -> Vec<u16> {
+        let mut palette_ram = vec![0; PALETTE_RAM_SIZE];
+        palette_ram[OBJ_PALETTE_OFFSET + 2..OBJ_PALETTE_OFFSET + 4]
+            .copy_from_slice(&0x001Fu16.to_le_bytes());
+        palette_ram[OBJ_PALETTE_OFFSET + 4..OBJ_PALETTE_OFFSET + 6]
+            .copy_from_slice(&0x03E0u16.to_le_bytes());
+        let mut framebuffer = vec![0; SCREEN_WIDTH];
+        render_sprites_scanline(
+            0,
+            SCREEN_WIDTH,
+            oam,
+            vram,
+            obj_tile_start,
+            obj_cycles,
+            &palette_ram,
+            &mut framebuffer,
+            &[],
+        );
+        framebuffer
+    }
+
+    #[test]
+    fn test_bitmap_modes_hide_low_obj_tiles() {
+        let mut vram = vec![0; 0x18000];
+        vram[OBJ_TILE_BASE..OBJ_TILE_BASE + 32].fill(0x11);
+        vram[OBJ_TILE_BASE_BITMAP..OBJ_TILE_BASE_BITMAP + 32].fill(0x11);
+
+        // Tile 0 is visible in Mode 0-2 but overlaps the frame buffer in Mode 3-5
+        let oam = oam_with_sprite(0);
+        assert_eq!(render(&oam, &vram, OBJ_TILE_BASE)[0], 0x001F);
+        assert_eq!(render(&oam, &vram, OBJ_TILE_BASE_BITMAP)[0], 0);
+
+        // Tile 512 is the first OBJ tile in bitmap modes
+        let oam = oam_with_sprite(512);
+        assert_eq!(render(&oam, &vram, OBJ_TILE_BASE_BITMAP)[0], 0x001F);
+    }
+
+    #[test]
+    fn test_obj_tiles_wrap_inside_obj_area() {
+        let mut vram = vec![0; 0x18000];
+        vram[OBJ_TILE_BASE..OBJ_TILE_BASE + 32].fill(0x11);
+
+        // 16x8 sprite on tile 1023: its second tile wraps to tile 0
+        let mut oam = oam_with_sprite(1023);
+        oam[1] |= 0x40;
+        let framebuffer = render(&oam, &vram, OBJ_TILE_BASE);
+        assert_eq!(framebuffer[0], 0);
+        assert_eq!(framebuffer[8], 0x001F);
+    }
+
+    #[test]
+    fn test_double_size_sprite_covers_twice_the_lines() {
+        // 16x16 affine sprite at Y = 10, double-size
+        let mut oam = oam_with_sprite(0);
+        oam[0] = 10;
+        oam[1] = 0x03;
+        oam[3] = 0x40;
+        let sprite = SpriteAttribute::from_oam_bytes(&oam[0..6]);
+
+        assert_eq!(sprite.get_size(), (16, 16));
+        assert_eq!(sprite.bounding_size(), (32, 32));
+        let visible: Vec<usize> = (0..160)
+            .filter(|&line| sprite.box_row(line).is_some())
+            .collect();
+        assert_eq!(visible, (10..42).collect::<Vec<_>>());
+
+        // Normal affine sprites keep their size
+        oam[1] = 0x01;
+        let sprite = SpriteAttribute::from_oam_bytes(&oam[0..6]);
+        assert_eq!(sprite.bounding_size(), (16, 16));
+    }
+
+    #[test]
+    fn test_sprite_y_wraps_over_top_of_screen() {
+        let mut vram = vec![0; 0x18000];
+        vram[OBJ_TILE_BASE..].fill(0x11);
+
+        // 32x32 sprite at Y = 250: rows 6-31 are drawn on lines 0-25
+        let mut oam = oam_with_sprite(0);
+        oam[0] = 250;
+        oam[3] = 0x80;
+        let sprite = SpriteAttribute::from_oam_bytes(&oam[0..6]);
+        let visible: Vec<usize> = (0..228)
+            .filter(|&line| sprite.box_row(line).is_some())
+            .collect();
+        assert_eq!(visible, (0..26).collect::<Vec<_>>());
+        assert_eq!(sprite.box_row(0), Some(6));
+        assert_eq!(sprite.box_row(25), Some(31));
+
+        let framebuffer = render(&oam, &vram, OBJ_TILE_BASE);
+        assert!(framebuffer[..32].iter().all(|&color| color == 0x001F));
+        assert_eq!(framebuffer[32], 0);
+
+        // The same box as a double-size 16x16 affine sprite: the source is
+        // centered, so only box rows 8-23 (lines 2-17) hold pixels
+        oam[1] = 0x03;
+        oam[3] = 0x40;
+        let sprite = SpriteAttribute::from_oam_bytes(&oam[0..6]);
+        assert_eq!(sprite.bounding_size(), (32, 32));
+        assert_eq!(sprite.box_row(2), Some(8));
+        assert_eq!(sprite.box_row(25), Some(31));
+        assert_eq!(sprite.box_row(26), None);
+        assert_eq!(render(&oam, &vram, OBJ_TILE_BASE)[8], 0);
+    }
+
+    #[test]
+    fn test_double_size_sprite_draws_source_centered() {
+        let mut vram = vec![0; 0x18000];
+        vram[OBJ_TILE_BASE..OBJ_TILE_BASE + 32].fill(0x11);
+
+        // 8x8 double-size sprite at Y = -4: line 0 is the first source row,
+        // centered at X 4..12
+        let mut oam = oam_with_sprite(0);
+        oam[1] = 0x03;
+        oam[0] = 252;
+        let framebuffer = render(&oam, &vram, OBJ_TILE_BASE);
+        assert_eq!(framebuffer[3], 0);
+        assert_eq!(framebuffer[4], 0x001F);
+        assert_eq!(framebuffer[11], 0x001F);
+        assert_eq!(framebuffer[12], 0);
+    }
+
+    #[test]
+    fn test_obj_cycle_budget_drops_late_sprites() {
+        let mut vram = vec![0; 0x18000];
+        vram[OBJ_TILE_BASE..OBJ_TILE_BASE + 32].fill(0x11);
+
+        // `count` transparent 64x64 sprites ahead of an opaque 8x8 one
+        let oam_with_big_sprites = |count: usize, affine: bool| {
+            let mut oam = oam_with_sprite(0);
+            oam.copy_within(0..8, count * 8);
+            for sprite in oam.chunks_mut(8).take(count) {
+                sprite[0] = 0;
+                sprite[1] = if affine { 0x01 } else { 0x00 };
+                sprite[2] = 100;
+                sprite[3] = 0xC0; // 64x64
+                sprite[4..6].copy_from_slice(&100u16.to_le_bytes());
+            }
+            oam
+        };
+
+        // 17 * 64 + 8 cycles fit in 1210, 19 * 64 don't
+        let oam = oam_with_big_sprites(17, false);
+        assert_eq!(render(&oam, &vram, OBJ_TILE_BASE)[0], 0x001F);
+        let oam = oam_with_big_sprites(19, false);
+        assert_eq!(render(&oam, &vram, OBJ_TILE_BASE)[0], 0);
+
+        // H-Blank interval free leaves a smaller budget
+        let oam = oam_with_big_sprites(17, false);
+        let framebuffer = render_with_budget(&oam, &vram, OBJ_TILE_BASE, OBJ_CYCLES_HBLANK_FREE);
+        assert_eq!(framebuffer[0], 0);
+
+        // Affine sprites cost 10 + 2 per pixel: 9 of them exhaust the budget
+        let oam = oam_with_big_sprites(9, true);
+        assert_eq!(render(&oam, &vram, OBJ_TILE_BASE)[0], 0);
+        let oam = oam_with_big_sprites(9, false);
+        assert_eq!(render(&oam, &vram, OBJ_TILE_BASE)[0], 0x001F);
+    }
+
+    #[test]
+    fn test_sprite_priority_before_oam_index() {
+        let mut vram = vec![0; 0x18000];
+        vram[OBJ_TILE_BASE..OBJ_TILE_BASE + 32].fill(0x11);
+        vram[OBJ_TILE_BASE + 32..OBJ_TILE_BASE + 64].fill(0x22);
+
+        // Sprite 0 (tile 0, red) and sprite 1 (tile 1, green) overlap at (0, 0)
+        let with_priorities = |first: u8, second: u8| {
+            let mut oam = oam_with_sprite(0);
+            oam.copy_within(0..8, 8);
+            oam[12..14].copy_from_slice(&1u16.to_le_bytes());
+            oam[5] |= first << 2;
+            oam[13] |= second << 2;
+            oam
+        };
+
+        // The higher index draws on top when its priority is better
+        let oam = with_priorities(2, 1);
+        assert_eq!(render(&oam, &vram, OBJ_TILE_BASE)[0], 0x03E0);
+
+        // Same priority: the lower OAM index wins
+        let oam = with_priorities(1, 1);
+        assert_eq!(render(&oam, &vram, OBJ_TILE_BASE)[0], 0x001F);
+        let oam = with_priorities(0, 3);
+        assert_eq!(render(&oam, &vram, OBJ_TILE_BASE)[0], 0x001F);
+    }
+}
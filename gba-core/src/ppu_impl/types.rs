@@ -9,6 +9,33 @@ pub enum DisplayMode {
     Mode5 = 5, // Bitmap 160x128, 16-bit color
 }
 
+impl DisplayMode {
+    /// Check if this is a bitmap mode (Mode 3-5)
+    pub fn is_bitmap(&self) -> bool {
+        matches!(self, DisplayMode::Mode3 | DisplayMode::Mode4 | DisplayMode::Mode5)
+    }
+}
+
+/// Layers that can be hidden for debugging (see `PPU::set_layer_enabled`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+    Bg0 = 0,
+    Bg1 = 1,
+    Bg2 = 2,
+    Bg3 = 3,
+    Obj = 4,
+    Win0 = 5,
+    Win1 = 6,
+    WinObj = 7,
+}
+
+impl Layer {
+    /// Enable bit of the layer in DISPCNT (bits 8-15)
+    pub fn dispcnt_bit(self) -> u16 {
+        1 << (8 + self as u16)
+    }
+}
+
 /// Background Control Register
 #[derive(Debug, Clone, Copy, Default)]
 pub struct BgControl {
@@ -0,0 +1,138 @@
+/// Debug viewers: tile sheets and palettes rendered from VRAM/palette RAM
+///
+/// Read-only helpers for tools (tile viewer, palette viewer); they never
+/// touch the framebuffer or PPU state.
+use super::constants::*;
+use super::mode0::tile_palette_index;
+use super::PPU;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Width of a rendered tile block in pixels (32 tiles per row)
+pub const TILE_VIEWER_WIDTH: usize = 256;
+
+/// Size of a character base block in VRAM
+const CHAR_BLOCK_SIZE: usize = 16384;
+
+impl PPU {
+    /// Render all tiles of a char base block as an RGB555 image
+    ///
+    /// The image is `TILE_VIEWER_WIDTH` pixels wide, 32 tiles per row:
+    /// 512 tiles (256x128) in 16-color mode, 256 tiles (256x64) in
+    /// 256-color mode. `palette` selects the 16-color bank and is ignored
+    /// in 256-color mode. Char bases 4-5 (OBJ VRAM) use the OBJ palette.
+    pub fn render_tile_block(
+        &self,
+        char_base: usize,
+        palette: usize,
+        is_256color: bool,
+        vram: &[u8],
+    ) -> Vec<u16> {
+        let tile_count = if is_256color { 256 } else { 512 };
+        let tiles_per_row = TILE_VIEWER_WIDTH / 8;
+        let height = tile_count / tiles_per_row * 8;
+
+        let char_base_addr = char_base * CHAR_BLOCK_SIZE;
+        let palette_base = if char_base_addr >= OBJ_TILE_BASE {
+            OBJ_PALETTE_OFFSET / 2
+        } else {
+            0
+        };
+        let bank = if is_256color { 0 } else { (palette & 0xF) * 16 };
+
+        let mut image = vec![0; TILE_VIEWER_WIDTH * height];
+        for (i, pixel) in image.iter_mut().enumerate() {
+            let (x, y) = (i % TILE_VIEWER_WIDTH, i / TILE_VIEWER_WIDTH);
+            let tile_num = (y / 8) * tiles_per_row + x / 8;
+            let index =
+                tile_palette_index(vram, char_base_addr, tile_num, x % 8, y % 8, is_256color);
+            *pixel = self.palette_color(palette_base + bank + index);
+        }
+        image
+    }
+
+    /// BG palette as 256 RGB555 colors
+    pub fn render_palette(&self) -> [u16; 256] {
+        core::array::from_fn(|i| self.palette_color(i))
+    }
+
+    /// OBJ palette as 256 RGB555 colors
+    pub fn render_obj_palette(&self) -> [u16; 256] {
+        core::array::from_fn(|i| self.palette_color(OBJ_PALETTE_OFFSET / 2 + i))
+    }
+
+    /// RGB555 color at a palette RAM entry (0-511)
+    fn palette_color(&self, entry: usize) -> u16 {
+        self.read_palette_halfword(entry * 2) & 0x7FFF
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ppu_with_palette(colors: &[(usize, u16)]) -> PPU {
+        let mut ppu = PPU::new();
+        for &(entry, color) in colors {
+            ppu.write_palette_halfword(entry * 2, color);
+        }
+        ppu
+    }
+
+    #[test]
+    fn test_render_tile_block_16_colors() {
+        // Bank 1: index 1 = red, index 2 = green
+        let ppu = ppu_with_palette(&[(17, 0x001F), (18, 0x03E0)]);
+
+        // Tile 1 in char base 0: first row alternates index 1 and 2
+        let mut vram = vec![0u8; 0x18000];
+        for byte in &mut vram[32..36] {
+            *byte = 0x21;
+        }
+
+        let image = ppu.render_tile_block(0, 1, false, &vram);
+        assert_eq!(image.len(), TILE_VIEWER_WIDTH * 128);
+
+        // Tile 1 starts at x = 8 in the first tile row
+        assert_eq!(image[8], 0x001F);
+        assert_eq!(image[9], 0x03E0);
+        assert_eq!(image[15], 0x03E0);
+        // Second row of the tile is index 0 (bank 1 entry 0, black)
+        assert_eq!(image[TILE_VIEWER_WIDTH + 8], 0);
+    }
+
+    #[test]
+    fn test_render_tile_block_256_colors() {
+        let ppu = ppu_with_palette(&[(0xAB, 0x7C00)]);
+
+        // Tile 33 (second row, second column) in char base 1
+        let mut vram = vec![0u8; 0x18000];
+        vram[CHAR_BLOCK_SIZE + 33 * 64 + 8 + 3] = 0xAB;
+
+        let image = ppu.render_tile_block(1, 0, true, &vram);
+        assert_eq!(image.len(), TILE_VIEWER_WIDTH * 64);
+        assert_eq!(image[9 * TILE_VIEWER_WIDTH + 8 + 3], 0x7C00);
+        assert_eq!(image[9 * TILE_VIEWER_WIDTH + 8 + 4], 0);
+    }
+
+    #[test]
+    fn test_obj_char_base_uses_obj_palette() {
+        let ppu = ppu_with_palette(&[(1, 0x001F), (256 + 1, 0x7FFF)]);
+
+        let mut vram = vec![0u8; 0x18000];
+        vram[OBJ_TILE_BASE] = 0x01;
+
+        let image = ppu.render_tile_block(4, 0, false, &vram);
+        assert_eq!(image[0], 0x7FFF);
+    }
+
+    #[test]
+    fn test_render_palettes() {
+        let ppu = ppu_with_palette(&[(0, 0x1234), (255, 0x7FFF), (256, 0x03E0)]);
+
+        let bg = ppu.render_palette();
+        assert_eq!(bg[0], 0x1234);
+        assert_eq!(bg[255], 0x7FFF);
+        assert_eq!(ppu.render_obj_palette()[0], 0x03E0);
+    }
+}
@@ -12,6 +12,9 @@
 /// - WININ: Control for inside WIN0/WIN1
 /// - WINOUT: Control for outside windows and OBJ window
 
+use alloc::vec;
+use alloc::vec::Vec;
+
 /// Window control flags (WININ/WINOUT)
 #[derive(Debug, Clone, Copy, Default)]
 pub struct WindowControl {
@@ -35,6 +38,22 @@ impl WindowControl {
         }
     }
 
+    /// Everything visible (used when no window is enabled)
+    pub fn all() -> Self {
+        Self::from_u8(0x3F)
+    }
+
+    /// Check if background `bg` (0-3) is visible
+    pub fn bg_enabled(&self, bg: usize) -> bool {
+        match bg {
+            0 => self.bg0_enable,
+            1 => self.bg1_enable,
+            2 => self.bg2_enable,
+            3 => self.bg3_enable,
+            _ => false,
+        }
+    }
+
     pub fn to_u8(&self) -> u8 {
         (self.bg0_enable as u8)
             | ((self.bg1_enable as u8) << 1)
@@ -99,6 +118,7 @@ impl WindowBounds {
 }
 
 /// Window system state
+#[derive(Clone)]
 pub struct Windows {
     pub win0: WindowBounds,
     pub win1: WindowBounds,
@@ -126,9 +146,43 @@ impl Windows {
         }
     }
 
+    /// Update the enable flags from DISPCNT (bits 13-15)
+    pub fn set_dispcnt(&mut self, dispcnt: u16) {
+        self.win0_enabled = (dispcnt & (1 << 13)) != 0;
+        self.win1_enabled = (dispcnt & (1 << 14)) != 0;
+        self.winobj_enabled = (dispcnt & (1 << 15)) != 0;
+    }
+
+    /// Check if any window is enabled (otherwise everything is visible)
+    pub fn any_enabled(&self) -> bool {
+        self.win0_enabled || self.win1_enabled || self.winobj_enabled
+    }
+
+    /// Window control for each pixel of scanline `y`
+    ///
+    /// `obj_window` is the OBJ window coverage of the scanline
+    /// (see `sprites::render_obj_window_scanline`).
+    pub fn scanline_controls(
+        &self,
+        y: u8,
+        width: usize,
+        obj_window: &[bool],
+    ) -> Vec<WindowControl> {
+        if !self.any_enabled() {
+            return vec![WindowControl::all(); width];
+        }
+
+        (0..width)
+            .map(|x| {
+                let in_obj_window = obj_window.get(x).copied().unwrap_or(false);
+                self.get_control(x as u8, y, in_obj_window)
+            })
+            .collect()
+    }
+
     /// Get the window control for a pixel at (x, y)
     /// Priority: WIN0 > WIN1 > WINOBJ > WINOUT
-    pub fn get_control(&self, x: u8, y: u8, _in_obj_window: bool) -> WindowControl {
+    pub fn get_control(&self, x: u8, y: u8, in_obj_window: bool) -> WindowControl {
         // WIN0 has highest priority
         if self.win0_enabled && self.win0.contains(x, y) {
             return self.win0_control;
@@ -139,10 +193,10 @@ impl Windows {
             return self.win1_control;
         }
 
-        // WINOBJ third priority (TODO: check if pixel is in OBJ window)
-        // if self.winobj_enabled && in_obj_window {
-        //     return self.winobj_control;
-        // }
+        // WINOBJ third priority (pixel covered by an OBJ window sprite)
+        if self.winobj_enabled && in_obj_window {
+            return self.winobj_control;
+        }
 
         // Default: WINOUT (outside all windows)
         self.winout_control
@@ -251,6 +305,136 @@ mod tests {
         assert!(ctrl.bg2_enable);
     }
 
+    #[test]
+    fn test_obj_window_controls() {
+        let mut windows = Windows::new();
+        windows.set_dispcnt(1 << 15);
+        windows.winobj_control.bg0_enable = true;
+        windows.winout_control.bg1_enable = true;
+
+        let obj_window = [false, true, true, false];
+        let controls = windows.scanline_controls(0, 4, &obj_window);
+        assert!(!controls[0].bg0_enable && controls[0].bg1_enable);
+        assert!(controls[1].bg0_enable && !controls[1].bg1_enable);
+        assert!(controls[2].bg0_enable);
+        assert!(!controls[3].bg0_enable);
+
+        // No window enabled: everything visible
+        windows.set_dispcnt(0);
+        let controls = windows.scanline_controls(0, 4, &obj_window);
+        assert!(controls.iter().all(|c| c.to_u8() == 0x3F));
+    }
+
+    #[test]
+    fn test_obj_window_masks_bg0() {
+        use super::super::constants::*;
+        use super::super::PPU;
+
+        let mut ppu = PPU::new();
+        // Mode 0, BG0 + OBJ, OBJ window enabled
+        ppu.write_register(DISPCNT, (1 << 8) | (1 << 12) | (1 << 15));
+        // BG0: char base 1, map at screen base 0 (all tile 0)
+        ppu.write_register(BG0CNT, 0x0004);
+        // Outside: nothing, OBJ window: BG0 only
+        ppu.write_register(WINOUT, 0x0100);
+
+        // BG palette 1 = red, OBJ palette 1 = white
+        ppu.palette_ram[2..4].copy_from_slice(&0x001Fu16.to_le_bytes());
+        ppu.palette_ram[0x202..0x204].copy_from_slice(&0x7FFFu16.to_le_bytes());
+
+        let mut vram = vec![0u8; 0x18000];
+        vram[0x4000..0x4020].fill(0x11);
+        // OBJ tile 0: only the first 4 pixels of each row are opaque
+        for row in 0..8 {
+            vram[OBJ_TILE_BASE + row * 4] = 0x11;
+            vram[OBJ_TILE_BASE + row * 4 + 1] = 0x11;
+        }
+
+        // Sprite 0: OBJ window (gfx_mode 2) at x = 8, the others disabled
+        for sprite in 1..128 {
+            ppu.oam[sprite * 8 + 1] = 0x02;
+        }
+        ppu.oam[0..6].copy_from_slice(&[0x00, 0x08, 0x08, 0x00, 0x00, 0x00]);
+
+        ppu.step(CYCLES_PER_SCANLINE, &vram);
+
+        let line = &ppu.framebuffer[..SCREEN_WIDTH];
+        // BG0 visible only within the sprite silhouette, sprite not drawn
+        assert_eq!(&line[8..12], &[0x001F; 4]);
+        assert_eq!(&line[12..16], &[0; 4]);
+        assert_eq!(line[0], 0);
+        assert_eq!(line[100], 0);
+    }
+
+    #[test]
+    fn test_mode2_windows_per_affine_bg() {
+        use super::super::constants::*;
+        use super::super::PPU;
+
+        let mut ppu = PPU::new();
+        // Mode 2, BG2 + BG3, WIN0 enabled
+        ppu.write_register(DISPCNT, 2 | (1 << 10) | (1 << 11) | (1 << 13));
+        // BG2: priority 1, char base 1; BG3: priority 0, char base 2;
+        // both 128x128 with wraparound and the map (all tile 0) at 0
+        ppu.write_register(BG2CNT, 0x2005);
+        ppu.write_register(BG3CNT, 0x2008);
+        for pa_pd in [BG2PA, BG2PD, BG3PA, BG3PD] {
+            ppu.write_register(pa_pd, 0x0100);
+        }
+        // WIN0 covers x 0-7: inside BG2 only, outside BG3 only
+        ppu.write_register(WIN0H, 0x0008);
+        ppu.write_register(WIN0V, 0x00A0);
+        ppu.write_register(WININ, 0x0004);
+        ppu.write_register(WINOUT, 0x0008);
+
+        // Backdrop blue, BG colour 1 red, BG colour 2 green
+        ppu.palette_ram[0..2].copy_from_slice(&0x7C00u16.to_le_bytes());
+        ppu.palette_ram[2..4].copy_from_slice(&0x001Fu16.to_le_bytes());
+        ppu.palette_ram[4..6].copy_from_slice(&0x03E0u16.to_le_bytes());
+
+        let mut vram = vec![0u8; 0x18000];
+        vram[0x4000..0x4040].fill(1);
+        vram[0x8000..0x8040].fill(2);
+
+        ppu.step(CYCLES_PER_SCANLINE, &vram);
+        let line = &ppu.framebuffer[..SCREEN_WIDTH];
+        // BG2 is behind BG3 but the only layer enabled inside WIN0
+        assert_eq!(&line[..8], &[0x001F; 8]);
+        assert!(line[8..].iter().all(|&c| c == 0x03E0));
+
+        // Outside nothing enabled: the backdrop shows through
+        ppu.write_register(WINOUT, 0x0000);
+        ppu.step(CYCLES_PER_SCANLINE, &vram);
+        let line = &ppu.framebuffer[SCREEN_WIDTH..2 * SCREEN_WIDTH];
+        assert_eq!(&line[..8], &[0x001F; 8]);
+        assert!(line[8..].iter().all(|&c| c == 0x7C00));
+    }
+
+    #[test]
+    fn test_bitmap_window_shows_backdrop() {
+        use super::super::constants::*;
+        use super::super::PPU;
+
+        let mut ppu = PPU::new();
+        // Mode 3, BG2, WIN0 over x 0-7 with BG2 inside only
+        ppu.write_register(DISPCNT, 3 | (1 << 10) | (1 << 13));
+        ppu.write_register(WIN0H, 0x0008);
+        ppu.write_register(WIN0V, 0x00A0);
+        ppu.write_register(WININ, 0x0004);
+        ppu.write_register(WINOUT, 0x0000);
+        ppu.palette_ram[0..2].copy_from_slice(&0x7C00u16.to_le_bytes());
+
+        let mut vram = vec![0u8; 0x18000];
+        for pixel in vram[..SCREEN_WIDTH * 2].chunks_exact_mut(2) {
+            pixel.copy_from_slice(&0x001Fu16.to_le_bytes());
+        }
+
+        ppu.step(CYCLES_PER_SCANLINE, &vram);
+        let line = &ppu.framebuffer[..SCREEN_WIDTH];
+        assert_eq!(&line[..8], &[0x001F; 8]);
+        assert!(line[8..].iter().all(|&c| c == 0x7C00));
+    }
+
     #[test]
     fn test_horizontal_vertical_parsing() {
         // WIN0H = 0x5020 means right=0x20, left=0x50
@@ -0,0 +1,224 @@
+// Rewind Buffer - Ring buffer di save state per il riavvolgimento
+//
+// Ogni N frame viene catturato uno snapshot. Solo lo snapshot più recente
+// è tenuto per intero: quelli precedenti salvano la RAM come differenza
+// (XOR) rispetto allo snapshot successivo, compressa con RLE sugli zeri.
+// Tra due frame vicini cambia poca RAM, quindi ogni entry occupa pochi KB.
+// Oltre al numero di snapshot si può limitare la memoria totale delle delta:
+// superato il limite vengono scartati gli snapshot più vecchi.
+
+use crate::savestate::SaveState;
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+/// Numero di snapshot mantenuti di default
+pub const DEFAULT_REWIND_CAPACITY: usize = 600;
+
+/// Snapshot storico con RAM delta-compressa
+struct RewindEntry {
+    /// Stato senza RAM
+    state: SaveState,
+    /// RAM codificata come XOR rispetto allo snapshot successivo
+    ram_delta: Vec<u8>,
+}
+
+/// Buffer circolare di snapshot per il rewind
+pub struct RewindBuffer {
+    interval: usize,
+    capacity: usize,
+    frames_until_capture: usize,
+    memory_limit: Option<usize>,
+    delta_bytes: usize,
+    head: Option<SaveState>,
+    history: VecDeque<RewindEntry>,
+}
+
+impl RewindBuffer {
+    /// Cattura uno snapshot ogni `interval` frame, fino a `capacity` snapshot
+    pub fn new(interval: usize, capacity: usize) -> Self {
+        Self {
+            interval: interval.max(1),
+            capacity: capacity.max(1),
+            frames_until_capture: 0,
+            memory_limit: None,
+            delta_bytes: 0,
+            head: None,
+            history: VecDeque::new(),
+        }
+    }
+
+    /// Limita la memoria occupata dalle delta (in byte, `None` = nessun limite)
+    pub fn set_memory_limit(&mut self, bytes: Option<usize>) {
+        self.memory_limit = bytes;
+        self.trim();
+    }
+
+    /// Limite di memoria corrente
+    pub fn memory_limit(&self) -> Option<usize> {
+        self.memory_limit
+    }
+
+    /// Avanza di un frame, ritorna true se va catturato uno snapshot
+    pub fn tick(&mut self) -> bool {
+        if self.frames_until_capture == 0 {
+            self.frames_until_capture = self.interval - 1;
+            true
+        } else {
+            self.frames_until_capture -= 1;
+            false
+        }
+    }
+
+    /// Aggiunge uno snapshot
+    pub fn push(&mut self, state: SaveState) {
+        // Il save della cartridge ha cambiato dimensione (EEPROM da 512 byte
+        // a 8 KB, tipo forzato): le delta verso il passato non reggono più
+        if self
+            .head
+            .as_ref()
+            .is_some_and(|head| head.ram().len() != state.ram().len())
+        {
+            self.head = None;
+            self.history.clear();
+            self.delta_bytes = 0;
+        }
+
+        if let Some(mut previous) = self.head.take() {
+            let ram = previous.take_ram();
+            let ram_delta = encode_delta(&ram, state.ram());
+            self.delta_bytes += ram_delta.len();
+            self.history.push_back(RewindEntry {
+                state: previous,
+                ram_delta,
+            });
+        }
+        self.head = Some(state);
+        self.trim();
+    }
+
+    /// Scarta gli snapshot più vecchi oltre la capacità o il limite di memoria.
+    /// Il più vecchio può essere scartato: le delta vanno verso il passato.
+    fn trim(&mut self) {
+        while self.len() > self.capacity
+            || self
+                .memory_limit
+                .is_some_and(|limit| self.delta_bytes > limit)
+        {
+            let Some(entry) = self.history.pop_front() else {
+                break;
+            };
+            self.delta_bytes -= entry.ram_delta.len();
+        }
+    }
+
+    /// Estrae lo snapshot più recente
+    pub fn pop(&mut self) -> Option<SaveState> {
+        let head = self.head.take()?;
+
+        if let Some(entry) = self.history.pop_back() {
+            self.delta_bytes -= entry.ram_delta.len();
+            let mut ram = head.ram().to_vec();
+            apply_delta(&mut ram, &entry.ram_delta);
+            let mut previous = entry.state;
+            previous.set_ram(ram);
+            self.head = Some(previous);
+        }
+
+        // Il prossimo frame ricattura lo stato ripristinato
+        self.frames_until_capture = 0;
+        Some(head)
+    }
+
+    /// Numero di snapshot disponibili
+    pub fn len(&self) -> usize {
+        self.history.len() + usize::from(self.head.is_some())
+    }
+
+    /// Verifica se il buffer è vuoto
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    /// Memoria occupata dalle delta compresse (in byte)
+    pub fn compressed_size(&self) -> usize {
+        self.delta_bytes
+    }
+}
+
+/// Codifica `old XOR new` come sequenza di
+/// [zeri: u32 LE][lunghezza literal: u32 LE][literal...]
+///
+/// I due buffer devono avere la stessa lunghezza.
+pub fn encode_delta(old: &[u8], new: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut i = 0;
+
+    while i < old.len() {
+        let zero_start = i;
+        while i < old.len() && old[i] == new[i] {
+            i += 1;
+        }
+        let zeros = i - zero_start;
+
+        let literal_start = i;
+        while i < old.len() && old[i] != new[i] {
+            i += 1;
+        }
+
+        out.extend_from_slice(&(zeros as u32).to_le_bytes());
+        out.extend_from_slice(&((i - literal_start) as u32).to_le_bytes());
+        out.extend(
+            old[literal_start..i]
+                .iter()
+                .zip(&new[literal_start..i])
+                .map(|(a, b)| a ^ b),
+        );
+    }
+
+    out
+}
+
+/// Applica una delta prodotta da `encode_delta` (XOR in place): applicata
+/// a `new` ricostruisce `old`, e viceversa
+pub fn apply_delta(data: &mut [u8], delta: &[u8]) {
+    let mut pos = 0;
+    let mut i = 0;
+
+    while i + 8 <= delta.len() {
+        let zeros = u32::from_le_bytes([delta[i], delta[i + 1], delta[i + 2], delta[i + 3]]);
+        let literal = u32::from_le_bytes([delta[i + 4], delta[i + 5], delta[i + 6], delta[i + 7]]);
+        i += 8;
+        pos += zeros as usize;
+
+        for byte in &delta[i..i + literal as usize] {
+            data[pos] ^= byte;
+            pos += 1;
+        }
+        i += literal as usize;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delta_roundtrip() {
+        let old = vec![0u8, 1, 2, 3, 4, 5, 6, 7];
+        let mut new = old.clone();
+        new[2] = 0xAA;
+        new[7] = 0x55;
+
+        let delta = encode_delta(&old, &new);
+        let mut restored = new.clone();
+        apply_delta(&mut restored, &delta);
+        assert_eq!(restored, old);
+    }
+
+    #[test]
+    fn test_tick_interval() {
+        let mut rewind = RewindBuffer::new(3, 10);
+        let captures: Vec<bool> = (0..6).map(|_| rewind.tick()).collect();
+        assert_eq!(captures, vec![true, false, false, true, false, false]);
+    }
+}
@@ -0,0 +1,132 @@
+use crate::rewind::{apply_delta, encode_delta, RewindBuffer};
+use crate::GbaEmulator;
+use gba_arm7tdmi::cpu::MemoryBus;
+
+#[test]
+fn test_rewind_disabled() {
+    let mut emu = GbaEmulator::new();
+    emu.run_frame();
+    assert!(!emu.rewind_one());
+}
+
+#[test]
+fn test_rewind_restores_earlier_snapshot() {
+    let mut emu = GbaEmulator::new();
+    emu.enable_rewind(1);
+
+    // Stato dopo ogni frame: registri + un contatore in EWRAM
+    let mut history = Vec::new();
+    for frame in 1..=100u32 {
+        emu.run_frame();
+        emu.bus.write_word(0x0200_0000, frame);
+        history.push((emu.cpu.regs.r, emu.cpu.regs.cpsr));
+    }
+
+    for _ in 0..10 {
+        assert!(emu.rewind_one());
+    }
+
+    // Ogni snapshot è preso all'inizio del frame: 10 rewind -> dopo il frame 90
+    let (regs, cpsr) = history[89];
+    assert_eq!(emu.cpu.regs.r, regs);
+    assert_eq!(emu.cpu.regs.cpsr, cpsr);
+    assert_eq!(emu.bus.read_word(0x0200_0000), 90);
+}
+
+#[test]
+fn test_rewind_then_continue() {
+    let mut emu = GbaEmulator::new();
+    emu.enable_rewind(1);
+
+    for _ in 0..5 {
+        emu.run_frame();
+    }
+    let regs = emu.cpu.regs.r;
+
+    emu.run_frame();
+    assert!(emu.rewind_one());
+    assert_eq!(emu.cpu.regs.r, regs);
+
+    // Dopo un altro frame il rewind torna allo stesso punto
+    emu.run_frame();
+    assert!(emu.rewind_one());
+    assert_eq!(emu.cpu.regs.r, regs);
+}
+
+#[test]
+fn test_delta_of_similar_ram_states() {
+    let mut emu = GbaEmulator::new();
+    emu.run_frame();
+    let old = emu.save_state();
+
+    // Pochi byte cambiati in EWRAM, IWRAM e VRAM
+    emu.run_frame();
+    emu.bus.write_word(0x0200_1000, 0xDEAD_BEEF);
+    emu.bus.write_word(0x0300_0100, 0x1234_5678);
+    emu.bus.write_halfword(0x0600_0000, 0x7FFF);
+    let new = emu.save_state();
+    assert_ne!(old.ram(), new.ram());
+
+    let delta = encode_delta(old.ram(), new.ram());
+    assert!(delta.len() < new.ram().len() / 100);
+
+    // La delta ricostruisce entrambi gli stati
+    let mut restored = new.ram().to_vec();
+    apply_delta(&mut restored, &delta);
+    assert_eq!(restored, old.ram());
+    apply_delta(&mut restored, &delta);
+    assert_eq!(restored, new.ram());
+}
+
+#[test]
+fn test_rewind_memory_limit_drops_oldest() {
+    let mut emu = GbaEmulator::new();
+    let mut rewind = RewindBuffer::new(1, 1000);
+
+    for frame in 0..50u32 {
+        emu.bus.write_word(0x0200_0000 + frame * 0x100, frame);
+        rewind.push(emu.save_state());
+    }
+    assert_eq!(rewind.len(), 50);
+    let full = rewind.compressed_size();
+
+    // Con metà memoria restano circa metà snapshot
+    rewind.set_memory_limit(Some(full / 2));
+    assert!(rewind.compressed_size() <= full / 2);
+    assert!(rewind.len() < 50 && rewind.len() > 1);
+
+    // Il limite vale anche per i nuovi snapshot
+    for frame in 50..100u32 {
+        emu.bus.write_word(0x0200_0000 + frame * 0x100, frame);
+        rewind.push(emu.save_state());
+        assert!(rewind.compressed_size() <= full / 2);
+    }
+
+    // Lo snapshot più recente è ancora disponibile
+    let latest = rewind.pop().unwrap();
+    latest.restore(&mut emu);
+    assert_eq!(emu.bus.read_word(0x0200_0000 + 99 * 0x100), 99);
+}
+
+#[test]
+fn test_rewind_keeps_save_memory() {
+    use crate::save::SaveType;
+
+    let mut emu = GbaEmulator::new();
+    emu.bus.save.force_save_type(SaveType::Sram);
+    let mut rewind = RewindBuffer::new(1, 10);
+
+    for frame in 0..3u8 {
+        emu.bus.write_byte(0x0E00_0010, frame);
+        rewind.push(emu.save_state());
+    }
+    rewind.pop();
+    rewind.pop().unwrap().restore(&mut emu);
+    assert_eq!(emu.bus.read_byte(0x0E00_0010), 1);
+
+    // Un save di dimensione diversa azzera la storia invece delle delta
+    rewind.push(emu.save_state());
+    emu.bus.save.force_save_type(SaveType::Flash64K);
+    rewind.push(emu.save_state());
+    assert_eq!(rewind.len(), 1);
+}
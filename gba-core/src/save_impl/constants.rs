@@ -12,6 +12,10 @@ pub const SRAM_START: u32 = 0x0E000000;
 pub const SRAM_END: u32 = 0x0E00FFFF;
 pub const SRAM_SIZE: usize = 0x10000; // 64 KB max
 
+/// The SRAM/Flash bus mirrors its 64 KB window up to 0x0FFFFFFF
+pub const SAVE_BUS_END: u32 = 0x0FFFFFFF;
+pub const SAVE_BUS_MASK: u32 = 0xFFFF;
+
 pub const FLASH_START: u32 = 0x0E000000;
 pub const FLASH_END: u32 = 0x0E01FFFF;
 pub const FLASH_64K_SIZE: usize = 0x10000;  // 64 KB
@@ -22,6 +26,17 @@ pub const EEPROM_END: u32 = 0x0DFFFFFF;
 pub const EEPROM_512B_SIZE: usize = 0x200;   // 512 bytes
 pub const EEPROM_8K_SIZE: usize = 0x2000;    // 8 KB
 
+/// EEPROM serial commands (first two bits of the stream)
+pub const EEPROM_CMD_READ: u8 = 0b11;
+pub const EEPROM_CMD_WRITE: u8 = 0b10;
+
+/// EEPROM address width: 6 bits on 512-byte chips, 14 bits on 8 KB chips
+pub const EEPROM_512B_ADDRESS_BITS: u32 = 6;
+pub const EEPROM_8K_ADDRESS_BITS: u32 = 14;
+
+/// A read returns 4 dummy bits followed by one 64-bit block
+pub const EEPROM_READ_BITS: u32 = 68;
+
 /// Flash commands
 pub const FLASH_CMD_READ: u8 = 0xFF;
 pub const FLASH_CMD_WRITE_ENABLE: u8 = 0xAA;
@@ -46,3 +61,6 @@ pub const FLASH_SANYO_128K: u16 = 0x1362;
 
 /// Flash sector size (typically 4 KB)
 pub const FLASH_SECTOR_SIZE: usize = 0x1000;
+
+/// Atmel AT29LV512 sector size (128 bytes)
+pub const FLASH_ATMEL_SECTOR_SIZE: usize = 0x80;
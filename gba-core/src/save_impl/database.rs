@@ -0,0 +1,51 @@
+/// Save System - Per-game overrides
+/// Save types for games whose ROM strings are missing or misleading
+use super::types::{FlashChip, SaveType};
+use alloc::string::{String, ToString};
+
+/// Known overrides, keyed on the 4-character game code in the ROM header
+const SAVE_TYPE_OVERRIDES: &[(&str, SaveType)] = &[
+    // Pokémon Ruby / Sapphire / Emerald / FireRed / LeafGreen
+    ("AXVE", SaveType::Flash128K),
+    ("AXPE", SaveType::Flash128K),
+    ("BPEE", SaveType::Flash128K),
+    ("BPRE", SaveType::Flash128K),
+    ("BPGE", SaveType::Flash128K),
+    // Super Mario Advance 4
+    ("AX4E", SaveType::Flash128K),
+    // Advance Wars / Advance Wars 2
+    ("AWRE", SaveType::Flash64K),
+    ("AW2E", SaveType::Flash64K),
+];
+
+/// Games that probe for a specific Flash manufacturer
+const FLASH_CHIP_OVERRIDES: &[(&str, FlashChip)] = &[
+    // Pokémon Ruby / Sapphire / Emerald / FireRed / LeafGreen
+    ("AXVE", FlashChip::Sanyo128K),
+    ("AXPE", FlashChip::Sanyo128K),
+    ("BPEE", FlashChip::Sanyo128K),
+    ("BPRE", FlashChip::Sanyo128K),
+    ("BPGE", FlashChip::Sanyo128K),
+];
+
+/// Look up the save type override for a game code
+pub fn save_type_override(game_code: &str) -> Option<SaveType> {
+    SAVE_TYPE_OVERRIDES
+        .iter()
+        .find(|(code, _)| *code == game_code)
+        .map(|(_, save_type)| *save_type)
+}
+
+/// Look up the Flash chip override for a game code
+pub fn flash_chip_override(game_code: &str) -> Option<FlashChip> {
+    FLASH_CHIP_OVERRIDES
+        .iter()
+        .find(|(code, _)| *code == game_code)
+        .map(|(_, chip)| *chip)
+}
+
+/// Game code from the ROM header (0xAC-0xAF), if the ROM is large enough
+pub fn rom_game_code(rom: &[u8]) -> Option<String> {
+    rom.get(0xAC..0xB0)
+        .map(|code| String::from_utf8_lossy(code).to_string())
+}
@@ -2,6 +2,7 @@
 /// Detect save type from ROM data
 use super::constants::*;
 use super::types::SaveType;
+use alloc::string::String;
 
 /// Detect save type by scanning ROM for identification strings
 pub fn detect_save_type(rom: &[u8]) -> SaveType {
@@ -1,159 +1,168 @@
 /// Save System - EEPROM Implementation
 /// Serial EEPROM (512 bytes or 8 KB)
+use super::constants::*;
 use super::types::SaveType;
+use alloc::vec;
+use alloc::vec::Vec;
 
 /// EEPROM uses a serial protocol with DMA
-/// Simplified implementation for basic functionality
+///
+/// The game sends a whole command with one DMA (bit 0 of each halfword),
+/// then reads back the result. A command is only decoded once the game
+/// starts reading, because its length tells the address width apart:
+/// 9/73 bits for a 6-bit address (512 bytes), 17/81 bits for a 14-bit
+/// address (8 KB). A 14-bit stream on a 512-byte chip upgrades it to 8 KB.
+#[derive(Clone)]
 pub struct Eeprom {
     data: Vec<u8>,
     size: usize,
     address_bits: u32, // 6 bits for 512B, 14 bits for 8KB
-    
-    // Serial state
-    buffer: u64,
-    bit_count: u32,
-    reading: bool,
-    writing: bool,
+
+    // Command bits received since the last decode (most recent in bit 0)
+    command: u128,
+    command_bits: u32,
+
+    // Read in progress: 4 dummy bits then the 64-bit block, MSB first
+    read_data: u64,
+    read_bits: u32,
 }
 
 impl Eeprom {
     pub fn new(save_type: SaveType) -> Self {
         let size = save_type.size();
         let address_bits = match save_type {
-            SaveType::Eeprom512B => 6,  // 64 addresses * 8 bytes = 512 bytes
-            SaveType::Eeprom8K => 14,   // 1024 addresses * 8 bytes = 8 KB
-            _ => 6,
+            SaveType::Eeprom8K => EEPROM_8K_ADDRESS_BITS, // 1024 addresses * 8 bytes = 8 KB
+            _ => EEPROM_512B_ADDRESS_BITS,                // 64 addresses * 8 bytes = 512 bytes
         };
 
         Self {
             data: vec![0xFF; size],
             size,
             address_bits,
-            buffer: 0,
-            bit_count: 0,
-            reading: false,
-            writing: false,
+            command: 0,
+            command_bits: 0,
+            read_data: 0,
+            read_bits: 0,
         }
     }
 
-    /// Process a single bit (DMA-based serial communication)
-    pub fn process_bit(&mut self, bit: bool) -> bool {
-        // Shift bit into buffer
-        self.buffer = (self.buffer << 1) | (bit as u64);
-        self.bit_count += 1;
-
-        if self.reading {
-            // Reading mode: return next bit from read buffer
-            let out_bit = (self.buffer >> 63) != 0;
-            self.buffer <<= 1;
-            
-            if self.bit_count >= 68 { // 4 dummy + 64 data bits
-                self.reading = false;
-                self.bit_count = 0;
-                self.buffer = 0;
-            }
-            
-            out_bit
-        } else if self.writing {
-            // Writing mode: collect bits
-            if self.bit_count >= (2 + self.address_bits + 64) {
-                // Command (2) + Address + Data (64)
-                self.perform_write();
-                self.writing = false;
-                self.bit_count = 0;
-                self.buffer = 0;
-            }
-            true
+    /// Chip size, which can grow to 8 KB after a 14-bit command
+    pub fn save_type(&self) -> SaveType {
+        if self.size == EEPROM_8K_SIZE {
+            SaveType::Eeprom8K
         } else {
-            // Check for command
-            if self.bit_count >= (2 + self.address_bits) {
-                let command = (self.buffer >> (self.address_bits + 62)) & 0x3;
-                
-                match command {
-                    0b11 => {
-                        // Read request
-                        self.perform_read();
-                        self.reading = true;
-                        self.bit_count = 0;
-                    }
-                    0b10 => {
-                        // Write request
-                        self.writing = true;
-                    }
-                    _ => {
-                        // Unknown command
-                        self.bit_count = 0;
-                        self.buffer = 0;
-                    }
-                }
-            }
-            true
+            SaveType::Eeprom512B
         }
     }
 
-    /// Perform read operation
-    fn perform_read(&mut self) {
-        let address = ((self.buffer >> 62) & ((1 << self.address_bits) - 1)) as usize;
-        let byte_addr = address * 8;
-        
-        // Load 8 bytes (64 bits) into buffer
-        let mut data = 0u64;
-        for i in 0..8 {
-            let byte_idx = byte_addr + i;
-            if byte_idx < self.data.len() {
-                data = (data << 8) | (self.data[byte_idx] as u64);
-            } else {
-                data = (data << 8) | 0xFF;
-            }
-        }
-        
-        self.buffer = data;
+    /// Receive one bit of a command
+    pub fn write_bit(&mut self, bit: bool) {
+        // A new command aborts a read the game didn't finish
+        self.read_bits = 0;
+        self.command = (self.command << 1) | bit as u128;
+        self.command_bits += 1;
     }
 
-    /// Perform write operation
-    fn perform_write(&mut self) {
-        let shift_amt = 64;
-        // Extract address from upper bits (avoiding overflow)
-        let address = if shift_amt < 64 {
-            ((self.buffer >> shift_amt) & ((1 << self.address_bits) - 1)) as usize
+    /// Send one bit back: data during a read, otherwise 1 (ready)
+    pub fn read_bit(&mut self) -> bool {
+        if self.command_bits > 0 {
+            self.decode_command();
+        }
+
+        if self.read_bits == 0 {
+            return true;
+        }
+        self.read_bits -= 1;
+        if self.read_bits < 64 {
+            (self.read_data >> self.read_bits) & 1 != 0
         } else {
-            0
+            false // Dummy bits
+        }
+    }
+
+    /// Decode and execute the command received so far
+    fn decode_command(&mut self) {
+        let length = self.command_bits;
+        let command = self.command;
+        self.command = 0;
+        self.command_bits = 0;
+
+        // The stream length gives the address width
+        let address_bits = match length {
+            9 | 73 => EEPROM_512B_ADDRESS_BITS,
+            17 | 81 => EEPROM_8K_ADDRESS_BITS,
+            _ => return, // Truncated or garbage stream
         };
-        let byte_addr = address * 8;
-        
-        // Extract 64 bits of data
-        let data = self.buffer & 0xFFFFFFFFFFFFFFFF;
-        
-        // Write 8 bytes
-        for i in 0..8 {
-            let byte_idx = byte_addr + i;
-            if byte_idx < self.data.len() {
-                self.data[byte_idx] = ((data >> (56 - i * 8)) & 0xFF) as u8;
+        self.address_bits = address_bits;
+        if address_bits == EEPROM_8K_ADDRESS_BITS && self.size < EEPROM_8K_SIZE {
+            self.size = EEPROM_8K_SIZE;
+            self.data.resize(EEPROM_8K_SIZE, 0xFF);
+        }
+
+        let opcode = ((command >> (length - 2)) & 0x3) as u8;
+        let is_read = length == 2 + address_bits + 1;
+        match opcode {
+            EEPROM_CMD_READ if is_read => {
+                let address = (command >> 1) as usize;
+                self.perform_read(address);
+            }
+            EEPROM_CMD_WRITE if !is_read => {
+                let address = (command >> 65) as usize;
+                self.perform_write(address, (command >> 1) as u64);
             }
+            _ => {}
         }
     }
 
+    /// Byte offset of a block (the 8 KB chip ignores the top 4 address bits)
+    fn block_offset(&self, address: usize) -> usize {
+        (address & (self.size / 8 - 1)) * 8
+    }
+
+    /// Perform read operation
+    fn perform_read(&mut self, address: usize) {
+        let byte_addr = self.block_offset(address);
+
+        // Load 8 bytes (64 bits) into the read buffer
+        self.read_data = self.data[byte_addr..byte_addr + 8]
+            .iter()
+            .fold(0u64, |data, &byte| (data << 8) | byte as u64);
+        self.read_bits = EEPROM_READ_BITS;
+    }
+
+    /// Perform write operation
+    fn perform_write(&mut self, address: usize, data: u64) {
+        let byte_addr = self.block_offset(address);
+        self.data[byte_addr..byte_addr + 8].copy_from_slice(&data.to_be_bytes());
+    }
+
     /// Get entire data for saving
     pub fn data(&self) -> &[u8] {
         &self.data
     }
 
+    /// Move the data out, leaving the chip empty
+    pub(crate) fn take_data(&mut self) -> Vec<u8> {
+        core::mem::take(&mut self.data)
+    }
+
     /// Load data from file
     pub fn load_data(&mut self, data: Vec<u8>) {
-        if data.len() == self.size {
-            self.data = data;
-        } else {
-            self.data = data;
-            self.data.resize(self.size, 0xFF);
+        // An 8 KB save file means an 8 KB chip
+        if data.len() > self.size {
+            self.size = EEPROM_8K_SIZE;
+            self.address_bits = EEPROM_8K_ADDRESS_BITS;
         }
+        self.data = data;
+        self.data.resize(self.size, 0xFF);
     }
 
     /// Reset EEPROM state
     pub fn reset(&mut self) {
-        self.buffer = 0;
-        self.bit_count = 0;
-        self.reading = false;
-        self.writing = false;
+        self.command = 0;
+        self.command_bits = 0;
+        self.read_data = 0;
+        self.read_bits = 0;
     }
 }
 
@@ -161,6 +170,21 @@ impl Eeprom {
 mod tests {
     use super::*;
 
+    /// Send a command stream, MSB first
+    fn send(eeprom: &mut Eeprom, value: u128, bits: u32) {
+        for i in (0..bits).rev() {
+            eeprom.write_bit((value >> i) & 1 != 0);
+        }
+    }
+
+    /// Read back a 64-bit block, skipping the 4 dummy bits
+    fn receive(eeprom: &mut Eeprom) -> u64 {
+        for _ in 0..4 {
+            assert!(!eeprom.read_bit());
+        }
+        (0..64).fold(0, |data, _| (data << 1) | eeprom.read_bit() as u64)
+    }
+
     #[test]
     fn test_eeprom_basic() {
         let eeprom = Eeprom::new(SaveType::Eeprom512B);
@@ -191,4 +215,38 @@ mod tests {
         assert_eq!(eeprom.data[0], 0x42);
         assert_eq!(eeprom.data[511], 0x42);
     }
+
+    #[test]
+    fn test_eeprom_write_then_read_6bit() {
+        let mut eeprom = Eeprom::new(SaveType::Eeprom512B);
+
+        // Write: 10, address 3, data, stop bit
+        let data = 0x0123_4567_89AB_CDEFu128;
+        send(&mut eeprom, (0b10 << 71) | (3 << 65) | (data << 1), 73);
+        assert!(eeprom.read_bit());
+        assert_eq!(&eeprom.data[24..32], &0x0123_4567_89AB_CDEFu64.to_be_bytes());
+
+        // Read: 11, address 3, stop bit
+        send(&mut eeprom, (0b11 << 7) | (3 << 1), 9);
+        assert_eq!(receive(&mut eeprom), 0x0123_4567_89AB_CDEF);
+        assert_eq!(eeprom.save_type(), SaveType::Eeprom512B);
+
+        // Read finished: the chip reports ready
+        assert!(eeprom.read_bit());
+    }
+
+    #[test]
+    fn test_eeprom_14bit_stream_upgrades_to_8k() {
+        let mut eeprom = Eeprom::new(SaveType::Eeprom512B);
+
+        // Write: 10, address 0x3FF (last 8 KB block), data, stop bit
+        let data = 0xDEAD_BEEF_CAFE_F00Du128;
+        send(&mut eeprom, (0b10 << 79) | (0x3FF << 65) | (data << 1), 81);
+        assert!(eeprom.read_bit());
+        assert_eq!(eeprom.save_type(), SaveType::Eeprom8K);
+        assert_eq!(eeprom.data().len(), 8192);
+
+        send(&mut eeprom, (0b11 << 15) | (0x3FF << 1), 17);
+        assert_eq!(receive(&mut eeprom), 0xDEAD_BEEF_CAFE_F00D);
+    }
 }
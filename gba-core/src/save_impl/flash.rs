@@ -1,44 +1,53 @@
 /// Save System - Flash Memory Implementation
 /// 64 KB or 128 KB Flash with sector erase
 use super::constants::*;
-use super::types::{FlashState, SaveType};
+use super::types::{FlashChip, FlashState, SaveType};
+use alloc::vec;
+use alloc::vec::Vec;
 
+#[derive(Clone)]
 pub struct Flash {
     data: Vec<u8>,
     size: usize,
     state: FlashState,
     bank: u8,          // Current bank (0 or 1 for 128K)
-    chip_id: u16,      // Chip identification
+    chip: FlashChip,   // Chip identification
     write_enable: bool,
 }
 
 impl Flash {
     pub fn new(save_type: SaveType) -> Self {
-        let size = save_type.size();
-        let chip_id = match save_type {
-            SaveType::Flash64K => FLASH_MACRONIX_64K,
-            SaveType::Flash128K => FLASH_MACRONIX_128K,
-            _ => 0,
-        };
+        Self::with_chip(FlashChip::default_for(save_type))
+    }
+
+    /// Create Flash emulating a specific chip
+    pub fn with_chip(chip: FlashChip) -> Self {
+        let size = chip.save_type().size();
 
         Self {
             data: vec![0xFF; size],
             size,
             state: FlashState::Ready,
             bank: 0,
-            chip_id,
+            chip,
             write_enable: false,
         }
     }
 
+    /// Emulated chip
+    pub fn chip(&self) -> FlashChip {
+        self.chip
+    }
+
     /// Read byte from Flash
     pub fn read_byte(&self, offset: u32) -> u8 {
         match self.state {
             FlashState::ChipId => {
                 // Return chip ID bytes
+                let chip_id = self.chip.id();
                 match offset & 0x1 {
-                    0 => (self.chip_id & 0xFF) as u8,
-                    1 => (self.chip_id >> 8) as u8,
+                    0 => (chip_id & 0xFF) as u8,
+                    1 => (chip_id >> 8) as u8,
                     _ => 0xFF,
                 }
             }
@@ -101,15 +110,16 @@ impl Flash {
                 }
             }
             FlashState::Erase => {
-                // Erase 4KB sector
-                let sector = ((offset as usize) / FLASH_SECTOR_SIZE) * FLASH_SECTOR_SIZE;
+                // Erase sector (4KB, 128 bytes on Atmel)
+                let sector_size = self.chip.sector_size();
+                let sector = ((offset as usize) / sector_size) * sector_size;
                 let bank_offset = if self.size > FLASH_64K_SIZE {
                     (self.bank as usize) * FLASH_64K_SIZE
                 } else {
                     0
                 };
                 let start = (sector + bank_offset) & (self.size - 1);
-                let end = (start + FLASH_SECTOR_SIZE).min(self.data.len());
+                let end = (start + sector_size).min(self.data.len());
                 self.data[start..end].fill(0xFF);
                 self.state = FlashState::Ready;
             }
@@ -147,6 +157,11 @@ impl Flash {
         &self.data
     }
 
+    /// Move the data out, leaving the chip empty
+    pub(crate) fn take_data(&mut self) -> Vec<u8> {
+        core::mem::take(&mut self.data)
+    }
+
     /// Load data from file
     pub fn load_data(&mut self, data: Vec<u8>) {
         if data.len() == self.size {
@@ -259,4 +274,51 @@ mod tests {
         // Read from bank 0
         assert_eq!(flash.read_byte(0), 0x11);
     }
+
+    fn read_chip_id(flash: &mut Flash) -> u16 {
+        flash.write_byte(FLASH_ADDR_CMD1, FLASH_CMD_WRITE_ENABLE);
+        flash.write_byte(FLASH_ADDR_CMD2, FLASH_CMD_WRITE_DISABLE);
+        flash.write_byte(FLASH_ADDR_CMD1, FLASH_CMD_ENTER_ID);
+        let chip_id = (flash.read_byte(1) as u16) << 8 | flash.read_byte(0) as u16;
+        flash.write_byte(0, FLASH_CMD_EXIT_ID);
+        chip_id
+    }
+
+    #[test]
+    fn test_flash_chip_ids() {
+        let chips = [
+            (FlashChip::Macronix64K, FLASH_MACRONIX_64K, 0x10000),
+            (FlashChip::Panasonic64K, FLASH_PANASONIC_64K, 0x10000),
+            (FlashChip::Atmel64K, FLASH_ATMEL_64K, 0x10000),
+            (FlashChip::Macronix128K, FLASH_MACRONIX_128K, 0x20000),
+            (FlashChip::Sanyo128K, FLASH_SANYO_128K, 0x20000),
+        ];
+
+        for (chip, id, size) in chips {
+            let mut flash = Flash::with_chip(chip);
+            assert_eq!(read_chip_id(&mut flash), id, "{:?}", chip);
+            assert_eq!(flash.data().len(), size, "{:?}", chip);
+            // After leaving ID mode reads hit the memory again
+            assert_eq!(flash.read_byte(0), 0xFF);
+        }
+    }
+
+    #[test]
+    fn test_atmel_erases_128_byte_sectors() {
+        let mut flash = Flash::with_chip(FlashChip::Atmel64K);
+        for addr in [0x00, 0x80] {
+            flash.write_byte(FLASH_ADDR_CMD1, FLASH_CMD_WRITE_ENABLE);
+            flash.write_byte(FLASH_ADDR_CMD2, FLASH_CMD_WRITE_DISABLE);
+            flash.write_byte(FLASH_ADDR_CMD1, FLASH_CMD_WRITE_BYTE);
+            flash.write_byte(addr, 0x42);
+        }
+
+        flash.write_byte(FLASH_ADDR_CMD1, FLASH_CMD_WRITE_ENABLE);
+        flash.write_byte(FLASH_ADDR_CMD2, FLASH_CMD_WRITE_DISABLE);
+        flash.write_byte(FLASH_ADDR_CMD1, FLASH_CMD_ERASE_SECTOR);
+        flash.write_byte(0x00, 0x30);
+
+        assert_eq!(flash.read_byte(0x00), 0xFF);
+        assert_eq!(flash.read_byte(0x80), 0x42);
+    }
 }
@@ -1,6 +1,7 @@
 /// Save System - Main Module
-/// Unified save system with file persistence
+/// Unified save system with file persistence (`std` feature)
 mod constants;
+mod database;
 mod detection;
 pub mod eeprom;
 pub mod flash;
@@ -8,17 +9,23 @@ pub mod sram;
 mod types;
 
 pub use constants::*;
+pub use database::*;
 pub use detection::*;
-pub use types::{SaveMetadata, SaveType};
+pub use types::{FlashChip, SaveFileError, SaveMetadata, SaveType};
 
+use alloc::vec::Vec;
 use eeprom::Eeprom;
 use flash::Flash;
 use sram::Sram;
+#[cfg(feature = "std")]
 use std::fs;
+#[cfg(feature = "std")]
 use std::io;
+#[cfg(feature = "std")]
 use std::path::{Path, PathBuf};
 
 /// Main Save controller
+#[derive(Clone)]
 pub struct SaveController {
     save_type: SaveType,
     metadata: SaveMetadata,
@@ -27,6 +34,8 @@ pub struct SaveController {
     sram: Option<Sram>,
     flash: Option<Flash>,
     eeprom: Option<Eeprom>,
+    // Flash chip override (None = default chip for the save type)
+    flash_chip: Option<FlashChip>,
     
     // Modified flag for auto-save
     modified: bool,
@@ -41,17 +50,69 @@ impl SaveController {
             sram: None,
             flash: None,
             eeprom: None,
+            flash_chip: None,
             modified: false,
         }
     }
 
     /// Initialize with detected save type from ROM
-    pub fn init_from_rom(&mut self, rom: &[u8], rom_path: Option<PathBuf>) {
-        let save_type = detect_save_type(rom);
+    ///
+    /// The built-in override database (keyed on the header game code)
+    /// takes precedence over the ROM string detection. The save starts
+    /// blank; use `load_from_bytes` to restore it.
+    pub fn init_from_rom(&mut self, rom: &[u8]) {
+        #[cfg(feature = "std")]
+        {
+            self.metadata.rom_path = None;
+        }
+        self.detect_save_type(rom);
+    }
+
+    /// Initialize from a ROM loaded from `rom_path`
+    ///
+    /// The save file lives next to the ROM and is loaded if it exists.
+    #[cfg(feature = "std")]
+    pub fn init_from_rom_file(&mut self, rom: &[u8], rom_path: PathBuf) {
+        self.metadata.rom_path = Some(rom_path);
+        self.detect_save_type(rom);
+    }
+
+    fn detect_save_type(&mut self, rom: &[u8]) {
+        let game_code = rom_game_code(rom);
+        let save_type = game_code
+            .as_deref()
+            .and_then(save_type_override)
+            .unwrap_or_else(|| detect_save_type(rom));
+        self.flash_chip = game_code.as_deref().and_then(flash_chip_override);
+        self.force_save_type(save_type);
+    }
+
+    /// Use `save_type` regardless of detection
+    ///
+    /// Recreates the save media (blank) and reloads the save file, if any.
+    /// Data written since the last save is carried over to the new media
+    /// and stays marked modified.
+    pub fn force_save_type(&mut self, save_type: SaveType) {
+        let pending = if self.modified {
+            self.data().map(<[u8]>::to_vec)
+        } else {
+            None
+        };
+
+        #[cfg(feature = "std")]
+        let rom_path = self.metadata.rom_path.take();
         self.save_type = save_type;
         self.metadata = SaveMetadata::new(save_type);
-        self.metadata.rom_path = rom_path;
-        self.metadata.generate_save_path();
+        #[cfg(feature = "std")]
+        {
+            self.metadata.rom_path = rom_path;
+            self.metadata.generate_save_path();
+        }
+
+        self.sram = None;
+        self.flash = None;
+        self.eeprom = None;
+        self.modified = false;
 
         // Create appropriate save media
         match save_type {
@@ -59,7 +120,11 @@ impl SaveController {
                 self.sram = Some(Sram::new(save_type));
             }
             SaveType::Flash64K | SaveType::Flash128K => {
-                self.flash = Some(Flash::new(save_type));
+                let chip = self
+                    .flash_chip
+                    .filter(|chip| chip.save_type() == save_type)
+                    .unwrap_or_else(|| FlashChip::default_for(save_type));
+                self.flash = Some(Flash::with_chip(chip));
             }
             SaveType::Eeprom512B | SaveType::Eeprom8K => {
                 self.eeprom = Some(Eeprom::new(save_type));
@@ -68,9 +133,27 @@ impl SaveController {
         }
 
         // Try to load existing save file
+        #[cfg(feature = "std")]
         if let Some(save_path) = self.metadata.save_path.clone() {
             let _ = self.load_from_file(&save_path);
         }
+
+        // Unsaved data is newer than the save file
+        if let Some(data) = pending {
+            self.load_from_bytes(data);
+            self.modified = self.save_type != SaveType::None;
+        }
+    }
+
+    /// Use Flash emulating `chip` (the save type follows the chip size)
+    pub fn force_flash_chip(&mut self, chip: FlashChip) {
+        self.flash_chip = Some(chip);
+        self.force_save_type(chip.save_type());
+    }
+
+    /// Emulated Flash chip, if the save is Flash
+    pub fn flash_chip(&self) -> Option<FlashChip> {
+        self.flash.as_ref().map(|flash| flash.chip())
     }
 
     /// Read byte from save memory
@@ -114,74 +197,169 @@ impl SaveController {
         }
     }
 
-    /// Process EEPROM DMA bit (for EEPROM only)
-    pub fn eeprom_process_bit(&mut self, bit: bool) -> bool {
+    /// Read through the 8-bit SRAM/Flash bus (0x0E000000-0x0FFFFFFF)
+    ///
+    /// The 64 KB window is mirrored over the whole region, and 16/32-bit
+    /// reads return the addressed byte repeated on every byte lane.
+    pub fn read_bus(&self, addr: u32, size: u32) -> u32 {
+        let byte = self.read_byte(addr & SAVE_BUS_MASK) as u32;
+        match size {
+            1 => byte,
+            2 => byte * 0x0101,
+            _ => byte * 0x0101_0101,
+        }
+    }
+
+    /// Write through the 8-bit SRAM/Flash bus
+    ///
+    /// Only the byte lane selected by the low address bits reaches the chip.
+    pub fn write_bus(&mut self, addr: u32, size: u32, value: u32) {
+        let lane = addr & (size - 1);
+        self.write_byte(addr & SAVE_BUS_MASK, (value >> (lane * 8)) as u8);
+    }
+
+    /// Send one EEPROM command bit (for EEPROM only)
+    pub fn eeprom_write_bit(&mut self, bit: bool) {
         if let Some(eeprom) = &mut self.eeprom {
             self.modified = true;
-            return eeprom.process_bit(bit);
+            eeprom.write_bit(bit);
         }
-        true
     }
 
-    /// Save to file
-    pub fn save_to_file(&mut self, path: &Path) -> io::Result<()> {
-        let data = match self.save_type {
-            SaveType::Sram => {
-                self.sram.as_ref().map(|s| s.data())
-            }
-            SaveType::Flash64K | SaveType::Flash128K => {
-                self.flash.as_ref().map(|f| f.data())
-            }
-            SaveType::Eeprom512B | SaveType::Eeprom8K => {
-                self.eeprom.as_ref().map(|e| e.data())
-            }
-            SaveType::None => None,
+    /// Read one EEPROM bit (1 = ready when no read is in progress)
+    ///
+    /// The first read after a command decodes it, which may reveal an
+    /// 8 KB chip: the save type follows the detected size.
+    pub fn eeprom_read_bit(&mut self) -> bool {
+        let Some(eeprom) = &mut self.eeprom else {
+            return true;
         };
+        let bit = eeprom.read_bit();
+        let save_type = eeprom.save_type();
+        if save_type != self.save_type {
+            self.save_type = save_type;
+            self.metadata.save_type = save_type;
+        }
+        bit
+    }
 
-        if let Some(data) = data {
-            fs::write(path, data)?;
-            self.modified = false;
-            Ok(())
+    /// Copy of the save memory, for the frontend to persist
+    ///
+    /// Clears the modified flag. Returns None when the game has no save
+    /// memory.
+    pub fn save_to_bytes(&mut self) -> Option<Vec<u8>> {
+        let data = self.data()?.to_vec();
+        self.modified = false;
+        Some(data)
+    }
+
+    /// Restore the save memory from data persisted by the frontend
+    pub fn load_from_bytes(&mut self, data: Vec<u8>) {
+        if let Some(sram) = &mut self.sram {
+            sram.load_data(data);
+        } else if let Some(flash) = &mut self.flash {
+            flash.load_data(data);
+        } else if let Some(eeprom) = &mut self.eeprom {
+            eeprom.load_data(data);
+        }
+        self.modified = false;
+    }
+
+    /// Move the save memory out, for save states
+    ///
+    /// The controller keeps the chip state (Flash/EEPROM command in
+    /// progress, bank, address width) so a snapshot only has to clone it
+    /// without the data. Returns an empty buffer when there's no save media.
+    pub(crate) fn take_data(&mut self) -> Vec<u8> {
+        if let Some(sram) = &mut self.sram {
+            sram.take_data()
+        } else if let Some(flash) = &mut self.flash {
+            flash.take_data()
+        } else if let Some(eeprom) = &mut self.eeprom {
+            eeprom.take_data()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Put back save memory from a save state
+    ///
+    /// Marks the save as modified: the restored contents replace what's on
+    /// disk at the next auto-save.
+    pub(crate) fn restore_data(&mut self, data: Vec<u8>) {
+        self.load_from_bytes(data);
+        self.modified = self.save_type != SaveType::None;
+    }
+
+    /// Contents of the active save media
+    fn data(&self) -> Option<&[u8]> {
+        if let Some(sram) = &self.sram {
+            Some(sram.data())
+        } else if let Some(flash) = &self.flash {
+            Some(flash.data())
         } else {
-            Err(io::Error::new(
-                io::ErrorKind::Other,
-                "No save data to write",
-            ))
+            self.eeprom.as_ref().map(|eeprom| eeprom.data())
         }
     }
 
+    /// Save to file
+    #[cfg(feature = "std")]
+    pub fn save_to_file(&mut self, path: &Path) -> io::Result<()> {
+        let data = self.save_to_bytes().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::Other, "No save data to write")
+        })?;
+        fs::write(path, data)
+    }
+
     /// Load from file
+    #[cfg(feature = "std")]
     pub fn load_from_file(&mut self, path: &Path) -> io::Result<()> {
         if !path.exists() {
             return Ok(()); // No save file yet - not an error
         }
 
-        let data = fs::read(path)?;
+        self.load_from_bytes(fs::read(path)?);
+        Ok(())
+    }
 
-        match self.save_type {
-            SaveType::Sram => {
-                if let Some(sram) = &mut self.sram {
-                    sram.load_data(data);
-                }
-            }
-            SaveType::Flash64K | SaveType::Flash128K => {
-                if let Some(flash) = &mut self.flash {
-                    flash.load_data(data);
-                }
-            }
-            SaveType::Eeprom512B | SaveType::Eeprom8K => {
-                if let Some(eeprom) = &mut self.eeprom {
-                    eeprom.load_data(data);
-                }
-            }
-            SaveType::None => {}
+    /// Replace the save memory with a user-supplied file
+    ///
+    /// The file must be exactly the size of the current save type. The
+    /// imported data is marked modified, so auto-save writes it to the
+    /// game's own save file.
+    #[cfg(feature = "std")]
+    pub fn import_file(&mut self, path: &Path) -> Result<(), SaveFileError> {
+        let expected = self.save_size();
+        if expected == 0 {
+            return Err(SaveFileError::NoSaveMemory);
+        }
+        let data = fs::read(path)?;
+        if data.len() != expected {
+            return Err(SaveFileError::SizeMismatch {
+                save_type: self.save_type,
+                expected,
+                actual: data.len(),
+            });
         }
 
-        self.modified = false;
+        self.load_from_bytes(data);
+        self.modified = true;
+        Ok(())
+    }
+
+    /// Write a copy of the save memory to `path`
+    ///
+    /// Unlike `save_to_file` this leaves the modified flag alone: the
+    /// game's own save file still needs updating.
+    #[cfg(feature = "std")]
+    pub fn export_file(&self, path: &Path) -> Result<(), SaveFileError> {
+        let data = self.data().ok_or(SaveFileError::NoSaveMemory)?;
+        fs::write(path, data)?;
         Ok(())
     }
 
     /// Auto-save if modified
+    #[cfg(feature = "std")]
     pub fn auto_save(&mut self) -> io::Result<()> {
         if self.modified {
             if let Some(save_path) = self.metadata.save_path.clone() {
@@ -201,7 +379,13 @@ impl SaveController {
         self.save_type
     }
 
+    /// Get the size of the active save media in bytes
+    pub fn save_size(&self) -> usize {
+        self.data().map_or(0, |data| data.len())
+    }
+
     /// Get save path
+    #[cfg(feature = "std")]
     pub fn save_path(&self) -> Option<&Path> {
         self.metadata.save_path.as_deref()
     }
@@ -231,7 +415,7 @@ mod tests {
         let marker = b"SRAM_V123";
         rom[100..100 + marker.len()].copy_from_slice(marker);
 
-        controller.init_from_rom(&rom, None);
+        controller.init_from_rom(&rom);
         assert_eq!(controller.save_type, SaveType::Sram);
     }
 
@@ -242,7 +426,7 @@ mod tests {
         let marker = b"SRAM_V";
         rom[100..100 + marker.len()].copy_from_slice(marker);
 
-        controller.init_from_rom(&rom, None);
+        controller.init_from_rom(&rom);
 
         // Write
         controller.write_byte(0, 0x42);
@@ -261,7 +445,7 @@ mod tests {
         let marker = b"FLASH1M_V";
         rom[100..100 + marker.len()].copy_from_slice(marker);
 
-        controller.init_from_rom(&rom, None);
+        controller.init_from_rom(&rom);
         assert_eq!(controller.save_type, SaveType::Flash128K);
     }
 }
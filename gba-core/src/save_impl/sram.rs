@@ -1,7 +1,10 @@
 /// Save System - SRAM Implementation
 /// Simple battery-backed SRAM (32-64 KB)
 use super::types::SaveType;
+use alloc::vec;
+use alloc::vec::Vec;
 
+#[derive(Clone)]
 pub struct Sram {
     data: Vec<u8>,
     size: usize,
@@ -35,6 +38,11 @@ impl Sram {
         &self.data
     }
 
+    /// Move the data out, leaving the chip empty
+    pub(crate) fn take_data(&mut self) -> Vec<u8> {
+        core::mem::take(&mut self.data)
+    }
+
     /// Load data from file
     pub fn load_data(&mut self, data: Vec<u8>) {
         if data.len() == self.size {
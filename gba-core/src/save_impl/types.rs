@@ -1,6 +1,11 @@
 /// Save System - Types
 /// Save types and detection
+use super::constants::*;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
 use std::path::PathBuf;
+use thiserror::Error;
 
 /// Type of save memory used by the game
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -47,6 +52,55 @@ impl SaveType {
     }
 }
 
+/// Flash chip model, reported by the chip-ID command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlashChip {
+    Macronix64K,  // MX29L512
+    Panasonic64K, // MN63F805MNP
+    Atmel64K,     // AT29LV512, 128-byte sectors
+    Macronix128K, // MX29L010
+    Sanyo128K,    // LE26FV10N1TS
+}
+
+impl FlashChip {
+    /// Default chip for a Flash save type
+    pub fn default_for(save_type: SaveType) -> Self {
+        match save_type {
+            SaveType::Flash128K => FlashChip::Macronix128K,
+            _ => FlashChip::Macronix64K,
+        }
+    }
+
+    /// Two-byte ID (manufacturer in the low byte, device in the high byte)
+    pub fn id(&self) -> u16 {
+        match self {
+            FlashChip::Macronix64K => FLASH_MACRONIX_64K,
+            FlashChip::Panasonic64K => FLASH_PANASONIC_64K,
+            FlashChip::Atmel64K => FLASH_ATMEL_64K,
+            FlashChip::Macronix128K => FLASH_MACRONIX_128K,
+            FlashChip::Sanyo128K => FLASH_SANYO_128K,
+        }
+    }
+
+    /// Save type matching the chip capacity
+    pub fn save_type(&self) -> SaveType {
+        match self {
+            FlashChip::Macronix64K | FlashChip::Panasonic64K | FlashChip::Atmel64K => {
+                SaveType::Flash64K
+            }
+            FlashChip::Macronix128K | FlashChip::Sanyo128K => SaveType::Flash128K,
+        }
+    }
+
+    /// Size of the block cleared by the sector erase command
+    pub fn sector_size(&self) -> usize {
+        match self {
+            FlashChip::Atmel64K => FLASH_ATMEL_SECTOR_SIZE,
+            _ => FLASH_SECTOR_SIZE,
+        }
+    }
+}
+
 /// Flash state machine
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FlashState {
@@ -63,7 +117,9 @@ pub enum FlashState {
 #[derive(Debug, Clone)]
 pub struct SaveMetadata {
     pub save_type: SaveType,
+    #[cfg(feature = "std")]
     pub rom_path: Option<PathBuf>,
+    #[cfg(feature = "std")]
     pub save_path: Option<PathBuf>,
     pub modified: bool,
 }
@@ -72,13 +128,16 @@ impl SaveMetadata {
     pub fn new(save_type: SaveType) -> Self {
         Self {
             save_type,
+            #[cfg(feature = "std")]
             rom_path: None,
+            #[cfg(feature = "std")]
             save_path: None,
             modified: false,
         }
     }
 
     /// Generate save file path from ROM path
+    #[cfg(feature = "std")]
     pub fn generate_save_path(&mut self) {
         if let Some(rom_path) = &self.rom_path {
             let mut save_path = rom_path.clone();
@@ -87,3 +146,21 @@ impl SaveMetadata {
         }
     }
 }
+
+/// Error importing or exporting a save file
+#[derive(Error, Debug)]
+pub enum SaveFileError {
+    #[error("The game has no save memory")]
+    NoSaveMemory,
+
+    #[error("Save file is {actual} bytes, expected {expected} for {save_type:?}")]
+    SizeMismatch {
+        save_type: SaveType,
+        expected: usize,
+        actual: usize,
+    },
+
+    #[cfg(feature = "std")]
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+}
@@ -1,6 +1,10 @@
 /// Save System Tests - Separated test module
 use crate::save::*;
+#[cfg(feature = "std")]
+use gba_arm7tdmi::cpu::MemoryBus;
+#[cfg(feature = "std")]
 use std::fs;
+#[cfg(feature = "std")]
 use std::path::PathBuf;
 
 // ========== SaveType Tests ==========
@@ -178,6 +182,27 @@ fn test_eeprom_8k() {
     assert_eq!(eeprom.data().len(), 8192);
 }
 
+#[test]
+fn test_eeprom_14bit_address_switches_to_8k() {
+    let mut controller = SaveController::new();
+    controller.force_save_type(SaveType::Eeprom512B);
+    assert_eq!(controller.save_size(), 512);
+
+    // Read request: 11, 14-bit address, stop bit
+    let stream = (0b11u32 << 15) | (0x0123 << 1);
+    for i in (0..17).rev() {
+        controller.eeprom_write_bit((stream >> i) & 1 != 0);
+    }
+
+    // 4 dummy bits, then the (blank) block
+    for _ in 0..4 {
+        assert!(!controller.eeprom_read_bit());
+    }
+    assert!(controller.eeprom_read_bit());
+    assert_eq!(controller.save_type(), SaveType::Eeprom8K);
+    assert_eq!(controller.save_size(), 8192);
+}
+
 // ========== SaveController Tests ==========
 
 #[test]
@@ -194,7 +219,7 @@ fn test_save_controller_sram_detection() {
     let marker = b"SRAM_V123";
     rom[100..100 + marker.len()].copy_from_slice(marker);
 
-    controller.init_from_rom(&rom, None);
+    controller.init_from_rom(&rom);
     assert_eq!(controller.save_type(), SaveType::Sram);
 }
 
@@ -205,7 +230,7 @@ fn test_save_controller_sram_read_write() {
     let marker = b"SRAM_V";
     rom[100..100 + marker.len()].copy_from_slice(marker);
 
-    controller.init_from_rom(&rom, None);
+    controller.init_from_rom(&rom);
 
     controller.write_byte(0, 0x42);
     controller.write_byte(100, 0xAB);
@@ -222,13 +247,41 @@ fn test_save_controller_flash_detection() {
     let marker = b"FLASH1M_V";
     rom[100..100 + marker.len()].copy_from_slice(marker);
 
-    controller.init_from_rom(&rom, None);
+    controller.init_from_rom(&rom);
     assert_eq!(controller.save_type(), SaveType::Flash128K);
 }
 
-// ========== File Persistence Tests ==========
+// ========== Persistence Tests ==========
+
+#[test]
+fn test_save_load_bytes() {
+    let mut rom = vec![0u8; 1024];
+    let marker = b"SRAM_V";
+    rom[100..100 + marker.len()].copy_from_slice(marker);
+
+    let mut controller = SaveController::new();
+    controller.init_from_rom(&rom);
+    controller.write_byte(0, 0x11);
+    controller.write_byte(1000, 0x33);
+    assert!(controller.is_modified());
+
+    let data = controller.save_to_bytes().unwrap();
+    assert_eq!(data.len(), SaveType::Sram.size());
+    assert!(!controller.is_modified());
+
+    let mut controller2 = SaveController::new();
+    controller2.init_from_rom(&rom);
+    controller2.load_from_bytes(data);
+    assert_eq!(controller2.read_byte(0), 0x11);
+    assert_eq!(controller2.read_byte(1000), 0x33);
+    assert!(!controller2.is_modified());
+
+    // No save memory, nothing to persist
+    assert_eq!(SaveController::new().save_to_bytes(), None);
+}
 
 #[test]
+#[cfg(feature = "std")]
 fn test_save_load_file() {
     let temp_dir = std::env::temp_dir();
     let save_path = temp_dir.join("test_save.sav");
@@ -241,7 +294,7 @@ fn test_save_load_file() {
     let marker = b"SRAM_V";
     rom[100..100 + marker.len()].copy_from_slice(marker);
 
-    controller.init_from_rom(&rom, Some(PathBuf::from("test.gba")));
+    controller.init_from_rom_file(&rom, PathBuf::from("test.gba"));
 
     // Write data
     controller.write_byte(0, 0x11);
@@ -253,7 +306,7 @@ fn test_save_load_file() {
 
     // Create new controller and load
     let mut controller2 = SaveController::new();
-    controller2.init_from_rom(&rom, Some(PathBuf::from("test.gba")));
+    controller2.init_from_rom_file(&rom, PathBuf::from("test.gba"));
     controller2.load_from_file(&save_path).unwrap();
 
     // Verify data
@@ -266,6 +319,7 @@ fn test_save_load_file() {
 }
 
 #[test]
+#[cfg(feature = "std")]
 fn test_auto_save() {
     let temp_dir = std::env::temp_dir();
     let save_path = temp_dir.join("test_autosave.sav");
@@ -277,7 +331,7 @@ fn test_auto_save() {
     let marker = b"SRAM_V";
     rom[100..100 + marker.len()].copy_from_slice(marker);
 
-    controller.init_from_rom(&rom, Some(save_path.clone()));
+    controller.init_from_rom_file(&rom, save_path.clone());
 
     // Write data
     controller.write_byte(0, 0xAA);
@@ -293,3 +347,151 @@ fn test_auto_save() {
     // Clean up
     let _ = fs::remove_file(&save_path);
 }
+
+// ========== Override Tests ==========
+
+#[test]
+fn test_force_save_type_flash_128k() {
+    let mut controller = SaveController::new();
+    let rom = vec![0u8; 1024];
+
+    controller.init_from_rom(&rom);
+    assert_eq!(controller.save_type(), SaveType::None);
+    assert_eq!(controller.save_size(), 0);
+
+    controller.force_save_type(SaveType::Flash128K);
+    assert_eq!(controller.save_type(), SaveType::Flash128K);
+    assert_eq!(controller.save_size(), 0x20000);
+
+    // Il chip ID è quello del flash da 128K
+    controller.write_byte(FLASH_ADDR_CMD1, FLASH_CMD_WRITE_ENABLE);
+    controller.write_byte(FLASH_ADDR_CMD2, FLASH_CMD_WRITE_DISABLE);
+    controller.write_byte(FLASH_ADDR_CMD1, FLASH_CMD_ENTER_ID);
+    let chip_id = (controller.read_byte(1) as u16) << 8 | controller.read_byte(0) as u16;
+    assert_eq!(chip_id, FLASH_MACRONIX_128K);
+}
+
+#[test]
+fn test_force_save_type_keeps_unsaved_data() {
+    let mut controller = SaveController::new();
+    controller.force_save_type(SaveType::Sram);
+    controller.write_byte(0x0010, 0x42);
+    assert!(controller.is_modified());
+
+    controller.force_save_type(SaveType::Flash64K);
+    assert!(controller.is_modified());
+    assert_eq!(controller.read_byte(0x0010), 0x42);
+    assert_eq!(controller.save_to_bytes().unwrap()[0x10], 0x42);
+
+    // Niente da portare con sé: il nuovo supporto parte pulito
+    controller.force_save_type(SaveType::Sram);
+    assert!(!controller.is_modified());
+    assert_eq!(controller.read_byte(0x0010), 0xFF);
+}
+
+#[test]
+fn test_save_type_override_database() {
+    assert_eq!(save_type_override("BPEE"), Some(SaveType::Flash128K));
+    assert_eq!(save_type_override("ZZZZ"), None);
+
+    // Il database ha la precedenza sulle stringhe nella ROM
+    let mut rom = vec![0u8; 1024];
+    rom[0xAC..0xB0].copy_from_slice(b"AXVE");
+    let marker = b"SRAM_V";
+    rom[0x200..0x200 + marker.len()].copy_from_slice(marker);
+
+    let mut controller = SaveController::new();
+    controller.init_from_rom(&rom);
+    assert_eq!(controller.save_type(), SaveType::Flash128K);
+}
+
+#[test]
+fn test_emulator_set_save_type() {
+    let mut emu = crate::GbaEmulator::new();
+    emu.set_save_type(SaveType::Eeprom8K);
+    assert_eq!(emu.bus.save.save_type(), SaveType::Eeprom8K);
+    assert_eq!(emu.bus.save.save_size(), 0x2000);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_emulator_import_export_save() {
+    let temp_dir = std::env::temp_dir();
+    let import_path = temp_dir.join("test_import.sav");
+    let export_path = temp_dir.join("test_export.sav");
+
+    let mut emu = crate::GbaEmulator::new();
+    emu.set_save_type(SaveType::Sram);
+
+    let mut data = vec![0u8; SaveType::Sram.size()];
+    data[0] = 0x5A;
+    data[0x1234] = 0xC3;
+    fs::write(&import_path, &data).unwrap();
+
+    emu.import_save(&import_path).unwrap();
+    assert_eq!(emu.bus.read_byte(0x0E000000), 0x5A);
+    assert_eq!(emu.bus.read_byte(0x0E001234), 0xC3);
+    assert!(emu.bus.save.is_modified());
+
+    emu.export_save(&export_path).unwrap();
+    assert_eq!(fs::read(&export_path).unwrap(), data);
+
+    let _ = fs::remove_file(&import_path);
+    let _ = fs::remove_file(&export_path);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_import_save_rejects_wrong_size() {
+    let import_path = std::env::temp_dir().join("test_import_wrong_size.sav");
+    fs::write(&import_path, vec![0x42u8; 0x200]).unwrap();
+
+    let mut controller = SaveController::new();
+    assert!(matches!(
+        controller.import_file(&import_path),
+        Err(SaveFileError::NoSaveMemory)
+    ));
+
+    controller.force_save_type(SaveType::Flash64K);
+    assert!(matches!(
+        controller.import_file(&import_path),
+        Err(SaveFileError::SizeMismatch {
+            expected: 0x10000,
+            actual: 0x200,
+            ..
+        })
+    ));
+    // The existing save is left untouched
+    assert_eq!(controller.read_byte(0), 0xFF);
+    assert!(!controller.is_modified());
+
+    let _ = fs::remove_file(&import_path);
+}
+
+#[test]
+fn test_force_flash_chip() {
+    let mut controller = SaveController::new();
+    controller.force_flash_chip(FlashChip::Atmel64K);
+    assert_eq!(controller.save_type(), SaveType::Flash64K);
+    assert_eq!(controller.flash_chip(), Some(FlashChip::Atmel64K));
+
+    controller.write_byte(FLASH_ADDR_CMD1, FLASH_CMD_WRITE_ENABLE);
+    controller.write_byte(FLASH_ADDR_CMD2, FLASH_CMD_WRITE_DISABLE);
+    controller.write_byte(FLASH_ADDR_CMD1, FLASH_CMD_ENTER_ID);
+    let chip_id = (controller.read_byte(1) as u16) << 8 | controller.read_byte(0) as u16;
+    assert_eq!(chip_id, FLASH_ATMEL_64K);
+
+    // Un tipo di salvataggio di dimensione diversa torna al chip di default
+    controller.force_save_type(SaveType::Flash128K);
+    assert_eq!(controller.flash_chip(), Some(FlashChip::Macronix128K));
+}
+
+#[test]
+fn test_flash_chip_override_database() {
+    let mut rom = vec![0u8; 1024];
+    rom[0xAC..0xB0].copy_from_slice(b"BPEE");
+
+    let mut controller = SaveController::new();
+    controller.init_from_rom(&rom);
+    assert_eq!(controller.flash_chip(), Some(FlashChip::Sanyo128K));
+}
@@ -0,0 +1,170 @@
+// Save State - Snapshot dello stato dell'emulatore
+//
+// Cattura CPU, RAM (EWRAM, IWRAM, I/O, VRAM, Palette, OAM) e lo stato di
+// PPU, APU, interrupt, timer, DMA, seriale e wait state, più il save della
+// cartridge (SRAM/Flash/EEPROM con l'eventuale comando in corso). Non include
+// la ROM, il BIOS né i sample audio già pronti per l'host.
+//
+// Le regioni di RAM e la memoria del save sono concatenate in un unico
+// buffer, così da poter essere confrontate/compresse in blocco (vedi rewind.rs).
+
+use crate::apu::APU;
+use crate::bios::Bios;
+use crate::dma::DMA;
+use crate::emulator::GbaEmulator;
+use crate::interrupt::{InterruptController, PowerState};
+use crate::ppu::PPU;
+use crate::save::SaveController;
+use crate::serial::Serial;
+use crate::timer::Timer;
+use crate::timing::MemoryTiming;
+use alloc::vec::Vec;
+use gba_arm7tdmi::Registers;
+
+/// Snapshot completo dello stato emulato
+#[derive(Clone)]
+pub struct SaveState {
+    pub regs: Registers,
+    pub cpu_cycles: u64,
+    pub total_cycles: u64,
+    cycle_carry: u64,
+    pub halted: bool,
+    pub power: PowerState,
+    /// Cartuccia o immagine multiboot: da dove riparte il reset
+    entry_point: u32,
+    /// Attesa di IntrWait emulata ad alto livello
+    bios: Bios,
+
+    /// PPU senza framebuffer, palette e OAM (che stanno in `ram`)
+    ppu: PPU,
+    /// APU senza il resampler dell'host
+    apu: APU,
+    interrupt: InterruptController,
+    timer: Timer,
+    dma: DMA,
+    serial: Serial,
+    timing: MemoryTiming,
+    /// Stato del chip di save senza i dati (che stanno in `ram`)
+    save: SaveController,
+
+    /// EWRAM | IWRAM | I/O | VRAM | Palette | OAM | save
+    ram: Vec<u8>,
+}
+
+impl SaveState {
+    /// Cattura lo stato corrente dell'emulatore
+    pub fn capture(emu: &GbaEmulator) -> Self {
+        let memory = &emu.bus.memory;
+        let ppu = &emu.bus.ppu;
+
+        let mut ram = Vec::with_capacity(
+            memory.ewram.len()
+                + memory.iwram.len()
+                + memory.io_registers.len()
+                + memory.vram.len()
+                + ppu.palette_ram.len()
+                + ppu.oam.len(),
+        );
+        ram.extend_from_slice(&memory.ewram);
+        ram.extend_from_slice(&memory.iwram);
+        ram.extend_from_slice(&memory.io_registers);
+        ram.extend_from_slice(&memory.vram);
+        ram.extend_from_slice(&ppu.palette_ram);
+        ram.extend_from_slice(&ppu.oam);
+
+        let mut save = emu.bus.save.clone();
+        ram.extend_from_slice(&save.take_data());
+
+        let mut ppu = ppu.clone();
+        ppu.framebuffer = Vec::new();
+        ppu.palette_ram = Vec::new();
+        ppu.oam = Vec::new();
+
+        Self {
+            regs: emu.cpu.regs.clone(),
+            cpu_cycles: emu.cpu.cycles,
+            total_cycles: emu.total_cycles,
+            cycle_carry: emu.cycle_carry,
+            halted: emu.cpu.halted,
+            power: emu.bus.power,
+            entry_point: emu.entry_point,
+            bios: emu.bios.clone(),
+            ppu,
+            apu: emu.bus.apu.snapshot(),
+            interrupt: emu.bus.interrupt.clone(),
+            timer: emu.bus.timer.clone(),
+            dma: emu.bus.dma.clone(),
+            serial: emu.bus.serial.clone(),
+            timing: emu.bus.timing.clone(),
+            save,
+            ram,
+        }
+    }
+
+    /// Ripristina lo stato nell'emulatore
+    ///
+    /// Il framebuffer corrente viene mantenuto fino al prossimo frame.
+    pub fn restore(&self, emu: &mut GbaEmulator) {
+        emu.cpu.regs = self.regs.clone();
+        emu.cpu.cycles = self.cpu_cycles;
+        emu.total_cycles = self.total_cycles;
+        emu.cycle_carry = self.cycle_carry;
+        emu.cpu.halted = self.halted;
+        emu.bus.power = self.power;
+        emu.entry_point = self.entry_point;
+        emu.bios = self.bios.clone();
+
+        let framebuffer = core::mem::take(&mut emu.bus.ppu.framebuffer);
+        let palette_len = emu.bus.ppu.palette_ram.len();
+        let oam_len = emu.bus.ppu.oam.len();
+        // I layer nascosti per debug non fanno parte dello stato emulato
+        let layer_mask = emu.bus.ppu.layer_mask;
+        emu.bus.ppu = self.ppu.clone();
+        emu.bus.ppu.framebuffer = framebuffer;
+        emu.bus.ppu.layer_mask = layer_mask;
+
+        emu.bus.apu.restore(&self.apu);
+        emu.bus.interrupt = self.interrupt.clone();
+        emu.bus.timer = self.timer.clone();
+        emu.bus.dma = self.dma.clone();
+        emu.bus.serial = self.serial.clone();
+        emu.bus.timing = self.timing.clone();
+
+        let memory = &mut emu.bus.memory;
+        let mut rest = self.ram.as_slice();
+        rest = copy_region(&mut memory.ewram, rest);
+        rest = copy_region(&mut memory.iwram, rest);
+        rest = copy_region(&mut memory.io_registers, rest);
+        rest = copy_region(&mut memory.vram, rest);
+
+        let (palette, rest) = rest.split_at(palette_len);
+        let (oam, save) = rest.split_at(oam_len);
+        emu.bus.ppu.palette_ram = palette.to_vec();
+        emu.bus.ppu.oam = oam.to_vec();
+
+        emu.bus.save = self.save.clone();
+        emu.bus.save.restore_data(save.to_vec());
+    }
+
+    /// Buffer RAM concatenato
+    pub fn ram(&self) -> &[u8] {
+        &self.ram
+    }
+
+    /// Sostituisce il buffer RAM (stessa dimensione)
+    pub(crate) fn set_ram(&mut self, ram: Vec<u8>) {
+        self.ram = ram;
+    }
+
+    /// Estrae il buffer RAM lasciando lo snapshot senza RAM
+    pub(crate) fn take_ram(&mut self) -> Vec<u8> {
+        core::mem::take(&mut self.ram)
+    }
+}
+
+/// Copia l'inizio di `src` in `dst` e ritorna il resto
+fn copy_region<'a>(dst: &mut [u8], src: &'a [u8]) -> &'a [u8] {
+    let (head, rest) = src.split_at(dst.len());
+    dst.copy_from_slice(head);
+    rest
+}
@@ -0,0 +1,78 @@
+use crate::save::SaveType;
+use crate::GbaEmulator;
+
+/// Registri audio visibili dalla CPU (0x04000060-0x0400009F)
+fn apu_registers(emu: &GbaEmulator) -> Vec<u8> {
+    (0x0400_0060..0x0400_00A0)
+        .map(|addr| emu.bus.apu.read_byte(addr))
+        .collect()
+}
+
+/// Svuota il FIFO A un overflow del timer 0 alla volta
+fn drain_fifo_a(emu: &mut GbaEmulator) -> Vec<(i16, i16)> {
+    (0..8)
+        .map(|_| {
+            emu.bus.apu.timer_overflow(0);
+            emu.bus.apu.generate_sample()
+        })
+        .collect()
+}
+
+/// Invia un comando EEPROM, MSB first
+fn send_eeprom(emu: &mut GbaEmulator, value: u32, bits: u32) {
+    for i in (0..bits).rev() {
+        emu.bus.save.eeprom_write_bit((value >> i) & 1 != 0);
+    }
+}
+
+/// Legge i 4 bit dummy e il blocco da 64 bit
+fn read_eeprom_block(emu: &mut GbaEmulator) -> u64 {
+    for _ in 0..4 {
+        emu.bus.save.eeprom_read_bit();
+    }
+    (0..64).fold(0, |block, _| (block << 1) | emu.bus.save.eeprom_read_bit() as u64)
+}
+
+#[test]
+fn test_savestate_roundtrip_apu_and_save_chip() {
+    let mut emu = GbaEmulator::new();
+
+    // APU: master on, channel 2 attivo, FIFO A al 100% su sinistra
+    emu.bus.apu.write_byte(0x0400_0084, 0x80);
+    emu.bus.apu.write_halfword(0x0400_0080, 0xFF77);
+    emu.bus.apu.write_halfword(0x0400_0082, 0x0206);
+    emu.bus.apu.write_halfword(0x0400_0068, 0xF080);
+    emu.bus.apu.write_halfword(0x0400_006C, 0x8400);
+    for sample in [0x10, 0x20, 0x30, 0x40, -0x10, -0x20] {
+        emu.bus.apu.write_fifo_a(sample);
+    }
+    emu.bus.apu.tick(10_000);
+
+    // EEPROM 8 KB con dati noti e una lettura del blocco 3 già comandata
+    emu.bus.save.force_save_type(SaveType::Eeprom8K);
+    let data: Vec<u8> = (0..0x2000).map(|i| (i * 7) as u8).collect();
+    emu.bus.save.load_from_bytes(data.clone());
+    send_eeprom(&mut emu, (0b11 << 15) | (3 << 1), 17);
+
+    let state = emu.save_state();
+    let registers = apu_registers(&emu);
+    let fifo = drain_fifo_a(&mut emu);
+
+    // Cambia tutto dopo lo snapshot
+    emu.bus.apu.write_halfword(0x0400_0080, 0x0000);
+    emu.bus.apu.write_halfword(0x0400_0082, 0x0B0F);
+    emu.bus.apu.write_halfword(0x0400_0068, 0x0000);
+    emu.bus.apu.write_fifo_a(0x7F);
+    emu.bus.save.load_from_bytes(vec![0xFF; 0x2000]);
+    send_eeprom(&mut emu, (0b11 << 15) | (9 << 1), 17);
+
+    emu.load_state(&state);
+
+    assert_eq!(apu_registers(&emu), registers);
+    assert_eq!(drain_fifo_a(&mut emu), fifo);
+
+    // Il comando in corso riparte, sui dati catturati
+    let block = u64::from_be_bytes(data[24..32].try_into().unwrap());
+    assert_eq!(read_eeprom_block(&mut emu), block);
+    assert_eq!(emu.bus.save.save_to_bytes(), Some(data));
+}
@@ -0,0 +1,217 @@
+// Serial (SIO) - Registri della porta link e modalità loopback
+//
+// Nessun cavo link è emulato: i trasferimenti avviati dal gioco terminano
+// subito come se dall'altra parte non ci fosse nessuno (linee a 1, dati
+// ricevuti 0xFF/0xFFFF). In modalità loopback i dati trasmessi vengono
+// invece ricevuti indietro, utile per homebrew e test del link.
+//
+// Registri (0x04000120-0x0400015A):
+// - SIODATA32 / SIOMULTI0-3 (0x120-0x126): dati a 32 bit o ricevuti in multiplayer
+// - SIOCNT (0x128): controllo; bit 7 avvia il trasferimento, bit 14 abilita l'IRQ
+// - SIODATA8 / SIOMLT_SEND (0x12A): dato a 8 bit o da inviare in multiplayer
+// - RCNT (0x134): selezione modalità (SIO, GPIO, JOY Bus)
+// - JOYCNT, JOY_RECV, JOY_TRANS, JOYSTAT (0x140-0x158): JOY Bus, solo registri
+
+pub const SIODATA32_L: u32 = 0x04000120;
+pub const SIODATA32_H: u32 = 0x04000122;
+pub const SIOMULTI0: u32 = 0x04000120;
+pub const SIOMULTI1: u32 = 0x04000122;
+pub const SIOMULTI2: u32 = 0x04000124;
+pub const SIOMULTI3: u32 = 0x04000126;
+pub const SIOCNT: u32 = 0x04000128;
+pub const SIODATA8: u32 = 0x0400012A;
+pub const SIOMLT_SEND: u32 = 0x0400012A;
+pub const RCNT: u32 = 0x04000134;
+pub const JOYCNT: u32 = 0x04000140;
+pub const JOY_RECV_L: u32 = 0x04000150;
+pub const JOY_RECV_H: u32 = 0x04000152;
+pub const JOY_TRANS_L: u32 = 0x04000154;
+pub const JOY_TRANS_H: u32 = 0x04000156;
+pub const JOYSTAT: u32 = 0x04000158;
+
+/// SIOCNT bit 0: clock interno (modalità normale)
+const SIOCNT_INTERNAL_CLOCK: u16 = 1 << 0;
+/// SIOCNT bit 2: stato della linea SI (normale) / terminale SI (multiplayer)
+const SIOCNT_SI: u16 = 1 << 2;
+/// SIOCNT bit 3: linea SD, a 1 quando tutti i GBA sono pronti (multiplayer)
+const SIOCNT_SD: u16 = 1 << 3;
+/// SIOCNT bit 7: start/busy
+const SIOCNT_START: u16 = 1 << 7;
+/// SIOCNT bit 14: IRQ a fine trasferimento
+const SIOCNT_IRQ: u16 = 1 << 14;
+
+/// RCNT all'accensione: porta in modalità GPIO, linee in ingresso
+const RCNT_DEFAULT: u16 = 0x8000;
+
+/// Modalità della porta seriale (RCNT bit 14-15, SIOCNT bit 12-13)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SerialMode {
+    Normal8,
+    Normal32,
+    Multiplayer,
+    Uart,
+    GeneralPurpose,
+    JoyBus,
+}
+
+/// Porta seriale
+#[derive(Clone)]
+pub struct Serial {
+    siocnt: u16,
+    rcnt: u16,
+    /// SIODATA32 (0-1) / SIOMULTI0-3
+    data: [u16; 4],
+    /// SIODATA8 / SIOMLT_SEND
+    send: u16,
+
+    joycnt: u16,
+    joy_recv: u32,
+    joy_trans: u32,
+    joystat: u16,
+
+    /// Riceve indietro i dati trasmessi invece di simulare l'assenza del cavo
+    pub loopback: bool,
+}
+
+impl Serial {
+    pub fn new() -> Self {
+        Self {
+            siocnt: 0,
+            rcnt: RCNT_DEFAULT,
+            data: [0; 4],
+            send: 0,
+            joycnt: 0,
+            joy_recv: 0,
+            joy_trans: 0,
+            joystat: 0,
+            loopback: false,
+        }
+    }
+
+    /// Modalità selezionata da RCNT e SIOCNT
+    pub fn mode(&self) -> SerialMode {
+        match self.rcnt >> 14 {
+            2 => SerialMode::GeneralPurpose,
+            3 => SerialMode::JoyBus,
+            _ => match (self.siocnt >> 12) & 3 {
+                0 => SerialMode::Normal8,
+                1 => SerialMode::Normal32,
+                2 => SerialMode::Multiplayer,
+                _ => SerialMode::Uart,
+            },
+        }
+    }
+
+    /// Trasferimento in corso (SIOCNT bit 7)
+    pub fn busy(&self) -> bool {
+        self.siocnt & SIOCNT_START != 0
+    }
+
+    pub fn read_register(&self, addr: u32) -> u16 {
+        match addr & !1 {
+            0x04000120 => self.data[0],
+            0x04000122 => self.data[1],
+            0x04000124 => self.data[2],
+            0x04000126 => self.data[3],
+            SIOCNT => self.read_siocnt(),
+            SIODATA8 => self.send,
+            RCNT => self.rcnt,
+            JOYCNT => self.joycnt,
+            JOY_RECV_L => self.joy_recv as u16,
+            JOY_RECV_H => (self.joy_recv >> 16) as u16,
+            JOY_TRANS_L => self.joy_trans as u16,
+            JOY_TRANS_H => (self.joy_trans >> 16) as u16,
+            JOYSTAT => self.joystat,
+            _ => 0,
+        }
+    }
+
+    /// Scrive un registro; ritorna true se va richiesto l'IRQ seriale
+    pub fn write_register(&mut self, addr: u32, value: u16) -> bool {
+        match addr & !1 {
+            0x04000120 => self.data[0] = value,
+            0x04000122 => self.data[1] = value,
+            0x04000124 => self.data[2] = value,
+            0x04000126 => self.data[3] = value,
+            SIOCNT => return self.write_siocnt(value),
+            SIODATA8 => self.send = value,
+            RCNT => self.rcnt = value & 0xC1FF,
+            JOYCNT => self.joycnt = (self.joycnt & 0x07 & !value) | (value & 0x40),
+            JOY_RECV_L => self.joy_recv = (self.joy_recv & 0xFFFF_0000) | value as u32,
+            JOY_RECV_H => self.joy_recv = (self.joy_recv & 0x0000_FFFF) | (value as u32) << 16,
+            JOY_TRANS_L => self.joy_trans = (self.joy_trans & 0xFFFF_0000) | value as u32,
+            JOY_TRANS_H => self.joy_trans = (self.joy_trans & 0x0000_FFFF) | (value as u32) << 16,
+            JOYSTAT => self.joystat = (self.joystat & !0x30) | (value & 0x30),
+            _ => {}
+        }
+        false
+    }
+
+    /// SIOCNT con le linee in ingresso come le vede il gioco
+    fn read_siocnt(&self) -> u16 {
+        match self.mode() {
+            // Senza cavo SI resta alto; in loopback riflette SO (bit 3)
+            SerialMode::Normal8 | SerialMode::Normal32 => {
+                let si = if self.loopback {
+                    (self.siocnt & SIOCNT_SD) >> 1
+                } else {
+                    SIOCNT_SI
+                };
+                (self.siocnt & !SIOCNT_SI) | si
+            }
+            // Sempre master (SI = 0); SD alto solo se c'è qualcuno collegato
+            SerialMode::Multiplayer => {
+                let sd = if self.loopback { SIOCNT_SD } else { 0 };
+                (self.siocnt & !(SIOCNT_SI | SIOCNT_SD)) | sd
+            }
+            _ => self.siocnt,
+        }
+    }
+
+    fn write_siocnt(&mut self, value: u16) -> bool {
+        let was_busy = self.busy();
+        // In multiplayer i bit 2-6 sono in sola lettura
+        let writable = if self.mode() == SerialMode::Multiplayer && (value >> 12) & 3 == 2 {
+            0x7F83
+        } else {
+            0x7FFF
+        };
+        self.siocnt = (self.siocnt & !writable) | (value & writable);
+
+        if was_busy || !self.busy() {
+            return false;
+        }
+        self.transfer()
+    }
+
+    /// Completa subito il trasferimento avviato
+    fn transfer(&mut self) -> bool {
+        match self.mode() {
+            SerialMode::Normal8 | SerialMode::Normal32 => {
+                // Con clock esterno e nessun partner il trasferimento non parte mai
+                if !self.loopback && self.siocnt & SIOCNT_INTERNAL_CLOCK == 0 {
+                    return false;
+                }
+                if !self.loopback {
+                    self.send = (self.send & 0xFF00) | 0xFF;
+                    self.data[0] = 0xFFFF;
+                    self.data[1] = 0xFFFF;
+                }
+            }
+            SerialMode::Multiplayer => {
+                let echo = if self.loopback { self.send } else { 0xFFFF };
+                self.data = [self.send, echo, 0xFFFF, 0xFFFF];
+            }
+            _ => return false,
+        }
+
+        self.siocnt &= !SIOCNT_START;
+        self.siocnt & SIOCNT_IRQ != 0
+    }
+}
+
+impl Default for Serial {
+    fn default() -> Self {
+        Self::new()
+    }
+}
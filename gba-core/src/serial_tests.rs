@@ -0,0 +1,71 @@
+use crate::bus::Bus;
+use crate::interrupt::InterruptFlags;
+use crate::serial::*;
+use gba_arm7tdmi::cpu::MemoryBus;
+
+/// SIOCNT: modalità normale 8 bit, clock interno, IRQ abilitato, start
+const START_NORMAL8_IRQ: u16 = 0x4081;
+
+#[test]
+fn test_serial_idle_defaults() {
+    let serial = Serial::new();
+    assert_eq!(serial.read_register(RCNT), 0x8000);
+    assert_eq!(serial.mode(), SerialMode::GeneralPurpose);
+    assert!(!serial.busy());
+}
+
+#[test]
+fn test_loopback_echoes_byte_and_raises_irq() {
+    let mut bus = Bus::new();
+    bus.serial.loopback = true;
+
+    bus.write_halfword(RCNT, 0);
+    bus.write_halfword(SIODATA8, 0x5A);
+    bus.write_halfword(SIOCNT, START_NORMAL8_IRQ);
+
+    assert_eq!(bus.read_halfword(SIODATA8) & 0xFF, 0x5A);
+    assert_eq!(
+        bus.read_halfword(SIOCNT) & 0x80,
+        0,
+        "trasferimento completato"
+    );
+    assert_ne!(bus.interrupt.if_ & InterruptFlags::SERIAL.bits(), 0);
+}
+
+#[test]
+fn test_no_cable_receives_ones() {
+    let mut bus = Bus::new();
+
+    bus.write_halfword(RCNT, 0);
+    bus.write_halfword(SIODATA8, 0x5A);
+    bus.write_halfword(SIOCNT, START_NORMAL8_IRQ & !0x4000);
+
+    assert_eq!(bus.read_halfword(SIODATA8) & 0xFF, 0xFF);
+    assert_eq!(bus.interrupt.if_ & InterruptFlags::SERIAL.bits(), 0);
+}
+
+#[test]
+fn test_multiplayer_loopback() {
+    let mut serial = Serial::new();
+    serial.loopback = true;
+
+    serial.write_register(RCNT, 0);
+    serial.write_register(SIOMLT_SEND, 0x1234);
+    let irq = serial.write_register(SIOCNT, 0x6080);
+
+    assert!(irq);
+    assert_eq!(serial.read_register(SIOMULTI0), 0x1234);
+    assert_eq!(serial.read_register(SIOMULTI1), 0x1234);
+    assert_eq!(serial.read_register(SIOMULTI2), 0xFFFF);
+    assert_ne!(
+        serial.read_register(SIOCNT) & 0x08,
+        0,
+        "SD alto con un partner"
+    );
+}
+
+#[test]
+fn test_keyinput_is_not_serial() {
+    let mut bus = Bus::new();
+    assert_eq!(bus.read_halfword(0x04000130), 0x03FF);
+}
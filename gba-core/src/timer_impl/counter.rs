@@ -27,48 +27,43 @@ impl TimerCounter {
         self.cycles = 0;
     }
 
-    /// Step timer by CPU cycles, returns true if overflow occurred
-    pub fn step(&mut self, cpu_cycles: u32) -> bool {
+    /// Step timer by CPU cycles, returns how many times it overflowed
+    pub fn step(&mut self, cpu_cycles: u32) -> u32 {
         if !self.control.enabled || self.control.count_up {
-            return false;
+            return 0;
         }
 
         self.cycles += cpu_cycles;
         let prescaler = self.control.get_prescaler_cycles();
+        let ticks = self.cycles / prescaler;
+        self.cycles %= prescaler;
 
-        let mut overflowed = false;
-
-        while self.cycles >= prescaler {
-            self.cycles -= prescaler;
-
-            let (new_counter, overflow) = self.counter.overflowing_add(1);
-            self.counter = new_counter;
-
-            if overflow {
-                // Reload on overflow
-                self.counter = self.reload;
-                overflowed = true;
-            }
-        }
-
-        overflowed
+        self.add_ticks(ticks)
     }
 
-    /// Cascade increment (from previous timer overflow)
-    pub fn cascade_increment(&mut self) -> bool {
+    /// Cascade increment (`count` overflows of the previous timer),
+    /// returns how many times this timer overflowed
+    pub fn cascade_increment(&mut self, count: u32) -> u32 {
         if !self.control.enabled || !self.control.count_up {
-            return false;
+            return 0;
         }
 
-        let (new_counter, overflow) = self.counter.overflowing_add(1);
-        self.counter = new_counter;
+        self.add_ticks(count)
+    }
 
-        if overflow {
-            self.counter = self.reload;
-            true
-        } else {
-            false
+    /// Advance the counter by `ticks`, reloading on every overflow
+    fn add_ticks(&mut self, ticks: u32) -> u32 {
+        let until_overflow = 0x1_0000 - self.counter as u32;
+        if ticks < until_overflow {
+            self.counter += ticks as u16;
+            return 0;
         }
+
+        // After the first overflow the counter runs from reload to 0xFFFF
+        let period = 0x1_0000 - self.reload as u32;
+        let rest = ticks - until_overflow;
+        self.counter = self.reload + (rest % period) as u16;
+        1 + rest / period
     }
 
     /// Read counter value
@@ -9,7 +9,18 @@ pub use registers::TimerControl;
 
 use counter::TimerCounter;
 
+/// Outcome of stepping the timers
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TimerStepResult {
+    /// Interrupt flags to request (timer IRQs are bits 3-6)
+    pub irq_flags: u8,
+    /// Overflows of each timer in this step (each one clocks the Direct
+    /// Sound FIFOs): a long step can overflow a timer several times
+    pub overflows: [u32; TIMER_COUNT],
+}
+
 /// Timer system (4 hardware timers)
+#[derive(Clone)]
 pub struct Timer {
     timers: [TimerCounter; TIMER_COUNT],
 }
@@ -34,34 +45,26 @@ impl Timer {
     }
 
     /// Step timers by CPU cycles
-    pub fn step(&mut self, cycles: u32) -> u8 {
-        let mut irq_flags = 0u8;
+    pub fn step(&mut self, cycles: u32) -> TimerStepResult {
+        let mut result = TimerStepResult::default();
+        // Overflows of the previous timer, counted by a cascaded timer
+        let mut carry = 0;
 
-        // Process each timer
         for i in 0..TIMER_COUNT {
-            let overflow = if i > 0 && self.timers[i].control.count_up {
-                // Cascade mode: increment only on previous timer overflow
-                false // Will be handled by cascade logic below
+            let overflows = if i > 0 && self.timers[i].control.count_up {
+                self.timers[i].cascade_increment(carry)
             } else {
-                // Normal mode: increment by CPU cycles
                 self.timers[i].step(cycles)
             };
 
-            // Check for IRQ
-            if overflow && self.timers[i].control.irq_enable {
-                irq_flags |= 1 << (3 + i); // Timer IRQs are bits 3-6
-            }
-
-            // Handle cascade to next timer
-            if overflow && i < TIMER_COUNT - 1 {
-                let cascade_overflow = self.timers[i + 1].cascade_increment();
-                if cascade_overflow && self.timers[i + 1].control.irq_enable {
-                    irq_flags |= 1 << (3 + i + 1);
-                }
+            result.overflows[i] = overflows;
+            if overflows > 0 && self.timers[i].control.irq_enable {
+                result.irq_flags |= 1 << (3 + i); // Timer IRQs are bits 3-6
             }
+            carry = overflows;
         }
 
-        irq_flags
+        result
     }
 
     /// Read timer register
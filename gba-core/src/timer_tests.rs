@@ -53,11 +53,12 @@ fn test_timer_overflow() {
     timer.write_register(TM0CNT_H, 0x0080); // Enable, prescaler 1
 
     // Step past overflow
-    let irq = timer.step(5);
+    let result = timer.step(5);
 
     // Should overflow and reload
     assert_eq!(timer.read_register(TM0CNT_L), 0xFFFF); // Reloaded + 1
-    assert_eq!(irq, 0); // IRQ not enabled
+    assert_eq!(result.irq_flags, 0); // IRQ not enabled
+    assert_eq!(result.overflows, [2, 0, 0, 0]); // 0xFFFE -> 0xFFFF -> reload, twice
 }
 
 #[test]
@@ -69,7 +70,7 @@ fn test_timer_overflow_irq() {
     timer.write_register(TM0CNT_H, 0x00C0); // Enable + IRQ, prescaler 1
 
     // Step 1 cycle to overflow
-    let irq = timer.step(1);
+    let irq = timer.step(1).irq_flags;
 
     // Should set bit 3 (Timer 0 IRQ)
     assert_eq!(irq & (1 << 3), 1 << 3);
@@ -206,3 +207,37 @@ fn test_timer_enable_reloads() {
     // Now should increment
     assert_eq!(timer.read_register(TM0CNT_L), 0x1235);
 }
+
+#[test]
+fn test_cascade_overflow_reported() {
+    let mut timer = Timer::new();
+
+    // Timer 1 counts timer 0 overflows
+    timer.write_register(TM0CNT_L, 0xFFFF);
+    timer.write_register(TM0CNT_H, 0x0080);
+    timer.write_register(TM1CNT_L, 0xFFFF);
+    timer.write_register(TM1CNT_H, 0x0084); // Enable + count-up
+
+    let result = timer.step(1);
+    assert_eq!(result.overflows, [1, 1, 0, 0]);
+    assert_eq!(result.irq_flags, 0);
+}
+
+#[test]
+fn test_long_step_counts_every_overflow() {
+    let mut timer = Timer::new();
+
+    // Timer 0: period 100 cycles; timer 1 overflows every 4 of them
+    timer.write_register(TM0CNT_L, 0xFF9C);
+    timer.write_register(TM0CNT_H, 0x0080);
+    timer.write_register(TM1CNT_L, 0xFFFC);
+    timer.write_register(TM1CNT_H, 0x0084);
+    // Timer 2 counts timer 1 overflows
+    timer.write_register(TM2CNT_L, 0);
+    timer.write_register(TM2CNT_H, 0x0084);
+
+    let result = timer.step(1250);
+    assert_eq!(result.overflows, [12, 3, 0, 0]);
+    assert_eq!(timer.read_register(TM0CNT_L), 0xFF9C + 50);
+    assert_eq!(timer.read_register(TM2CNT_L), 3);
+}
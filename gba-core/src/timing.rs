@@ -0,0 +1,234 @@
+// Memory Timing - Wait state della cartridge (WAITCNT) e prefetch buffer
+//
+// Il GamePak ha un bus a 16 bit con wait state configurabili in WAITCNT
+// (0x04000204). Ogni accesso costa 1 ciclo + i wait state dell'area:
+// - accesso non sequenziale (N): indirizzo diverso dal successivo atteso
+// - accesso sequenziale (S): indirizzo successivo al precedente
+// Un accesso a 32 bit sono due accessi a 16 bit (N + S oppure S + S).
+//
+// Con il prefetch abilitato (WAITCNT bit 14) il GamePak legge in anticipo
+// fino a 8 halfword mentre la CPU esegue istruzioni: i fetch sequenziali
+// che trovano il dato nel buffer non pagano wait state. Un fetch non
+// sequenziale o un accesso dati alla ROM svuotano il buffer.
+//
+// Anche EWRAM, palette e VRAM hanno un bus a 16 bit: per questo il codice
+// THUMB (fetch da 16 bit) gira più veloce del codice ARM fuori da IWRAM.
+//
+// Opzionalmente (vedi Bus::set_video_contention) gli accessi della CPU a
+// palette, VRAM e OAM mentre il PPU disegna costano VIDEO_STALL_CYCLES in
+// più per accesso a 16 bit: sull'hardware la CPU aspetta che il PPU liberi
+// il bus. È un'approssimazione, disattivata di default.
+//
+// I wait state della EWRAM (2 di default) si cambiano con il registro non
+// documentato Internal Memory Control (0x04000800), bit 24-27: wait state
+// = 15 - valore. Alcuni homebrew lo portano a 0x0E per avere 1 wait state.
+
+/// Wait state N per WS0/WS1/WS2/SRAM (indice = campo di 2 bit)
+const N_WAIT_STATES: [u32; 4] = [4, 3, 2, 8];
+
+/// Wait state S per WS0, WS1, WS2 (indice = bit del campo)
+const S_WAIT_STATES: [[u32; 2]; 3] = [[2, 1], [4, 1], [8, 1]];
+
+/// Capacità del prefetch buffer (in halfword)
+pub const PREFETCH_CAPACITY: u32 = 8;
+
+/// Bit di WAITCNT che abilita il prefetch
+const PREFETCH_ENABLE: u16 = 1 << 14;
+
+/// Cicli di attesa per accesso a 16 bit alla memoria video durante il
+/// disegno (con la contesa abilitata)
+pub const VIDEO_STALL_CYCLES: u32 = 1;
+
+/// Valore di Internal Memory Control al reset: EWRAM abilitata (bit 5)
+/// con 2 wait state (bit 24-27 = 0x0D)
+pub const MEMCNT_DEFAULT: u32 = 0x0D00_0020;
+
+/// Timing degli accessi alla cartridge
+#[derive(Clone)]
+pub struct MemoryTiming {
+    waitcnt: u16,
+    /// Internal Memory Control (0x04000800)
+    memcnt: u32,
+
+    /// Wait state accumulati durante lo step CPU corrente
+    wait_cycles: u32,
+    /// Prossimo indirizzo ROM sequenziale atteso
+    next_rom_addr: Option<u32>,
+
+    /// Halfword pronte nel prefetch buffer
+    prefetch_count: u32,
+    /// Cicli spesi verso la prossima halfword
+    prefetch_progress: u32,
+}
+
+impl Default for MemoryTiming {
+    fn default() -> Self {
+        Self {
+            waitcnt: 0,
+            memcnt: MEMCNT_DEFAULT,
+            wait_cycles: 0,
+            next_rom_addr: None,
+            prefetch_count: 0,
+            prefetch_progress: 0,
+        }
+    }
+}
+
+impl MemoryTiming {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Leggi WAITCNT
+    pub fn read_waitcnt(&self) -> u16 {
+        self.waitcnt
+    }
+
+    /// Scrivi WAITCNT (bit 15 è read-only)
+    pub fn write_waitcnt(&mut self, value: u16) {
+        self.waitcnt = value & 0x7FFF;
+        if !self.prefetch_enabled() {
+            self.flush_prefetch();
+        }
+    }
+
+    /// Leggi Internal Memory Control
+    pub fn read_memcnt(&self) -> u32 {
+        self.memcnt
+    }
+
+    /// Scrivi Internal Memory Control
+    pub fn write_memcnt(&mut self, value: u32) {
+        self.memcnt = value;
+    }
+
+    /// Wait state della EWRAM (15 - bit 24-27 di Internal Memory Control)
+    ///
+    /// Con 0 wait state la console si blocca: qui si resta a 1.
+    pub fn ewram_wait(&self) -> u32 {
+        (15 - ((self.memcnt >> 24) & 0xF)).max(1)
+    }
+
+    /// Prefetch abilitato (WAITCNT bit 14)
+    pub fn prefetch_enabled(&self) -> bool {
+        self.waitcnt & PREFETCH_ENABLE != 0
+    }
+
+    /// Halfword attualmente nel prefetch buffer
+    pub fn prefetch_count(&self) -> u32 {
+        self.prefetch_count
+    }
+
+    /// Wait state di un accesso a 16 bit alla ROM
+    fn rom_wait(&self, addr: u32, sequential: bool) -> u32 {
+        // WS0: 0x08/0x09, WS1: 0x0A/0x0B, WS2: 0x0C/0x0D
+        let ws = (((addr >> 24) - 0x08) / 2).min(2) as usize;
+        let shift = 2 + ws * 3;
+
+        if sequential {
+            let s_bit = (self.waitcnt >> (shift + 2)) & 1;
+            S_WAIT_STATES[ws][s_bit as usize]
+        } else {
+            N_WAIT_STATES[((self.waitcnt >> shift) & 3) as usize]
+        }
+    }
+
+    /// Registra un accesso alla ROM di `size` byte (2 o 4)
+    ///
+    /// `fetch` distingue i fetch di istruzioni dagli accessi dati.
+    pub fn access_rom(&mut self, addr: u32, size: u32, fetch: bool) {
+        let sequential = self.next_rom_addr == Some(addr);
+        let halfwords = size.div_ceil(2);
+
+        if fetch && sequential && self.prefetch_enabled() && self.prefetch_count >= halfwords {
+            // Servito dal prefetch buffer
+            self.prefetch_count -= halfwords;
+        } else {
+            let first = self.rom_wait(addr, sequential);
+            let rest = (halfwords - 1) * (self.rom_wait(addr, true) + 1);
+            self.wait_cycles += first + rest;
+            self.flush_prefetch();
+        }
+
+        // Un accesso dati interrompe la sequenza dei fetch
+        self.next_rom_addr = fetch.then(|| addr.wrapping_add(size));
+    }
+
+    /// Cicli totali (1 + wait state) di un accesso di `size` byte (2 o 4)
+    ///
+    /// Non tocca il prefetch buffer: serve per i trasferimenti DMA, che
+    /// accedono al bus senza passare dalla CPU.
+    pub fn access_cycles(&self, addr: u32, size: u32, sequential: bool) -> u32 {
+        let word = size == 4;
+        match addr >> 24 {
+            // EWRAM: bus a 16 bit, 2 wait state salvo Internal Memory Control
+            0x02 => {
+                let cycles = 1 + self.ewram_wait();
+                if word {
+                    2 * cycles
+                } else {
+                    cycles
+                }
+            }
+            // Palette e VRAM: bus a 16 bit senza wait state
+            0x05 | 0x06 if word => 2,
+            0x05 | 0x06 => 1,
+            // GamePak: la seconda metà di una word è sempre sequenziale
+            0x08..=0x0D if word => {
+                1 + self.rom_wait(addr, sequential) + 1 + self.rom_wait(addr, true)
+            }
+            0x08..=0x0D => 1 + self.rom_wait(addr, sequential),
+            0x0E | 0x0F => 1 + N_WAIT_STATES[(self.waitcnt & 3) as usize],
+            // BIOS, IWRAM, I/O, OAM: bus a 32 bit
+            _ => 1,
+        }
+    }
+
+    /// Registra un accesso della CPU a EWRAM, palette o VRAM
+    ///
+    /// Sono aree con bus a 16 bit: un fetch ARM (32 bit) costa due accessi,
+    /// un fetch THUMB uno solo. EWRAM aggiunge anche i suoi wait state.
+    pub fn access_ram(&mut self, addr: u32, size: u32) {
+        self.wait_cycles += self.access_cycles(addr, size, true) - 1;
+    }
+
+    /// Registra l'attesa di un accesso della CPU alla memoria video mentre
+    /// il PPU disegna (OAM ha un bus a 32 bit: un solo accesso)
+    pub fn access_video_stall(&mut self, addr: u32, size: u32) {
+        let accesses = if addr >> 24 == 0x07 {
+            1
+        } else {
+            size.div_ceil(2)
+        };
+        self.wait_cycles += accesses * VIDEO_STALL_CYCLES;
+    }
+
+    /// Registra un accesso alla SRAM (bus a 8 bit)
+    pub fn access_sram(&mut self) {
+        self.wait_cycles += N_WAIT_STATES[(self.waitcnt & 3) as usize];
+    }
+
+    /// Chiude lo step CPU: il prefetch avanza durante i cicli di
+    /// esecuzione e ritorna i wait state da aggiungere allo step.
+    pub fn end_step(&mut self, cpu_cycles: u32) -> u32 {
+        if let (true, Some(next)) = (self.prefetch_enabled(), self.next_rom_addr) {
+            let cost = self.rom_wait(next, true) + 1;
+
+            self.prefetch_progress += cpu_cycles;
+            while self.prefetch_progress >= cost && self.prefetch_count < PREFETCH_CAPACITY {
+                self.prefetch_progress -= cost;
+                self.prefetch_count += 1;
+            }
+            if self.prefetch_count == PREFETCH_CAPACITY {
+                self.prefetch_progress = 0;
+            }
+        }
+
+        core::mem::take(&mut self.wait_cycles)
+    }
+
+    fn flush_prefetch(&mut self) {
+        self.prefetch_count = 0;
+        self.prefetch_progress = 0;
+    }
+}
@@ -0,0 +1,226 @@
+use crate::bus::Bus;
+use crate::bus::MEMCNT;
+use crate::timing::{MemoryTiming, MEMCNT_DEFAULT, PREFETCH_CAPACITY, VIDEO_STALL_CYCLES};
+use gba_arm7tdmi::cpu::MemoryBus;
+use gba_arm7tdmi::ARM7TDMI;
+
+/// Loop THUMB in ROM: 7x LDR r1,[r0] (da IWRAM) + B all'inizio
+fn rom_loop_bus(waitcnt: u16) -> Bus {
+    let mut rom = Vec::new();
+    for _ in 0..7 {
+        rom.extend_from_slice(&0x6801u16.to_le_bytes());
+    }
+    rom.extend_from_slice(&0xE7F7u16.to_le_bytes());
+
+    let mut bus = Bus::new();
+    bus.load_rom(rom);
+    bus.write_halfword(0x04000204, waitcnt);
+    bus
+}
+
+/// Esegue `steps` istruzioni e ritorna i cicli totali (wait state inclusi)
+fn run_rom_loop(waitcnt: u16, steps: usize) -> u32 {
+    let mut bus = rom_loop_bus(waitcnt);
+    let mut cpu = ARM7TDMI::new();
+    cpu.regs.set_thumb(true);
+    cpu.regs.set_pc(0x08000000);
+    cpu.regs.r[0] = 0x03000000;
+
+    let mut total = 0;
+    for _ in 0..steps {
+        let cycles = cpu.step(&mut bus);
+        total += cycles + bus.timing.end_step(cycles);
+    }
+    total
+}
+
+#[test]
+fn test_waitcnt_register() {
+    let mut bus = Bus::new();
+    bus.write_halfword(0x04000204, 0xFFFF);
+    // Bit 15 (tipo di cartridge) è read-only
+    assert_eq!(bus.read_halfword(0x04000204), 0x7FFF);
+    assert!(bus.timing.prefetch_enabled());
+}
+
+#[test]
+fn test_memcnt_fast_ewram() {
+    let mut bus = Bus::new();
+    assert_eq!(bus.read_word(MEMCNT), MEMCNT_DEFAULT);
+    assert_eq!(bus.timing.ewram_wait(), 2);
+    assert_eq!(bus.timing.access_cycles(0x02000000, 2, false), 3);
+    assert_eq!(bus.timing.access_cycles(0x02000000, 4, false), 6);
+
+    // Bit 24-27 = 0x0E: EWRAM con 1 wait state
+    bus.write_word(MEMCNT, 0x0E00_0020);
+    assert_eq!(bus.read_word(MEMCNT), 0x0E00_0020);
+    assert_eq!(bus.timing.ewram_wait(), 1);
+    assert_eq!(bus.timing.access_cycles(0x02000000, 2, false), 2);
+    assert_eq!(bus.timing.access_cycles(0x02000000, 4, false), 4);
+
+    // Anche una lettura della CPU costa meno
+    bus.timing.end_step(0);
+    bus.read_halfword(0x02000000);
+    assert_eq!(bus.timing.end_step(0), 1);
+
+    // Non è un alias di DISPCNT
+    assert_eq!(bus.read_halfword(0x04000000), 0);
+}
+
+#[test]
+fn test_rom_wait_states() {
+    let mut timing = MemoryTiming::new();
+
+    // WS0 default: N = 4, S = 2
+    timing.access_rom(0x08000000, 2, true);
+    assert_eq!(timing.end_step(0), 4);
+    timing.access_rom(0x08000002, 2, true);
+    assert_eq!(timing.end_step(0), 2);
+
+    // Accesso a 32 bit: N + (1 + S)
+    timing.access_rom(0x08000100, 4, false);
+    assert_eq!(timing.end_step(0), 4 + 3);
+
+    // WS0 N = 2, S = 1
+    timing.write_waitcnt(0x0018);
+    timing.access_rom(0x08000000, 2, true);
+    timing.access_rom(0x08000002, 2, true);
+    assert_eq!(timing.end_step(0), 2 + 1);
+}
+
+#[test]
+fn test_prefetch_fills_and_flushes() {
+    let mut timing = MemoryTiming::new();
+    timing.write_waitcnt(0x4000);
+
+    timing.access_rom(0x08000000, 2, true);
+    timing.end_step(9);
+    assert_eq!(timing.prefetch_count(), 3);
+
+    // Fetch sequenziale servito dal buffer senza wait state
+    timing.access_rom(0x08000002, 2, true);
+    assert_eq!(timing.end_step(0), 0);
+    assert_eq!(timing.prefetch_count(), 2);
+
+    // Un accesso dati alla ROM svuota il buffer
+    timing.access_rom(0x08001000, 2, false);
+    assert_eq!(timing.prefetch_count(), 0);
+
+    // Il buffer non supera la capacità
+    timing.access_rom(0x08000000, 2, true);
+    timing.end_step(1000);
+    assert_eq!(timing.prefetch_count(), PREFETCH_CAPACITY);
+}
+
+#[test]
+fn test_prefetch_speeds_up_rom_loop() {
+    let without_prefetch = run_rom_loop(0x0000, 800);
+    let with_prefetch = run_rom_loop(0x4000, 800);
+
+    assert!(
+        with_prefetch < without_prefetch,
+        "prefetch on: {} cicli, off: {} cicli",
+        with_prefetch,
+        without_prefetch
+    );
+}
+
+/// Esegue `steps` istruzioni a partire da `pc` e ritorna i cicli totali
+fn run_code(bus: &mut Bus, pc: u32, thumb: bool, steps: usize) -> u32 {
+    let mut cpu = ARM7TDMI::new();
+    cpu.regs.set_thumb(thumb);
+    cpu.regs.set_pc(pc);
+    // Scarta i wait state accumulati preparando il codice
+    bus.timing.end_step(0);
+
+    let mut total = 0;
+    for _ in 0..steps {
+        let cycles = cpu.step(bus);
+        total += cycles + bus.timing.end_step(cycles);
+    }
+    total
+}
+
+/// Stesso codice in ARM (MOV r1, r1) e in THUMB (MOV r1, r1), 16 istruzioni
+fn arm_and_thumb_code() -> (Vec<u8>, Vec<u8>) {
+    let arm = 0xE1A0_1001u32.to_le_bytes().repeat(16);
+    let thumb = 0x1C09u16.to_le_bytes().repeat(16);
+    (arm, thumb)
+}
+
+#[test]
+fn test_thumb_fetch_faster_than_arm_from_rom() {
+    let (arm, thumb) = arm_and_thumb_code();
+
+    let mut bus = Bus::new();
+    bus.load_rom(arm);
+    let arm_cycles = run_code(&mut bus, 0x08000000, false, 16);
+
+    let mut bus = Bus::new();
+    bus.load_rom(thumb);
+    let thumb_cycles = run_code(&mut bus, 0x08000000, true, 16);
+
+    // WS0 default (N = 4, S = 2), 1 ciclo per istruzione:
+    // ARM: 1 + (N + 1 + S) poi 1 + (S + 1 + S), THUMB: 1 + N poi 1 + S
+    assert_eq!(arm_cycles, 8 + 15 * 6);
+    assert_eq!(thumb_cycles, 5 + 15 * 3);
+}
+
+#[test]
+fn test_thumb_fetch_faster_than_arm_from_ewram() {
+    let (arm, thumb) = arm_and_thumb_code();
+
+    let mut bus = Bus::new();
+    for (i, byte) in arm.iter().enumerate() {
+        bus.write_byte(0x02000000 + i as u32, *byte);
+    }
+    for (i, byte) in thumb.iter().enumerate() {
+        bus.write_byte(0x02001000 + i as u32, *byte);
+    }
+
+    // EWRAM: 3 cicli per halfword, 6 per word
+    assert_eq!(run_code(&mut bus, 0x02000000, false, 16), 16 * 6);
+    assert_eq!(run_code(&mut bus, 0x02001000, true, 16), 16 * 3);
+
+    // IWRAM (bus a 32 bit): nessuna differenza
+    for (i, byte) in arm.iter().enumerate() {
+        bus.write_byte(0x03000000 + i as u32, *byte);
+    }
+    for (i, byte) in thumb.iter().enumerate() {
+        bus.write_byte(0x03001000 + i as u32, *byte);
+    }
+    assert_eq!(run_code(&mut bus, 0x03000000, false, 16), 16);
+    assert_eq!(run_code(&mut bus, 0x03001000, true, 16), 16);
+}
+
+#[test]
+fn test_video_contention_while_drawing() {
+    let mut bus = Bus::new();
+    bus.ppu.scanline = 10;
+    bus.ppu.cycles = 100;
+    assert!(bus.ppu.is_drawing());
+
+    // Disattivata di default
+    bus.timing.end_step(0);
+    bus.write_word(0x06000000, 0x1234_5678);
+    let fast = bus.timing.end_step(0);
+    assert_eq!(fast, 1);
+
+    bus.set_video_contention(true);
+    bus.write_word(0x06000000, 0x1234_5678);
+    let drawing = bus.timing.end_step(0);
+    assert_eq!(drawing, fast + 2 * VIDEO_STALL_CYCLES);
+
+    // In VBlank il bus video è libero
+    bus.ppu.scanline = 170;
+    bus.write_word(0x06000000, 0x1234_5678);
+    let vblank = bus.timing.end_step(0);
+    assert_eq!(vblank, fast);
+    assert!(drawing > vblank);
+
+    // Anche in HBlank
+    bus.ppu.scanline = 10;
+    bus.ppu.cycles = 1000;
+    bus.write_halfword(0x05000000, 0x7FFF);
+    assert_eq!(bus.timing.end_step(0), 0);
+}
@@ -77,3 +77,18 @@ fn test_mode3_full_scanline() {
     assert!(fb[0] < 0x0400, "First pixel should be dark red");
     assert!(fb[239] > 0x7000, "Last pixel should be bright red");
 }
+
+#[test]
+fn test_vram_byte_write_duplicates() {
+    let mut emu = GbaEmulator::new();
+
+    // Mode 3: scrittura a 8 bit in area BG duplica il byte nell'halfword
+    emu.bus.write_halfword(0x04000000, 0x0403);
+    emu.bus.write_byte(0x06000001, 0x1F);
+    assert_eq!(emu.bus.read_halfword(0x06000000), 0x1F1F);
+
+    // Area OBJ (0x06014000+ in bitmap mode): scrittura a 8 bit ignorata
+    emu.bus.write_halfword(0x06014000, 0x1234);
+    emu.bus.write_byte(0x06014000, 0xFF);
+    assert_eq!(emu.bus.read_halfword(0x06014000), 0x1234);
+}
@@ -0,0 +1,29 @@
+// Salvataggi in memoria, senza accesso ai file
+//
+// Va eseguito anche senza la feature `std`, per verificare che il core
+// compili senza la persistenza su file:
+//   cargo test -p gba-core --no-default-features
+
+use gba_arm7tdmi::cpu::MemoryBus;
+use gba_core::save::SaveType;
+use gba_core::GbaEmulator;
+
+#[test]
+fn test_save_round_trip_through_bytes() {
+    let mut emu = GbaEmulator::new();
+    emu.set_save_type(SaveType::Sram);
+
+    emu.bus.write_byte(0x0E000000, 0x5A);
+    emu.bus.write_byte(0x0E001234, 0xC3);
+    assert!(emu.save_modified());
+
+    let data = emu.save_to_bytes().unwrap();
+    assert_eq!(data.len(), SaveType::Sram.size());
+    assert!(!emu.save_modified());
+
+    let mut other = GbaEmulator::new();
+    other.set_save_type(SaveType::Sram);
+    other.load_from_bytes(data);
+    assert_eq!(other.bus.read_byte(0x0E000000), 0x5A);
+    assert_eq!(other.bus.read_byte(0x0E001234), 0xC3);
+}
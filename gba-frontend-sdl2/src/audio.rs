@@ -0,0 +1,128 @@
+// Output audio via SDL2 AudioQueue
+
+use anyhow::Result;
+use gba_core::GbaEmulator;
+use sdl2::audio::{AudioQueue, AudioSpecDesired};
+use sdl2::Sdl;
+
+/// Sample rate richiesto al device
+const DEVICE_SAMPLE_RATE: i32 = 48000;
+
+/// Dimensione del buffer del device (in sample)
+const DEVICE_BUFFER_SAMPLES: u16 = 1024;
+
+/// Latenza massima in coda prima di scartare audio (in sample stereo)
+const MAX_QUEUED_SAMPLES: u32 = 4096;
+
+/// Sample stereo di silenzio accodati in caso di underrun
+const UNDERRUN_PADDING_SAMPLES: usize = 512;
+
+/// Pipeline audio: APU -> resampler -> SDL AudioQueue
+pub struct AudioOutput {
+    queue: Option<AudioQueue<i16>>,
+    buffer: Vec<i16>,
+}
+
+impl AudioOutput {
+    /// Apre il device audio e configura il sample rate dell'APU
+    ///
+    /// Con `muted` non viene aperto alcun device: i sample vengono scartati.
+    pub fn open(sdl_context: &Sdl, emulator: &mut GbaEmulator, muted: bool) -> Result<Self> {
+        if muted {
+            log::info!("Audio muted");
+            return Ok(Self::muted());
+        }
+
+        let audio_subsystem = sdl_context
+            .audio()
+            .map_err(|e| anyhow::anyhow!("Failed to initialize audio: {}", e))?;
+
+        let desired = AudioSpecDesired {
+            freq: Some(DEVICE_SAMPLE_RATE),
+            channels: Some(2),
+            samples: Some(DEVICE_BUFFER_SAMPLES),
+        };
+
+        let queue = audio_subsystem
+            .open_queue::<i16, _>(None, &desired)
+            .map_err(|e| anyhow::anyhow!("Failed to open audio device: {}", e))?;
+
+        let rate = queue.spec().freq as u32;
+        log::info!("Audio device opened at {} Hz", rate);
+        emulator.set_audio_output_rate(rate);
+        queue.resume();
+
+        Ok(Self {
+            queue: Some(queue),
+            buffer: Vec::new(),
+        })
+    }
+
+    /// Pipeline senza device (audio disabilitato)
+    pub fn muted() -> Self {
+        Self {
+            queue: None,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Trasferisce i sample generati nell'ultimo frame al device
+    pub fn queue_frame(&mut self, emulator: &mut GbaEmulator) {
+        drain_pending(emulator, &mut self.buffer);
+
+        let Some(queue) = &self.queue else {
+            return;
+        };
+
+        // size() è in byte: 2 canali * 2 byte per sample
+        let queued = queue.size() / 4;
+
+        if queued > MAX_QUEUED_SAMPLES {
+            // Troppa latenza: riparti da zero
+            queue.clear();
+        } else if queued == 0 {
+            // Underrun: accoda silenzio per evitare click
+            let silence = vec![0i16; UNDERRUN_PADDING_SAMPLES * 2];
+            if let Err(e) = queue.queue_audio(&silence) {
+                log::warn!("Audio queue error: {}", e);
+            }
+        }
+
+        if !self.buffer.is_empty() {
+            if let Err(e) = queue.queue_audio(&self.buffer) {
+                log::warn!("Audio queue error: {}", e);
+            }
+        }
+    }
+
+    /// Scarta i sample dell'ultimo frame (fast-forward)
+    pub fn discard_frame(&mut self, emulator: &mut GbaEmulator) {
+        drain_pending(emulator, &mut self.buffer);
+        self.buffer.clear();
+    }
+}
+
+/// Svuota il ring buffer dell'APU in `buffer`
+fn drain_pending(emulator: &mut GbaEmulator, buffer: &mut Vec<i16>) {
+    buffer.clear();
+    emulator.drain_audio(buffer);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drain_empty_ring_buffer() {
+        let mut emulator = GbaEmulator::new();
+        let mut buffer = vec![1, 2, 3];
+
+        drain_pending(&mut emulator, &mut buffer);
+        assert!(buffer.is_empty());
+
+        // Pipeline muta: nessun panic senza device
+        let mut output = AudioOutput::muted();
+        output.queue_frame(&mut emulator);
+        output.queue_frame(&mut emulator);
+    }
+}
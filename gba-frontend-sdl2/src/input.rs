@@ -1,53 +1,300 @@
-// Input handling placeholder
-// TODO: Implementare mappatura tasti GBA
+// Input - Mappatura tastiera/gamepad -> pulsanti GBA
+//
+// Le associazioni sono configurabili (KeyConfig) e salvate in un file di
+// testo, una riga per pulsante:
+//
+//     # commento
+//     A = key:Z, pad:a
+//     Start = key:Return, pad:start
+//
+// I tasti usano i nomi degli scancode SDL (indipendenti dal layout), i
+// pulsanti del gamepad i nomi dei GameController SDL (a, b, dpup, ...).
 
-use sdl2::keyboard::Keycode;
+use anyhow::{anyhow, Context, Result};
+use gba_core::Button;
+use sdl2::controller::Button as PadButton;
+use sdl2::keyboard::Scancode;
+use std::collections::HashMap;
+use std::path::Path;
 
-#[allow(dead_code)]
-#[derive(Debug, Clone, Copy)]
-pub enum GbaButton {
-    A,
-    B,
-    L,
-    R,
-    Start,
-    Select,
-    Up,
-    Down,
-    Left,
-    Right,
+/// Associazioni tasti/pulsanti gamepad -> pulsanti GBA
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyConfig {
+    keys: HashMap<Scancode, Button>,
+    pad: HashMap<PadButton, Button>,
 }
 
-#[allow(dead_code)]
-pub struct InputMapper {
-    // TODO: Implementazione mappatura input
+impl KeyConfig {
+    /// Configurazione vuota (nessuna associazione)
+    pub fn empty() -> Self {
+        Self {
+            keys: HashMap::new(),
+            pad: HashMap::new(),
+        }
+    }
+
+    /// Associa un tasto a un pulsante (sostituisce l'associazione del tasto)
+    pub fn bind_key(mut self, button: Button, key: Scancode) -> Self {
+        self.set_key(button, key);
+        self
+    }
+
+    /// Associa un pulsante del gamepad a un pulsante GBA
+    pub fn bind_pad(mut self, button: Button, pad: PadButton) -> Self {
+        self.set_pad(button, pad);
+        self
+    }
+
+    pub fn set_key(&mut self, button: Button, key: Scancode) {
+        self.keys.insert(key, button);
+    }
+
+    pub fn set_pad(&mut self, button: Button, pad: PadButton) {
+        self.pad.insert(pad, button);
+    }
+
+    pub fn key_button(&self, key: Scancode) -> Option<Button> {
+        self.keys.get(&key).copied()
+    }
+
+    pub fn pad_button(&self, pad: PadButton) -> Option<Button> {
+        self.pad.get(&pad).copied()
+    }
+
+    /// Parsa una configurazione nel formato `Pulsante = key:Nome, pad:nome`
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut config = Self::empty();
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (name, bindings) = line
+                .split_once('=')
+                .ok_or_else(|| anyhow!("line {}: expected `Button = bindings`", number + 1))?;
+            let button = Button::from_name(name.trim())
+                .ok_or_else(|| anyhow!("line {}: unknown button `{}`", number + 1, name.trim()))?;
+
+            for binding in bindings.split(',').map(str::trim).filter(|b| !b.is_empty()) {
+                match binding.split_once(':') {
+                    Some(("key", key)) => {
+                        let key = Scancode::from_name(key.trim())
+                            .ok_or_else(|| anyhow!("line {}: unknown key `{}`", number + 1, key))?;
+                        config.set_key(button, key);
+                    }
+                    Some(("pad", pad)) => {
+                        let pad = PadButton::from_string(pad.trim()).ok_or_else(|| {
+                            anyhow!("line {}: unknown gamepad button `{}`", number + 1, pad)
+                        })?;
+                        config.set_pad(button, pad);
+                    }
+                    _ => {
+                        return Err(anyhow!(
+                            "line {}: invalid binding `{}`",
+                            number + 1,
+                            binding
+                        ))
+                    }
+                }
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Serializza nel formato letto da `parse`
+    pub fn to_config_string(&self) -> String {
+        let mut out = String::from("# GBA key bindings\n");
+
+        for button in Button::ALL {
+            let mut bindings: Vec<String> = self
+                .keys
+                .iter()
+                .filter(|(_, b)| **b == button)
+                .map(|(key, _)| format!("key:{}", key.name()))
+                .collect();
+            bindings.sort();
+
+            let mut pad: Vec<String> = self
+                .pad
+                .iter()
+                .filter(|(_, b)| **b == button)
+                .map(|(pad, _)| format!("pad:{}", pad.string()))
+                .collect();
+            pad.sort();
+            bindings.extend(pad);
+
+            out.push_str(&format!("{} = {}\n", button.name(), bindings.join(", ")));
+        }
+
+        out
+    }
+
+    /// Carica la configurazione da file
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read key config: {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("Invalid key config: {}", path.display()))
+    }
+
+    /// Salva la configurazione su file
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, self.to_config_string())
+            .with_context(|| format!("Failed to write key config: {}", path.display()))
+    }
+}
+
+impl Default for KeyConfig {
+    /// Tastiera: frecce, Z/X, A/S, Invio/Backspace. Gamepad: layout standard.
+    fn default() -> Self {
+        Self::empty()
+            .bind_key(Button::Up, Scancode::Up)
+            .bind_key(Button::Down, Scancode::Down)
+            .bind_key(Button::Left, Scancode::Left)
+            .bind_key(Button::Right, Scancode::Right)
+            .bind_key(Button::A, Scancode::Z)
+            .bind_key(Button::B, Scancode::X)
+            .bind_key(Button::L, Scancode::A)
+            .bind_key(Button::R, Scancode::S)
+            .bind_key(Button::Start, Scancode::Return)
+            .bind_key(Button::Select, Scancode::Backspace)
+            .bind_pad(Button::Up, PadButton::DPadUp)
+            .bind_pad(Button::Down, PadButton::DPadDown)
+            .bind_pad(Button::Left, PadButton::DPadLeft)
+            .bind_pad(Button::Right, PadButton::DPadRight)
+            .bind_pad(Button::A, PadButton::B)
+            .bind_pad(Button::B, PadButton::A)
+            .bind_pad(Button::L, PadButton::LeftShoulder)
+            .bind_pad(Button::R, PadButton::RightShoulder)
+            .bind_pad(Button::Start, PadButton::Start)
+            .bind_pad(Button::Select, PadButton::Back)
+    }
+}
+
+/// Remap interattivo: chiede un tasto per ogni pulsante GBA in sequenza
+pub struct Remapper {
+    next: usize,
 }
 
-#[allow(dead_code)]
-impl InputMapper {
+impl Remapper {
     pub fn new() -> Self {
-        Self {}
-    }
-    
-    pub fn map_key(&self, key: Keycode) -> Option<GbaButton> {
-        match key {
-            Keycode::Z => Some(GbaButton::A),
-            Keycode::X => Some(GbaButton::B),
-            Keycode::A => Some(GbaButton::L),
-            Keycode::S => Some(GbaButton::R),
-            Keycode::Return => Some(GbaButton::Start),
-            Keycode::Backspace => Some(GbaButton::Select),
-            Keycode::Up => Some(GbaButton::Up),
-            Keycode::Down => Some(GbaButton::Down),
-            Keycode::Left => Some(GbaButton::Left),
-            Keycode::Right => Some(GbaButton::Right),
-            _ => None,
-        }
+        Self { next: 0 }
+    }
+
+    /// Pulsante GBA in attesa di un'associazione
+    pub fn current(&self) -> Option<Button> {
+        Button::ALL.get(self.next).copied()
+    }
+
+    /// Associa il tasto premuto al pulsante corrente e passa al successivo
+    ///
+    /// Se il tasto era già di un altro pulsante le associazioni vengono
+    /// scambiate: ritorna il pulsante che ha ricevuto i vecchi tasti.
+    pub fn assign_key(&mut self, config: &mut KeyConfig, key: Scancode) -> Option<Button> {
+        let button = self.current()?;
+        let swapped = swap_binding(&mut config.keys, button, key);
+        self.next += 1;
+        swapped
+    }
+
+    /// Associa il pulsante del gamepad premuto al pulsante corrente
+    /// (scambiando le associazioni come `assign_key`)
+    pub fn assign_pad(&mut self, config: &mut KeyConfig, pad: PadButton) -> Option<Button> {
+        let button = self.current()?;
+        let swapped = swap_binding(&mut config.pad, button, pad);
+        self.next += 1;
+        swapped
+    }
+}
+
+/// Associa `input` a `button` togliendogli le associazioni precedenti; se
+/// `input` apparteneva a un altro pulsante, quello riceve le vecchie
+fn swap_binding<K: Copy + Eq + std::hash::Hash>(
+    bindings: &mut HashMap<K, Button>,
+    button: Button,
+    input: K,
+) -> Option<Button> {
+    let other = bindings.get(&input).copied().filter(|b| *b != button);
+    let previous: Vec<K> = bindings
+        .iter()
+        .filter(|(_, b)| **b == button)
+        .map(|(k, _)| *k)
+        .collect();
+
+    bindings.retain(|_, b| *b != button);
+    bindings.insert(input, button);
+
+    let other = other?;
+    for old in previous.into_iter().filter(|k| *k != input) {
+        bindings.insert(old, other);
     }
+    Some(other)
 }
 
-impl Default for InputMapper {
+impl Default for Remapper {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_round_trip() {
+        let config = KeyConfig::default()
+            .bind_key(Button::A, Scancode::K)
+            .bind_pad(Button::Start, PadButton::Guide);
+
+        let text = config.to_config_string();
+        assert_eq!(KeyConfig::parse(&text).unwrap(), config);
+        assert_eq!(config.key_button(Scancode::K), Some(Button::A));
+        // Il vecchio tasto resta associato: più tasti per lo stesso pulsante
+        assert_eq!(config.key_button(Scancode::Z), Some(Button::A));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(KeyConfig::parse("Turbo = key:Z").is_err());
+        assert!(KeyConfig::parse("A = key:NotAKey").is_err());
+        assert!(KeyConfig::parse("A = mouse:left").is_err());
+        assert!(KeyConfig::parse("A").is_err());
+        assert_eq!(KeyConfig::parse("# vuoto\n\n").unwrap(), KeyConfig::empty());
+    }
+
+    #[test]
+    fn test_remapper_replaces_bindings() {
+        let mut config = KeyConfig::default();
+        let mut remapper = Remapper::new();
+
+        assert_eq!(remapper.current(), Some(Button::Up));
+        remapper.assign_key(&mut config, Scancode::W);
+        assert_eq!(config.key_button(Scancode::W), Some(Button::Up));
+        assert_eq!(config.key_button(Scancode::Up), None);
+
+        for _ in 1..Button::ALL.len() {
+            remapper.assign_pad(&mut config, PadButton::X);
+        }
+        assert_eq!(remapper.current(), None);
+        assert_eq!(config.pad_button(PadButton::X), Some(Button::Select));
+    }
+
+    #[test]
+    fn test_remapper_swaps_duplicate_key() {
+        let mut config = KeyConfig::default();
+        let mut remapper = Remapper::new();
+
+        // Z era di A: Up lo prende e A riceve la freccia su
+        assert_eq!(remapper.assign_key(&mut config, Scancode::Z), Some(Button::A));
+        assert_eq!(config.key_button(Scancode::Z), Some(Button::Up));
+        assert_eq!(config.key_button(Scancode::Up), Some(Button::A));
+
+        // Nessuno scambio se il tasto è libero o già del pulsante corrente
+        assert_eq!(remapper.assign_key(&mut config, Scancode::Down), None);
+        assert_eq!(remapper.assign_pad(&mut config, PadButton::Y), None);
+        assert_eq!(config.pad_button(PadButton::Y), Some(Button::Left));
+    }
+}
@@ -1,11 +1,19 @@
 mod ui;
 mod input;
+mod audio;
+mod screenshot;
+mod speed;
 
+use gba_core::emulator::BIOS_INTRO_MAX_CYCLES;
 use gba_core::{Cartridge, GbaEmulator};
+use speed::SpeedControl;
 use std::env;
 use std::path::PathBuf;
 use anyhow::{Context, Result};
 
+/// File delle associazioni tasti usato se non si passa `--keys`
+const DEFAULT_KEY_CONFIG: &str = "gba-keys.cfg";
+
 fn main() -> Result<()> {
     // Inizializza logging
     env_logger::Builder::from_default_env()
@@ -19,10 +27,18 @@ fn main() -> Result<()> {
     let args: Vec<String> = env::args().collect();
     
     if args.len() < 2 {
-        eprintln!("Usage: {} <rom_file> [--bios <bios_file>]", args[0]);
+        eprintln!("Usage: {} <rom_file> [--bios <bios_file>] [--skip-bios] [--fast-intro] [--keys <config_file>] [--frameskip <n>] [--turbo <n>] [--fps <rate>] [--vsync] [--color-correction] [--no-header-check] [--import-save <save_file>] [--mute]", args[0]);
         eprintln!("\nExample:");
         eprintln!("  {} pokemon_emerald.gba", args[0]);
+        eprintln!("  {} pokemon_emerald.zip", args[0]);
         eprintln!("  {} pokemon_emerald.gba --bios gba_bios.bin", args[0]);
+        eprintln!("  {} pokemon_emerald.gba --bios gba_bios.bin --skip-bios", args[0]);
+        eprintln!("  {} pokemon_emerald.gba --bios gba_bios.bin --fast-intro", args[0]);
+        eprintln!("  {} pokemon_emerald.gba --mute", args[0]);
+        eprintln!("  {} pokemon_emerald.gba --keys my_keys.cfg", args[0]);
+        eprintln!("  {} pokemon_emerald.gba --frameskip 1", args[0]);
+        eprintln!("  {} pokemon_emerald.gba --vsync", args[0]);
+        eprintln!("  {} pokemon_emerald.gba --import-save backup.sav", args[0]);
         std::process::exit(1);
     }
     
@@ -31,32 +47,108 @@ fn main() -> Result<()> {
         .position(|arg| arg == "--bios")
         .and_then(|i| args.get(i + 1))
         .map(PathBuf::from);
+    let key_config_path = args.iter()
+        .position(|arg| arg == "--keys")
+        .and_then(|i| args.get(i + 1))
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_KEY_CONFIG));
+    let import_save_path = args.iter()
+        .position(|arg| arg == "--import-save")
+        .and_then(|i| args.get(i + 1))
+        .map(PathBuf::from);
+    let mute = args.iter().any(|arg| arg == "--mute");
+    let frame_skip = match args.iter().position(|arg| arg == "--frameskip") {
+        Some(i) => args
+            .get(i + 1)
+            .and_then(|n| n.parse().ok())
+            .context("--frameskip expects a number of frames")?,
+        None => 0,
+    };
+    let turbo_frames = match args.iter().position(|arg| arg == "--turbo") {
+        Some(i) => args
+            .get(i + 1)
+            .and_then(|n| n.parse().ok())
+            .context("--turbo expects a number of frames")?,
+        None => speed::DEFAULT_TURBO_FRAMES,
+    };
+    let target_fps = match args.iter().position(|arg| arg == "--fps") {
+        Some(i) => args
+            .get(i + 1)
+            .and_then(|n| n.parse().ok())
+            .filter(|fps: &f64| fps.is_finite() && *fps > 0.0)
+            .context("--fps expects a positive frame rate")?,
+        None => speed::GBA_FRAME_RATE,
+    };
+    let vsync = args.iter().any(|arg| arg == "--vsync");
+    let color_correction = args.iter().any(|arg| arg == "--color-correction");
+    // Con un BIOS reale: salta l'intro e usa comunque l'HLE
+    let skip_bios = args.iter().any(|arg| arg == "--skip-bios");
+    let fast_intro = args.iter().any(|arg| arg == "--fast-intro");
+    // Homebrew senza logo Nintendo valido
+    let check_header = !args.iter().any(|arg| arg == "--no-header-check");
     
     // Crea emulatore
     let mut emulator = GbaEmulator::new();
+    emulator.set_color_correction(color_correction);
     
     // Carica BIOS (opzionale)
     if let Some(bios_path) = bios_path {
         log::info!("Loading BIOS from: {}", bios_path.display());
         let bios = std::fs::read(&bios_path)
             .with_context(|| format!("Failed to load BIOS: {}", bios_path.display()))?;
-        emulator.load_bios(bios);
+        emulator
+            .load_bios(bios)
+            .with_context(|| format!("Invalid BIOS: {}", bios_path.display()))?;
+        if skip_bios {
+            log::info!("Skipping BIOS intro - using HLE (High Level Emulation)");
+            emulator.set_bios_hle(true);
+        }
     } else {
         log::warn!("No BIOS provided - using HLE (High Level Emulation)");
-        // TODO: Implementa HLE BIOS
     }
     
-    // Carica ROM
+    // Carica ROM (le immagini .mb partono dalla EWRAM)
     log::info!("Loading ROM from: {}", rom_path.display());
-    let cartridge = Cartridge::load(&rom_path)
-        .with_context(|| format!("Failed to load ROM: {}", rom_path.display()))?;
+    let is_multiboot = rom_path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("mb"));
+    if is_multiboot {
+        let image = std::fs::read(&rom_path)
+            .with_context(|| format!("Failed to load multiboot ROM: {}", rom_path.display()))?;
+        emulator
+            .load_multiboot(image)
+            .with_context(|| format!("Failed to load multiboot ROM: {}", rom_path.display()))?;
+    } else {
+        let cartridge = Cartridge::load_with_validation(&rom_path, check_header)
+            .with_context(|| format!("Failed to load ROM: {}", rom_path.display()))?;
+
+        emulator.load_cartridge(cartridge);
+        emulator.reset();
+    }
+
+    // Intro del BIOS reale eseguita a velocità piena, senza video né audio
+    if fast_intro && !emulator.bios_hle() {
+        log::info!("Fast-forwarding through the BIOS intro");
+        if !emulator.skip_bios_intro(BIOS_INTRO_MAX_CYCLES) {
+            log::warn!("BIOS intro did not reach the ROM entry point");
+        }
+    }
+    
+    // Salvataggio scelto dall'utente al posto del .sav accanto alla ROM
+    if let Some(save_path) = import_save_path {
+        emulator
+            .import_save(&save_path)
+            .with_context(|| format!("Failed to import save: {}", save_path.display()))?;
+    }
     
-    emulator.load_cartridge(cartridge);
-    emulator.reset();
+    let speed = SpeedControl::new(frame_skip)
+        .with_turbo_frames(turbo_frames)
+        .with_target_fps(target_fps)
+        .with_vsync(vsync);
     
     // Avvia UI
     log::info!("Starting emulator...");
-    ui::run(emulator)?;
+    ui::run(emulator, mute, &key_config_path, speed)?;
     
     Ok(())
 }
@@ -0,0 +1,61 @@
+// Screenshot - Esporta il framebuffer corrente in PNG
+
+use anyhow::{Context, Result};
+use gba_core::ppu::{SCREEN_HEIGHT, SCREEN_WIDTH};
+use gba_core::GbaEmulator;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Salva lo schermo corrente in `dir` con un nome basato sull'ora
+pub fn save_screenshot(emulator: &GbaEmulator, dir: &Path) -> Result<PathBuf> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let path = dir.join(format!(
+        "screenshot-{}-{:03}.png",
+        timestamp.as_secs(),
+        timestamp.subsec_millis()
+    ));
+
+    let png = encode_png(
+        SCREEN_WIDTH as u32,
+        SCREEN_HEIGHT as u32,
+        &emulator.screenshot_rgba(),
+    )?;
+    std::fs::write(&path, png)
+        .with_context(|| format!("Failed to write screenshot: {}", path.display()))?;
+    Ok(path)
+}
+
+/// Codifica un'immagine RGBA (8 bit per canale) in PNG
+pub fn encode_png(width: u32, height: u32, rgba: &[u8]) -> Result<Vec<u8>> {
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header().context("Failed to encode PNG")?;
+    writer
+        .write_image_data(rgba)
+        .context("Failed to encode PNG")?;
+    writer.finish().context("Failed to encode PNG")?;
+    Ok(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_png_round_trip() {
+        let rgba = [0xFF, 0x00, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0x80];
+        let png = encode_png(2, 1, &rgba).unwrap();
+
+        let mut reader = png::Decoder::new(png.as_slice()).read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        assert_eq!((info.width, info.height), (2, 1));
+        assert_eq!(info.color_type, png::ColorType::Rgba);
+        assert_eq!(pixels, rgba);
+    }
+}
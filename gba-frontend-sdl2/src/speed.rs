@@ -0,0 +1,268 @@
+// Velocità di emulazione - Turbo (fast-forward), frame-skip e pausa
+//
+// Ogni iterazione del main loop esegue uno o più frame emulati e ne mostra
+// solo l'ultimo. Il frame limiter aspetta il tempo reale corrispondente ai
+// frame eseguiti, tranne in turbo dove la velocità non ha limiti.
+//
+// Il limiter usa un clock monotono (`Instant`) e scadenze assolute, così gli
+// errori di sleep non si accumulano: il ritmo resta quello del GBA
+// (~59.7275 Hz) anche se il monitor va a 60 Hz. In alternativa si può
+// lasciare il ritmo al vsync del display.
+//
+// In pausa non viene eseguito nessun frame (la finestra continua a
+// ridisegnare l'ultimo framebuffer), salvo un singolo frame per ogni
+// richiesta di avanzamento.
+
+use std::time::{Duration, Instant};
+
+/// Frequenza dei frame del GBA: 16.78 MHz / 280896 cicli per frame
+pub const GBA_FRAME_RATE: f64 = 16_777_216.0 / 280_896.0;
+
+/// Frame emulati per ogni frame mostrato in turbo
+pub const DEFAULT_TURBO_FRAMES: u32 = 8;
+
+/// Stato di turbo, frame-skip e pausa
+#[derive(Debug, Clone)]
+pub struct SpeedControl {
+    /// Turbo attivo (tasto tenuto premuto)
+    pub turbo: bool,
+    turbo_frames: u32,
+    frame_skip: u32,
+    paused: bool,
+    /// Avanzamento di un frame richiesto durante la pausa
+    frame_advance: bool,
+    /// Frame al secondo a velocità normale
+    target_fps: f64,
+    /// Il ritmo è dato dal vsync del display invece che dal limiter
+    vsync: bool,
+}
+
+impl SpeedControl {
+    /// `frame_skip` frame emulati vengono saltati dopo ogni frame mostrato
+    pub fn new(frame_skip: u32) -> Self {
+        Self {
+            turbo: false,
+            turbo_frames: DEFAULT_TURBO_FRAMES,
+            frame_skip,
+            paused: false,
+            frame_advance: false,
+            target_fps: GBA_FRAME_RATE,
+            vsync: false,
+        }
+    }
+
+    /// Imposta i frame emulati per frame mostrato in turbo (minimo 1)
+    pub fn with_turbo_frames(mut self, frames: u32) -> Self {
+        self.turbo_frames = frames.max(1);
+        self
+    }
+
+    /// Imposta la frequenza dei frame a velocità normale (valori non validi ignorati)
+    pub fn with_target_fps(mut self, fps: f64) -> Self {
+        if fps.is_finite() && fps > 0.0 {
+            self.target_fps = fps;
+        }
+        self
+    }
+
+    /// Lascia il ritmo al vsync del display, disattivando il limiter
+    pub fn with_vsync(mut self, vsync: bool) -> Self {
+        self.vsync = vsync;
+        self
+    }
+
+    /// Ritmo dato dal vsync
+    pub fn vsync(&self) -> bool {
+        self.vsync
+    }
+
+    /// Durata reale di un frame emulato a velocità normale
+    pub fn frame_duration(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.target_fps)
+    }
+
+    /// Frame da emulare prima del prossimo rendering
+    pub fn frames_per_render(&self) -> u32 {
+        if self.turbo {
+            self.turbo_frames
+        } else {
+            1 + self.frame_skip
+        }
+    }
+
+    /// Emulazione in pausa
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Mette in pausa o riprende l'emulazione
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        self.frame_advance = false;
+    }
+
+    /// Chiede di eseguire un solo frame (solo in pausa)
+    pub fn request_frame_advance(&mut self) {
+        if self.paused {
+            self.frame_advance = true;
+        }
+    }
+
+    /// Frame da emulare in questa iterazione del main loop
+    ///
+    /// In pausa è 1 solo dopo una richiesta di avanzamento, che viene
+    /// consumata: l'iterazione successiva torna a 0.
+    pub fn next_frames(&mut self) -> u32 {
+        if self.paused {
+            u32::from(std::mem::take(&mut self.frame_advance))
+        } else {
+            self.frames_per_render()
+        }
+    }
+
+    /// Tempo reale che deve durare l'iterazione, `None` se non va limitata
+    /// (turbo, o vsync che blocca già la presentazione del frame)
+    pub fn frame_budget(&self) -> Option<Duration> {
+        if self.vsync {
+            None
+        } else if self.paused {
+            Some(self.frame_duration())
+        } else if self.turbo {
+            None
+        } else {
+            Some(self.frame_duration() * self.frames_per_render())
+        }
+    }
+}
+
+impl Default for SpeedControl {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+/// Frame limiter a scadenze assolute
+#[derive(Debug, Clone)]
+pub struct FrameLimiter {
+    /// Istante in cui termina l'iterazione corrente
+    deadline: Instant,
+}
+
+impl FrameLimiter {
+    pub fn new(now: Instant) -> Self {
+        Self { deadline: now }
+    }
+
+    /// Quanto dormire a fine iterazione, dato il budget dell'iterazione
+    ///
+    /// La scadenza avanza di `budget` a ogni chiamata: un ritardo minore di
+    /// un budget viene recuperato nei frame successivi, uno maggiore (o un
+    /// frame senza limite) riallinea la scadenza ad adesso.
+    pub fn sleep_duration(&mut self, now: Instant, budget: Option<Duration>) -> Duration {
+        let Some(budget) = budget else {
+            self.deadline = now;
+            return Duration::ZERO;
+        };
+
+        self.deadline += budget;
+        if now > self.deadline + budget {
+            self.deadline = now;
+        }
+        self.deadline.saturating_duration_since(now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normal_speed_is_limited() {
+        let speed = SpeedControl::default();
+        assert_eq!(speed.frames_per_render(), 1);
+        assert_eq!(speed.frame_budget(), Some(speed.frame_duration()));
+        // 1 / 59.7275 Hz ≈ 16.743 ms
+        assert_eq!(speed.frame_duration().as_micros(), 16742);
+    }
+
+    #[test]
+    fn test_target_fps_and_vsync() {
+        let speed = SpeedControl::default().with_target_fps(50.0);
+        assert_eq!(speed.frame_budget(), Some(Duration::from_millis(20)));
+
+        // Valori non validi lasciano la frequenza del GBA
+        let speed = SpeedControl::default().with_target_fps(0.0);
+        assert_eq!(speed.frame_duration().as_micros(), 16742);
+
+        // Con vsync il limiter non dorme mai
+        let mut speed = SpeedControl::default().with_vsync(true);
+        assert_eq!(speed.frame_budget(), None);
+        speed.toggle_pause();
+        assert_eq!(speed.frame_budget(), None);
+    }
+
+    #[test]
+    fn test_limiter_sleeps_remainder_of_frame() {
+        let start = Instant::now();
+        let budget = Some(Duration::from_millis(20));
+        let mut limiter = FrameLimiter::new(start);
+
+        // Frame da 5 ms: dorme i restanti 15 ms
+        let sleep = limiter.sleep_duration(start + Duration::from_millis(5), budget);
+        assert_eq!(sleep, Duration::from_millis(15));
+
+        // Il frame seguente è partito in ritardo di 3 ms: li recupera
+        let sleep = limiter.sleep_duration(start + Duration::from_millis(33), budget);
+        assert_eq!(sleep, Duration::from_millis(7));
+
+        // Troppo in ritardo: nessuno sleep e scadenza riallineata
+        let late = start + Duration::from_millis(200);
+        assert_eq!(limiter.sleep_duration(late, budget), Duration::ZERO);
+        let sleep = limiter.sleep_duration(late + Duration::from_millis(4), budget);
+        assert_eq!(sleep, Duration::from_millis(16));
+
+        // Senza budget (turbo) non si dorme
+        assert_eq!(limiter.sleep_duration(late, None), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_turbo_runs_n_frames_unlimited() {
+        let mut speed = SpeedControl::new(2).with_turbo_frames(5);
+        speed.turbo = true;
+        assert_eq!(speed.frames_per_render(), 5);
+        assert_eq!(speed.frame_budget(), None);
+
+        // Rilasciando il tasto torna il frame-skip configurato
+        speed.turbo = false;
+        assert_eq!(speed.frames_per_render(), 3);
+        assert_eq!(speed.frame_budget(), Some(speed.frame_duration() * 3));
+    }
+
+    #[test]
+    fn test_frame_advance_runs_one_frame_then_pauses() {
+        let mut speed = SpeedControl::default();
+        assert_eq!(speed.next_frames(), 1);
+
+        // Fuori dalla pausa l'avanzamento non ha effetto
+        speed.request_frame_advance();
+        speed.toggle_pause();
+        assert!(speed.is_paused());
+        assert_eq!(speed.next_frames(), 0);
+        assert_eq!(speed.next_frames(), 0);
+
+        speed.request_frame_advance();
+        assert_eq!(speed.next_frames(), 1);
+        assert_eq!(speed.next_frames(), 0);
+        assert!(speed.is_paused());
+
+        // Anche in turbo la pausa esegue un frame alla volta
+        speed.turbo = true;
+        speed.request_frame_advance();
+        assert_eq!(speed.next_frames(), 1);
+        assert_eq!(speed.frame_budget(), Some(speed.frame_duration()));
+
+        speed.turbo = false;
+        speed.toggle_pause();
+        assert_eq!(speed.next_frames(), 1);
+    }
+}
@@ -1,16 +1,28 @@
+use crate::audio::AudioOutput;
+use crate::input::{KeyConfig, Remapper};
+use crate::screenshot;
+use crate::speed::{FrameLimiter, SpeedControl};
+use gba_core::ppu::Layer;
 use gba_core::GbaEmulator;
+use sdl2::controller::GameController;
 use sdl2::event::Event;
 use sdl2::keyboard::Keycode;
 use sdl2::pixels::PixelFormatEnum;
 use sdl2::rect::Rect;
 use anyhow::Result;
+use std::cell::Cell;
+use std::path::Path;
+use std::rc::Rc;
 use std::time::{Duration, Instant};
 
+const WINDOW_TITLE: &str = "GBA Emulator - Rust";
 const SCREEN_WIDTH: u32 = 240;
 const SCREEN_HEIGHT: u32 = 160;
 const SCALE: u32 = 3; // Scala x3 per visibilità migliore
+const RUMBLE_REFRESH_MS: u32 = 100; // Rinnovato ogni frame finché il motore è acceso
+const EXPORT_SAVE_FILE: &str = "gba-export.sav"; // Copia del salvataggio esportata con F6
 
-pub fn run(mut emulator: GbaEmulator) -> Result<()> {
+pub fn run(mut emulator: GbaEmulator, mute: bool, key_config_path: &Path, mut speed: SpeedControl) -> Result<()> {
     // Inizializza SDL2
     let sdl_context = sdl2::init().map_err(|e| anyhow::anyhow!("Failed to initialize SDL2: {}", e))?;
     let video_subsystem = sdl_context.video().map_err(|e| anyhow::anyhow!("Failed to initialize video: {}", e))?;
@@ -18,14 +30,19 @@ pub fn run(mut emulator: GbaEmulator) -> Result<()> {
     // Crea finestra
     let window = video_subsystem
         .window(
-            "GBA Emulator - Rust",
+            WINDOW_TITLE,
             SCREEN_WIDTH * SCALE,
             SCREEN_HEIGHT * SCALE,
         )
         .position_centered()
         .build()?;
     
-    let mut canvas = window.into_canvas().accelerated().build()?;
+    // Con --vsync il ritmo lo dà il display, altrimenti il frame limiter
+    let mut canvas_builder = window.into_canvas().accelerated();
+    if speed.vsync() {
+        canvas_builder = canvas_builder.present_vsync();
+    }
+    let mut canvas = canvas_builder.build()?;
     let texture_creator = canvas.texture_creator();
     
     // Crea texture per il framebuffer (RGB888 per compatibilità)
@@ -35,16 +52,36 @@ pub fn run(mut emulator: GbaEmulator) -> Result<()> {
         SCREEN_HEIGHT,
     )?;
     
+    // Audio
+    let mut audio = AudioOutput::open(&sdl_context, &mut emulator, mute)?;
+    
+    // Input: tastiera + gamepad
+    let mut key_config = if key_config_path.exists() {
+        KeyConfig::load(key_config_path)?
+    } else {
+        KeyConfig::default()
+    };
+    let mut remapper: Option<Remapper> = None;
+
+    let controller_subsystem = sdl_context.game_controller().map_err(|e| anyhow::anyhow!("Failed to initialize game controllers: {}", e))?;
+    let mut controllers: Vec<GameController> = Vec::new();
+
+    // Rumble della cartuccia: il callback segna lo stato, il loop lo
+    // applica ai gamepad collegati
+    let rumble = Rc::new(Cell::new(false));
+    let rumble_state = Rc::clone(&rumble);
+    emulator.set_rumble_handler(Box::new(move |on| rumble_state.set(on)));
+    let mut rumbling = false;
+    
     let mut event_pump = sdl_context.event_pump().map_err(|e| anyhow::anyhow!("Failed to get event pump: {}", e))?;
     
-    // Timing (60 FPS target)
-    let frame_duration = Duration::from_micros(16666); // ~60 FPS
-    let mut last_frame = Instant::now();
+    // Timing (~59.73 FPS target, vedi speed.rs)
+    let mut limiter = FrameLimiter::new(Instant::now());
     let mut fps_counter = 0;
     let mut fps_timer = Instant::now();
     
     log::info!("✓ Emulator started successfully!");
-    log::info!("Controls:");
+    log::info!("Controls ({}):", key_config_path.display());
     log::info!("  Arrow Keys - D-Pad");
     log::info!("  Z - Button A");
     log::info!("  X - Button B");
@@ -52,8 +89,16 @@ pub fn run(mut emulator: GbaEmulator) -> Result<()> {
     log::info!("  S - Button R");
     log::info!("  Enter - Start");
     log::info!("  Backspace - Select");
+    log::info!("  Tab (hold) - Fast-forward");
+    log::info!("  P - Pause/Resume");
+    log::info!("  . - Advance one frame (paused)");
+    log::info!("  F1 - Remap keys");
+    log::info!("  F2 - Color correction on/off");
+    log::info!("  1-4 - Show/hide BG0-BG3, 5 - OBJ, 6-8 - WIN0/WIN1/OBJWIN (debug)");
     log::info!("  F5 - Save State");
+    log::info!("  F6 - Export save to {}", EXPORT_SAVE_FILE);
     log::info!("  F9 - Load State");
+    log::info!("  F12 - Screenshot");
     log::info!("  ESC - Exit");
     
     'running: loop {
@@ -69,53 +114,172 @@ pub fn run(mut emulator: GbaEmulator) -> Result<()> {
                     break 'running;
                 }
                 
+                // Gestione input GBA - Pressione: un tasto associato a un
+                // pulsante ha la precedenza sugli hotkey
+                Event::KeyDown { scancode: Some(scancode), .. }
+                    if remapper.is_none() && key_config.key_button(scancode).is_some() =>
+                {
+                    if let Some(button) = key_config.key_button(scancode) {
+                        emulator.set_button(button, true);
+                    }
+                }
+                
+                // Gestione input GBA - Rilascio
+                Event::KeyUp { scancode: Some(scancode), .. }
+                    if key_config.key_button(scancode).is_some() =>
+                {
+                    if let Some(button) = key_config.key_button(scancode) {
+                        emulator.set_button(button, false);
+                    }
+                }
+                
                 Event::KeyDown {
                     keycode: Some(Keycode::F5),
                     ..
-                } => {
+                } if remapper.is_none() => {
                     log::info!("Save State (not implemented yet)");
                 }
                 
+                Event::KeyDown {
+                    keycode: Some(Keycode::F6),
+                    repeat: false,
+                    ..
+                } if remapper.is_none() => {
+                    match emulator.export_save(Path::new(EXPORT_SAVE_FILE)) {
+                        Ok(()) => log::info!("Save exported to {}", EXPORT_SAVE_FILE),
+                        Err(e) => log::error!("Failed to export save: {}", e),
+                    }
+                }
+                
                 Event::KeyDown {
                     keycode: Some(Keycode::F9),
                     ..
-                } => {
+                } if remapper.is_none() => {
                     log::info!("Load State (not implemented yet)");
                 }
                 
-                // Gestione input GBA - Pressione
-                Event::KeyDown { keycode: Some(key), .. } => {
-                    let input = emulator.input_mut();
-                    match key {
-                        Keycode::Up => input.set_dpad_up(true),
-                        Keycode::Down => input.set_dpad_down(true),
-                        Keycode::Left => input.set_dpad_left(true),
-                        Keycode::Right => input.set_dpad_right(true),
-                        Keycode::Z => input.set_button_a(true),
-                        Keycode::X => input.set_button_b(true),
-                        Keycode::A => input.set_button_l(true),
-                        Keycode::S => input.set_button_r(true),
-                        Keycode::Return => input.set_button_start(true),
-                        Keycode::Backspace => input.set_button_select(true),
-                        _ => {}
+                // Turbo finché Tab resta premuto
+                Event::KeyDown {
+                    keycode: Some(Keycode::Tab),
+                    repeat: false,
+                    ..
+                } if remapper.is_none() => {
+                    speed.turbo = true;
+                }
+                
+                Event::KeyUp {
+                    keycode: Some(Keycode::Tab),
+                    ..
+                } => {
+                    speed.turbo = false;
+                }
+                
+                Event::KeyDown {
+                    keycode: Some(Keycode::P),
+                    repeat: false,
+                    ..
+                } if remapper.is_none() => {
+                    speed.toggle_pause();
+                    log::info!("{}", if speed.is_paused() { "Paused" } else { "Resumed" });
+                }
+                
+                Event::KeyDown {
+                    keycode: Some(Keycode::Period),
+                    ..
+                } if remapper.is_none() && speed.is_paused() => {
+                    speed.request_frame_advance();
+                }
+                
+                Event::KeyDown {
+                    keycode: Some(Keycode::F12),
+                    repeat: false,
+                    ..
+                } if remapper.is_none() => {
+                    match screenshot::save_screenshot(&emulator, Path::new(".")) {
+                        Ok(path) => log::info!("Screenshot saved to {}", path.display()),
+                        Err(e) => log::error!("{:#}", e),
                     }
                 }
                 
-                // Gestione input GBA - Rilascio
-                Event::KeyUp { keycode: Some(key), .. } => {
-                    let input = emulator.input_mut();
-                    match key {
-                        Keycode::Up => input.set_dpad_up(false),
-                        Keycode::Down => input.set_dpad_down(false),
-                        Keycode::Left => input.set_dpad_left(false),
-                        Keycode::Right => input.set_dpad_right(false),
-                        Keycode::Z => input.set_button_a(false),
-                        Keycode::X => input.set_button_b(false),
-                        Keycode::A => input.set_button_l(false),
-                        Keycode::S => input.set_button_r(false),
-                        Keycode::Return => input.set_button_start(false),
-                        Keycode::Backspace => input.set_button_select(false),
-                        _ => {}
+                Event::KeyDown {
+                    keycode: Some(Keycode::F2),
+                    repeat: false,
+                    ..
+                } if remapper.is_none() => {
+                    let enabled = !emulator.color_correction();
+                    emulator.set_color_correction(enabled);
+                    log::info!("Color correction {}", if enabled { "on" } else { "off" });
+                }
+                
+                Event::KeyDown {
+                    keycode: Some(keycode),
+                    repeat: false,
+                    ..
+                } if remapper.is_none() && debug_layer(keycode).is_some() => {
+                    if let Some(layer) = debug_layer(keycode) {
+                        let ppu = &mut emulator.bus.ppu;
+                        let enabled = !ppu.layer_enabled(layer);
+                        ppu.set_layer_enabled(layer, enabled);
+                        log::info!("{:?} {}", layer, if enabled { "shown" } else { "hidden" });
+                    }
+                }
+                
+                Event::KeyDown {
+                    keycode: Some(Keycode::F1),
+                    ..
+                } => {
+                    let remap = Remapper::new();
+                    if let Some(button) = remap.current() {
+                        log::info!("Remap: press a key or gamepad button for {}", button.name());
+                    }
+                    remapper = Some(remap);
+                }
+                
+                // Gamepad collegati/scollegati
+                Event::ControllerDeviceAdded { which, .. } => {
+                    match controller_subsystem.open(which) {
+                        Ok(controller) => {
+                            log::info!("Gamepad connected: {}", controller.name());
+                            controllers.push(controller);
+                        }
+                        Err(e) => log::warn!("Failed to open gamepad {}: {}", which, e),
+                    }
+                }
+                
+                Event::ControllerDeviceRemoved { which, .. } => {
+                    controllers.retain(|c| c.instance_id() != which);
+                }
+                
+                // Remap in corso: il tasto premuto viene associato
+                Event::KeyDown { scancode: Some(scancode), repeat: false, .. } if remapper.is_some() => {
+                    if let Some(remap) = &mut remapper {
+                        let current = remap.current();
+                        if let (Some(other), Some(button)) = (remap.assign_key(&mut key_config, scancode), current) {
+                            log::warn!("Remap: input was bound to {}, swapped with {}", other.name(), button.name());
+                        }
+                    }
+                    finish_remap_step(&mut remapper, &key_config, key_config_path);
+                }
+                
+                Event::ControllerButtonDown { button, .. } if remapper.is_some() => {
+                    if let Some(remap) = &mut remapper {
+                        let current = remap.current();
+                        if let (Some(other), Some(button)) = (remap.assign_pad(&mut key_config, button), current) {
+                            log::warn!("Remap: input was bound to {}, swapped with {}", other.name(), button.name());
+                        }
+                    }
+                    finish_remap_step(&mut remapper, &key_config, key_config_path);
+                }
+                
+                Event::ControllerButtonDown { button, .. } => {
+                    if let Some(button) = key_config.pad_button(button) {
+                        emulator.set_button(button, true);
+                    }
+                }
+                
+                Event::ControllerButtonUp { button, .. } => {
+                    if let Some(button) = key_config.pad_button(button) {
+                        emulator.set_button(button, false);
                     }
                 }
                 
@@ -123,23 +287,35 @@ pub fn run(mut emulator: GbaEmulator) -> Result<()> {
             }
         }
         
-        // Esegui frame emulatore
-        emulator.run_frame();
+        // Esegui i frame emulati (più di uno in turbo o con frame-skip,
+        // nessuno in pausa: si ridisegna l'ultimo framebuffer)
+        for _ in 0..speed.next_frames() {
+            emulator.run_frame();
+            
+            // Invia i sample del frame al device audio; in turbo vengono
+            // scartati per non accumulare latenza
+            if speed.turbo {
+                audio.discard_frame(&mut emulator);
+            } else {
+                audio.queue_frame(&mut emulator);
+            }
+        }
+
+        if rumble.get() || rumbling {
+            rumbling = rumble.get();
+            let (strength, duration) = if rumbling { (u16::MAX, RUMBLE_REFRESH_MS) } else { (0, 0) };
+            for controller in &mut controllers {
+                let _ = controller.set_rumble(strength, strength, duration);
+            }
+        }
         
         // Converti framebuffer RGB555 -> RGB888
         let framebuffer_rgb555 = emulator.framebuffer();
         let mut framebuffer_rgb888 = vec![0u8; (SCREEN_WIDTH * SCREEN_HEIGHT * 3) as usize];
         
         for (i, &pixel) in framebuffer_rgb555.iter().enumerate() {
-            // Estrai componenti RGB555 (5-5-5 bit)
-            let r5 = ((pixel >> 10) & 0x1F) as u8;
-            let g5 = ((pixel >> 5) & 0x1F) as u8;
-            let b5 = (pixel & 0x1F) as u8;
-            
-            // Converti a RGB888 (8-8-8 bit) con espansione
-            let r8 = (r5 << 3) | (r5 >> 2);
-            let g8 = (g5 << 3) | (g5 >> 2);
-            let b8 = (b5 << 3) | (b5 >> 2);
+            // Stessa conversione degli screenshot (correzione colore inclusa)
+            let [r8, g8, b8] = emulator.present_pixel(pixel);
             
             // Scrivi pixel in formato RGB888
             framebuffer_rgb888[i * 3] = r8;
@@ -159,21 +335,55 @@ pub fn run(mut emulator: GbaEmulator) -> Result<()> {
         ).map_err(|e| anyhow::anyhow!("Failed to copy texture: {}", e))?;
         canvas.present();
         
-        // FPS counter
+        // FPS counter e tempo di emulazione dell'ultimo frame nel titolo
         fps_counter += 1;
         if fps_timer.elapsed() >= Duration::from_secs(1) {
-            log::debug!("FPS: {}", fps_counter);
+            let stats = emulator.last_frame_stats();
+            let frame_ms = stats.wall_time_ns as f64 / 1_000_000.0;
+            log::debug!("FPS: {} ({} instructions, {:.2} ms/frame)", fps_counter, stats.instructions, frame_ms);
+            let title = format!("{} - {} FPS - {:.2} ms/frame", WINDOW_TITLE, fps_counter, frame_ms);
+            let _ = canvas.window_mut().set_title(&title);
             fps_counter = 0;
             fps_timer = Instant::now();
         }
         
-        // Limita a 60 FPS
-        let elapsed = last_frame.elapsed();
-        if elapsed < frame_duration {
-            std::thread::sleep(frame_duration - elapsed);
+        // Limita alla frequenza del GBA (nessun limite in turbo o con vsync)
+        let sleep = limiter.sleep_duration(Instant::now(), speed.frame_budget());
+        if !sleep.is_zero() {
+            std::thread::sleep(sleep);
         }
-        last_frame = Instant::now();
     }
     
     Ok(())
 }
+
+/// Dopo ogni associazione: chiede il pulsante successivo o salva
+fn finish_remap_step(remapper: &mut Option<Remapper>, config: &KeyConfig, path: &Path) {
+    match remapper.as_ref().and_then(|r| r.current()) {
+        Some(button) => {
+            log::info!("Remap: press a key or gamepad button for {}", button.name());
+        }
+        None => {
+            *remapper = None;
+            match config.save(path) {
+                Ok(()) => log::info!("Key bindings saved to {}", path.display()),
+                Err(e) => log::error!("{:#}", e),
+            }
+        }
+    }
+}
+
+/// Layer mostrato/nascosto dai tasti numerici (debug grafico)
+fn debug_layer(keycode: Keycode) -> Option<Layer> {
+    match keycode {
+        Keycode::Num1 => Some(Layer::Bg0),
+        Keycode::Num2 => Some(Layer::Bg1),
+        Keycode::Num3 => Some(Layer::Bg2),
+        Keycode::Num4 => Some(Layer::Bg3),
+        Keycode::Num5 => Some(Layer::Obj),
+        Keycode::Num6 => Some(Layer::Win0),
+        Keycode::Num7 => Some(Layer::Win1),
+        Keycode::Num8 => Some(Layer::WinObj),
+        _ => None,
+    }
+}